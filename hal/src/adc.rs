@@ -120,6 +120,38 @@ pub fn vref_cal() -> u16 {
     unsafe { read_volatile(0x1FFF_75AA as *const u16) }
 }
 
+/// V<sub>REF+</sub> voltage in millivolts used during production test for
+/// [`vref_cal`] and the temperature sensor calibration.
+pub const VREF_CAL_MV: u16 = 3300;
+
+/// Full-scale ADC data for a 12-bit conversion.
+pub const DATA_MAX: u16 = 0xFFF;
+
+/// Convert raw 12-bit ADC data to millivolts.
+///
+/// `vref_plus_mv` is the V<sub>REF+</sub> voltage in millivolts, this is
+/// [`Vrefbuf::millivolts`](crate::vrefbuf::Vrefbuf::millivolts) when the
+/// internal voltage reference buffer is used.
+///
+/// The result is rounded to the nearest millivolt.
+/// Data from oversampling must be shifted down to 12 bits before conversion.
+///
+/// # Example
+///
+/// ```
+/// use stm32wlxx_hal::{adc, vrefbuf::Scale};
+///
+/// assert_eq!(adc::to_millivolts(0, 3300), 0);
+/// assert_eq!(adc::to_millivolts(adc::DATA_MAX, 3300), 3300);
+/// assert_eq!(adc::to_millivolts(2048, Scale::V2_048.millivolts()), 1024);
+/// assert_eq!(adc::to_millivolts(2048, Scale::V2_5.millivolts()), 1250);
+/// ```
+pub const fn to_millivolts(data: u16, vref_plus_mv: u16) -> u16 {
+    let numer: u32 = (data as u32) * (vref_plus_mv as u32);
+    let denom: u32 = DATA_MAX as u32;
+    ((numer + denom / 2) / denom) as u16
+}
+
 /// ADC clock mode
 ///
 /// In all synchronous clock modes, there is no jitter in the delay from a
@@ -1394,6 +1426,87 @@ impl Adc {
         self.start_conversion();
         self.poll_data()
     }
+
+    /// Sample the V<sub>BAT</sub> pin and convert it to millivolts.
+    ///
+    /// This accounts for the bridge divider described in [`vbat`].
+    ///
+    /// `vref_plus_mv` is the V<sub>REF+</sub> voltage in millivolts, see
+    /// [`to_millivolts`].
+    ///
+    /// # Panics
+    ///
+    /// * (debug) ADC is not enabled
+    /// * (debug) ADC has a pending disable request
+    /// * (debug) V<sub>BAT</sub> is not enabled
+    ///
+    /// [`vbat`]: Self::vbat
+    pub fn vbat_mv(&mut self, vref_plus_mv: u16) -> u16 {
+        to_millivolts(self.vbat(), vref_plus_mv).saturating_mul(3)
+    }
+
+    /// Sample a GPIO pin and convert it to millivolts.
+    ///
+    /// `vref_plus_mv` is the V<sub>REF+</sub> voltage in millivolts, see
+    /// [`to_millivolts`].
+    ///
+    /// # Panics
+    ///
+    /// * (debug) ADC is not enabled
+    /// * (debug) ADC has a pending disable request
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use stm32wlxx_hal::{
+    ///     adc::{self, Adc},
+    ///     gpio::{pins::B4, Analog, PortB},
+    ///     pac,
+    ///     vrefbuf::{Scale, Vrefbuf},
+    /// };
+    ///
+    /// let mut dp: pac::Peripherals = pac::Peripherals::take().unwrap();
+    ///
+    /// let vrefbuf: Vrefbuf = Vrefbuf::new(dp.VREFBUF, Scale::V2_5);
+    ///
+    /// // enable the HSI16 source clock
+    /// dp.RCC.cr.modify(|_, w| w.hsion().set_bit());
+    /// while dp.RCC.cr.read().hsirdy().is_not_ready() {}
+    ///
+    /// let mut adc = Adc::new(dp.ADC, adc::Clk::RccHsi, &mut dp.RCC);
+    /// adc.set_max_sample_time();
+    /// adc.enable();
+    ///
+    /// let gpiob: PortB = PortB::split(dp.GPIOB, &mut dp.RCC);
+    /// let b4: Analog<B4> = cortex_m::interrupt::free(|cs| Analog::new(gpiob.b4, cs));
+    ///
+    /// let millivolts: u16 = adc.pin_mv(&b4, vrefbuf.millivolts());
+    /// ```
+    pub fn pin_mv<P: gpio::sealed::AdcCh>(
+        &mut self,
+        pin: &gpio::Analog<P>,
+        vref_plus_mv: u16,
+    ) -> u16 {
+        to_millivolts(self.pin(pin), vref_plus_mv)
+    }
+
+    /// Measure the V<sub>REF+</sub> voltage in millivolts.
+    ///
+    /// This samples the internal voltage reference and scales it by the
+    /// factory calibration ([`vref_cal`]).
+    /// This is useful when V<sub>REF+</sub> is connected to V<sub>DDA</sub>
+    /// and the internal voltage reference buffer is not used.
+    ///
+    /// # Panics
+    ///
+    /// * (debug) ADC is not enabled
+    /// * (debug) ADC has a pending disable request
+    /// * (debug) Voltage reference is not enabled
+    pub fn vref_plus_mv(&mut self) -> u16 {
+        let vref: u32 = u32::from(self.vref()).max(1);
+        let numer: u32 = u32::from(VREF_CAL_MV) * u32::from(vref_cal());
+        ((numer + vref / 2) / vref).min(u32::from(u16::MAX)) as u16
+    }
 }
 
 // on-off control
@@ -0,0 +1,821 @@
+//! Analog comparators
+//!
+//! The two comparators keep running in Stop 2 mode, and their outputs can
+//! wake the device through the EXTI.
+//!
+//! Inputs are selected with types:
+//!
+//! * Non-inverting input: an [`Analog`] pin, or [`Window`] to share the
+//!   non-inverting input of COMP1 with COMP2.
+//! * Inverting input: an [`Analog`] pin, a [`Vref`] fraction of VREFINT, or
+//!   the DAC output with [`DacOut`].
+//!
+//! The comparator outputs can be routed to:
+//!
+//! * LPTIM triggers with [`lptim::TrgSel::Comp1`] and
+//!   [`lptim::TrgSel::Comp2`]
+//! * The TIM1 break input with
+//!   [`Tim1::set_comp_break`](crate::tim::Tim1::set_comp_break)
+//! * The EXTI with [`Comp::setup_exti_c1`]
+//!
+//! # Example
+//!
+//! Compare pin B2 against half of VREFINT.
+//!
+//! ```no_run
+//! use stm32wlxx_hal::{
+//!     comp::{self, Comp, Vref},
+//!     cortex_m,
+//!     gpio::{pins, Analog, PortB},
+//!     pac,
+//! };
+//!
+//! let mut dp: pac::Peripherals = pac::Peripherals::take().unwrap();
+//!
+//! let gpiob: PortB = PortB::split(dp.GPIOB, &mut dp.RCC);
+//! let b2: Analog<pins::B2> = cortex_m::interrupt::free(|cs| Analog::new(gpiob.b2, cs));
+//!
+//! let (comp1, _comp2) = comp::split(dp.COMP);
+//! let comp1: Comp<1, Analog<pins::B2>, Vref> =
+//!     Comp::new(comp1, b2, Vref::Half, comp::Cfg::DEFAULT);
+//!
+//! let high: bool = comp1.level();
+//! ```
+//!
+//! [`lptim::TrgSel::Comp1`]: crate::lptim::TrgSel::Comp1
+//! [`lptim::TrgSel::Comp2`]: crate::lptim::TrgSel::Comp2
+
+use crate::{
+    dac::Dac,
+    gpio::{
+        pins::{B1, B2, B3, B4},
+        Analog, ExtiTrg,
+    },
+    pac,
+};
+
+/// Comparator hysteresis.
+#[repr(u8)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Hyst {
+    /// No hysteresis.
+    None = 0b00,
+    /// Low hysteresis.
+    Low = 0b01,
+    /// Medium hysteresis.
+    Medium = 0b10,
+    /// High hysteresis.
+    High = 0b11,
+}
+
+/// Comparator power mode.
+///
+/// Lower power modes have a longer propagation delay.
+#[repr(u8)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum PwrMode {
+    /// High speed, highest power consumption.
+    HighSpeed = 0b00,
+    /// Medium speed, medium power consumption.
+    Medium = 0b01,
+    /// Low speed, low power consumption.
+    Low = 0b10,
+    /// Ultra-low power, lowest speed.
+    UltraLow = 0b11,
+}
+
+/// Comparator configuration.
+///
+/// # Example
+///
+/// ```
+/// use stm32wlxx_hal::comp::{Cfg, Hyst, PwrMode};
+///
+/// const CFG: Cfg = Cfg::DEFAULT
+///     .set_hyst(Hyst::Medium)
+///     .set_pwr_mode(PwrMode::UltraLow)
+///     .set_inverted(true);
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Cfg {
+    hyst: Hyst,
+    pwr: PwrMode,
+    inv: bool,
+}
+
+impl Cfg {
+    /// Default configuration.
+    ///
+    /// * No hysteresis
+    /// * High speed
+    /// * Non-inverted output
+    pub const DEFAULT: Cfg = Cfg {
+        hyst: Hyst::None,
+        pwr: PwrMode::HighSpeed,
+        inv: false,
+    };
+
+    /// Set the hysteresis.
+    #[must_use = "set_hyst returns a modified Cfg"]
+    pub const fn set_hyst(mut self, hyst: Hyst) -> Cfg {
+        self.hyst = hyst;
+        self
+    }
+
+    /// Set the power mode.
+    #[must_use = "set_pwr_mode returns a modified Cfg"]
+    pub const fn set_pwr_mode(mut self, pwr: PwrMode) -> Cfg {
+        self.pwr = pwr;
+        self
+    }
+
+    /// Invert the comparator output.
+    #[must_use = "set_inverted returns a modified Cfg"]
+    pub const fn set_inverted(mut self, inv: bool) -> Cfg {
+        self.inv = inv;
+        self
+    }
+}
+
+impl Default for Cfg {
+    fn default() -> Self {
+        Cfg::DEFAULT
+    }
+}
+
+pub(crate) mod sealed {
+    pub trait Inp<const N: u8> {
+        /// INPSEL bits.
+        const INPSEL: u8;
+        /// WINMODE bit.
+        const WINMODE: bool = false;
+    }
+
+    pub trait Inm<const N: u8> {
+        /// INMSEL bits.
+        fn inmsel(&self) -> u8;
+
+        /// SCALEN bit.
+        fn scalen(&self) -> bool {
+            false
+        }
+
+        /// BRGEN bit.
+        fn brgen(&self) -> bool {
+            false
+        }
+    }
+}
+
+/// Non-inverting input of comparator `N`.
+pub trait Inp<const N: u8>: sealed::Inp<N> {}
+
+/// Inverting input of comparator `N`.
+pub trait Inm<const N: u8>: sealed::Inm<N> {}
+
+macro_rules! impl_inp_pin {
+    ($n:expr, $pin:ident, $inpsel:expr) => {
+        impl sealed::Inp<$n> for Analog<$pin> {
+            const INPSEL: u8 = $inpsel;
+        }
+        impl Inp<$n> for Analog<$pin> {}
+    };
+}
+
+macro_rules! impl_inm_pin {
+    ($n:expr, $pin:ident, $inmsel:expr) => {
+        impl sealed::Inm<$n> for Analog<$pin> {
+            #[inline(always)]
+            fn inmsel(&self) -> u8 {
+                $inmsel
+            }
+        }
+        impl Inm<$n> for Analog<$pin> {}
+    };
+}
+
+impl_inp_pin!(1, B2, 0b01);
+impl_inp_pin!(2, B4, 0b00);
+impl_inm_pin!(1, B1, 0b110);
+impl_inm_pin!(2, B3, 0b110);
+
+/// Fraction of VREFINT for the inverting input.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Vref {
+    /// 1/4 VREFINT.
+    Quarter,
+    /// 1/2 VREFINT.
+    Half,
+    /// 3/4 VREFINT.
+    ThreeQuarter,
+    /// VREFINT.
+    Full,
+}
+
+impl<const N: u8> sealed::Inm<N> for Vref {
+    #[inline]
+    fn inmsel(&self) -> u8 {
+        match self {
+            Vref::Quarter => 0b000,
+            Vref::Half => 0b001,
+            Vref::ThreeQuarter => 0b010,
+            Vref::Full => 0b011,
+        }
+    }
+
+    #[inline]
+    fn scalen(&self) -> bool {
+        true
+    }
+
+    // the scaler bridge is required for fractions of VREFINT
+    #[inline]
+    fn brgen(&self) -> bool {
+        !matches!(self, Vref::Full)
+    }
+}
+impl<const N: u8> Inm<N> for Vref {}
+
+/// DAC output for the inverting input.
+///
+/// The DAC must be in a mode with output to chip peripherals, for example
+/// [`ModeChip::Norm`](crate::dac::ModeChip::Norm) or
+/// [`ModePin::NormChipBuf`](crate::dac::ModePin::NormChipBuf).
+///
+/// The DAC is owned by the input, it can be used through
+/// [`Comp::inm_mut`] to change the comparator threshold.
+#[derive(Debug)]
+pub struct DacOut {
+    dac: Dac,
+}
+
+impl DacOut {
+    /// Use the output of a DAC as the inverting input.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use stm32wlxx_hal::{
+    ///     comp::DacOut,
+    ///     dac::{Dac, ModeChip},
+    ///     pac,
+    /// };
+    ///
+    /// let mut dp: pac::Peripherals = pac::Peripherals::take().unwrap();
+    /// let mut dac: Dac = Dac::new(dp.DAC, &mut dp.RCC);
+    /// dac.set_mode_chip(ModeChip::Norm);
+    /// let dac_out: DacOut = DacOut::new(dac);
+    /// ```
+    #[inline]
+    pub const fn new(dac: Dac) -> Self {
+        Self { dac }
+    }
+
+    /// Free the DAC.
+    #[inline]
+    pub fn free(self) -> Dac {
+        self.dac
+    }
+
+    /// Get a reference to the DAC.
+    #[inline]
+    pub fn dac(&self) -> &Dac {
+        &self.dac
+    }
+
+    /// Get a mutable reference to the DAC.
+    ///
+    /// # Example
+    ///
+    /// Change the threshold of a comparator.
+    ///
+    /// ```no_run
+    /// use stm32wlxx_hal::{
+    ///     comp::{self, Comp, DacOut},
+    ///     cortex_m,
+    ///     dac::{Dac, ModeChip},
+    ///     gpio::{pins, Analog, PortB},
+    ///     pac,
+    /// };
+    ///
+    /// let mut dp: pac::Peripherals = pac::Peripherals::take().unwrap();
+    ///
+    /// let gpiob: PortB = PortB::split(dp.GPIOB, &mut dp.RCC);
+    /// let b2: Analog<pins::B2> = cortex_m::interrupt::free(|cs| Analog::new(gpiob.b2, cs));
+    ///
+    /// let mut dac: Dac = Dac::new(dp.DAC, &mut dp.RCC);
+    /// dac.set_mode_chip(ModeChip::Norm);
+    /// dac.setup_soft_trigger();
+    ///
+    /// let (comp1, _comp2) = comp::split(dp.COMP);
+    /// let mut comp1: Comp<1, Analog<pins::B2>, DacOut> =
+    ///     Comp::new(comp1, b2, DacOut::new(dac), comp::Cfg::DEFAULT);
+    ///
+    /// comp1.inm_mut().dac_mut().soft_trigger(2048);
+    /// ```
+    #[inline]
+    pub fn dac_mut(&mut self) -> &mut Dac {
+        &mut self.dac
+    }
+}
+
+impl<const N: u8> sealed::Inm<N> for DacOut {
+    #[inline]
+    fn inmsel(&self) -> u8 {
+        0b100
+    }
+}
+impl<const N: u8> Inm<N> for DacOut {}
+
+/// COMP1 non-inverting input shared with COMP2 for window mode.
+///
+/// Created with [`Comp::window`].
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Window {
+    _priv: (),
+}
+
+impl sealed::Inp<2> for Window {
+    const INPSEL: u8 = 0b00;
+    const WINMODE: bool = true;
+}
+impl Inp<2> for Window {}
+
+/// CSR field values, without EN and LOCK.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+struct Csr {
+    pwrmode: u8,
+    inmsel: u8,
+    inpsel: u8,
+    winmode: bool,
+    polarity: bool,
+    hyst: u8,
+    brgen: bool,
+    scalen: bool,
+}
+
+impl Csr {
+    fn new<const N: u8, INP: Inp<N>, INM: Inm<N>>(cfg: Cfg, inm: &INM) -> Csr {
+        Csr {
+            pwrmode: cfg.pwr as u8,
+            inmsel: inm.inmsel(),
+            inpsel: INP::INPSEL,
+            winmode: INP::WINMODE,
+            polarity: cfg.inv,
+            hyst: cfg.hyst as u8,
+            brgen: inm.brgen(),
+            scalen: inm.scalen(),
+        }
+    }
+}
+
+// COMP1_CSR and COMP2_CSR are distinct PAC types with the same fields
+macro_rules! write_csr_fields {
+    ($w:ident, $csr:ident, $en:ident) => {
+        $w.pwrmode()
+            .bits($csr.pwrmode)
+            .inmsel()
+            .bits($csr.inmsel)
+            .inpsel()
+            .bits($csr.inpsel)
+            .polarity()
+            .bit($csr.polarity)
+            .hyst()
+            .bits($csr.hyst)
+            .brgen()
+            .bit($csr.brgen)
+            .scalen()
+            .bit($csr.scalen)
+            .en()
+            .bit($en)
+    };
+}
+
+/// Unconfigured comparator.
+///
+/// Created with [`split`].
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Instance<const N: u8> {
+    _priv: (),
+}
+
+impl<const N: u8> Instance<N> {
+    /// Steal the comparator.
+    ///
+    /// # Safety
+    ///
+    /// 1. Ensure that the code stealing the comparator has exclusive access
+    ///    to it.
+    ///    Singleton checks are bypassed with this method.
+    /// 2. `N` must be 1 or 2.
+    #[inline]
+    pub const unsafe fn steal() -> Self {
+        Self { _priv: () }
+    }
+
+    #[inline(always)]
+    fn regs(&self) -> &pac::comp::RegisterBlock {
+        // safety: each instance only writes its own CSR, reads are atomic
+        // with no side effects
+        unsafe { &*pac::COMP::PTR }
+    }
+
+    #[inline(always)]
+    fn write_csr(&mut self, csr: Csr, en: bool) {
+        // safety: INMSEL and INPSEL values come from the sealed input traits
+        match N {
+            1 => self
+                .regs()
+                .comp1_csr
+                .write(|w| unsafe { write_csr_fields!(w, csr, en) }),
+            _ => self
+                .regs()
+                .comp2_csr
+                .write(|w| unsafe { write_csr_fields!(w, csr, en).winmode().bit(csr.winmode) }),
+        }
+    }
+
+    #[inline(always)]
+    fn reset_csr(&mut self) {
+        match N {
+            1 => self.regs().comp1_csr.reset(),
+            _ => self.regs().comp2_csr.reset(),
+        }
+    }
+
+    #[inline(always)]
+    fn value(&self) -> bool {
+        match N {
+            1 => self.regs().comp1_csr.read().value().bit_is_set(),
+            _ => self.regs().comp2_csr.read().value().bit_is_set(),
+        }
+    }
+
+    #[inline(always)]
+    fn lock(&mut self) {
+        match N {
+            1 => self.regs().comp1_csr.modify(|_, w| w.lock().set_bit()),
+            _ => self.regs().comp2_csr.modify(|_, w| w.lock().set_bit()),
+        }
+    }
+
+    #[inline(always)]
+    fn is_locked(&self) -> bool {
+        match N {
+            1 => self.regs().comp1_csr.read().lock().bit_is_set(),
+            _ => self.regs().comp2_csr.read().lock().bit_is_set(),
+        }
+    }
+}
+
+/// Split the COMP peripheral into the two comparators.
+///
+/// The COMP peripheral is returned by [`join`].
+///
+/// # Example
+///
+/// ```no_run
+/// use stm32wlxx_hal::{comp, pac};
+///
+/// let dp: pac::Peripherals = pac::Peripherals::take().unwrap();
+/// let (comp1, comp2) = comp::split(dp.COMP);
+/// ```
+#[allow(unused_variables)]
+#[inline]
+pub fn split(comp: pac::COMP) -> (Instance<1>, Instance<2>) {
+    unsafe { (Instance::steal(), Instance::steal()) }
+}
+
+/// Join the two comparators to get the COMP peripheral back.
+///
+/// This is the inverse of [`split`], it requires both comparators because
+/// they share the peripheral.
+///
+/// # Example
+///
+/// ```no_run
+/// use stm32wlxx_hal::{comp, pac};
+///
+/// let dp: pac::Peripherals = pac::Peripherals::take().unwrap();
+/// let (comp1, comp2) = comp::split(dp.COMP);
+/// // ... use the comparators
+/// let comp: pac::COMP = comp::join(comp1, comp2);
+/// ```
+#[allow(unused_variables)]
+#[inline]
+pub fn join(comp1: Instance<1>, comp2: Instance<2>) -> pac::COMP {
+    // safety: both comparators are consumed, which were split from the
+    // peripheral
+    unsafe { pac::Peripherals::steal().COMP }
+}
+
+/// Comparator driver.
+///
+/// `N` is the comparator number, 1 or 2.
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Comp<const N: u8, INP, INM> {
+    inst: Instance<N>,
+    inp: INP,
+    inm: INM,
+}
+
+impl<const N: u8, INP, INM> Comp<N, INP, INM>
+where
+    INP: Inp<N>,
+    INM: Inm<N>,
+{
+    // EXTI line of the comparator output
+    const EXTI_LINE: u8 = 20 + N;
+
+    /// Create and enable a comparator.
+    ///
+    /// # Panics
+    ///
+    /// * (debug) the comparator is locked, see [`lock`](Self::lock).
+    ///
+    /// # Example
+    ///
+    /// See the [module level documentation](self).
+    #[inline]
+    pub fn new(inst: Instance<N>, inp: INP, inm: INM, cfg: Cfg) -> Self {
+        debug_assert!(!inst.is_locked(), "comparator is locked");
+        let mut comp: Self = Self { inst, inp, inm };
+        let csr: Csr = Csr::new::<N, INP, INM>(cfg, &comp.inm);
+        comp.inst.write_csr(csr, false);
+        comp.inst.write_csr(csr, true);
+        comp
+    }
+
+    /// Disable the comparator, and free the inputs.
+    ///
+    /// This has no effect on the hardware if the comparator is locked.
+    #[inline]
+    pub fn free(mut self) -> (Instance<N>, INP, INM) {
+        self.inst.reset_csr();
+        (self.inst, self.inp, self.inm)
+    }
+
+    /// Get a reference to the non-inverting input.
+    #[inline]
+    pub fn inp(&self) -> &INP {
+        &self.inp
+    }
+
+    /// Get a reference to the inverting input.
+    #[inline]
+    pub fn inm(&self) -> &INM {
+        &self.inm
+    }
+
+    /// Get a mutable reference to the inverting input.
+    ///
+    /// # Example
+    ///
+    /// See [`DacOut::dac_mut`].
+    #[inline]
+    pub fn inm_mut(&mut self) -> &mut INM {
+        &mut self.inm
+    }
+
+    /// Set the comparator configuration.
+    ///
+    /// This has no effect if the comparator is locked.
+    #[inline]
+    pub fn set_cfg(&mut self, cfg: Cfg) {
+        let csr: Csr = Csr::new::<N, INP, INM>(cfg, &self.inm);
+        self.inst.write_csr(csr, true)
+    }
+
+    /// Get the comparator output level.
+    ///
+    /// Returns `true` if the output is high, which is when the non-inverting
+    /// input is above the inverting input, unless the output is inverted.
+    #[inline]
+    pub fn level(&self) -> bool {
+        self.inst.value()
+    }
+
+    /// Lock the comparator configuration.
+    ///
+    /// The configuration is read-only until the next system reset.
+    #[inline]
+    pub fn lock(&mut self) {
+        self.inst.lock()
+    }
+
+    /// Returns `true` if the comparator configuration is locked.
+    #[inline]
+    pub fn is_locked(&self) -> bool {
+        self.inst.is_locked()
+    }
+
+    /// Setup the comparator output as an EXTI interrupt and wakeup source
+    /// on core 1.
+    ///
+    /// The comparator output is EXTI line 21 for COMP1 and 22 for COMP2.
+    ///
+    /// # Example
+    ///
+    /// Wakeup from Stop 2 when pin B2 rises above VREFINT.
+    ///
+    /// ```no_run
+    /// use stm32wlxx_hal::{
+    ///     comp::{self, Comp, PwrMode, Vref},
+    ///     cortex_m,
+    ///     gpio::{pins, Analog, ExtiTrg, PortB},
+    ///     pac,
+    /// };
+    ///
+    /// let mut dp: pac::Peripherals = pac::Peripherals::take().unwrap();
+    ///
+    /// let gpiob: PortB = PortB::split(dp.GPIOB, &mut dp.RCC);
+    /// let b2: Analog<pins::B2> = cortex_m::interrupt::free(|cs| Analog::new(gpiob.b2, cs));
+    ///
+    /// let (comp1, _comp2) = comp::split(dp.COMP);
+    /// let cfg: comp::Cfg = comp::Cfg::DEFAULT.set_pwr_mode(PwrMode::UltraLow);
+    /// let comp1: Comp<1, Analog<pins::B2>, Vref> = Comp::new(comp1, b2, Vref::Full, cfg);
+    /// comp1.setup_exti_c1(&mut dp.EXTI, ExtiTrg::Rising);
+    /// ```
+    #[inline]
+    pub fn setup_exti_c1(&self, exti: &mut pac::EXTI, trg: ExtiTrg) {
+        let bit: u32 = 1 << Self::EXTI_LINE;
+        let rising: bool = matches!(trg, ExtiTrg::Rising | ExtiTrg::Both);
+        let falling: bool = matches!(trg, ExtiTrg::Falling | ExtiTrg::Both);
+        exti.rtsr1.modify(|r, w| unsafe {
+            w.bits(if rising {
+                r.bits() | bit
+            } else {
+                r.bits() & !bit
+            })
+        });
+        exti.ftsr1.modify(|r, w| unsafe {
+            w.bits(if falling {
+                r.bits() | bit
+            } else {
+                r.bits() & !bit
+            })
+        });
+        exti.c1imr1.modify(|r, w| unsafe { w.bits(r.bits() | bit) });
+    }
+
+    /// Disable the comparator output as an EXTI interrupt source on core 1.
+    #[inline]
+    pub fn disable_exti_c1(&self, exti: &mut pac::EXTI) {
+        let bit: u32 = 1 << Self::EXTI_LINE;
+        exti.c1imr1
+            .modify(|r, w| unsafe { w.bits(r.bits() & !bit) });
+    }
+
+    /// Returns `true` if an EXTI trigger request occurred.
+    #[inline]
+    pub fn is_exti_pending() -> bool {
+        // safety: atomic read with no side effects
+        unsafe { (*pac::EXTI::PTR).pr1.read().bits() & (1 << Self::EXTI_LINE) != 0 }
+    }
+
+    /// Clear the pending EXTI interrupt.
+    #[inline]
+    pub fn clear_exti() {
+        // safety: atomic write, writing 0 has no effect
+        unsafe {
+            (*pac::EXTI::PTR)
+                .pr1
+                .write(|w| w.bits(1 << Self::EXTI_LINE))
+        }
+    }
+
+    /// Unmask the COMP IRQ in the NVIC.
+    ///
+    /// The IRQ is shared by both comparators.
+    ///
+    /// # Safety
+    ///
+    /// This can break mask-based critical sections.
+    #[cfg(all(not(feature = "stm32wl5x_cm0p"), feature = "rt"))]
+    #[inline]
+    pub unsafe fn unmask_irq() {
+        pac::NVIC::unmask(pac::Interrupt::COMP)
+    }
+}
+
+impl<INP: Inp<1>, INM: Inm<1>> Comp<1, INP, INM> {
+    /// Share the COMP1 non-inverting input with COMP2 for window mode.
+    ///
+    /// # Example
+    ///
+    /// Window comparator, both outputs are high when pin B2 is between
+    /// 1/4 and 3/4 of VREFINT when COMP2 is inverted.
+    ///
+    /// ```no_run
+    /// use stm32wlxx_hal::{
+    ///     comp::{self, Comp, Vref, Window},
+    ///     cortex_m,
+    ///     gpio::{pins, Analog, PortB},
+    ///     pac,
+    /// };
+    ///
+    /// let mut dp: pac::Peripherals = pac::Peripherals::take().unwrap();
+    ///
+    /// let gpiob: PortB = PortB::split(dp.GPIOB, &mut dp.RCC);
+    /// let b2: Analog<pins::B2> = cortex_m::interrupt::free(|cs| Analog::new(gpiob.b2, cs));
+    ///
+    /// let (comp1, comp2) = comp::split(dp.COMP);
+    /// let comp1: Comp<1, Analog<pins::B2>, Vref> =
+    ///     Comp::new(comp1, b2, Vref::Quarter, comp::Cfg::DEFAULT);
+    /// let comp2: Comp<2, Window, Vref> = Comp::new(
+    ///     comp2,
+    ///     comp1.window(),
+    ///     Vref::ThreeQuarter,
+    ///     comp::Cfg::DEFAULT.set_inverted(true),
+    /// );
+    ///
+    /// let in_window: bool = comp1.level() && comp2.level();
+    /// ```
+    #[inline]
+    pub fn window(&self) -> Window {
+        Window { _priv: () }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{sealed::Inp, Cfg, Csr, Hyst, PwrMode, Vref, Window};
+    use crate::gpio::{
+        pins::{B2, B4},
+        Analog,
+    };
+
+    #[test]
+    fn inp() {
+        assert_eq!(<Analog<B2> as Inp<1>>::INPSEL, 0b01);
+        assert!(!<Analog<B2> as Inp<1>>::WINMODE);
+        assert_eq!(<Analog<B4> as Inp<2>>::INPSEL, 0b00);
+        assert!(!<Analog<B4> as Inp<2>>::WINMODE);
+        assert_eq!(<Window as Inp<2>>::INPSEL, 0b00);
+        assert!(<Window as Inp<2>>::WINMODE);
+    }
+
+    #[test]
+    fn vref() {
+        let csr = |vref: Vref| Csr::new::<1, Analog<B2>, Vref>(Cfg::DEFAULT, &vref);
+
+        let quarter: Csr = csr(Vref::Quarter);
+        assert_eq!(quarter.inmsel, 0b000);
+        assert!(quarter.scalen && quarter.brgen);
+
+        let half: Csr = csr(Vref::Half);
+        assert_eq!(half.inmsel, 0b001);
+        assert!(half.scalen && half.brgen);
+
+        let three_quarter: Csr = csr(Vref::ThreeQuarter);
+        assert_eq!(three_quarter.inmsel, 0b010);
+        assert!(three_quarter.scalen && three_quarter.brgen);
+
+        let full: Csr = csr(Vref::Full);
+        assert_eq!(full.inmsel, 0b011);
+        assert!(full.scalen && !full.brgen);
+    }
+
+    #[test]
+    fn cfg() {
+        assert_eq!(
+            Csr::new::<1, Analog<B2>, Vref>(Cfg::DEFAULT, &Vref::Full),
+            Csr {
+                pwrmode: 0b00,
+                inmsel: 0b011,
+                inpsel: 0b01,
+                winmode: false,
+                polarity: false,
+                hyst: 0b00,
+                brgen: false,
+                scalen: true,
+            }
+        );
+
+        let cfg: Cfg = Cfg::DEFAULT
+            .set_hyst(Hyst::Medium)
+            .set_pwr_mode(PwrMode::UltraLow)
+            .set_inverted(true);
+        let csr: Csr = Csr::new::<2, Window, Vref>(cfg, &Vref::Half);
+        assert_eq!(
+            csr,
+            Csr {
+                pwrmode: 0b11,
+                inmsel: 0b001,
+                inpsel: 0b00,
+                winmode: true,
+                polarity: true,
+                hyst: 0b10,
+                brgen: true,
+                scalen: true,
+            }
+        );
+    }
+}
@@ -0,0 +1,669 @@
+//! Cyclic redundancy check calculation unit
+//!
+//! The CRC unit supports 7, 8, 16, and 32-bit polynomials, with a
+//! programmable initial value and optional bit reversal of the input and
+//! output data.
+//!
+//! Quickstart:
+//!
+//! * [`Crc::new`] with one of the [`Config`] presets
+//! * [`Crc::checksum`] to compute the CRC of a byte slice
+//!
+//! The CRC unit also implements [`core::hash::Hasher`] to feed data
+//! incrementally.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use stm32wlxx_hal::{
+//!     crc::{Config, Crc},
+//!     pac,
+//! };
+//!
+//! let mut dp: pac::Peripherals = pac::Peripherals::take().unwrap();
+//!
+//! let mut crc: Crc = Crc::new(dp.CRC, &Config::CRC32, &mut dp.RCC);
+//! assert_eq!(crc.checksum(b"123456789"), 0xCBF4_3926);
+//! ```
+
+use crate::pac;
+
+/// Polynomial size.
+#[repr(u8)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum PolySize {
+    /// 32-bit polynomial.
+    Bits32 = 0b00,
+    /// 16-bit polynomial.
+    Bits16 = 0b01,
+    /// 8-bit polynomial.
+    Bits8 = 0b10,
+    /// 7-bit polynomial.
+    Bits7 = 0b11,
+}
+
+impl PolySize {
+    /// Width of the polynomial in bits.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::crc::PolySize;
+    ///
+    /// assert_eq!(PolySize::Bits32.width(), 32);
+    /// assert_eq!(PolySize::Bits16.width(), 16);
+    /// assert_eq!(PolySize::Bits8.width(), 8);
+    /// assert_eq!(PolySize::Bits7.width(), 7);
+    /// ```
+    pub const fn width(&self) -> u8 {
+        match self {
+            PolySize::Bits32 => 32,
+            PolySize::Bits16 => 16,
+            PolySize::Bits8 => 8,
+            PolySize::Bits7 => 7,
+        }
+    }
+
+    /// Mask of the valid bits for the polynomial size.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::crc::PolySize;
+    ///
+    /// assert_eq!(PolySize::Bits32.mask(), 0xFFFF_FFFF);
+    /// assert_eq!(PolySize::Bits7.mask(), 0x7F);
+    /// ```
+    pub const fn mask(&self) -> u32 {
+        u32::MAX >> (32 - self.width())
+    }
+}
+
+impl From<PolySize> for pac::crc::cr::POLYSIZE_A {
+    fn from(size: PolySize) -> Self {
+        match size {
+            PolySize::Bits32 => pac::crc::cr::POLYSIZE_A::Polysize32,
+            PolySize::Bits16 => pac::crc::cr::POLYSIZE_A::Polysize16,
+            PolySize::Bits8 => pac::crc::cr::POLYSIZE_A::Polysize8,
+            PolySize::Bits7 => pac::crc::cr::POLYSIZE_A::Polysize7,
+        }
+    }
+}
+
+/// Input data bit reversal.
+#[repr(u8)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum RevIn {
+    /// Bit order not affected.
+    None = 0b00,
+    /// Bit reversal done by byte.
+    Byte = 0b01,
+    /// Bit reversal done by half-word.
+    HalfWord = 0b10,
+    /// Bit reversal done by word.
+    Word = 0b11,
+}
+
+impl From<RevIn> for pac::crc::cr::REV_IN_A {
+    fn from(rev_in: RevIn) -> Self {
+        match rev_in {
+            RevIn::None => pac::crc::cr::REV_IN_A::Normal,
+            RevIn::Byte => pac::crc::cr::REV_IN_A::Byte,
+            RevIn::HalfWord => pac::crc::cr::REV_IN_A::HalfWord,
+            RevIn::Word => pac::crc::cr::REV_IN_A::Word,
+        }
+    }
+}
+
+/// CRC configuration.
+///
+/// This follows the parameterization of the
+/// [CRC catalogue](https://reveng.sourceforge.io/crc-catalogue/), with the
+/// exception that the input reflection can be applied to half-words and words
+/// in addition to bytes.
+///
+/// The final XOR is not performed by the hardware, it is applied by
+/// [`Crc::result`].
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Config {
+    size: PolySize,
+    poly: u32,
+    init: u32,
+    rev_in: RevIn,
+    rev_out: bool,
+    xor_out: u32,
+}
+
+impl Config {
+    /// CRC-32/ISO-HDLC, the CRC used by ethernet and zip.
+    ///
+    /// Check value `0xCBF43926`.
+    pub const CRC32: Config = Config::new(PolySize::Bits32, 0x04C1_1DB7)
+        .set_init(0xFFFF_FFFF)
+        .set_rev_in(RevIn::Byte)
+        .set_rev_out(true)
+        .set_xor_out(0xFFFF_FFFF);
+
+    /// CRC-32/MPEG-2, the power-on-reset configuration of the hardware.
+    ///
+    /// Check value `0x0376E6E7`.
+    pub const CRC32_MPEG2: Config =
+        Config::new(PolySize::Bits32, 0x04C1_1DB7).set_init(0xFFFF_FFFF);
+
+    /// CRC-16/IBM-3740, also known as CRC-16/CCITT-FALSE.
+    ///
+    /// Check value `0x29B1`.
+    pub const CRC16_IBM_3740: Config = Config::new(PolySize::Bits16, 0x1021).set_init(0xFFFF);
+
+    /// CRC-16/XMODEM.
+    ///
+    /// Check value `0x31C3`.
+    pub const CRC16_XMODEM: Config = Config::new(PolySize::Bits16, 0x1021);
+
+    /// CRC-16/KERMIT, the CRC used by the IEEE 802.15.4 frame check sequence.
+    ///
+    /// Check value `0x2189`.
+    pub const CRC16_KERMIT: Config = Config::new(PolySize::Bits16, 0x1021)
+        .set_rev_in(RevIn::Byte)
+        .set_rev_out(true);
+
+    /// CRC-16/ARC.
+    ///
+    /// Check value `0xBB3D`.
+    pub const CRC16_ARC: Config = Config::new(PolySize::Bits16, 0x8005)
+        .set_rev_in(RevIn::Byte)
+        .set_rev_out(true);
+
+    /// CRC-8/SMBUS.
+    ///
+    /// Check value `0xF4`.
+    pub const CRC8_SMBUS: Config = Config::new(PolySize::Bits8, 0x07);
+
+    /// CRC-7/MMC, the CRC used by SD card commands.
+    ///
+    /// Check value `0x75`.
+    pub const CRC7_MMC: Config = Config::new(PolySize::Bits7, 0x09);
+
+    /// Create a new configuration from a polynomial.
+    ///
+    /// The polynomial is given in normal (non-reversed) representation,
+    /// without the implicit highest term.
+    /// Bits outside the polynomial size are masked.
+    ///
+    /// The initial value and final XOR are zero, and input and output
+    /// reflection are disabled.
+    ///
+    /// # Example
+    ///
+    /// CRC-16/GENIBUS.
+    ///
+    /// ```
+    /// use stm32wlxx_hal::crc::{Config, PolySize};
+    ///
+    /// const CRC16_GENIBUS: Config = Config::new(PolySize::Bits16, 0x1021)
+    ///     .set_init(0xFFFF)
+    ///     .set_xor_out(0xFFFF);
+    /// assert_eq!(CRC16_GENIBUS.compute_sw(b"123456789"), 0xD64E);
+    /// ```
+    pub const fn new(size: PolySize, poly: u32) -> Config {
+        Config {
+            size,
+            poly: poly & size.mask(),
+            init: 0,
+            rev_in: RevIn::None,
+            rev_out: false,
+            xor_out: 0,
+        }
+    }
+
+    /// Set the initial value.
+    ///
+    /// Bits outside the polynomial size are masked.
+    #[must_use = "set_init returns a modified Config"]
+    pub const fn set_init(mut self, init: u32) -> Config {
+        self.init = init & self.size.mask();
+        self
+    }
+
+    /// Set the input data bit reversal.
+    #[must_use = "set_rev_in returns a modified Config"]
+    pub const fn set_rev_in(mut self, rev_in: RevIn) -> Config {
+        self.rev_in = rev_in;
+        self
+    }
+
+    /// Set the output data bit reversal.
+    #[must_use = "set_rev_out returns a modified Config"]
+    pub const fn set_rev_out(mut self, rev_out: bool) -> Config {
+        self.rev_out = rev_out;
+        self
+    }
+
+    /// Set the value XOR'd with the result.
+    ///
+    /// Bits outside the polynomial size are masked.
+    #[must_use = "set_xor_out returns a modified Config"]
+    pub const fn set_xor_out(mut self, xor_out: u32) -> Config {
+        self.xor_out = xor_out & self.size.mask();
+        self
+    }
+
+    /// Get the polynomial size.
+    pub const fn size(&self) -> PolySize {
+        self.size
+    }
+
+    /// Get the polynomial.
+    pub const fn poly(&self) -> u32 {
+        self.poly
+    }
+
+    /// Get the initial value.
+    pub const fn init(&self) -> u32 {
+        self.init
+    }
+
+    /// Get the input data bit reversal.
+    pub const fn rev_in(&self) -> RevIn {
+        self.rev_in
+    }
+
+    /// Get the output data bit reversal.
+    pub const fn rev_out(&self) -> bool {
+        self.rev_out
+    }
+
+    /// Get the value XOR'd with the result.
+    pub const fn xor_out(&self) -> u32 {
+        self.xor_out
+    }
+
+    /// Write the control register fields for this configuration and reset
+    /// the calculation.
+    fn write_cr<'w>(&self, w: &'w mut pac::crc::cr::W) -> &'w mut pac::crc::cr::W {
+        w.polysize()
+            .variant(self.size.into())
+            .rev_in()
+            .variant(self.rev_in.into())
+            .rev_out()
+            .bit(self.rev_out)
+            .reset()
+            .reset()
+    }
+
+    /// Compute a CRC in software.
+    ///
+    /// This is a bitwise reference model of the hardware for byte input,
+    /// it is slow, and it is intended for validating configurations on the
+    /// host.
+    ///
+    /// For byte input all [`RevIn`] modes other than [`RevIn::None`] reflect
+    /// each byte.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::crc::Config;
+    ///
+    /// assert_eq!(Config::CRC32.compute_sw(b"123456789"), 0xCBF4_3926);
+    /// assert_eq!(Config::CRC8_SMBUS.compute_sw(b"123456789"), 0xF4);
+    /// ```
+    pub fn compute_sw(&self, data: &[u8]) -> u32 {
+        let width: u8 = self.size.width();
+        let mask: u32 = self.size.mask();
+        let top: u32 = 1 << (width - 1);
+
+        let mut crc: u32 = self.init;
+        for byte in data {
+            let byte: u8 = match self.rev_in {
+                RevIn::None => *byte,
+                _ => byte.reverse_bits(),
+            };
+
+            for bit in (0..8).rev() {
+                let input: bool = (byte >> bit) & 1 == 1;
+                let msb: bool = crc & top != 0;
+                crc = (crc << 1) & mask;
+                if input ^ msb {
+                    crc ^= self.poly;
+                }
+            }
+        }
+
+        self.finalize(crc)
+    }
+
+    /// Apply output reflection and the final XOR to a raw CRC register
+    /// value.
+    const fn finalize(&self, crc: u32) -> u32 {
+        let crc: u32 = if self.rev_out {
+            crc.reverse_bits() >> (32 - self.size.width())
+        } else {
+            crc
+        };
+        (crc ^ self.xor_out) & self.size.mask()
+    }
+}
+
+impl Default for Config {
+    /// Power-on-reset configuration, [`Config::CRC32_MPEG2`].
+    fn default() -> Self {
+        Config::CRC32_MPEG2
+    }
+}
+
+/// Cyclic redundancy check calculation unit driver.
+#[derive(Debug)]
+pub struct Crc {
+    crc: pac::CRC,
+    config: Config,
+}
+
+impl Crc {
+    /// Create a new CRC driver from a CRC peripheral.
+    ///
+    /// This will enable clocks, reset the CRC peripheral, and apply the
+    /// configuration.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use stm32wlxx_hal::{
+    ///     crc::{Config, Crc},
+    ///     pac,
+    /// };
+    ///
+    /// let mut dp: pac::Peripherals = pac::Peripherals::take().unwrap();
+    /// let crc: Crc = Crc::new(dp.CRC, &Config::CRC16_IBM_3740, &mut dp.RCC);
+    /// ```
+    pub fn new(crc: pac::CRC, config: &Config, rcc: &mut pac::RCC) -> Crc {
+        Self::enable_clock(rcc);
+        unsafe { Self::pulse_reset(rcc) };
+        let mut crc: Crc = Crc {
+            crc,
+            config: Config::default(),
+        };
+        crc.set_config(config);
+        crc
+    }
+
+    /// Free the CRC peripheral from the driver.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use stm32wlxx_hal::{
+    ///     crc::{Config, Crc},
+    ///     pac,
+    /// };
+    ///
+    /// let mut dp: pac::Peripherals = pac::Peripherals::take().unwrap();
+    /// let crc: Crc = Crc::new(dp.CRC, &Config::CRC32, &mut dp.RCC);
+    /// // ... use CRC
+    /// let crc: pac::CRC = crc.free();
+    /// ```
+    #[inline]
+    pub fn free(self) -> pac::CRC {
+        self.crc
+    }
+
+    /// Steal the CRC peripheral from whatever is currently using it.
+    ///
+    /// This will **not** initialize the CRC peripheral (unlike [`new`]).
+    ///
+    /// The configuration is assumed to be the power-on-reset configuration
+    /// without reading the hardware, use [`set_config`] to synchronize the
+    /// driver with the hardware.
+    ///
+    /// # Safety
+    ///
+    /// 1. Ensure that the code stealing the CRC has exclusive access to the
+    ///    peripheral. Singleton checks are bypassed with this method.
+    /// 2. You are responsible for setting up the CRC correctly.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::crc::Crc;
+    ///
+    /// // ... setup happens here
+    ///
+    /// let crc = unsafe { Crc::steal() };
+    /// ```
+    ///
+    /// [`new`]: Crc::new
+    /// [`set_config`]: Crc::set_config
+    #[inline]
+    pub unsafe fn steal() -> Crc {
+        Crc {
+            crc: pac::Peripherals::steal().CRC,
+            config: Config::default(),
+        }
+    }
+
+    /// Disable the CRC clock.
+    ///
+    /// # Safety
+    ///
+    /// 1. You are responsible for ensuring the CRC is in a state where the
+    ///    clock can be disabled without entering an error state.
+    /// 2. You cannot use the CRC while the clock is disabled.
+    /// 3. You are responsible for re-enabling the clock before resuming use
+    ///    of the CRC.
+    #[inline]
+    pub unsafe fn disable_clock(rcc: &mut pac::RCC) {
+        rcc.ahb1enr.modify(|_, w| w.crcen().disabled());
+    }
+
+    /// Enable the CRC clock.
+    ///
+    /// [`new`](crate::crc::Crc::new) will enable clocks for you.
+    #[inline]
+    pub fn enable_clock(rcc: &mut pac::RCC) {
+        rcc.ahb1enr.modify(|_, w| w.crcen().enabled());
+        rcc.ahb1enr.read(); // delay after an RCC peripheral clock enabling
+    }
+
+    /// Pulse the CRC peripheral reset.
+    ///
+    /// [`new`](crate::crc::Crc::new) will pulse reset for you.
+    ///
+    /// # Safety
+    ///
+    /// 1. Ensure nothing is using the CRC before calling this function.
+    /// 2. You are responsible for setting up the CRC after a reset.
+    #[inline]
+    pub unsafe fn pulse_reset(rcc: &mut pac::RCC) {
+        rcc.ahb1rstr.modify(|_, w| w.crcrst().set_bit());
+        rcc.ahb1rstr.modify(|_, w| w.crcrst().clear_bit());
+    }
+
+    /// Apply a new configuration.
+    ///
+    /// This will also reset the CRC calculation to the initial value.
+    pub fn set_config(&mut self, config: &Config) {
+        self.config = *config;
+        self.crc.pol.write(|w| w.bits(config.poly));
+        self.crc.init.write(|w| w.bits(config.init));
+        self.crc.cr.write(|w| config.write_cr(w));
+    }
+
+    /// Get the current configuration.
+    #[inline]
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Reset the CRC calculation to the initial value.
+    #[inline]
+    pub fn reset(&mut self) {
+        self.crc.cr.write(|w| self.config.write_cr(w))
+    }
+
+    /// Feed bytes into the CRC calculation.
+    pub fn feed_bytes(&mut self, data: &[u8]) {
+        data.iter()
+            .for_each(|byte| self.crc.dr8().write(|w| w.bits(*byte)))
+    }
+
+    /// Feed half-words into the CRC calculation.
+    ///
+    /// Each half-word is written to the data register as-is, the most
+    /// significant bit is processed first unless input reversal is enabled.
+    pub fn feed_half_words(&mut self, data: &[u16]) {
+        data.iter()
+            .for_each(|half_word| self.crc.dr16().write(|w| w.bits(*half_word)))
+    }
+
+    /// Feed words into the CRC calculation.
+    ///
+    /// Each word is written to the data register as-is, the most
+    /// significant bit is processed first unless input reversal is enabled.
+    ///
+    /// For reflected algorithms, such as [`Config::CRC32`], feeding a word
+    /// with [`RevIn::Word`] is equivalent to feeding its little-endian
+    /// bytes with [`RevIn::Byte`].
+    pub fn feed_words(&mut self, data: &[u32]) {
+        data.iter()
+            .for_each(|word| self.crc.dr().write(|w| w.bits(*word)))
+    }
+
+    /// Get the result of the CRC calculation.
+    ///
+    /// This applies the final XOR of the configuration, and does not reset
+    /// the calculation.
+    #[inline]
+    pub fn result(&self) -> u32 {
+        let raw: u32 = self.crc.dr().read().bits();
+        // the hardware performs the output reversal
+        (raw ^ self.config.xor_out) & self.config.size.mask()
+    }
+
+    /// Compute the CRC of a byte slice.
+    ///
+    /// This resets the calculation before feeding the data.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use stm32wlxx_hal::{
+    ///     crc::{Config, Crc},
+    ///     pac,
+    /// };
+    ///
+    /// let mut dp: pac::Peripherals = pac::Peripherals::take().unwrap();
+    /// let mut crc: Crc = Crc::new(dp.CRC, &Config::CRC16_ARC, &mut dp.RCC);
+    /// assert_eq!(crc.checksum(b"123456789"), 0xBB3D);
+    /// ```
+    pub fn checksum(&mut self, data: &[u8]) -> u32 {
+        self.reset();
+        self.feed_bytes(data);
+        self.result()
+    }
+
+    /// Read the independent data register.
+    ///
+    /// This is a 32-bit general purpose register that is not affected by
+    /// the CRC calculation or [`reset`](Self::reset).
+    #[inline]
+    pub fn idr(&self) -> u32 {
+        self.crc.idr.read().bits()
+    }
+
+    /// Write the independent data register.
+    #[inline]
+    pub fn set_idr(&mut self, idr: u32) {
+        // safety: any value is valid
+        self.crc.idr.write(|w| unsafe { w.bits(idr) })
+    }
+}
+
+impl core::hash::Hasher for Crc {
+    /// Returns the CRC of the bytes written so far.
+    #[inline]
+    fn finish(&self) -> u64 {
+        self.result().into()
+    }
+
+    #[inline]
+    fn write(&mut self, bytes: &[u8]) {
+        self.feed_bytes(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Config, PolySize, RevIn};
+
+    const CHECK: &[u8] = b"123456789";
+
+    #[test]
+    fn catalogue_check_values() {
+        assert_eq!(Config::CRC32.compute_sw(CHECK), 0xCBF4_3926);
+        assert_eq!(Config::CRC32_MPEG2.compute_sw(CHECK), 0x0376_E6E7);
+        assert_eq!(Config::CRC16_IBM_3740.compute_sw(CHECK), 0x29B1);
+        assert_eq!(Config::CRC16_XMODEM.compute_sw(CHECK), 0x31C3);
+        assert_eq!(Config::CRC16_KERMIT.compute_sw(CHECK), 0x2189);
+        assert_eq!(Config::CRC16_ARC.compute_sw(CHECK), 0xBB3D);
+        assert_eq!(Config::CRC8_SMBUS.compute_sw(CHECK), 0xF4);
+        assert_eq!(Config::CRC7_MMC.compute_sw(CHECK), 0x75);
+
+        // CRC-32/BZIP2
+        let bzip2: Config = Config::new(PolySize::Bits32, 0x04C1_1DB7)
+            .set_init(0xFFFF_FFFF)
+            .set_xor_out(0xFFFF_FFFF);
+        assert_eq!(bzip2.compute_sw(CHECK), 0xFC89_1918);
+
+        // CRC-8/MAXIM-DOW
+        let maxim: Config = Config::new(PolySize::Bits8, 0x31)
+            .set_rev_in(RevIn::Byte)
+            .set_rev_out(true);
+        assert_eq!(maxim.compute_sw(CHECK), 0xA1);
+    }
+
+    #[test]
+    fn empty() {
+        assert_eq!(Config::CRC32.compute_sw(&[]), 0);
+        assert_eq!(Config::CRC32_MPEG2.compute_sw(&[]), 0xFFFF_FFFF);
+    }
+
+    #[test]
+    fn cr_encoding() {
+        use crate::pac::crc::cr::{POLYSIZE_A, REV_IN_A};
+
+        assert_eq!(POLYSIZE_A::from(PolySize::Bits32), POLYSIZE_A::Polysize32);
+        assert_eq!(POLYSIZE_A::from(PolySize::Bits16), POLYSIZE_A::Polysize16);
+        assert_eq!(POLYSIZE_A::from(PolySize::Bits8), POLYSIZE_A::Polysize8);
+        assert_eq!(POLYSIZE_A::from(PolySize::Bits7), POLYSIZE_A::Polysize7);
+
+        assert_eq!(REV_IN_A::from(RevIn::None), REV_IN_A::Normal);
+        assert_eq!(REV_IN_A::from(RevIn::Byte), REV_IN_A::Byte);
+        assert_eq!(REV_IN_A::from(RevIn::HalfWord), REV_IN_A::HalfWord);
+        assert_eq!(REV_IN_A::from(RevIn::Word), REV_IN_A::Word);
+
+        for size in [
+            PolySize::Bits32,
+            PolySize::Bits16,
+            PolySize::Bits8,
+            PolySize::Bits7,
+        ] {
+            assert_eq!(u8::from(POLYSIZE_A::from(size)), size as u8);
+        }
+        for rev_in in [RevIn::None, RevIn::Byte, RevIn::HalfWord, RevIn::Word] {
+            assert_eq!(u8::from(REV_IN_A::from(rev_in)), rev_in as u8);
+        }
+    }
+
+    #[test]
+    fn masking() {
+        let config: Config = Config::new(PolySize::Bits7, 0xFFFF_FFFF)
+            .set_init(0xFFFF_FFFF)
+            .set_xor_out(0xFFFF_FFFF);
+        assert_eq!(config.poly(), 0x7F);
+        assert_eq!(config.init(), 0x7F);
+        assert_eq!(config.xor_out(), 0x7F);
+    }
+}
@@ -6,6 +6,8 @@
 //! 2. [`Flash::page_erase`]
 //! 3. [`Flash::program_bytes`]
 
+#[cfg(any(feature = "stm32wl5x_cm0p", feature = "stm32wl5x_cm4"))]
+use crate::hsem::{Hsem, Sem, SemGuard};
 use crate::pac;
 use core::{mem::size_of, ops::Range, ptr::write_volatile, slice::ChunksExact};
 use num_integer::Integer;
@@ -408,6 +410,9 @@ pub enum Error {
 #[derive(Debug)]
 pub struct Flash<'a> {
     flash: &'a mut pac::FLASH,
+    // released after the flash is locked in drop
+    #[cfg(any(feature = "stm32wl5x_cm0p", feature = "stm32wl5x_cm4"))]
+    _sem: Option<SemGuard<'a>>,
 }
 
 impl Drop for Flash<'_> {
@@ -435,7 +440,39 @@ impl<'a> Flash<'a> {
     pub fn unlock(flash: &'a mut pac::FLASH) -> Self {
         flash.keyr.write(|w| w.key().bits(0x4567_0123));
         flash.keyr.write(|w| w.key().bits(0xCDEF_89AB));
-        Self { flash }
+        Self {
+            flash,
+            #[cfg(any(feature = "stm32wl5x_cm0p", feature = "stm32wl5x_cm4"))]
+            _sem: None,
+        }
+    }
+
+    /// Unlock the flash memory for program or erase operations, holding the
+    /// [`Sem::FLASH`] hardware semaphore.
+    ///
+    /// This spins until the other core releases the semaphore.
+    /// The semaphore is released after the flash memory is locked when this
+    /// struct is dropped.
+    ///
+    /// Both cores must use this method to unlock the flash for the
+    /// semaphore to be effective.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use stm32wlxx_hal::{flash::Flash, hsem::Hsem, pac};
+    ///
+    /// let mut dp: pac::Peripherals = pac::Peripherals::take().unwrap();
+    /// let hsem: Hsem = Hsem::new(dp.HSEM, &mut dp.RCC);
+    ///
+    /// let mut flash: Flash = Flash::unlock_hsem(&mut dp.FLASH, &hsem);
+    /// ```
+    #[cfg(any(feature = "stm32wl5x_cm0p", feature = "stm32wl5x_cm4"))]
+    pub fn unlock_hsem(flash: &'a mut pac::FLASH, hsem: &'a Hsem) -> Self {
+        let sem: SemGuard = hsem.lock(Sem::FLASH, 0);
+        let mut ret: Self = Self::unlock(flash);
+        ret._sem = Some(sem);
+        ret
     }
 
     #[inline(always)]
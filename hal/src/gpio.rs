@@ -260,6 +260,20 @@ pub(crate) mod sealed {
     af_trait!(LpTim3Out, set_lptim3_out_af);
     af_trait!(LpTim3Etr, set_lptim3_etr_af);
     af_trait!(LpTim3In1, set_lptim3_in1_af);
+    af_trait!(Tim1Ch1, set_tim1_ch1_af);
+    af_trait!(Tim1Ch2, set_tim1_ch2_af);
+    af_trait!(Tim1Ch3, set_tim1_ch3_af);
+    af_trait!(Tim1Ch4, set_tim1_ch4_af);
+    af_trait!(Tim1Ch1n, set_tim1_ch1n_af);
+    af_trait!(Tim1Ch2n, set_tim1_ch2n_af);
+    af_trait!(Tim1Ch3n, set_tim1_ch3n_af);
+    af_trait!(Tim1Bkin, set_tim1_bkin_af);
+    af_trait!(Tim1Etr, set_tim1_etr_af);
+    af_trait!(Tim2Ch1, set_tim2_ch1_af);
+    af_trait!(Tim2Ch2, set_tim2_ch2_af);
+    af_trait!(Tim2Ch3, set_tim2_ch3_af);
+    af_trait!(Tim2Ch4, set_tim2_ch4_af);
+    af_trait!(Tim2Etr, set_tim2_etr_af);
     af_trait!(Tim16Ch1, set_tim16_ch1_af);
     af_trait!(Tim16Bkin, set_tim16_bkin_af);
     af_trait!(Tim16Ch1n, set_tim16_ch1n_af);
@@ -597,6 +611,30 @@ pub mod pins {
 
     impl_af!(Lsco, A2, set_lsco_af, 0);
 
+    impl_af!(Tim2Ch1, A0, set_tim2_ch1_af, 1);
+    impl_af!(Tim2Ch2, A1, set_tim2_ch2_af, 1);
+    impl_af!(Tim2Ch3, A2, set_tim2_ch3_af, 1);
+    impl_af!(Tim2Ch4, A3, set_tim2_ch4_af, 1);
+    impl_af!(Tim2Ch1, A5, set_tim2_ch1_af, 1);
+    impl_af!(Tim1Bkin, A6, set_tim1_bkin_af, 1);
+    impl_af!(Tim1Ch1n, A7, set_tim1_ch1n_af, 1);
+    impl_af!(Tim1Ch1, A8, set_tim1_ch1_af, 1);
+    impl_af!(Tim1Ch2, A9, set_tim1_ch2_af, 1);
+    impl_af!(Tim1Ch3, A10, set_tim1_ch3_af, 1);
+    impl_af!(Tim1Ch4, A11, set_tim1_ch4_af, 1);
+    impl_af!(Tim1Etr, A12, set_tim1_etr_af, 1);
+    impl_af!(Tim2Ch1, A15, set_tim2_ch1_af, 1);
+    impl_af!(Tim2Ch2, B3, set_tim2_ch2_af, 1);
+    impl_af!(Tim2Ch3, B10, set_tim2_ch3_af, 1);
+    impl_af!(Tim2Ch4, B11, set_tim2_ch4_af, 1);
+    impl_af!(Tim1Bkin, B12, set_tim1_bkin_af, 1);
+    impl_af!(Tim1Ch1n, B13, set_tim1_ch1n_af, 1);
+    impl_af!(Tim1Ch2n, B14, set_tim1_ch2n_af, 1);
+    impl_af!(Tim1Ch3n, B15, set_tim1_ch3n_af, 1);
+
+    impl_af!(Tim2Etr, A5, set_tim2_etr_af, 2);
+    impl_af!(Tim2Etr, A15, set_tim2_etr_af, 2);
+
     impl_af!(LpTim1Out, A4, set_lptim1_out_af, 1);
     impl_af!(LpTim1Out, A14, set_lptim1_out_af, 1);
     impl_af!(LpTim1Out, B2, set_lptim1_out_af, 1);
@@ -713,6 +751,7 @@ pub mod pins {
     impl_af!(LpTim2In1, C0, set_lptim2_in1_af, 14);
     impl_af!(LpTim2Etr, C3, set_lptim2_etr_af, 14);
 
+    impl_af!(Tim2Etr, A0, set_tim2_etr_af, 14);
     impl_af!(Tim16Ch1, A6, set_tim16_ch1_af, 14);
     impl_af!(Tim17Ch1, A7, set_tim17_ch1_af, 14);
     impl_af!(Tim17Bkin, A10, set_tim17_bkin_af, 14);
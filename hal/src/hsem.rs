@@ -0,0 +1,586 @@
+//! Hardware semaphore
+//!
+//! The HSEM provides 16 semaphores for arbitrating access to resources shared
+//! between the two cores, or between processes on the same core.
+//!
+//! A semaphore is locked by a core and a process ID, and it can only be
+//! released by the same core and process ID.
+//!
+//! * 1-step locking with [`Hsem::try_lock_1step`] uses a single read, the
+//!   process ID is always 0.
+//! * 2-step locking with [`Hsem::try_lock`] writes the process ID and reads
+//!   back the semaphore to confirm the lock.
+//!
+//! Locks are represented by a [`SemGuard`] which releases the semaphore when
+//! it is dropped.
+//!
+//! Semaphores [`Sem::FLASH`] and [`Sem::RCC`] are reserved by this crate, see
+//! [`Flash::unlock_hsem`](crate::flash::Flash::unlock_hsem) and
+//! [`rcc::set_sysclk_msi_max_hsem`](crate::rcc::set_sysclk_msi_max_hsem).
+//!
+//! # Example
+//!
+//! ```no_run
+//! use stm32wlxx_hal::{
+//!     hsem::{Hsem, Sem, SemGuard},
+//!     pac,
+//! };
+//!
+//! let mut dp: pac::Peripherals = pac::Peripherals::take().unwrap();
+//! let hsem: Hsem = Hsem::new(dp.HSEM, &mut dp.RCC);
+//!
+//! let my_sem: Sem = Sem::new(5).unwrap();
+//!
+//! {
+//!     let guard: SemGuard = hsem.lock(my_sem, 1);
+//!     // ... access the shared resource
+//! } // semaphore is released here
+//! ```
+
+use crate::pac;
+
+const R_LOCK: u32 = 1 << 31;
+const COREID_CPU1: u8 = 0x4;
+const COREID_CPU2: u8 = 0x8;
+const COREID: u8 = c1_c2!(COREID_CPU1, COREID_CPU2);
+
+/// Number of semaphores.
+pub const NUM_SEM: u8 = 16;
+
+const fn r_val(core: u8, pid: u8) -> u32 {
+    R_LOCK | ((core as u32) << 8) | (pid as u32)
+}
+
+const fn r_owner(val: u32) -> Option<Owner> {
+    if val & R_LOCK == 0 {
+        None
+    } else {
+        Some(Owner {
+            core: CoreId::from_bits(((val >> 8) & 0xF) as u8),
+            pid: val as u8,
+        })
+    }
+}
+
+const fn cr_val(core: u8, key: u16) -> u32 {
+    ((key as u32) << 16) | ((core as u32) << 8)
+}
+
+/// Semaphore ID.
+///
+/// # Example
+///
+/// ```
+/// use stm32wlxx_hal::hsem::Sem;
+///
+/// assert_eq!(Sem::new(15).map(Sem::id), Some(15));
+/// assert_eq!(Sem::new(16), None);
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone, Copy, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Sem(u8);
+
+impl Sem {
+    /// Semaphore used by this crate for flash program and erase operations.
+    pub const FLASH: Sem = Sem(2);
+
+    /// Semaphore used by this crate for clock configuration.
+    pub const RCC: Sem = Sem(3);
+
+    /// Create a semaphore ID.
+    ///
+    /// Returns `None` if `id` is greater than or equal to [`NUM_SEM`].
+    pub const fn new(id: u8) -> Option<Sem> {
+        if id < NUM_SEM {
+            Some(Sem(id))
+        } else {
+            None
+        }
+    }
+
+    /// Get the semaphore ID.
+    pub const fn id(self) -> u8 {
+        self.0
+    }
+
+    /// Semaphore bit for the interrupt registers.
+    pub const fn mask(self) -> u16 {
+        1 << self.0
+    }
+
+    const fn idx(self) -> usize {
+        self.0 as usize
+    }
+}
+
+/// Core owning a semaphore.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum CoreId {
+    /// CPU1, the Cortex-M4.
+    Cpu1,
+    /// CPU2, the Cortex-M0+.
+    Cpu2,
+    /// Unknown core ID.
+    Unknown(u8),
+}
+
+impl CoreId {
+    /// Core ID of the core running this code.
+    pub const CT: CoreId = c1_c2!(CoreId::Cpu1, CoreId::Cpu2);
+
+    const fn from_bits(bits: u8) -> CoreId {
+        match bits {
+            COREID_CPU1 => CoreId::Cpu1,
+            COREID_CPU2 => CoreId::Cpu2,
+            x => CoreId::Unknown(x),
+        }
+    }
+}
+
+/// Semaphore owner.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Owner {
+    /// Core that locked the semaphore.
+    pub core: CoreId,
+    /// Process ID that locked the semaphore.
+    pub pid: u8,
+}
+
+/// Locked semaphore.
+///
+/// The semaphore is released when this is dropped.
+#[derive(Debug)]
+#[must_use = "the semaphore is released when the guard is dropped"]
+pub struct SemGuard<'a> {
+    _hsem: &'a Hsem,
+    sem: Sem,
+    pid: u8,
+}
+
+impl SemGuard<'_> {
+    /// Get the locked semaphore.
+    #[inline]
+    pub fn sem(&self) -> Sem {
+        self.sem
+    }
+
+    /// Get the process ID used to lock the semaphore.
+    #[inline]
+    pub fn pid(&self) -> u8 {
+        self.pid
+    }
+}
+
+impl Drop for SemGuard<'_> {
+    fn drop(&mut self) {
+        // safety: this core and process own the semaphore
+        unsafe { Hsem::unlock(self.sem, self.pid) }
+    }
+}
+
+/// Hardware semaphore driver.
+#[derive(Debug)]
+pub struct Hsem {
+    hsem: pac::HSEM,
+}
+
+impl Hsem {
+    /// Create a new HSEM driver.
+    ///
+    /// This enables the HSEM clock.
+    ///
+    /// This does **not** reset the HSEM because the other core may already
+    /// be using it.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use stm32wlxx_hal::{hsem::Hsem, pac};
+    ///
+    /// let mut dp: pac::Peripherals = pac::Peripherals::take().unwrap();
+    /// let hsem: Hsem = Hsem::new(dp.HSEM, &mut dp.RCC);
+    /// ```
+    #[inline]
+    pub fn new(hsem: pac::HSEM, rcc: &mut pac::RCC) -> Self {
+        Self::enable_clock(rcc);
+        Self { hsem }
+    }
+
+    /// Free the HSEM peripheral from the driver.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use stm32wlxx_hal::{hsem::Hsem, pac};
+    ///
+    /// let mut dp: pac::Peripherals = pac::Peripherals::take().unwrap();
+    /// let hsem: Hsem = Hsem::new(dp.HSEM, &mut dp.RCC);
+    /// // ... use HSEM
+    /// let hsem: pac::HSEM = hsem.free();
+    /// ```
+    #[inline]
+    pub fn free(self) -> pac::HSEM {
+        self.hsem
+    }
+
+    /// Steal the HSEM peripheral from whatever is currently using it.
+    ///
+    /// This will **not** initialize the HSEM (unlike [`new`]).
+    ///
+    /// # Safety
+    ///
+    /// 1. Ensure that the code stealing the HSEM has exclusive access to the
+    ///    peripheral on this core.
+    ///    Singleton checks are bypassed with this method.
+    /// 2. You are responsible for enabling the HSEM clock before use.
+    ///
+    /// [`new`]: Hsem::new
+    #[inline]
+    pub unsafe fn steal() -> Self {
+        Self {
+            hsem: pac::Peripherals::steal().HSEM,
+        }
+    }
+
+    /// Reset the HSEM.
+    ///
+    /// # Safety
+    ///
+    /// 1. Ensure neither core is using the HSEM.
+    #[inline]
+    pub unsafe fn pulse_reset(rcc: &mut pac::RCC) {
+        rcc.ahb3rstr.modify(|_, w| w.hsemrst().set_bit());
+        rcc.ahb3rstr.modify(|_, w| w.hsemrst().clear_bit());
+    }
+
+    /// Disable the HSEM clock.
+    ///
+    /// # Safety
+    ///
+    /// 1. Ensure neither core is using the HSEM.
+    /// 2. You are responsible for re-enabling the clock before using the HSEM.
+    #[inline]
+    pub unsafe fn disable_clock(rcc: &mut pac::RCC) {
+        rcc.ahb3enr.modify(|_, w| w.hsemen().disabled());
+    }
+
+    /// Enable the HSEM clock.
+    ///
+    /// [`new`](Self::new) will enable clocks for you.
+    #[inline]
+    pub fn enable_clock(rcc: &mut pac::RCC) {
+        rcc.ahb3enr.modify(|_, w| w.hsemen().enabled());
+        rcc.ahb3enr.read(); // delay after an RCC peripheral clock enabling
+    }
+
+    /// Try to lock a semaphore with the 2-step procedure.
+    ///
+    /// Returns `None` if the semaphore is already locked, including when it
+    /// is locked by this core with the same process ID.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use stm32wlxx_hal::{
+    ///     hsem::{Hsem, Sem},
+    ///     pac,
+    /// };
+    ///
+    /// let mut dp: pac::Peripherals = pac::Peripherals::take().unwrap();
+    /// let hsem: Hsem = Hsem::new(dp.HSEM, &mut dp.RCC);
+    ///
+    /// let sem: Sem = Sem::new(7).unwrap();
+    /// if let Some(guard) = hsem.try_lock(sem, 3) {
+    ///     assert_eq!(guard.pid(), 3);
+    ///     // ... access the shared resource
+    /// };
+    /// ```
+    #[inline]
+    pub fn try_lock(&self, sem: Sem, pid: u8) -> Option<SemGuard<'_>> {
+        let val: u32 = r_val(COREID, pid);
+        let r = &self.hsem.r[sem.idx()];
+        if r.read().bits() & R_LOCK != 0 {
+            return None;
+        }
+        r.write(|w| unsafe { w.bits(val) });
+        if r.read().bits() == val {
+            Some(SemGuard {
+                _hsem: self,
+                sem,
+                pid,
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Try to lock a semaphore with the 1-step procedure.
+    ///
+    /// The process ID of the lock is always 0.
+    ///
+    /// Returns `None` if the semaphore is already locked, including when it
+    /// is locked by this core.
+    #[inline]
+    pub fn try_lock_1step(&self, sem: Sem) -> Option<SemGuard<'_>> {
+        if self.is_locked(sem) {
+            return None;
+        }
+        // reading RLR locks the semaphore if it is free
+        let val: u32 = self.hsem.rlr[sem.idx()].read().bits();
+        if val == r_val(COREID, 0) {
+            Some(SemGuard {
+                _hsem: self,
+                sem,
+                pid: 0,
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Lock a semaphore with the 2-step procedure, spinning until it is
+    /// available.
+    ///
+    /// # Example
+    ///
+    /// See the [module level documentation](self).
+    #[inline]
+    pub fn lock(&self, sem: Sem, pid: u8) -> SemGuard<'_> {
+        loop {
+            if let Some(guard) = self.try_lock(sem, pid) {
+                return guard;
+            }
+        }
+    }
+
+    /// Run a closure with a semaphore locked by this core.
+    ///
+    /// The semaphore is locked with the 2-step procedure and process ID 0,
+    /// spinning until it is available.
+    ///
+    /// # Example
+    ///
+    /// Switch the system clock on one core without the other core changing
+    /// the clock configuration at the same time.
+    ///
+    /// ```no_run
+    /// use stm32wlxx_hal::{
+    ///     hsem::{Hsem, Sem},
+    ///     pac, rcc,
+    /// };
+    ///
+    /// let mut dp: pac::Peripherals = pac::Peripherals::take().unwrap();
+    /// let hsem: Hsem = Hsem::new(dp.HSEM, &mut dp.RCC);
+    ///
+    /// hsem.with_lock(Sem::RCC, || {
+    ///     cortex_m::interrupt::free(|cs| unsafe {
+    ///         rcc::set_sysclk_msi_max(&mut dp.FLASH, &mut dp.PWR, &mut dp.RCC, cs)
+    ///     })
+    /// });
+    /// ```
+    #[inline]
+    pub fn with_lock<F, T>(&self, sem: Sem, f: F) -> T
+    where
+        F: FnOnce() -> T,
+    {
+        let _guard: SemGuard = self.lock(sem, 0);
+        f()
+    }
+
+    /// Returns `true` if the semaphore is locked by any core.
+    #[inline]
+    pub fn is_locked(&self, sem: Sem) -> bool {
+        self.hsem.r[sem.idx()].read().bits() & R_LOCK != 0
+    }
+
+    /// Get the owner of a semaphore.
+    ///
+    /// Returns `None` if the semaphore is free.
+    #[inline]
+    pub fn owner(&self, sem: Sem) -> Option<Owner> {
+        r_owner(self.hsem.r[sem.idx()].read().bits())
+    }
+
+    /// Release a semaphore without a guard.
+    ///
+    /// This does nothing if the semaphore is not locked by this core with
+    /// the given process ID.
+    ///
+    /// # Safety
+    ///
+    /// 1. The resource protected by the semaphore must not be in use by this
+    ///    core and process ID.
+    /// 2. A [`SemGuard`] for this semaphore must not exist, or it must be
+    ///    forgotten with [`core::mem::forget`].
+    #[inline]
+    pub unsafe fn unlock(sem: Sem, pid: u8) {
+        // the guard does not own the peripheral, access it through the
+        // pointer, writes to R only affect semaphores owned by this core
+        (*pac::HSEM::PTR).r[sem.idx()].write(|w| w.bits(r_val(COREID, pid) & !R_LOCK))
+    }
+
+    /// Set the key for [`unlock_all`](Self::unlock_all).
+    #[inline]
+    pub fn set_key(&mut self, key: u16) {
+        self.hsem
+            .keyr
+            .write(|w| unsafe { w.bits(u32::from(key) << 16) })
+    }
+
+    /// Release all semaphores locked by this core.
+    ///
+    /// Semaphores are only released if `key` matches the key set with
+    /// [`set_key`](Self::set_key).
+    ///
+    /// # Safety
+    ///
+    /// 1. The resources protected by the semaphores must not be in use by this
+    ///    core.
+    /// 2. All [`SemGuard`]s must be forgotten with [`core::mem::forget`].
+    #[inline]
+    pub unsafe fn unlock_all(&mut self, key: u16) {
+        self.hsem.cr.write(|w| w.bits(cr_val(COREID, key)))
+    }
+
+    /// Enable or disable the interrupts for when semaphores are released.
+    ///
+    /// Bits are from [`Sem::mask`].
+    ///
+    /// # Example
+    ///
+    /// Enable the interrupt for the release of [`Sem::FLASH`].
+    ///
+    /// ```no_run
+    /// use stm32wlxx_hal::{
+    ///     hsem::{Hsem, Sem},
+    ///     pac,
+    /// };
+    ///
+    /// let mut dp: pac::Peripherals = pac::Peripherals::take().unwrap();
+    /// let mut hsem: Hsem = Hsem::new(dp.HSEM, &mut dp.RCC);
+    /// hsem.set_irq_en(Sem::FLASH.mask());
+    /// ```
+    #[inline]
+    pub fn set_irq_en(&mut self, mask: u16) {
+        c1_c2!(
+            self.hsem
+                .c1ier
+                .write(|w| unsafe { w.bits(u32::from(mask)) }),
+            self.hsem
+                .c2ier
+                .write(|w| unsafe { w.bits(u32::from(mask)) })
+        )
+    }
+
+    /// Get the enabled release interrupts.
+    #[inline]
+    pub fn irq_en(&self) -> u16 {
+        c1_c2!(self.hsem.c1ier.read().bits(), self.hsem.c2ier.read().bits()) as u16
+    }
+
+    /// Get the release interrupt status, regardless of the enabled
+    /// interrupts.
+    #[inline]
+    pub fn isr(&self) -> u16 {
+        c1_c2!(self.hsem.c1isr.read().bits(), self.hsem.c2isr.read().bits()) as u16
+    }
+
+    /// Get the release interrupt status of the enabled interrupts.
+    #[inline]
+    pub fn masked_isr(&self) -> u16 {
+        c1_c2!(
+            self.hsem.c1misr.read().bits(),
+            self.hsem.c2misr.read().bits()
+        ) as u16
+    }
+
+    /// Clear release interrupt flags.
+    #[inline]
+    pub fn clear_isr(&mut self, mask: u16) {
+        c1_c2!(
+            self.hsem
+                .c1icr
+                .write(|w| unsafe { w.bits(u32::from(mask)) }),
+            self.hsem
+                .c2icr
+                .write(|w| unsafe { w.bits(u32::from(mask)) })
+        )
+    }
+
+    /// Unmask the HSEM IRQ in the NVIC.
+    ///
+    /// # Safety
+    ///
+    /// This can break mask-based critical sections.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # #[cfg(feature = "rt")]
+    /// unsafe { stm32wlxx_hal::hsem::Hsem::unmask_irq() };
+    /// ```
+    #[cfg(feature = "rt")]
+    #[inline]
+    pub unsafe fn unmask_irq() {
+        pac::NVIC::unmask(pac::Interrupt::HSEM)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{cr_val, r_owner, r_val, CoreId, Owner, Sem, COREID_CPU1, COREID_CPU2, R_LOCK};
+
+    #[test]
+    fn lock_val() {
+        assert_eq!(r_val(COREID_CPU1, 0), 0x8000_0400);
+        assert_eq!(r_val(COREID_CPU2, 0xA5), 0x8000_08A5);
+        assert_eq!(r_val(COREID_CPU1, 0xFF), 0x8000_04FF);
+    }
+
+    #[test]
+    fn unlock_val() {
+        // unlock writes the lock value with LOCK cleared
+        assert_eq!(r_val(COREID_CPU1, 3) & !R_LOCK, 0x0000_0403);
+        assert_eq!(r_val(COREID_CPU2, 7) & !R_LOCK, 0x0000_0807);
+        assert_eq!(cr_val(COREID_CPU1, 0xBEEF), 0xBEEF_0400);
+        assert_eq!(cr_val(COREID_CPU2, 0x0001), 0x0001_0800);
+    }
+
+    #[test]
+    fn owner() {
+        assert_eq!(r_owner(0), None);
+        assert_eq!(r_owner(r_val(COREID_CPU1, 3) & !R_LOCK), None);
+        assert_eq!(
+            r_owner(r_val(COREID_CPU1, 3)),
+            Some(Owner {
+                core: CoreId::Cpu1,
+                pid: 3
+            })
+        );
+        assert_eq!(
+            r_owner(r_val(COREID_CPU2, 0xFF)),
+            Some(Owner {
+                core: CoreId::Cpu2,
+                pid: 0xFF
+            })
+        );
+        assert_eq!(
+            r_owner(r_val(0x1, 0)),
+            Some(Owner {
+                core: CoreId::Unknown(0x1),
+                pid: 0
+            })
+        );
+    }
+
+    #[test]
+    fn sem() {
+        assert_eq!(Sem::new(0).map(Sem::mask), Some(0x0001));
+        assert_eq!(Sem::new(15).map(Sem::mask), Some(0x8000));
+        assert_eq!(Sem::new(16), None);
+        assert_eq!(Sem::FLASH.id(), 2);
+        assert_eq!(Sem::RCC.id(), 3);
+    }
+}
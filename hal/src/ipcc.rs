@@ -0,0 +1,699 @@
+//! Inter-processor communication controller
+//!
+//! The IPCC has 6 channels, each channel has one flag per direction.
+//! A core sets its transmit flag to signal that a message is *occupied*,
+//! and the other core clears the flag to signal that the channel is *free*.
+//! The messages themselves are exchanged in shared memory.
+//!
+//! Channels can be used in two modes:
+//!
+//! * Simplex, with [`Simplex`], each direction has its own flag and
+//!   mailbox, both cores can send at any time.
+//! * Half-duplex, with [`Requester`] and [`Responder`], a single flag and
+//!   mailbox are used for a request and the response to that request.
+//!
+//! # Shared memory
+//!
+//! Both cores must agree on the location and layout of the mailboxes.
+//! This is done by placing the mailboxes in a dedicated linker section at the
+//! same address in the linker script of both binaries, for example with the
+//! last 1 KiB of SRAM2:
+//!
+//! ```text
+//! MEMORY
+//! {
+//!   /* ... */
+//!   IPCC_SHARED : ORIGIN = 0x2000FC00, LENGTH = 1K
+//! }
+//!
+//! SECTIONS
+//! {
+//!   .ipcc_mailbox (NOLOAD) : { *(.ipcc_mailbox .ipcc_mailbox.*) } > IPCC_SHARED
+//! }
+//! ```
+//!
+//! The section must be `NOLOAD`, otherwise the startup code of each core
+//! would overwrite messages from the other core.
+//!
+//! # Example
+//!
+//! Ping-pong on channel 1, the CM0+ binary is identical except that it
+//! sends on `pong` and receives on `ping`.
+//!
+//! ```no_run
+//! use stm32wlxx_hal::{
+//!     ipcc::{Ipcc, Mailbox, Simplex},
+//!     pac,
+//! };
+//!
+//! #[repr(C)]
+//! struct Shared {
+//!     ping: Mailbox<u32>,
+//!     pong: Mailbox<u32>,
+//! }
+//!
+//! #[link_section = ".ipcc_mailbox"]
+//! static mut SHARED: Shared = Shared {
+//!     ping: Mailbox::new(),
+//!     pong: Mailbox::new(),
+//! };
+//!
+//! let mut dp: pac::Peripherals = pac::Peripherals::take().unwrap();
+//! let ipcc: Ipcc = Ipcc::new(dp.IPCC, &mut dp.RCC);
+//!
+//! // safety:
+//! // * the mailboxes are only borrowed once
+//! // * the other core uses the same types at the same address
+//! let mut c1: Simplex<1, u32, u32> =
+//!     unsafe { ipcc.c1.into_simplex(&mut SHARED.ping, &mut SHARED.pong) };
+//!
+//! nb::block!(c1.send(0x1234_5678)).unwrap();
+//! let pong: u32 = nb::block!(c1.recv()).unwrap();
+//! ```
+
+use crate::pac;
+use core::{
+    cell::UnsafeCell,
+    mem::MaybeUninit,
+    sync::atomic::{compiler_fence, Ordering::SeqCst},
+};
+use void::Void;
+
+// The channels are owned separately from the IPCC driver, they access the
+// registers through the pointer.
+// safety: all IPCC registers can be read without side effects, and writes to
+// the shared mask register are done in a critical section
+#[inline(always)]
+fn regs() -> &'static pac::ipcc::RegisterBlock {
+    unsafe { &*pac::IPCC::PTR }
+}
+
+// processor 1 to processor 2 status, the register name differs between the
+// PAC for each core
+#[inline(always)]
+fn c1_to_c2_sr() -> u32 {
+    cfg_if::cfg_if! {
+        if #[cfg(feature = "stm32wl5x_cm0p")] {
+            regs().c1toc2sr.read().bits()
+        } else {
+            regs().ic1toc2sr.read().bits()
+        }
+    }
+}
+
+// flags set by this core
+#[inline(always)]
+fn tx_sr() -> u32 {
+    c1_c2!(c1_to_c2_sr(), regs().c2toc1sr.read().bits())
+}
+
+// flags set by the other core
+#[inline(always)]
+fn rx_sr() -> u32 {
+    c1_c2!(regs().c2toc1sr.read().bits(), c1_to_c2_sr())
+}
+
+#[inline(always)]
+fn write_scr(scr: u32) {
+    // safety: the set and clear bits only affect the channel they are written
+    // for, writing 0 has no effect
+    c1_c2!(
+        regs().c1scr.write(|w| unsafe { w.bits(scr) }),
+        regs().c2scr.write(|w| unsafe { w.bits(scr) })
+    )
+}
+
+/// Shared memory mailbox.
+///
+/// This should be placed in the shared linker section, see the
+/// [module level documentation](self) for details.
+///
+/// The mailbox contents are only accessed through a channel that owns the
+/// mailbox, the IPCC flags arbitrate access between the two cores.
+#[repr(transparent)]
+pub struct Mailbox<T> {
+    buf: UnsafeCell<MaybeUninit<T>>,
+}
+
+// safety: accesses are arbitrated by the IPCC channel owning the mailbox
+unsafe impl<T: Send> Sync for Mailbox<T> {}
+
+impl<T> Mailbox<T> {
+    /// Create a new mailbox.
+    ///
+    /// The contents are uninitialized until the first message is sent.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::ipcc::Mailbox;
+    ///
+    /// #[link_section = ".ipcc_mailbox"]
+    /// static mut MAILBOX: Mailbox<[u8; 16]> = Mailbox::new();
+    /// ```
+    pub const fn new() -> Self {
+        Self {
+            buf: UnsafeCell::new(MaybeUninit::uninit()),
+        }
+    }
+
+    #[inline]
+    fn write(&mut self, msg: T) {
+        unsafe { (*self.buf.get()).as_mut_ptr().write_volatile(msg) };
+        // make the message visible to the other core before the flag
+        compiler_fence(SeqCst);
+        cortex_m::asm::dmb();
+    }
+
+    /// # Safety
+    ///
+    /// The other core must have written a message.
+    #[inline]
+    unsafe fn read(&self) -> T {
+        cortex_m::asm::dmb();
+        compiler_fence(SeqCst);
+        (*self.buf.get()).as_ptr().read_volatile()
+    }
+}
+
+impl<T> Default for Mailbox<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> core::fmt::Debug for Mailbox<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Mailbox")
+            .field("addr", &self.buf.get())
+            .finish()
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl<T> defmt::Format for Mailbox<T> {
+    fn format(&self, fmt: defmt::Formatter) {
+        defmt::write!(fmt, "Mailbox {{ addr: {} }}", self.buf.get())
+    }
+}
+
+/// IPCC channel.
+///
+/// `N` is the channel number, from 1 to 6.
+///
+/// This provides raw access to the channel flags, convert it to a
+/// [`Simplex`], [`Requester`], or [`Responder`] to exchange messages.
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Channel<const N: u8> {
+    _priv: (),
+}
+
+impl<const N: u8> Channel<N> {
+    // status flag, clear bit, and receive occupied mask bit
+    const FLAG: u32 = 1 << (N - 1);
+    // set bit and transmit free mask bit
+    const SET: u32 = 1 << (N + 15);
+
+    /// Steal the IPCC channel.
+    ///
+    /// # Safety
+    ///
+    /// 1. Ensure that the code stealing the channel has exclusive access to
+    ///    the channel on this core.
+    ///    Singleton checks are bypassed with this method.
+    /// 2. `N` must be in the range 1 to 6.
+    #[inline]
+    pub const unsafe fn steal() -> Self {
+        Self { _priv: () }
+    }
+
+    /// Returns `true` if the transmit flag is free.
+    ///
+    /// The transmit flag is set by this core with
+    /// [`set_tx_occupied`](Self::set_tx_occupied) and cleared by the other
+    /// core.
+    #[inline]
+    pub fn is_tx_free(&self) -> bool {
+        tx_sr() & Self::FLAG == 0
+    }
+
+    /// Set the transmit flag to occupied.
+    #[inline]
+    pub fn set_tx_occupied(&mut self) {
+        write_scr(Self::SET)
+    }
+
+    /// Returns `true` if the receive flag is occupied.
+    ///
+    /// The receive flag is set by the other core and cleared by this core
+    /// with [`clear_rx`](Self::clear_rx).
+    #[inline]
+    pub fn is_rx_occupied(&self) -> bool {
+        rx_sr() & Self::FLAG != 0
+    }
+
+    /// Clear the receive flag, setting it to free.
+    #[inline]
+    pub fn clear_rx(&mut self) {
+        write_scr(Self::FLAG)
+    }
+
+    /// Enable or disable the transmit free interrupt for this channel.
+    ///
+    /// The interrupt fires when the other core clears the transmit flag.
+    #[inline]
+    pub fn set_tx_free_irq_en(&mut self, en: bool) {
+        // the mask bits are set to disable the interrupt
+        modify_mr(Self::SET, !en)
+    }
+
+    /// Enable or disable the receive occupied interrupt for this channel.
+    ///
+    /// The interrupt fires when the other core sets the receive flag.
+    #[inline]
+    pub fn set_rx_occupied_irq_en(&mut self, en: bool) {
+        modify_mr(Self::FLAG, !en)
+    }
+
+    /// Use the channel in simplex mode.
+    ///
+    /// # Safety
+    ///
+    /// 1. The other core must use this channel in simplex mode, with the same
+    ///    mailboxes in reverse order.
+    /// 2. The mailbox types must be identical on both cores, and the
+    ///    mailboxes must be located at the same address on both cores.
+    /// 3. `T` and `R` must be valid for any message the other core can send.
+    ///
+    /// # Example
+    ///
+    /// See the [module level documentation](self).
+    #[inline]
+    pub unsafe fn into_simplex<T: Copy, R: Copy>(
+        self,
+        tx: &'static mut Mailbox<T>,
+        rx: &'static mut Mailbox<R>,
+    ) -> Simplex<N, T, R> {
+        Simplex { ch: self, tx, rx }
+    }
+
+    /// Use the channel in half-duplex mode, as the core sending requests.
+    ///
+    /// # Safety
+    ///
+    /// 1. The other core must use this channel with
+    ///    [`into_responder`](Self::into_responder) and the same mailbox.
+    /// 2. The mailbox type must be identical on both cores, and the mailbox
+    ///    must be located at the same address on both cores.
+    /// 3. `T` must be valid for any message the other core can send.
+    #[inline]
+    pub unsafe fn into_requester<T: Copy>(self, mb: &'static mut Mailbox<T>) -> Requester<N, T> {
+        Requester {
+            ch: self,
+            mb,
+            pending: false,
+        }
+    }
+
+    /// Use the channel in half-duplex mode, as the core sending responses.
+    ///
+    /// # Safety
+    ///
+    /// 1. The other core must use this channel with
+    ///    [`into_requester`](Self::into_requester) and the same mailbox.
+    /// 2. The mailbox type must be identical on both cores, and the mailbox
+    ///    must be located at the same address on both cores.
+    /// 3. `T` must be valid for any message the other core can send.
+    #[inline]
+    pub unsafe fn into_responder<T: Copy>(self, mb: &'static mut Mailbox<T>) -> Responder<N, T> {
+        Responder { ch: self, mb }
+    }
+}
+
+fn modify_mr(bit: u32, set: bool) {
+    let f = |mr: u32| if set { mr | bit } else { mr & !bit };
+    // safety: only the bit for the channel is modified
+    cortex_m::interrupt::free(|_| {
+        c1_c2!(
+            regs().c1mr.modify(|r, w| unsafe { w.bits(f(r.bits())) }),
+            regs().c2mr.modify(|r, w| unsafe { w.bits(f(r.bits())) })
+        )
+    })
+}
+
+/// Simplex channel.
+///
+/// Each direction has its own flag and mailbox, messages of type `T` are sent
+/// to the other core, and messages of type `R` are received from the other
+/// core.
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Simplex<const N: u8, T: 'static, R: 'static> {
+    ch: Channel<N>,
+    tx: &'static mut Mailbox<T>,
+    rx: &'static mut Mailbox<R>,
+}
+
+impl<const N: u8, T: Copy, R: Copy> Simplex<N, T, R> {
+    /// Free the channel and mailboxes.
+    #[inline]
+    #[allow(clippy::type_complexity)]
+    pub fn free(self) -> (Channel<N>, &'static mut Mailbox<T>, &'static mut Mailbox<R>) {
+        (self.ch, self.tx, self.rx)
+    }
+
+    /// Send a message to the other core.
+    ///
+    /// Returns [`nb::Error::WouldBlock`] if the other core has not yet
+    /// received the previous message.
+    #[inline]
+    pub fn send(&mut self, msg: T) -> nb::Result<(), Void> {
+        if self.ch.is_tx_free() {
+            self.tx.write(msg);
+            self.ch.set_tx_occupied();
+            Ok(())
+        } else {
+            Err(nb::Error::WouldBlock)
+        }
+    }
+
+    /// Receive a message from the other core.
+    ///
+    /// Returns [`nb::Error::WouldBlock`] if there is no message.
+    #[inline]
+    pub fn recv(&mut self) -> nb::Result<R, Void> {
+        if self.ch.is_rx_occupied() {
+            // safety: the other core wrote a message before setting the flag
+            let msg: R = unsafe { self.rx.read() };
+            self.ch.clear_rx();
+            Ok(msg)
+        } else {
+            Err(nb::Error::WouldBlock)
+        }
+    }
+
+    /// Enable or disable the interrupt for when the other core has received
+    /// the last message.
+    #[inline]
+    pub fn set_tx_free_irq_en(&mut self, en: bool) {
+        self.ch.set_tx_free_irq_en(en)
+    }
+
+    /// Enable or disable the interrupt for when a message has been received.
+    #[inline]
+    pub fn set_rx_occupied_irq_en(&mut self, en: bool) {
+        self.ch.set_rx_occupied_irq_en(en)
+    }
+}
+
+/// Half-duplex channel sending requests.
+///
+/// The request and response share a single flag and mailbox.
+/// The flag is set by this core when sending a request, and cleared by the
+/// other core when the response is written.
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Requester<const N: u8, T: 'static> {
+    ch: Channel<N>,
+    mb: &'static mut Mailbox<T>,
+    pending: bool,
+}
+
+impl<const N: u8, T: Copy> Requester<N, T> {
+    /// Free the channel and mailbox.
+    #[inline]
+    pub fn free(self) -> (Channel<N>, &'static mut Mailbox<T>) {
+        (self.ch, self.mb)
+    }
+
+    /// Send a request to the other core.
+    ///
+    /// Returns [`nb::Error::WouldBlock`] if the response to the previous
+    /// request has not been received with [`response`](Self::response).
+    #[inline]
+    pub fn request(&mut self, msg: T) -> nb::Result<(), Void> {
+        if !self.pending && self.ch.is_tx_free() {
+            self.mb.write(msg);
+            self.ch.set_tx_occupied();
+            self.pending = true;
+            Ok(())
+        } else {
+            Err(nb::Error::WouldBlock)
+        }
+    }
+
+    /// Receive the response to the last request.
+    ///
+    /// Returns [`nb::Error::WouldBlock`] if the other core has not yet
+    /// responded, or if there is no pending request.
+    #[inline]
+    pub fn response(&mut self) -> nb::Result<T, Void> {
+        if self.pending && self.ch.is_tx_free() {
+            self.pending = false;
+            // safety: the other core wrote a response before clearing the flag
+            Ok(unsafe { self.mb.read() })
+        } else {
+            Err(nb::Error::WouldBlock)
+        }
+    }
+
+    /// Returns `true` if a request is waiting for a response.
+    #[inline]
+    pub fn is_pending(&self) -> bool {
+        self.pending
+    }
+
+    /// Enable or disable the interrupt for when the response is ready.
+    #[inline]
+    pub fn set_response_irq_en(&mut self, en: bool) {
+        self.ch.set_tx_free_irq_en(en)
+    }
+}
+
+/// Half-duplex channel sending responses.
+///
+/// The request and response share a single flag and mailbox.
+/// The flag is set by the other core when sending a request, and cleared by
+/// this core when the response is written.
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Responder<const N: u8, T: 'static> {
+    ch: Channel<N>,
+    mb: &'static mut Mailbox<T>,
+}
+
+impl<const N: u8, T: Copy> Responder<N, T> {
+    /// Free the channel and mailbox.
+    #[inline]
+    pub fn free(self) -> (Channel<N>, &'static mut Mailbox<T>) {
+        (self.ch, self.mb)
+    }
+
+    /// Receive a request from the other core.
+    ///
+    /// Returns [`nb::Error::WouldBlock`] if there is no request.
+    ///
+    /// The request must be answered with [`respond`](Self::respond).
+    #[inline]
+    pub fn request(&self) -> nb::Result<T, Void> {
+        if self.ch.is_rx_occupied() {
+            // safety: the other core wrote a request before setting the flag
+            Ok(unsafe { self.mb.read() })
+        } else {
+            Err(nb::Error::WouldBlock)
+        }
+    }
+
+    /// Respond to the current request.
+    ///
+    /// Returns [`nb::Error::WouldBlock`] if there is no request.
+    #[inline]
+    pub fn respond(&mut self, msg: T) -> nb::Result<(), Void> {
+        if self.ch.is_rx_occupied() {
+            self.mb.write(msg);
+            self.ch.clear_rx();
+            Ok(())
+        } else {
+            Err(nb::Error::WouldBlock)
+        }
+    }
+
+    /// Enable or disable the interrupt for when a request is received.
+    #[inline]
+    pub fn set_request_irq_en(&mut self, en: bool) {
+        self.ch.set_rx_occupied_irq_en(en)
+    }
+}
+
+/// Inter-processor communication controller driver.
+///
+/// The driver for this core owns the channels, the other core has a driver of
+/// its own.
+#[derive(Debug)]
+pub struct Ipcc {
+    ipcc: pac::IPCC,
+    /// Channel 1.
+    pub c1: Channel<1>,
+    /// Channel 2.
+    pub c2: Channel<2>,
+    /// Channel 3.
+    pub c3: Channel<3>,
+    /// Channel 4.
+    pub c4: Channel<4>,
+    /// Channel 5.
+    pub c5: Channel<5>,
+    /// Channel 6.
+    pub c6: Channel<6>,
+}
+
+impl Ipcc {
+    /// Create a new IPCC driver.
+    ///
+    /// This enables the IPCC clock and the IPCC interrupts for this core,
+    /// the interrupts for each channel are masked by default.
+    ///
+    /// This does **not** reset the IPCC because the other core may already
+    /// be using it.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use stm32wlxx_hal::{ipcc::Ipcc, pac};
+    ///
+    /// let mut dp: pac::Peripherals = pac::Peripherals::take().unwrap();
+    /// let ipcc: Ipcc = Ipcc::new(dp.IPCC, &mut dp.RCC);
+    /// ```
+    #[inline]
+    pub fn new(ipcc: pac::IPCC, rcc: &mut pac::RCC) -> Self {
+        Self::enable_clock(rcc);
+        c1_c2!(
+            ipcc.c1cr.write(|w| w.rxoie().enabled().txfie().enabled()),
+            ipcc.c2cr.write(|w| w.rxoie().enabled().txfie().enabled())
+        );
+        Self {
+            ipcc,
+            // safety: the channels are owned by the driver
+            c1: unsafe { Channel::steal() },
+            c2: unsafe { Channel::steal() },
+            c3: unsafe { Channel::steal() },
+            c4: unsafe { Channel::steal() },
+            c5: unsafe { Channel::steal() },
+            c6: unsafe { Channel::steal() },
+        }
+    }
+
+    /// Free the IPCC peripheral from the driver.
+    ///
+    /// The channels must be returned to the driver before freeing it, the
+    /// interrupts for this core are left enabled.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use stm32wlxx_hal::{ipcc::Ipcc, pac};
+    ///
+    /// let mut dp: pac::Peripherals = pac::Peripherals::take().unwrap();
+    /// let ipcc: Ipcc = Ipcc::new(dp.IPCC, &mut dp.RCC);
+    /// // ... use IPCC
+    /// let ipcc: pac::IPCC = ipcc.free();
+    /// ```
+    #[inline]
+    pub fn free(self) -> pac::IPCC {
+        self.ipcc
+    }
+
+    /// Steal the IPCC peripheral from whatever is currently using it.
+    ///
+    /// This will **not** initialize the IPCC (unlike [`new`]).
+    ///
+    /// # Safety
+    ///
+    /// 1. Ensure that the code stealing the IPCC has exclusive access to the
+    ///    channels on this core.
+    ///    Singleton checks are bypassed with this method.
+    /// 2. You are responsible for enabling the IPCC clock and the interrupts
+    ///    for this core before use.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use stm32wlxx_hal::ipcc::Ipcc;
+    ///
+    /// // ... setup happens here
+    ///
+    /// let ipcc: Ipcc = unsafe { Ipcc::steal() };
+    /// ```
+    ///
+    /// [`new`]: Ipcc::new
+    #[inline]
+    pub unsafe fn steal() -> Self {
+        Self {
+            ipcc: pac::Peripherals::steal().IPCC,
+            c1: Channel::steal(),
+            c2: Channel::steal(),
+            c3: Channel::steal(),
+            c4: Channel::steal(),
+            c5: Channel::steal(),
+            c6: Channel::steal(),
+        }
+    }
+
+    /// Reset the IPCC.
+    ///
+    /// # Safety
+    ///
+    /// 1. Ensure neither core is using the IPCC.
+    #[inline]
+    pub unsafe fn pulse_reset(rcc: &mut pac::RCC) {
+        rcc.ahb3rstr.modify(|_, w| w.ipccrst().set_bit());
+        rcc.ahb3rstr.modify(|_, w| w.ipccrst().clear_bit());
+    }
+
+    /// Disable the IPCC clock.
+    ///
+    /// # Safety
+    ///
+    /// 1. Ensure neither core is using the IPCC.
+    /// 2. You are responsible for re-enabling the clock before using the IPCC.
+    #[inline]
+    pub unsafe fn disable_clock(rcc: &mut pac::RCC) {
+        rcc.ahb3enr.modify(|_, w| w.ipccen().disabled());
+    }
+
+    /// Enable the IPCC clock.
+    ///
+    /// [`new`](Self::new) will enable clocks for you.
+    #[inline]
+    pub fn enable_clock(rcc: &mut pac::RCC) {
+        rcc.ahb3enr.modify(|_, w| w.ipccen().enabled());
+        rcc.ahb3enr.read(); // delay after an RCC peripheral clock enabling
+    }
+
+    /// Unmask the IPCC receive occupied and transmit free IRQs in the NVIC.
+    ///
+    /// On the CM0+ both IRQs share the `IPCC_C2_RX_IT_IPCC_C2_TX_IT`
+    /// interrupt.
+    ///
+    /// # Safety
+    ///
+    /// This can break mask-based critical sections.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # #[cfg(feature = "rt")]
+    /// unsafe { stm32wlxx_hal::ipcc::Ipcc::unmask_irq() };
+    /// ```
+    #[cfg(feature = "rt")]
+    #[inline]
+    pub unsafe fn unmask_irq() {
+        c1_c2!(
+            {
+                pac::NVIC::unmask(pac::Interrupt::IPCC_C1_RX_IT);
+                pac::NVIC::unmask(pac::Interrupt::IPCC_C1_TX_IT);
+            },
+            pac::NVIC::unmask(pac::Interrupt::IPCC_C2_RX_IT_IPCC_C2_TX_IT)
+        )
+    }
+}
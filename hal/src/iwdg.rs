@@ -0,0 +1,559 @@
+//! Independent watchdog
+//!
+//! The independent watchdog is clocked by the LSI, it keeps running when the
+//! main clock fails, and once it has been started it can only be stopped by a
+//! reset.
+//!
+//! Quickstart:
+//!
+//! 1. Compute a [`Config`] from a timeout with [`Config::new`]
+//! 2. [`Iwdg::start`]
+//! 3. [`Iwdg::feed`] periodically
+//!
+//! # Example
+//!
+//! ```no_run
+//! use core::time::Duration;
+//! use stm32wlxx_hal::{
+//!     iwdg::{Config, Iwdg},
+//!     pac,
+//!     rcc::lsi_hz,
+//! };
+//!
+//! let dp: pac::Peripherals = pac::Peripherals::take().unwrap();
+//!
+//! let config: Config = Config::new(lsi_hz(&dp.RCC), Duration::from_millis(500))?;
+//!
+//! let mut iwdg: Iwdg = Iwdg::new(dp.IWDG);
+//! iwdg.start(config);
+//!
+//! loop {
+//!     // ... do work
+//!     iwdg.feed();
+//! }
+//! # Ok::<(), stm32wlxx_hal::iwdg::Error>(())
+//! ```
+
+use crate::pac;
+use core::{ptr::write_volatile, time::Duration};
+
+// key register (KR) values
+const KEY_RELOAD: u32 = 0xAAAA;
+const KEY_UNLOCK: u32 = 0x5555;
+const KEY_START: u32 = 0xCCCC;
+
+// status register (SR) flags
+const SR_PVU: u32 = 1 << 0;
+const SR_RVU: u32 = 1 << 1;
+const SR_WVU: u32 = 1 << 2;
+
+// window register (WINR) offset from the IWDG base address
+const WINR_OFFSET: usize = 0x10;
+
+// option byte register (OPTR) bits
+const OPTR_IWDG_SW: u32 = 1 << 16;
+const OPTR_IWDG_STOP: u32 = 1 << 17;
+const OPTR_IWDG_STDBY: u32 = 1 << 18;
+
+const NANOS_PER_SEC: u128 = 1_000_000_000;
+
+/// Independent watchdog configuration errors.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Error {
+    /// The timeout is shorter than a single tick of the watchdog with the
+    /// smallest prescaler.
+    TooShort,
+    /// The timeout is longer than the maximum reload value with the largest
+    /// prescaler.
+    TooLong,
+    /// The window is longer than the timeout.
+    Window,
+}
+
+/// Independent watchdog prescaler.
+#[repr(u8)]
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Prescaler {
+    /// LSI divided by 4.
+    Div4 = 0b000,
+    /// LSI divided by 8.
+    Div8 = 0b001,
+    /// LSI divided by 16.
+    Div16 = 0b010,
+    /// LSI divided by 32.
+    Div32 = 0b011,
+    /// LSI divided by 64.
+    Div64 = 0b100,
+    /// LSI divided by 128.
+    Div128 = 0b101,
+    /// LSI divided by 256.
+    Div256 = 0b110,
+}
+
+impl Prescaler {
+    const ALL: [Prescaler; 7] = [
+        Prescaler::Div4,
+        Prescaler::Div8,
+        Prescaler::Div16,
+        Prescaler::Div32,
+        Prescaler::Div64,
+        Prescaler::Div128,
+        Prescaler::Div256,
+    ];
+
+    /// Get the divisor of the prescaler.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::iwdg::Prescaler;
+    ///
+    /// assert_eq!(Prescaler::Div4.div(), 4);
+    /// assert_eq!(Prescaler::Div256.div(), 256);
+    /// ```
+    pub const fn div(&self) -> u16 {
+        4 << (*self as u8)
+    }
+}
+
+/// Independent watchdog configuration.
+///
+/// This is the register encoding of a timeout (and optional window), use
+/// [`Config::new`] to compute it from a [`Duration`].
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Config {
+    pre: Prescaler,
+    reload: u16,
+    window: u16,
+}
+
+impl Config {
+    /// Maximum reload value.
+    pub const MAX_RELOAD: u16 = 0xFFF;
+
+    /// Window value that disables the window.
+    pub const WINDOW_DISABLED: u16 = 0xFFF;
+
+    /// Create a configuration from raw register values.
+    ///
+    /// Values larger than 12 bits are truncated.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::iwdg::{Config, Prescaler};
+    ///
+    /// const CONFIG: Config = Config::from_raw(Prescaler::Div4, 0xFFF, Config::WINDOW_DISABLED);
+    /// assert_eq!(CONFIG.prescaler(), Prescaler::Div4);
+    /// assert_eq!(CONFIG.reload(), 0xFFF);
+    /// assert_eq!(CONFIG.window(), None);
+    /// ```
+    pub const fn from_raw(pre: Prescaler, reload: u16, window: u16) -> Config {
+        Config {
+            pre,
+            reload: reload & Self::MAX_RELOAD,
+            window: window & Self::WINDOW_DISABLED,
+        }
+    }
+
+    /// Compute a configuration from a timeout.
+    ///
+    /// The smallest prescaler that can represent the timeout is selected to
+    /// provide the best resolution, and the reload value is rounded to the
+    /// nearest tick.
+    ///
+    /// The LSI frequency is typically obtained with
+    /// [`rcc::lsi_hz`](crate::rcc::lsi_hz).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use core::time::Duration;
+    /// use stm32wlxx_hal::iwdg::{Config, Error, Prescaler};
+    ///
+    /// // 32 kHz / 4 = 8 kHz, 1 ms is 8 ticks
+    /// let config: Config = Config::new(32_000, Duration::from_millis(1))?;
+    /// assert_eq!(config.prescaler(), Prescaler::Div4);
+    /// assert_eq!(config.reload(), 7);
+    /// assert_eq!(config.timeout(32_000), Duration::from_millis(1));
+    ///
+    /// assert_eq!(
+    ///     Config::new(32_000, Duration::from_nanos(1)),
+    ///     Err(Error::TooShort)
+    /// );
+    /// assert_eq!(
+    ///     Config::new(32_000, Duration::from_secs(60)),
+    ///     Err(Error::TooLong)
+    /// );
+    /// # Ok::<(), stm32wlxx_hal::iwdg::Error>(())
+    /// ```
+    pub fn new(lsi_hz: u16, timeout: Duration) -> Result<Config, Error> {
+        let numer: u128 = timeout.as_nanos() * u128::from(lsi_hz);
+
+        for pre in Prescaler::ALL {
+            let denom: u128 = u128::from(pre.div()) * NANOS_PER_SEC;
+            let ticks: u128 = (numer + denom / 2) / denom;
+
+            if ticks == 0 {
+                return Err(Error::TooShort);
+            } else if ticks <= u128::from(Self::MAX_RELOAD) + 1 {
+                return Ok(Config {
+                    pre,
+                    reload: (ticks - 1) as u16,
+                    window: Self::WINDOW_DISABLED,
+                });
+            }
+        }
+
+        Err(Error::TooLong)
+    }
+
+    /// Set the refresh window.
+    ///
+    /// Feeding the watchdog before `min` has elapsed since the last feed
+    /// will cause a reset.
+    ///
+    /// The window is computed with the prescaler already selected by
+    /// [`new`](Self::new), and rounded to the nearest tick.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use core::time::Duration;
+    /// use stm32wlxx_hal::iwdg::{Config, Error};
+    ///
+    /// const LSI_HZ: u16 = 32_000;
+    ///
+    /// let config: Config = Config::new(LSI_HZ, Duration::from_millis(10))?
+    ///     .set_window(LSI_HZ, Duration::from_millis(2))?;
+    /// assert_eq!(config.reload(), 79);
+    /// assert_eq!(config.window(), Some(63));
+    ///
+    /// assert_eq!(
+    ///     Config::new(LSI_HZ, Duration::from_millis(10))?
+    ///         .set_window(LSI_HZ, Duration::from_millis(11)),
+    ///     Err(Error::Window)
+    /// );
+    /// # Ok::<(), stm32wlxx_hal::iwdg::Error>(())
+    /// ```
+    pub fn set_window(mut self, lsi_hz: u16, min: Duration) -> Result<Config, Error> {
+        let numer: u128 = min.as_nanos() * u128::from(lsi_hz);
+        let denom: u128 = u128::from(self.pre.div()) * NANOS_PER_SEC;
+        let ticks: u128 = (numer + denom / 2) / denom;
+
+        if ticks > u128::from(self.reload) {
+            Err(Error::Window)
+        } else {
+            self.window = self.reload - (ticks as u16);
+            Ok(self)
+        }
+    }
+
+    /// Disable the refresh window.
+    #[must_use = "set_window_disabled returns a modified Config"]
+    pub const fn set_window_disabled(mut self) -> Config {
+        self.window = Self::WINDOW_DISABLED;
+        self
+    }
+
+    /// Get the prescaler.
+    pub const fn prescaler(&self) -> Prescaler {
+        self.pre
+    }
+
+    /// Get the reload value.
+    pub const fn reload(&self) -> u16 {
+        self.reload
+    }
+
+    /// Get the window value.
+    ///
+    /// Returns `None` if the window is disabled.
+    pub const fn window(&self) -> Option<u16> {
+        if self.window == Self::WINDOW_DISABLED {
+            None
+        } else {
+            Some(self.window)
+        }
+    }
+
+    /// Calculate the timeout for a given LSI frequency.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use core::time::Duration;
+    /// use stm32wlxx_hal::iwdg::{Config, Prescaler};
+    ///
+    /// const MAX: Config = Config::from_raw(Prescaler::Div256, 0xFFF, Config::WINDOW_DISABLED);
+    /// assert_eq!(MAX.timeout(32_000), Duration::from_micros(32_768_000));
+    /// ```
+    pub fn timeout(&self, lsi_hz: u16) -> Duration {
+        let ticks: u64 = u64::from(self.reload) + 1;
+        let nanos: u64 = ticks * u64::from(self.pre.div()) * (NANOS_PER_SEC as u64);
+        Duration::from_nanos(nanos / u64::from(lsi_hz))
+    }
+}
+
+/// Returns `true` if the hardware watchdog option is selected.
+///
+/// When the hardware watchdog is selected the independent watchdog is
+/// started automatically after reset.
+///
+/// This reads the `IWDG_SW` bit of the user option bytes.
+#[inline]
+pub fn is_hw_mode(flash: &pac::FLASH) -> bool {
+    flash.optr.read().bits() & OPTR_IWDG_SW == 0
+}
+
+/// Returns `true` if the independent watchdog counter is frozen in stop mode.
+///
+/// This reads the `IWDG_STOP` bit of the user option bytes.
+///
+/// When the counter is not frozen the watchdog must be fed (by waking up)
+/// before it expires, or the device will reset while in stop mode.
+#[inline]
+pub fn is_frozen_in_stop(flash: &pac::FLASH) -> bool {
+    flash.optr.read().bits() & OPTR_IWDG_STOP == 0
+}
+
+/// Returns `true` if the independent watchdog counter is frozen in standby
+/// mode.
+///
+/// This reads the `IWDG_STDBY` bit of the user option bytes.
+#[inline]
+pub fn is_frozen_in_standby(flash: &pac::FLASH) -> bool {
+    flash.optr.read().bits() & OPTR_IWDG_STDBY == 0
+}
+
+/// Independent watchdog driver.
+#[derive(Debug)]
+pub struct Iwdg {
+    iwdg: pac::IWDG,
+}
+
+impl Iwdg {
+    /// Create a new independent watchdog driver.
+    ///
+    /// This does not start the watchdog, the watchdog has no peripheral
+    /// clock or reset in the RCC.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use stm32wlxx_hal::{iwdg::Iwdg, pac};
+    ///
+    /// let dp: pac::Peripherals = pac::Peripherals::take().unwrap();
+    /// let iwdg: Iwdg = Iwdg::new(dp.IWDG);
+    /// ```
+    #[inline]
+    pub const fn new(iwdg: pac::IWDG) -> Iwdg {
+        Iwdg { iwdg }
+    }
+
+    /// Free the IWDG peripheral from the driver.
+    ///
+    /// This will **not** stop the watchdog.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use stm32wlxx_hal::{iwdg::Iwdg, pac};
+    ///
+    /// let dp: pac::Peripherals = pac::Peripherals::take().unwrap();
+    /// let iwdg: Iwdg = Iwdg::new(dp.IWDG);
+    /// // ... use iwdg
+    /// let iwdg: pac::IWDG = iwdg.free();
+    /// ```
+    #[inline]
+    pub fn free(self) -> pac::IWDG {
+        self.iwdg
+    }
+
+    /// Steal the IWDG peripheral from whatever is currently using it.
+    ///
+    /// # Safety
+    ///
+    /// 1. Ensure that the code stealing the IWDG has exclusive access to the
+    ///    peripheral. Singleton checks are bypassed with this method.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::iwdg::Iwdg;
+    ///
+    /// // ... setup happens here
+    ///
+    /// let iwdg: Iwdg = unsafe { Iwdg::steal() };
+    /// ```
+    #[inline]
+    pub unsafe fn steal() -> Iwdg {
+        Iwdg {
+            iwdg: pac::Peripherals::steal().IWDG,
+        }
+    }
+
+    /// Start the watchdog, or reconfigure a running watchdog.
+    ///
+    /// Starting the watchdog forces the LSI on, and the watchdog cannot be
+    /// stopped except by a reset.
+    ///
+    /// This will block until the new configuration has been applied, and the
+    /// counter will be reloaded with the new reload value.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use core::time::Duration;
+    /// use stm32wlxx_hal::{
+    ///     iwdg::{Config, Iwdg},
+    ///     pac,
+    ///     rcc::lsi_hz,
+    /// };
+    ///
+    /// let dp: pac::Peripherals = pac::Peripherals::take().unwrap();
+    ///
+    /// let lsi: u16 = lsi_hz(&dp.RCC);
+    /// let config: Config =
+    ///     Config::new(lsi, Duration::from_secs(2))?.set_window(lsi, Duration::from_millis(100))?;
+    ///
+    /// let mut iwdg: Iwdg = Iwdg::new(dp.IWDG);
+    /// iwdg.start(config);
+    /// # Ok::<(), stm32wlxx_hal::iwdg::Error>(())
+    /// ```
+    pub fn start(&mut self, config: Config) {
+        // safety: all values written are valid keys or masked register values
+        self.iwdg.kr.write(|w| unsafe { w.bits(KEY_START) });
+        self.iwdg.kr.write(|w| unsafe { w.bits(KEY_UNLOCK) });
+        self.iwdg
+            .pr
+            .write(|w| unsafe { w.bits(u32::from(config.pre as u8)) });
+        self.iwdg
+            .rlr
+            .write(|w| unsafe { w.bits(u32::from(config.reload)) });
+        while self.iwdg.sr.read().bits() & (SR_PVU | SR_RVU | SR_WVU) != 0 {}
+
+        // writing the window register reloads the counter
+        // the PAC is missing the window register (WINR), write it directly
+        // safety: WINR is a 32-bit register at a valid offset from the base
+        unsafe {
+            write_volatile(
+                (pac::IWDG::PTR as *mut u8).add(WINR_OFFSET) as *mut u32,
+                u32::from(config.window),
+            )
+        };
+        while self.iwdg.sr.read().bits() & SR_WVU != 0 {}
+    }
+
+    /// Feed (refresh) the watchdog.
+    ///
+    /// This reloads the counter with the reload value.
+    ///
+    /// If a window is configured and the counter is above the window value
+    /// this will cause a reset.
+    #[inline]
+    pub fn feed(&mut self) {
+        // safety: KEY_RELOAD is a valid key
+        self.iwdg.kr.write(|w| unsafe { w.bits(KEY_RELOAD) })
+    }
+}
+
+impl embedded_hal::watchdog::Watchdog for Iwdg {
+    #[inline]
+    fn feed(&mut self) {
+        Iwdg::feed(self)
+    }
+}
+
+impl embedded_hal::watchdog::WatchdogEnable for Iwdg {
+    type Time = Config;
+
+    #[inline]
+    fn start<T>(&mut self, period: T)
+    where
+        T: Into<Self::Time>,
+    {
+        Iwdg::start(self, period.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Config, Error, Prescaler};
+    use core::time::Duration;
+
+    const LSI_HZ: u16 = 32_000;
+    const LSI_DIV_HZ: u16 = 250;
+
+    #[test]
+    fn prescaler_selection() {
+        // 4096 ticks at 8 kHz is 512 ms
+        let config: Config = Config::new(LSI_HZ, Duration::from_millis(512)).unwrap();
+        assert_eq!(config.prescaler(), Prescaler::Div4);
+        assert_eq!(config.reload(), 0xFFF);
+
+        // one tick more requires the next prescaler
+        let config: Config = Config::new(LSI_HZ, Duration::from_micros(512_250)).unwrap();
+        assert_eq!(config.prescaler(), Prescaler::Div8);
+        assert_eq!(config.reload(), 2048);
+
+        let config: Config = Config::new(LSI_HZ, Duration::from_secs(32)).unwrap();
+        assert_eq!(config.prescaler(), Prescaler::Div256);
+        assert_eq!(config.reload(), 3999);
+    }
+
+    #[test]
+    fn limits() {
+        // half a tick rounds up
+        assert!(Config::new(LSI_HZ, Duration::from_micros(63)).is_ok());
+        assert_eq!(
+            Config::new(LSI_HZ, Duration::from_micros(62)),
+            Err(Error::TooShort)
+        );
+
+        let max: Duration = Config::from_raw(Prescaler::Div256, 0xFFF, 0xFFF).timeout(LSI_HZ);
+        assert_eq!(Config::new(LSI_HZ, max).unwrap().reload(), 0xFFF);
+        assert_eq!(
+            Config::new(LSI_HZ, max + Duration::from_millis(5)),
+            Err(Error::TooLong)
+        );
+
+        // 4096 * 256 / 250 Hz
+        assert_eq!(
+            Config::new(LSI_DIV_HZ, Duration::from_millis(4_194_304)),
+            Ok(Config::from_raw(Prescaler::Div256, 0xFFF, 0xFFF))
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        for pre in Prescaler::ALL {
+            for reload in [0, 1, 100, 0x800, 0xFFF] {
+                let config: Config = Config::from_raw(pre, reload, Config::WINDOW_DISABLED);
+                let timeout: Duration = config.timeout(LSI_HZ);
+                let computed: Config = Config::new(LSI_HZ, timeout).unwrap();
+                assert_eq!(computed.timeout(LSI_HZ), timeout);
+            }
+        }
+    }
+
+    #[test]
+    fn window() {
+        let config: Config = Config::new(LSI_HZ, Duration::from_millis(100)).unwrap();
+        assert_eq!(config.window(), None);
+
+        let windowed: Config = config
+            .set_window(LSI_HZ, Duration::from_micros(99_875))
+            .unwrap();
+        assert_eq!(windowed.window(), Some(0));
+        assert_eq!(windowed.set_window_disabled(), config);
+
+        assert_eq!(
+            config.set_window(LSI_HZ, Duration::from_millis(100)),
+            Err(Error::Window)
+        );
+    }
+}
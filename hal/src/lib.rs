@@ -48,12 +48,21 @@ pub(crate) mod macros;
 
 pub mod adc;
 pub mod aes;
+pub mod comp;
+pub mod crc;
 pub mod dac;
 pub mod dma;
 pub mod flash;
 pub mod gpio;
+#[cfg(any(feature = "stm32wl5x_cm0p", feature = "stm32wl5x_cm4"))]
+pub mod hsem;
 pub mod i2c;
 pub mod info;
+#[cfg(any(feature = "stm32wl5x_cm0p", feature = "stm32wl5x_cm4"))]
+pub mod ipcc;
+pub mod iwdg;
+#[cfg(feature = "lorawan")]
+pub mod lorawan;
 pub mod lptim;
 pub mod pka;
 pub mod pwr;
@@ -63,8 +72,12 @@ pub mod rng;
 pub mod rtc;
 pub mod spi;
 pub mod subghz;
+pub mod tamp;
+pub mod tim;
 pub mod uart;
 pub mod util;
+pub mod vrefbuf;
+pub mod wwdg;
 
 mod ratio;
 pub use ratio::Ratio;
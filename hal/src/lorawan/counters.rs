@@ -0,0 +1,280 @@
+use crate::flash::{self, AlignedAddr, Flash, Page};
+
+const RECORD_LEN: usize = 16;
+const RECORD_MAGIC: u8 = 0xA5;
+const JOIN_NONCE_VALID: u8 = 0x00;
+const ERASED: u8 = 0xFF;
+
+/// Nonces and frame counters that must survive a reset.
+///
+/// Reusing a DevNonce or an uplink frame counter with the same keys breaks
+/// the LoRaWAN security model, and the network server rejects these frames.
+///
+/// Returned by [`Mac::counters`](super::Mac::counters), persisted with
+/// [`CounterStore`].
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Counters {
+    /// DevNonce of the next join request.
+    pub dev_nonce: u16,
+    /// Last accepted JoinNonce, `None` if the device never joined.
+    pub join_nonce: Option<u32>,
+    /// Frame counter of the next uplink.
+    pub fcnt_up: u32,
+    /// Lowest accepted downlink frame counter.
+    pub fcnt_down: u32,
+}
+
+impl Counters {
+    /// Counters of a device that never joined.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::lorawan::Counters;
+    ///
+    /// const COUNTERS: Counters = Counters::new();
+    /// assert_eq!(COUNTERS.dev_nonce, 0);
+    /// assert_eq!(COUNTERS.join_nonce, None);
+    /// ```
+    pub const fn new() -> Counters {
+        Counters {
+            dev_nonce: 0,
+            join_nonce: None,
+            fcnt_up: 0,
+            fcnt_down: 0,
+        }
+    }
+
+    // newer counters never decrease the DevNonce, and only reset the frame
+    // counters on a join which increases the DevNonce
+    fn key(&self) -> (u16, u32, u32) {
+        (self.dev_nonce, self.fcnt_up, self.fcnt_down)
+    }
+
+    fn to_record(self) -> [u8; RECORD_LEN] {
+        let mut record: [u8; RECORD_LEN] = [ERASED; RECORD_LEN];
+        record[0..4].copy_from_slice(&self.fcnt_up.to_le_bytes());
+        record[4..8].copy_from_slice(&self.fcnt_down.to_le_bytes());
+        record[8..10].copy_from_slice(&self.dev_nonce.to_le_bytes());
+        if let Some(join_nonce) = self.join_nonce {
+            record[10..13].copy_from_slice(&join_nonce.to_le_bytes()[..3]);
+            record[13] = JOIN_NONCE_VALID;
+        }
+        // the magic is in the last double-word, written last
+        record[15] = RECORD_MAGIC;
+        record
+    }
+
+    fn from_record(record: &[u8]) -> Option<Counters> {
+        if record[15] != RECORD_MAGIC {
+            return None;
+        }
+        let join_nonce: Option<u32> = match record[13] {
+            JOIN_NONCE_VALID => Some(u32::from_le_bytes([record[10], record[11], record[12], 0])),
+            ERASED => None,
+            _ => return None,
+        };
+        Some(Counters {
+            dev_nonce: u16::from_le_bytes([record[8], record[9]]),
+            join_nonce,
+            fcnt_up: u32::from_le_bytes([record[0], record[1], record[2], record[3]]),
+            fcnt_down: u32::from_le_bytes([record[4], record[5], record[6], record[7]]),
+        })
+    }
+}
+
+impl Default for Counters {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Scan a page for the last valid record, and the offset of the first free
+/// record.
+///
+/// Records that were partially written when power was lost are skipped.
+fn scan(page: &[u8]) -> (Option<Counters>, Option<usize>) {
+    let mut last: Option<Counters> = None;
+    for (n, record) in page.chunks_exact(RECORD_LEN).enumerate() {
+        if record.iter().all(|b| *b == ERASED) {
+            return (last, Some(n * RECORD_LEN));
+        }
+        if let Some(counters) = Counters::from_record(record) {
+            last = Some(counters);
+        }
+    }
+    (last, None)
+}
+
+/// Select the newest counters, and the index of the page holding them.
+fn newest(pages: [(Option<Counters>, Option<usize>); 2]) -> (Option<Counters>, usize) {
+    match (pages[0].0, pages[1].0) {
+        (Some(a), Some(b)) if b.key() > a.key() => (Some(b), 1),
+        (None, Some(b)) => (Some(b), 1),
+        (a, _) => (a, 0),
+    }
+}
+
+/// Wear-levelled flash storage for [`Counters`].
+///
+/// The counters are appended as 16 byte records to one of two flash pages,
+/// when a page is full the other page is erased and the records continue
+/// there.
+/// A page holds 128 records, so each page is erased once every 256 writes.
+///
+/// # Example
+///
+/// ```no_run
+/// use stm32wlxx_hal::{
+///     flash::{Flash, Page},
+///     lorawan::{CounterStore, Counters},
+///     pac,
+/// };
+///
+/// let mut dp: pac::Peripherals = pac::Peripherals::take().unwrap();
+///
+/// let mut store: CounterStore = CounterStore::new([
+///     Page::from_index(126).unwrap(),
+///     Page::from_index(127).unwrap(),
+/// ]);
+/// let mut counters: Counters = store.load().unwrap_or_default();
+/// counters.fcnt_up += 1;
+///
+/// let mut flash: Flash = Flash::unlock(&mut dp.FLASH);
+/// unsafe { store.store(&mut flash, &counters)? };
+/// # Ok::<(), stm32wlxx_hal::flash::Error>(())
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct CounterStore {
+    pages: [Page; 2],
+}
+
+impl CounterStore {
+    /// Create a new counter store on two flash pages.
+    ///
+    /// The pages must not be used for anything else.
+    pub const fn new(pages: [Page; 2]) -> CounterStore {
+        CounterStore { pages }
+    }
+
+    fn page(page: Page) -> &'static [u8] {
+        // safety: flash is always mapped and readable
+        unsafe { core::slice::from_raw_parts(page.addr() as *const u8, Page::SIZE) }
+    }
+
+    /// Load the newest counters.
+    ///
+    /// Returns `None` if no counters were stored.
+    pub fn load(&self) -> Option<Counters> {
+        newest(self.pages.map(|page| scan(Self::page(page)))).0
+    }
+
+    /// Store the counters.
+    ///
+    /// # Safety
+    ///
+    /// 1. The pages must not contain code or other data.
+    pub unsafe fn store(
+        &mut self,
+        flash: &mut Flash,
+        counters: &Counters,
+    ) -> Result<(), flash::Error> {
+        let scans: [(Option<Counters>, Option<usize>); 2] =
+            self.pages.map(|page| scan(Self::page(page)));
+        let (_, active) = newest(scans);
+
+        let (page, offset): (Page, usize) = match scans[active].1 {
+            Some(offset) => (self.pages[active], offset),
+            None => {
+                let next: Page = self.pages[active ^ 1];
+                flash.page_erase(next)?;
+                (next, 0)
+            }
+        };
+        // safety: page addresses and record offsets are multiples of 8
+        let addr: AlignedAddr = AlignedAddr::new_unchecked(page.addr() + offset);
+        flash.program_bytes(&counters.to_record(), addr)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{newest, scan, Counters, RECORD_LEN};
+
+    const PAGE_LEN: usize = 2048;
+
+    fn write(page: &mut [u8], n: usize, counters: Counters) {
+        page[n * RECORD_LEN..(n + 1) * RECORD_LEN].copy_from_slice(&counters.to_record());
+    }
+
+    #[test]
+    fn record_round_trip() {
+        let counters: Counters = Counters {
+            dev_nonce: 0x1234,
+            join_nonce: Some(0xAB_CDEF),
+            fcnt_up: 0xDEAD_BEEF,
+            fcnt_down: 7,
+        };
+        assert_eq!(Counters::from_record(&counters.to_record()), Some(counters));
+        assert_eq!(
+            Counters::from_record(&Counters::new().to_record()),
+            Some(Counters::new())
+        );
+        assert_eq!(Counters::from_record(&[0xFF; RECORD_LEN]), None);
+    }
+
+    #[test]
+    fn scan_page() {
+        let mut page: [u8; PAGE_LEN] = [0xFF; PAGE_LEN];
+        assert_eq!(scan(&page), (None, Some(0)));
+
+        let first: Counters = Counters {
+            fcnt_up: 1,
+            ..Counters::new()
+        };
+        let second: Counters = Counters {
+            fcnt_up: 2,
+            ..Counters::new()
+        };
+        write(&mut page, 0, first);
+        write(&mut page, 1, second);
+        assert_eq!(scan(&page), (Some(second), Some(32)));
+
+        // power lost after writing the first double-word of a record
+        page[32..40].copy_from_slice(&[0; 8]);
+        assert_eq!(scan(&page), (Some(second), Some(48)));
+
+        for n in 0..PAGE_LEN / RECORD_LEN {
+            write(&mut page, n, first);
+        }
+        assert_eq!(scan(&page), (Some(first), None));
+    }
+
+    #[test]
+    fn newest_page() {
+        let old: Counters = Counters {
+            fcnt_up: 100,
+            ..Counters::new()
+        };
+        // rejoin resets the frame counters and increments the DevNonce
+        let new: Counters = Counters {
+            dev_nonce: 1,
+            ..Counters::new()
+        };
+        assert_eq!(newest([(None, Some(0)), (None, Some(0))]), (None, 0));
+        assert_eq!(newest([(Some(old), None), (None, Some(0))]), (Some(old), 0));
+        assert_eq!(
+            newest([(None, None), (Some(old), Some(16))]),
+            (Some(old), 1)
+        );
+        assert_eq!(
+            newest([(Some(old), None), (Some(new), Some(16))]),
+            (Some(new), 1)
+        );
+        assert_eq!(
+            newest([(Some(new), Some(16)), (Some(old), None)]),
+            (Some(new), 0)
+        );
+    }
+}
@@ -0,0 +1,305 @@
+use crate::aes::{self, Aes};
+
+/// AES-128 block encryption.
+///
+/// This is the only cipher primitive required by LoRaWAN 1.0, the message
+/// integrity codes are AES-CMAC, the payload encryption is a counter mode,
+/// and join accept messages are decrypted with the encrypt operation.
+///
+/// This is implemented for the hardware [`Aes`] peripheral, implement it
+/// with a software AES to run the MAC on a host.
+pub trait BlockCipher {
+    /// Cipher error type.
+    type Error;
+
+    /// Encrypt a single block in-place.
+    fn encrypt_block(&mut self, key: &[u8; 16], block: &mut [u8; 16]) -> Result<(), Self::Error>;
+}
+
+const fn to_words(bytes: &[u8; 16]) -> [u32; 4] {
+    [
+        u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+        u32::from_be_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]),
+        u32::from_be_bytes([bytes[8], bytes[9], bytes[10], bytes[11]]),
+        u32::from_be_bytes([bytes[12], bytes[13], bytes[14], bytes[15]]),
+    ]
+}
+
+/// Requires the default [`SwapMode::None`](aes::SwapMode::None) data swapping.
+impl BlockCipher for Aes {
+    type Error = aes::Error;
+
+    fn encrypt_block(&mut self, key: &[u8; 16], block: &mut [u8; 16]) -> Result<(), aes::Error> {
+        let key: [u32; 4] = to_words(key);
+        let mut text: [u32; 4] = to_words(block);
+        self.encrypt_ecb_inplace(&key, &mut text)?;
+        text.iter()
+            .zip(block.chunks_exact_mut(4))
+            .for_each(|(word, chunk)| chunk.copy_from_slice(&word.to_be_bytes()));
+        Ok(())
+    }
+}
+
+/// Frame direction.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub(crate) enum Dir {
+    Up = 0,
+    Down = 1,
+}
+
+fn xor(dst: &mut [u8], src: &[u8]) {
+    dst.iter_mut().zip(src.iter()).for_each(|(d, s)| *d ^= s);
+}
+
+// subkey generation doubling, RFC 4493 section 2.3
+fn dbl(block: &[u8; 16]) -> [u8; 16] {
+    let mut ret: [u8; 16] = [0; 16];
+    let mut carry: u8 = 0;
+    for (r, b) in ret.iter_mut().zip(block.iter()).rev() {
+        *r = (b << 1) | carry;
+        carry = b >> 7;
+    }
+    if carry != 0 {
+        ret[15] ^= 0x87;
+    }
+    ret
+}
+
+/// AES-CMAC (RFC 4493) over the concatenation of `parts`.
+pub(crate) fn cmac<C: BlockCipher>(
+    cipher: &mut C,
+    key: &[u8; 16],
+    parts: &[&[u8]],
+) -> Result<[u8; 16], C::Error> {
+    let mut state: [u8; 16] = [0; 16];
+    let mut block: [u8; 16] = [0; 16];
+    let mut len: usize = 0;
+
+    for byte in parts.iter().flat_map(|part| part.iter()) {
+        if len == block.len() {
+            xor(&mut state, &block);
+            cipher.encrypt_block(key, &mut state)?;
+            len = 0;
+        }
+        block[len] = *byte;
+        len += 1;
+    }
+
+    let mut l: [u8; 16] = [0; 16];
+    cipher.encrypt_block(key, &mut l)?;
+    let k1: [u8; 16] = dbl(&l);
+    if len == block.len() {
+        xor(&mut block, &k1);
+    } else {
+        block[len] = 0x80;
+        block[len + 1..].iter_mut().for_each(|b| *b = 0);
+        xor(&mut block, &dbl(&k1));
+    }
+    xor(&mut state, &block);
+    cipher.encrypt_block(key, &mut state)?;
+    Ok(state)
+}
+
+/// Message integrity code of join frames.
+pub(crate) fn join_mic<C: BlockCipher>(
+    cipher: &mut C,
+    key: &[u8; 16],
+    msg: &[u8],
+) -> Result<[u8; 4], C::Error> {
+    let tag: [u8; 16] = cmac(cipher, key, &[msg])?;
+    Ok([tag[0], tag[1], tag[2], tag[3]])
+}
+
+// B0 and Ai blocks, LoRaWAN 1.0.4 sections 4.3.3 and 4.4
+const fn frame_block(first: u8, dir: Dir, dev_addr: u32, fcnt: u32, last: u8) -> [u8; 16] {
+    let addr: [u8; 4] = dev_addr.to_le_bytes();
+    let fcnt: [u8; 4] = fcnt.to_le_bytes();
+    [
+        first, 0, 0, 0, 0, dir as u8, addr[0], addr[1], addr[2], addr[3], fcnt[0], fcnt[1],
+        fcnt[2], fcnt[3], 0, last,
+    ]
+}
+
+/// Message integrity code of data frames.
+pub(crate) fn data_mic<C: BlockCipher>(
+    cipher: &mut C,
+    key: &[u8; 16],
+    dir: Dir,
+    dev_addr: u32,
+    fcnt: u32,
+    msg: &[u8],
+) -> Result<[u8; 4], C::Error> {
+    // the frame length is limited to 255 by the radio
+    let b0: [u8; 16] = frame_block(0x49, dir, dev_addr, fcnt, msg.len() as u8);
+    let tag: [u8; 16] = cmac(cipher, key, &[&b0, msg])?;
+    Ok([tag[0], tag[1], tag[2], tag[3]])
+}
+
+/// Encrypt or decrypt a data frame payload in-place.
+pub(crate) fn crypt_payload<C: BlockCipher>(
+    cipher: &mut C,
+    key: &[u8; 16],
+    dir: Dir,
+    dev_addr: u32,
+    fcnt: u32,
+    payload: &mut [u8],
+) -> Result<(), C::Error> {
+    for (i, chunk) in payload.chunks_mut(16).enumerate() {
+        // the payload length is limited to 255 by the radio
+        let mut s: [u8; 16] = frame_block(0x01, dir, dev_addr, fcnt, (i + 1) as u8);
+        cipher.encrypt_block(key, &mut s)?;
+        xor(chunk, &s);
+    }
+    Ok(())
+}
+
+/// Decrypt a join accept in-place, excluding the MHDR.
+///
+/// The network server encrypts with the AES decrypt operation, so the device
+/// decrypts with the AES encrypt operation.
+pub(crate) fn decrypt_join_accept<C: BlockCipher>(
+    cipher: &mut C,
+    key: &[u8; 16],
+    buf: &mut [u8],
+) -> Result<(), C::Error> {
+    for chunk in buf.chunks_exact_mut(16) {
+        let mut block: [u8; 16] = [0; 16];
+        block.copy_from_slice(chunk);
+        cipher.encrypt_block(key, &mut block)?;
+        chunk.copy_from_slice(&block);
+    }
+    Ok(())
+}
+
+/// Derive a LoRaWAN 1.0 session key.
+///
+/// `prefix` is `0x01` for the NwkSKey and `0x02` for the AppSKey.
+pub(crate) fn session_key<C: BlockCipher>(
+    cipher: &mut C,
+    app_key: &[u8; 16],
+    prefix: u8,
+    join_nonce: u32,
+    net_id: u32,
+    dev_nonce: u16,
+) -> Result<[u8; 16], C::Error> {
+    let join_nonce: [u8; 4] = join_nonce.to_le_bytes();
+    let net_id: [u8; 4] = net_id.to_le_bytes();
+    let dev_nonce: [u8; 2] = dev_nonce.to_le_bytes();
+    let mut key: [u8; 16] = [
+        prefix,
+        join_nonce[0],
+        join_nonce[1],
+        join_nonce[2],
+        net_id[0],
+        net_id[1],
+        net_id[2],
+        dev_nonce[0],
+        dev_nonce[1],
+        0,
+        0,
+        0,
+        0,
+        0,
+        0,
+        0,
+    ];
+    cipher.encrypt_block(app_key, &mut key)?;
+    Ok(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{cmac, crypt_payload, BlockCipher, Dir};
+    use crate::lorawan::soft_aes::SoftAes;
+
+    const KEY: [u8; 16] = [
+        0x2b, 0x7e, 0x15, 0x16, 0x28, 0xae, 0xd2, 0xa6, 0xab, 0xf7, 0x15, 0x88, 0x09, 0xcf, 0x4f,
+        0x3c,
+    ];
+
+    const MSG: [u8; 64] = [
+        0x6b, 0xc1, 0xbe, 0xe2, 0x2e, 0x40, 0x9f, 0x96, 0xe9, 0x3d, 0x7e, 0x11, 0x73, 0x93, 0x17,
+        0x2a, 0xae, 0x2d, 0x8a, 0x57, 0x1e, 0x03, 0xac, 0x9c, 0x9e, 0xb7, 0x6f, 0xac, 0x45, 0xaf,
+        0x8e, 0x51, 0x30, 0xc8, 0x1c, 0x46, 0xa3, 0x5c, 0xe4, 0x11, 0xe5, 0xfb, 0xc1, 0x19, 0x1a,
+        0x0a, 0x52, 0xef, 0xf6, 0x9f, 0x24, 0x45, 0xdf, 0x4f, 0x9b, 0x17, 0xad, 0x2b, 0x41, 0x7b,
+        0xe6, 0x6c, 0x37, 0x10,
+    ];
+
+    #[test]
+    fn aes_fips_197() {
+        let key: [u8; 16] = [
+            0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d,
+            0x0e, 0x0f,
+        ];
+        let mut block: [u8; 16] = [
+            0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88, 0x99, 0xaa, 0xbb, 0xcc, 0xdd,
+            0xee, 0xff,
+        ];
+        SoftAes.encrypt_block(&key, &mut block).unwrap();
+        assert_eq!(
+            block,
+            [
+                0x69, 0xc4, 0xe0, 0xd8, 0x6a, 0x7b, 0x04, 0x30, 0xd8, 0xcd, 0xb7, 0x80, 0x70, 0xb4,
+                0xc5, 0x5a
+            ]
+        );
+        SoftAes::decrypt_block(&key, &mut block);
+        assert_eq!(block[0], 0x00);
+        assert_eq!(block[15], 0xff);
+    }
+
+    // RFC 4493 section 4
+    #[test]
+    fn cmac_rfc_4493() {
+        assert_eq!(
+            cmac(&mut SoftAes, &KEY, &[]).unwrap(),
+            [
+                0xbb, 0x1d, 0x69, 0x29, 0xe9, 0x59, 0x37, 0x28, 0x7f, 0xa3, 0x7d, 0x12, 0x9b, 0x75,
+                0x67, 0x46
+            ]
+        );
+        assert_eq!(
+            cmac(&mut SoftAes, &KEY, &[&MSG[..16]]).unwrap(),
+            [
+                0x07, 0x0a, 0x16, 0xb4, 0x6b, 0x4d, 0x41, 0x44, 0xf7, 0x9b, 0xdd, 0x9d, 0xd0, 0x4a,
+                0x28, 0x7c
+            ]
+        );
+        assert_eq!(
+            cmac(&mut SoftAes, &KEY, &[&MSG[..40]]).unwrap(),
+            [
+                0xdf, 0xa6, 0x67, 0x47, 0xde, 0x9a, 0xe6, 0x30, 0x30, 0xca, 0x32, 0x61, 0x14, 0x97,
+                0xc8, 0x27
+            ]
+        );
+        assert_eq!(
+            cmac(&mut SoftAes, &KEY, &[&MSG]).unwrap(),
+            [
+                0x51, 0xf0, 0xbe, 0xbf, 0x7e, 0x3b, 0x9d, 0x92, 0xfc, 0x49, 0x74, 0x17, 0x79, 0x36,
+                0x3c, 0xfe
+            ]
+        );
+    }
+
+    #[test]
+    fn cmac_split_parts() {
+        let whole: [u8; 16] = cmac(&mut SoftAes, &KEY, &[&MSG[..40]]).unwrap();
+        let split: [u8; 16] = cmac(
+            &mut SoftAes,
+            &KEY,
+            &[&MSG[..3], &[], &MSG[3..17], &MSG[17..40]],
+        )
+        .unwrap();
+        assert_eq!(whole, split);
+    }
+
+    #[test]
+    fn payload_round_trip() {
+        let mut buf: [u8; 40] = [0; 40];
+        buf.copy_from_slice(&MSG[..40]);
+        crypt_payload(&mut SoftAes, &KEY, Dir::Up, 0x2601_1BDA, 7, &mut buf).unwrap();
+        assert_ne!(buf, MSG[..40]);
+        crypt_payload(&mut SoftAes, &KEY, Dir::Up, 0x2601_1BDA, 7, &mut buf).unwrap();
+        assert_eq!(buf, MSG[..40]);
+    }
+}
@@ -0,0 +1,832 @@
+use super::{BlockCipher, Downlink, Error, Mac, Rx, RxWindow};
+use crate::subghz::{
+    lora::{self, LoRaRadio},
+    region::{DataRate, Region},
+    HeaderType, LoRaModParams, LoRaPacketParams, RfFreq, RfState, Timeout, Transport,
+};
+use core::time::Duration;
+use embedded_hal::blocking::delay::DelayUs;
+
+const PREAMBLE_LEN: u16 = 8;
+
+// uplinks have a payload CRC and normal IQ
+const UPLINK_PACKET_PARAMS: LoRaPacketParams = LoRaPacketParams::new()
+    .set_preamble_len(PREAMBLE_LEN)
+    .set_header_type(HeaderType::Variable)
+    .set_payload_len(u8::MAX)
+    .set_crc_en(true)
+    .set_invert_iq(false);
+
+// downlinks have no payload CRC and inverted IQ
+const DOWNLINK_PACKET_PARAMS: LoRaPacketParams = LoRaPacketParams::new()
+    .set_preamble_len(PREAMBLE_LEN)
+    .set_header_type(HeaderType::Variable)
+    .set_payload_len(u8::MAX)
+    .set_crc_en(false)
+    .set_invert_iq(true);
+
+/// RF frequency and modulation parameters of a transmission or receive
+/// window.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct RfCfg {
+    rf_freq: RfFreq,
+    mod_params: LoRaModParams,
+}
+
+impl RfCfg {
+    /// Create a new RF configuration.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::{
+    ///     lorawan::RfCfg,
+    ///     subghz::{LoRaBandwidth, LoRaModParams, RfFreq, SpreadingFactor},
+    /// };
+    ///
+    /// // EU868 DR5 on the first default channel
+    /// const RF_CFG: RfCfg = RfCfg::new(
+    ///     RfFreq::from_frequency(868_100_000),
+    ///     LoRaModParams::new()
+    ///         .set_sf(SpreadingFactor::Sf7)
+    ///         .set_bw(LoRaBandwidth::Bw125),
+    /// );
+    /// ```
+    pub const fn new(rf_freq: RfFreq, mod_params: LoRaModParams) -> RfCfg {
+        RfCfg {
+            rf_freq,
+            mod_params,
+        }
+    }
+
+    /// RF configuration of an uplink on channel `ch` of `region` with data
+    /// rate `dr`.
+    ///
+    /// Returns `None` if the channel or data rate is not defined in the
+    /// region, or if the data rate is not a LoRa data rate.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::{
+    ///     lorawan::RfCfg,
+    ///     subghz::{
+    ///         region::Region, CodingRate, LoRaBandwidth, LoRaModParams, RfFreq, SpreadingFactor,
+    ///     },
+    /// };
+    ///
+    /// assert_eq!(
+    ///     RfCfg::uplink(Region::Eu868, 0, 5),
+    ///     Some(RfCfg::new(
+    ///         RfFreq::from_frequency(868_100_000),
+    ///         LoRaModParams::new()
+    ///             .set_sf(SpreadingFactor::Sf7)
+    ///             .set_bw(LoRaBandwidth::Bw125)
+    ///             .set_cr(CodingRate::Cr45),
+    ///     ))
+    /// );
+    /// assert_eq!(RfCfg::uplink(Region::Eu868, 3, 5), None);
+    /// ```
+    pub fn uplink(region: Region, ch: u8, dr: u8) -> Option<RfCfg> {
+        match region.channel_data_rates(ch) {
+            Some(drs) if drs.contains(&dr) => (),
+            _ => return None,
+        }
+        match region.data_rate(dr)? {
+            DataRate::LoRa(mod_params) => Some(RfCfg::new(region.channel(ch)?, mod_params)),
+            DataRate::Fsk(_) => None,
+        }
+    }
+
+    /// Get the RF frequency.
+    pub const fn rf_freq(&self) -> RfFreq {
+        self.rf_freq
+    }
+
+    /// Get the modulation parameters.
+    pub const fn mod_params(&self) -> LoRaModParams {
+        self.mod_params
+    }
+}
+
+/// Radio used by [`Device`].
+pub trait Radio {
+    /// Radio error type.
+    type Error;
+
+    /// Transmit an uplink.
+    ///
+    /// This must block until the transmission is complete.
+    fn transmit(&mut self, rf: &RfCfg, data: &[u8]) -> Result<(), Self::Error>;
+
+    /// Receive a downlink.
+    ///
+    /// This must return `Ok(None)` if no valid packet was detected within
+    /// `timeout`.
+    fn receive(
+        &mut self,
+        rf: &RfCfg,
+        timeout: Duration,
+        buf: &mut [u8],
+    ) -> Result<Option<usize>, Self::Error>;
+}
+
+/// The LoRa radio must be configured with the public
+/// [`LoRaSyncWord`](crate::subghz::LoRaSyncWord), which is the default.
+impl<MISO, MOSI, RFS, T> Radio for LoRaRadio<MISO, MOSI, RFS, T>
+where
+    T: Transport,
+    RFS: FnMut(RfState),
+{
+    type Error = lora::Error;
+
+    fn transmit(&mut self, rf: &RfCfg, data: &[u8]) -> Result<(), Self::Error> {
+        self.set_rf_freq(rf.rf_freq())?;
+        self.set_mod_params(rf.mod_params())?;
+        self.set_packet_params(UPLINK_PACKET_PARAMS)?;
+        LoRaRadio::transmit(self, data)
+    }
+
+    fn receive(
+        &mut self,
+        rf: &RfCfg,
+        timeout: Duration,
+        buf: &mut [u8],
+    ) -> Result<Option<usize>, Self::Error> {
+        self.set_rf_freq(rf.rf_freq())?;
+        self.set_mod_params(rf.mod_params())?;
+        self.set_packet_params(DOWNLINK_PACKET_PARAMS)?;
+        match LoRaRadio::receive(self, buf, Timeout::from_duration_sat(timeout)) {
+            Ok((len, _)) => Ok(Some(len.into())),
+            Err(lora::Error::Timeout | lora::Error::Crc | lora::Error::Header) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+/// LoRaWAN device errors.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum DeviceError<R, C> {
+    /// Radio error.
+    Radio(R),
+    /// MAC error.
+    Mac(Error<C>),
+    /// No join accept was received in either receive window.
+    NoJoinAccept,
+    /// The channel or data rate is not defined in the region, or is not a
+    /// LoRa data rate.
+    ///
+    /// This is also returned if the RX2 data rate of the session is not a
+    /// LoRa data rate of the region.
+    Channel,
+}
+
+impl<R, C> From<Error<C>> for DeviceError<R, C> {
+    fn from(e: Error<C>) -> Self {
+        DeviceError::Mac(e)
+    }
+}
+
+fn micros_sat(duration: Duration) -> u32 {
+    u32::try_from(duration.as_micros()).unwrap_or(u32::MAX)
+}
+
+/// LoRaWAN class A device.
+///
+/// This drives a [`Mac`] with a [`Radio`], a [`BlockCipher`], a delay, and
+/// a monotonic clock to time the receive windows.
+///
+/// The clock is a closure returning the current time, for example from the
+/// RTC or a LPTIM.
+/// The receive windows are timed from the end of the uplink with the clock,
+/// the time spent in RX1 is not added to the RX2 delay.
+///
+/// Operations block until the receive windows close.
+///
+/// # Example
+///
+/// ```no_run
+/// # fn now() -> core::time::Duration { core::time::Duration::ZERO }
+/// use stm32wlxx_hal::{
+///     aes::Aes,
+///     lorawan::{Counters, Device, Mac, Otaa, RfCfg},
+///     pac,
+///     spi::{SgMiso, SgMosi},
+///     subghz::{
+///         lora::{LoRaCfg, LoRaRadio},
+///         region::Region,
+///         CalibrateImage, LoRaBandwidth, LoRaModParams, RfFreq, RfState, SpreadingFactor,
+///         SubGhz,
+///     },
+///     util::new_delay,
+/// };
+///
+/// const TX: RfCfg = RfCfg::new(
+///     RfFreq::from_frequency(868_100_000),
+///     LoRaModParams::new()
+///         .set_sf(SpreadingFactor::Sf7)
+///         .set_bw(LoRaBandwidth::Bw125),
+/// );
+/// const CFG: LoRaCfg = LoRaCfg::new()
+///     .set_rf_freq(RfFreq::F868)
+///     .set_cal_image(CalibrateImage::ISM_863_870);
+/// const OTAA: Otaa = Otaa::new(0x0080_E115_0000_0001, 0x70B3_D57E_D000_0000, [0x2B; 16]);
+///
+/// let mut dp: pac::Peripherals = pac::Peripherals::take().unwrap();
+/// let cp: pac::CorePeripherals = pac::CorePeripherals::take().unwrap();
+///
+/// let delay = new_delay(cp.SYST, &dp.RCC);
+/// let aes: Aes = Aes::new(dp.AES, &mut dp.RCC);
+/// let sg: SubGhz<SgMiso, SgMosi> = SubGhz::new(dp.SPI3, &mut dp.RCC);
+/// let radio = LoRaRadio::new(sg, |_: RfState| (), CFG).unwrap();
+///
+/// let mac: Mac = Mac::new_otaa(OTAA, Counters::new());
+/// let mut device = Device::new(radio, aes, delay, now, mac, Region::Eu868);
+/// device.join(&TX).unwrap();
+/// if let Some(downlink) = device.send(&TX, 1, b"hello", false).unwrap() {
+///     // handle downlink
+/// }
+/// ```
+#[derive(Debug)]
+pub struct Device<R, C, D, N> {
+    radio: R,
+    cipher: C,
+    delay: D,
+    now: N,
+    mac: Mac,
+    region: Region,
+    buf: [u8; 255],
+}
+
+impl<R, C, D, N> Device<R, C, D, N>
+where
+    R: Radio,
+    C: BlockCipher,
+    D: DelayUs<u32>,
+    N: FnMut() -> Duration,
+{
+    /// Create a new device.
+    ///
+    /// `now` returns the current time of a monotonic clock, the epoch does
+    /// not matter as long as it does not change.
+    ///
+    /// The second receive window uses the RX2 configuration of `region`,
+    /// see [`Mac::rx2`].
+    pub fn new(radio: R, cipher: C, delay: D, now: N, mac: Mac, region: Region) -> Self {
+        Device {
+            radio,
+            cipher,
+            delay,
+            now,
+            mac,
+            region,
+            buf: [0; 255],
+        }
+    }
+
+    /// Free the radio, cipher, delay, clock, and MAC from the device.
+    pub fn free(self) -> (R, C, D, N, Mac) {
+        (self.radio, self.cipher, self.delay, self.now, self.mac)
+    }
+
+    /// Get the MAC.
+    ///
+    /// The [`Mac::counters`] must be persisted after each operation.
+    pub fn mac(&self) -> &Mac {
+        &self.mac
+    }
+
+    /// Join the network with over-the-air activation.
+    ///
+    /// The join request is sent with `tx`, and RX1 uses `tx`.
+    /// Use [`join_region`](Self::join_region) for regions with dedicated
+    /// downlink channels.
+    ///
+    /// Each attempt uses a new DevNonce, the [`Mac::counters`] must be
+    /// persisted after every attempt, including failed attempts.
+    pub fn join(&mut self, tx: &RfCfg) -> Result<(), DeviceError<R::Error, C::Error>> {
+        let tx_done: Duration = self.transmit_join_request(tx)?;
+        self.receive_join_accept(tx_done, tx)
+    }
+
+    /// Join the network with over-the-air activation on channel `ch` of
+    /// the region with data rate `dr`.
+    ///
+    /// RX1 uses the regional downlink channel and data rate, see
+    /// [`Mac::rx1`].
+    ///
+    /// Each attempt uses a new DevNonce, the [`Mac::counters`] must be
+    /// persisted after every attempt, including failed attempts.
+    ///
+    /// # Errors
+    ///
+    /// [`DeviceError::Channel`] if the channel or data rate are not valid for
+    /// the region, nothing is transmitted.
+    pub fn join_region(&mut self, ch: u8, dr: u8) -> Result<(), DeviceError<R::Error, C::Error>> {
+        let tx: RfCfg = RfCfg::uplink(self.region, ch, dr).ok_or(DeviceError::Channel)?;
+        let tx_done: Duration = self.transmit_join_request(&tx)?;
+        // the RX1 data rate offset is zero while the join is pending
+        let rx1: RfCfg = self
+            .mac
+            .rx1(self.region, ch, dr)
+            .ok_or(DeviceError::Channel)?;
+        self.receive_join_accept(tx_done, &rx1)
+    }
+
+    /// Transmit the first `len` bytes of the buffer, returns the time the
+    /// transmission ended.
+    fn transmit(
+        &mut self,
+        tx: &RfCfg,
+        len: usize,
+    ) -> Result<Duration, DeviceError<R::Error, C::Error>> {
+        self.radio
+            .transmit(tx, &self.buf[..len])
+            .map_err(DeviceError::Radio)?;
+        Ok((self.now)())
+    }
+
+    fn transmit_join_request(
+        &mut self,
+        tx: &RfCfg,
+    ) -> Result<Duration, DeviceError<R::Error, C::Error>> {
+        let len: usize = self.mac.join_request(&mut self.cipher, &mut self.buf)?;
+        self.transmit(tx, len)
+    }
+
+    fn receive_join_accept(
+        &mut self,
+        tx_done: Duration,
+        rx1: &RfCfg,
+    ) -> Result<(), DeviceError<R::Error, C::Error>> {
+        // the regional default RX2 data rate is used while the join is pending
+        let rx2: RfCfg = self.mac.rx2(self.region).ok_or(DeviceError::Channel)?;
+        match self.receive(tx_done, rx1, &rx2)? {
+            Some(Rx::JoinAccept) => Ok(()),
+            _ => Err(DeviceError::NoJoinAccept),
+        }
+    }
+
+    /// Send an uplink with `tx`, and receive the downlink.
+    ///
+    /// RX1 uses `tx`, use [`send_region`](Self::send_region) for regions
+    /// with dedicated downlink channels or a non-zero RX1 data rate offset.
+    ///
+    /// Returns `None` if no downlink was received.
+    ///
+    /// # Errors
+    ///
+    /// [`DeviceError::Channel`] if the RX2 data rate of the session is not
+    /// valid for the region, nothing is transmitted.
+    pub fn send(
+        &mut self,
+        tx: &RfCfg,
+        fport: u8,
+        payload: &[u8],
+        confirmed: bool,
+    ) -> Result<Option<Downlink<'_>>, DeviceError<R::Error, C::Error>> {
+        self.send_rx1(tx, tx, fport, payload, confirmed)
+    }
+
+    /// Send an uplink on channel `ch` of the region with data rate `dr`, and
+    /// receive the downlink.
+    ///
+    /// RX1 uses the regional downlink channel and the data rate with the RX1
+    /// data rate offset of the session, see [`Mac::rx1`].
+    ///
+    /// Returns `None` if no downlink was received.
+    ///
+    /// # Errors
+    ///
+    /// [`DeviceError::Channel`] if the channel or data rate, or the RX2 data
+    /// rate of the session, are not valid for the region, nothing is
+    /// transmitted.
+    pub fn send_region(
+        &mut self,
+        ch: u8,
+        dr: u8,
+        fport: u8,
+        payload: &[u8],
+        confirmed: bool,
+    ) -> Result<Option<Downlink<'_>>, DeviceError<R::Error, C::Error>> {
+        let tx: RfCfg = RfCfg::uplink(self.region, ch, dr).ok_or(DeviceError::Channel)?;
+        let rx1: RfCfg = self
+            .mac
+            .rx1(self.region, ch, dr)
+            .ok_or(DeviceError::Channel)?;
+        self.send_rx1(&tx, &rx1, fport, payload, confirmed)
+    }
+
+    fn send_rx1(
+        &mut self,
+        tx: &RfCfg,
+        rx1: &RfCfg,
+        fport: u8,
+        payload: &[u8],
+        confirmed: bool,
+    ) -> Result<Option<Downlink<'_>>, DeviceError<R::Error, C::Error>> {
+        let rx2: RfCfg = self.mac.rx2(self.region).ok_or(DeviceError::Channel)?;
+        let len: usize =
+            self.mac
+                .uplink(&mut self.cipher, fport, payload, confirmed, &mut self.buf)?;
+        let tx_done: Duration = self.transmit(tx, len)?;
+        match self.receive(tx_done, rx1, &rx2)? {
+            Some(Rx::Data(downlink)) => Ok(Some(downlink)),
+            _ => Ok(None),
+        }
+    }
+
+    /// Delay until `delay` after `start`.
+    ///
+    /// This returns immediately if that time has already passed.
+    fn delay_from(&mut self, start: Duration, delay: Duration) {
+        let elapsed: Duration = (self.now)().saturating_sub(start);
+        self.delay
+            .delay_us(micros_sat(delay.saturating_sub(elapsed)));
+    }
+
+    /// Open the receive windows after an uplink that ended at `tx_done`.
+    ///
+    /// RX2 is skipped if a packet was received in RX1.
+    fn receive(
+        &mut self,
+        tx_done: Duration,
+        rx1: &RfCfg,
+        rx2: &RfCfg,
+    ) -> Result<Option<Rx<'_>>, DeviceError<R::Error, C::Error>> {
+        let [rx1, rx2]: [RxWindow; 2] = self.mac.rx_windows(rx1, rx2);
+
+        self.delay_from(tx_done, rx1.delay());
+        let mut len: Option<usize> = self
+            .radio
+            .receive(rx1.rf(), rx1.timeout(), &mut self.buf)
+            .map_err(DeviceError::Radio)?;
+
+        // RX1 may end early, for example on a CRC error
+        if len.is_none() {
+            self.delay_from(tx_done, rx2.delay());
+            len = self
+                .radio
+                .receive(rx2.rf(), rx2.timeout(), &mut self.buf)
+                .map_err(DeviceError::Radio)?;
+        }
+
+        match len {
+            Some(len) => Ok(Some(
+                self.mac.downlink(&mut self.cipher, &mut self.buf[..len])?,
+            )),
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Device, DeviceError, Radio, RfCfg};
+    use crate::{
+        lorawan::{
+            crypto::{self, Dir},
+            soft_aes::SoftAes,
+            Counters, Error, Mac, Otaa,
+        },
+        subghz::{
+            region::{DataRate, Region},
+            LoRaBandwidth, LoRaModParams, RfFreq, SpreadingFactor,
+        },
+    };
+    use core::{cell::Cell, time::Duration};
+    use embedded_hal::blocking::delay::DelayUs;
+
+    const APP_KEY: [u8; 16] = [0x2B; 16];
+    const OTAA: Otaa = Otaa::new(1, 2, APP_KEY);
+    const TX: RfCfg = RfCfg::new(
+        RfFreq::from_frequency(868_100_000),
+        LoRaModParams::new()
+            .set_sf(SpreadingFactor::Sf7)
+            .set_bw(LoRaBandwidth::Bw125),
+    );
+
+    /// Regional default RX2 configuration.
+    fn rx2(region: Region) -> RfCfg {
+        let (rf_freq, dr): (RfFreq, u8) = region.rx2();
+        match region.data_rate(dr) {
+            Some(DataRate::LoRa(mod_params)) => RfCfg::new(rf_freq, mod_params),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Monotonic clock advanced by the delay and the radio.
+    #[derive(Default)]
+    struct Clock(Cell<Duration>);
+
+    impl Clock {
+        fn now(&self) -> Duration {
+            self.0.get()
+        }
+
+        fn advance(&self, duration: Duration) {
+            self.0.set(self.0.get() + duration)
+        }
+    }
+
+    impl DelayUs<u32> for &Clock {
+        fn delay_us(&mut self, us: u32) {
+            self.advance(Duration::from_micros(us.into()))
+        }
+    }
+
+    /// Radio connected to a simulated network server.
+    ///
+    /// The server answers a join request with a join accept in RX1, and
+    /// answers uplinks on port 2 with a downlink in RX2.
+    #[derive(Default)]
+    struct FakeNetwork {
+        rx_windows: Vec<(RfCfg, Duration)>,
+        reply: Option<(RfCfg, Vec<u8>)>,
+        nwk_skey: [u8; 16],
+        app_skey: [u8; 16],
+    }
+
+    impl FakeNetwork {
+        fn join_accept(&mut self, req: &[u8]) -> Vec<u8> {
+            let dev_nonce: u16 = u16::from_le_bytes([req[17], req[18]]);
+            let mut buf: Vec<u8> = vec![0x20, 1, 0, 0, 0x13, 0, 0, 4, 3, 2, 1, 0, 1];
+            let mic: [u8; 4] = crypto::join_mic(&mut SoftAes, &APP_KEY, &buf).unwrap();
+            buf.extend_from_slice(&mic);
+            let mut block: [u8; 16] = buf[1..].try_into().unwrap();
+            SoftAes::decrypt_block(&APP_KEY, &mut block);
+            buf[1..].copy_from_slice(&block);
+            self.nwk_skey =
+                crypto::session_key(&mut SoftAes, &APP_KEY, 1, 1, 0x13, dev_nonce).unwrap();
+            self.app_skey =
+                crypto::session_key(&mut SoftAes, &APP_KEY, 2, 1, 0x13, dev_nonce).unwrap();
+            buf
+        }
+
+        fn data_down(&mut self, payload: &[u8]) -> Vec<u8> {
+            let mut buf: Vec<u8> = vec![0x60, 4, 3, 2, 1, 0x20, 0, 0, 2];
+            let mut frm_payload: Vec<u8> = payload.to_vec();
+            crypto::crypt_payload(
+                &mut SoftAes,
+                &self.app_skey,
+                Dir::Down,
+                0x0102_0304,
+                0,
+                &mut frm_payload,
+            )
+            .unwrap();
+            buf.extend_from_slice(&frm_payload);
+            let mic: [u8; 4] = crypto::data_mic(
+                &mut SoftAes,
+                &self.nwk_skey,
+                Dir::Down,
+                0x0102_0304,
+                0,
+                &buf,
+            )
+            .unwrap();
+            buf.extend_from_slice(&mic);
+            buf
+        }
+    }
+
+    struct FakeRadio {
+        net: FakeNetwork,
+    }
+
+    impl Radio for FakeRadio {
+        type Error = ();
+
+        fn transmit(&mut self, rf: &RfCfg, data: &[u8]) -> Result<(), ()> {
+            assert_eq!(rf, &TX);
+            self.net.reply = match data[0] {
+                0x00 => Some((TX, self.net.join_accept(data))),
+                0x40 if data[8] == 2 => Some((rx2(Region::Eu868), self.net.data_down(b"pong"))),
+                _ => None,
+            };
+            Ok(())
+        }
+
+        fn receive(
+            &mut self,
+            rf: &RfCfg,
+            timeout: Duration,
+            buf: &mut [u8],
+        ) -> Result<Option<usize>, ()> {
+            self.net.rx_windows.push((*rf, timeout));
+            match self.net.reply.take() {
+                Some((reply_rf, data)) if reply_rf == *rf => {
+                    buf[..data.len()].copy_from_slice(&data);
+                    Ok(Some(data.len()))
+                }
+                reply => {
+                    self.net.reply = reply;
+                    Ok(None)
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn join_and_send() {
+        let radio: FakeRadio = FakeRadio {
+            net: FakeNetwork::default(),
+        };
+        let mac: Mac = Mac::new_otaa(OTAA, Counters::new());
+        let clock: Clock = Clock::default();
+        let mut device = Device::new(radio, SoftAes, &clock, || clock.now(), mac, Region::Eu868);
+        assert_eq!(
+            device.send(&TX, 1, b"x", false),
+            Err(DeviceError::Mac(Error::NotJoined))
+        );
+
+        device.join(&TX).unwrap();
+        assert!(device.mac().is_joined());
+        assert_eq!(device.mac().session().unwrap().dev_addr(), 0x0102_0304);
+        assert_eq!(device.radio.net.rx_windows.len(), 1);
+
+        // no reply on port 1, both windows open
+        assert_eq!(device.send(&TX, 1, b"ping", false), Ok(None));
+        assert_eq!(device.radio.net.rx_windows.len(), 3);
+        assert_eq!(device.radio.net.rx_windows[1].0, TX);
+        assert_eq!(device.radio.net.rx_windows[2].0, rx2(Region::Eu868));
+
+        let downlink = device.send(&TX, 2, b"ping", false).unwrap().unwrap();
+        assert_eq!(downlink.fport(), Some(2));
+        assert_eq!(downlink.payload(), b"pong");
+        assert!(downlink.ack());
+        assert_eq!(device.mac().counters().fcnt_up, 2);
+        assert_eq!(device.mac().counters().fcnt_down, 1);
+    }
+
+    #[test]
+    fn rx_window_timing() {
+        const AIRTIME: Duration = Duration::from_millis(50);
+
+        /// Receives nothing, RX1 ends after `rx1_listen` or the timeout.
+        struct Silent<'a> {
+            clock: &'a Clock,
+            rx1_listen: Option<Duration>,
+            // time the window opened, and the window timeout
+            windows: Vec<(Duration, Duration)>,
+        }
+
+        impl<'a> Radio for Silent<'a> {
+            type Error = ();
+
+            fn transmit(&mut self, _: &RfCfg, _: &[u8]) -> Result<(), ()> {
+                self.clock.advance(AIRTIME);
+                Ok(())
+            }
+
+            fn receive(
+                &mut self,
+                _: &RfCfg,
+                timeout: Duration,
+                _: &mut [u8],
+            ) -> Result<Option<usize>, ()> {
+                self.windows.push((self.clock.now(), timeout));
+                let listen: Duration = match (self.windows.len(), self.rx1_listen) {
+                    (1, Some(listen)) => listen,
+                    _ => timeout,
+                };
+                self.clock.advance(listen);
+                Ok(None)
+            }
+        }
+
+        // RX1 listens until the timeout, or ends early on a CRC error
+        for rx1_listen in [None, Some(Duration::from_millis(5))] {
+            let clock: Clock = Clock::default();
+            let radio: Silent = Silent {
+                clock: &clock,
+                rx1_listen,
+                windows: Vec::new(),
+            };
+            let mac: Mac = Mac::new_otaa(OTAA, Counters::new());
+            let mut device =
+                Device::new(radio, SoftAes, &clock, || clock.now(), mac, Region::Eu868);
+            assert_eq!(device.join(&TX), Err(DeviceError::NoJoinAccept));
+            let windows: Vec<(Duration, Duration)> = device.free().0.windows;
+
+            // the windows are timed from the end of the uplink, and open 20 ms
+            // before JOIN_ACCEPT_DELAY1 and JOIN_ACCEPT_DELAY2
+            assert_eq!(windows.len(), 2);
+            assert_eq!(windows[0].0, AIRTIME + Duration::from_millis(4980));
+            assert_eq!(windows[1].0, AIRTIME + Duration::from_millis(5980));
+            assert_eq!(
+                windows[0].1,
+                Duration::from_millis(40) + TX.mod_params().symbol_time() * 6
+            );
+            assert_eq!(
+                windows[1].1,
+                Duration::from_millis(40) + rx2(Region::Eu868).mod_params().symbol_time() * 6
+            );
+        }
+    }
+
+    #[test]
+    fn region_rx1() {
+        use crate::lorawan::Session;
+
+        /// Records the RF configuration of every transmission and window.
+        struct Recorder<'a>(&'a mut Vec<RfCfg>);
+
+        impl<'a> Radio for Recorder<'a> {
+            type Error = ();
+
+            fn transmit(&mut self, rf: &RfCfg, _: &[u8]) -> Result<(), ()> {
+                self.0.push(*rf);
+                Ok(())
+            }
+
+            fn receive(
+                &mut self,
+                rf: &RfCfg,
+                _: Duration,
+                _: &mut [u8],
+            ) -> Result<Option<usize>, ()> {
+                self.0.push(*rf);
+                Ok(None)
+            }
+        }
+
+        let uplink = |ch: u8, dr: u8| RfCfg::uplink(Region::Us915, ch, dr).unwrap();
+        let downlink = |mhz_x10: u32, dr: u8| match Region::Us915.data_rate(dr) {
+            Some(DataRate::LoRa(mod_params)) => {
+                RfCfg::new(RfFreq::from_frequency(mhz_x10 * 100_000), mod_params)
+            }
+            _ => unreachable!(),
+        };
+
+        let mut rf: Vec<RfCfg> = Vec::new();
+        let clock: Clock = Clock::default();
+        let mac: Mac = Mac::new_otaa(OTAA, Counters::new());
+        {
+            let mut device = Device::new(
+                Recorder(&mut rf),
+                SoftAes,
+                &clock,
+                || clock.now(),
+                mac,
+                Region::Us915,
+            );
+            assert_eq!(device.join_region(9, 0), Err(DeviceError::NoJoinAccept));
+        }
+        assert_eq!(rf, [uplink(9, 0), downlink(9239, 10), rx2(Region::Us915)]);
+
+        rf.clear();
+        let session: Session = Session::new(1, [1; 16], [2; 16]).set_rx1_dr_offset(1);
+        let mac: Mac = Mac::new_abp(session);
+        {
+            let mut device = Device::new(
+                Recorder(&mut rf),
+                SoftAes,
+                &clock,
+                || clock.now(),
+                mac,
+                Region::Us915,
+            );
+            assert_eq!(
+                device.send_region(64, 0, 1, b"x", false),
+                Err(DeviceError::Channel)
+            );
+            assert_eq!(device.send_region(9, 2, 1, b"x", false), Ok(None));
+        }
+        assert_eq!(rf, [uplink(9, 2), downlink(9239, 11), rx2(Region::Us915)]);
+
+        // RX2 data rate of the session
+        rf.clear();
+        let mac: Mac = Mac::new_abp(session.set_rx2_dr(10));
+        {
+            let mut device = Device::new(
+                Recorder(&mut rf),
+                SoftAes,
+                &clock,
+                || clock.now(),
+                mac,
+                Region::Us915,
+            );
+            assert_eq!(device.send_region(9, 2, 1, b"x", false), Ok(None));
+        }
+        assert_eq!(rf, [uplink(9, 2), downlink(9239, 11), downlink(9233, 10)]);
+
+        // FSK RX2 data rate, nothing is transmitted
+        rf.clear();
+        let mac: Mac = Mac::new_abp(session.set_rx2_dr(7));
+        {
+            let mut device = Device::new(
+                Recorder(&mut rf),
+                SoftAes,
+                &clock,
+                || clock.now(),
+                mac,
+                Region::Eu868,
+            );
+            assert_eq!(device.send(&TX, 1, b"x", false), Err(DeviceError::Channel));
+        }
+        assert!(rf.is_empty());
+    }
+}
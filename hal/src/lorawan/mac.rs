@@ -0,0 +1,1257 @@
+use super::{
+    crypto::{self, BlockCipher, Dir},
+    Counters, RfCfg,
+};
+use crate::subghz::{
+    region::{DataRate, Region},
+    RfFreq,
+};
+use core::time::Duration;
+
+const JOIN_REQUEST_LEN: usize = 23;
+// MHDR + DevAddr + FCtrl + FCnt + MIC
+const DATA_OVERHEAD: usize = 12;
+const MIC_LEN: usize = 4;
+const FPORT_MAX: u8 = 223;
+
+const JOIN_ACCEPT_DELAY1: Duration = Duration::from_secs(5);
+const RX_DELAY2_OFFSET: Duration = Duration::from_secs(1);
+// timing error allowed on each side of the receive window
+const RX_WINDOW_MARGIN: Duration = Duration::from_millis(20);
+// symbols required to detect the preamble
+const RX_MIN_SYMBOLS: u32 = 6;
+
+// FCtrl bits
+const FCTRL_ACK: u8 = 1 << 5;
+const FCTRL_FPENDING: u8 = 1 << 4;
+const FCTRL_FOPTS_LEN: u8 = 0x0F;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum MType {
+    JoinRequest = 0b000,
+    JoinAccept = 0b001,
+    UnconfirmedDataUp = 0b010,
+    UnconfirmedDataDown = 0b011,
+    ConfirmedDataUp = 0b100,
+    ConfirmedDataDown = 0b101,
+    Rfu = 0b110,
+    Proprietary = 0b111,
+}
+
+impl MType {
+    const fn from_mhdr(mhdr: u8) -> MType {
+        match mhdr >> 5 {
+            0b000 => MType::JoinRequest,
+            0b001 => MType::JoinAccept,
+            0b010 => MType::UnconfirmedDataUp,
+            0b011 => MType::UnconfirmedDataDown,
+            0b100 => MType::ConfirmedDataUp,
+            0b101 => MType::ConfirmedDataDown,
+            0b110 => MType::Rfu,
+            _ => MType::Proprietary,
+        }
+    }
+
+    // major version is always LoRaWAN R1 (0)
+    const fn mhdr(self) -> u8 {
+        (self as u8) << 5
+    }
+}
+
+/// LoRaWAN MAC errors.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Error<E> {
+    /// Block cipher error.
+    Cipher(E),
+    /// The buffer is too small for the frame.
+    BufTooSmall,
+    /// The frame would be longer than 255 bytes.
+    PayloadLen,
+    /// The port is reserved, ports 224 to 255 cannot be used.
+    FPort,
+    /// A data frame was requested before joining.
+    NotJoined,
+    /// A join was requested without OTAA credentials.
+    NoCredentials,
+    /// All DevNonce values have been used, the device cannot join again.
+    DevNonceExhausted,
+    /// A join accept was received without a pending join request.
+    JoinNotPending,
+    /// The received frame is malformed, or is not a downlink.
+    Frame,
+    /// The received frame is addressed to another device.
+    DevAddr,
+    /// The message integrity code of the received frame is invalid.
+    Mic,
+    /// The JoinNonce of the join accept was already used.
+    Replay,
+}
+
+impl<E> From<E> for Error<E> {
+    fn from(e: E) -> Self {
+        Error::Cipher(e)
+    }
+}
+
+/// Over-the-air activation credentials.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Otaa {
+    dev_eui: u64,
+    join_eui: u64,
+    app_key: [u8; 16],
+}
+
+impl Otaa {
+    /// Create new OTAA credentials.
+    ///
+    /// The EUIs are the numbers as written, for example
+    /// `0x70B3_D57E_D000_0000`, the key is in the order it is written.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::lorawan::Otaa;
+    ///
+    /// const OTAA: Otaa = Otaa::new(0x0080_E115_0000_0001, 0x70B3_D57E_D000_0000, [0x2B; 16]);
+    /// assert_eq!(OTAA.dev_eui(), 0x0080_E115_0000_0001);
+    /// assert_eq!(OTAA.join_eui(), 0x70B3_D57E_D000_0000);
+    /// ```
+    pub const fn new(dev_eui: u64, join_eui: u64, app_key: [u8; 16]) -> Otaa {
+        Otaa {
+            dev_eui,
+            join_eui,
+            app_key,
+        }
+    }
+
+    /// Get the DevEUI.
+    pub const fn dev_eui(&self) -> u64 {
+        self.dev_eui
+    }
+
+    /// Get the JoinEUI, previously called AppEUI.
+    pub const fn join_eui(&self) -> u64 {
+        self.join_eui
+    }
+}
+
+/// Network session.
+///
+/// This is created by a join accept for OTAA, or created with
+/// [`Session::new`] for activation by personalization (ABP).
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Session {
+    dev_addr: u32,
+    nwk_skey: [u8; 16],
+    app_skey: [u8; 16],
+    fcnt_up: u32,
+    fcnt_down: u32,
+    rx1_dr_offset: u8,
+    rx2_dr: Option<u8>,
+    rx_delay: u8,
+}
+
+impl Session {
+    /// Create a new session for activation by personalization (ABP).
+    ///
+    /// The frame counters start at zero, use
+    /// [`set_fcnt_up`](Self::set_fcnt_up) and
+    /// [`set_fcnt_down`](Self::set_fcnt_down) to restore persisted counters.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::lorawan::Session;
+    ///
+    /// const SESSION: Session = Session::new(0x2601_1BDA, [0x11; 16], [0x22; 16]).set_fcnt_up(42);
+    /// assert_eq!(SESSION.dev_addr(), 0x2601_1BDA);
+    /// assert_eq!(SESSION.fcnt_up(), 42);
+    /// assert_eq!(SESSION.fcnt_down(), 0);
+    /// assert_eq!(SESSION.rx_delay(), 1);
+    /// ```
+    pub const fn new(dev_addr: u32, nwk_skey: [u8; 16], app_skey: [u8; 16]) -> Session {
+        Session {
+            dev_addr,
+            nwk_skey,
+            app_skey,
+            fcnt_up: 0,
+            fcnt_down: 0,
+            rx1_dr_offset: 0,
+            rx2_dr: None,
+            rx_delay: 1,
+        }
+    }
+
+    /// Set the uplink frame counter of the next uplink.
+    #[must_use = "set_fcnt_up returns a modified Session"]
+    pub const fn set_fcnt_up(mut self, fcnt: u32) -> Session {
+        self.fcnt_up = fcnt;
+        self
+    }
+
+    /// Set the lowest accepted downlink frame counter.
+    #[must_use = "set_fcnt_down returns a modified Session"]
+    pub const fn set_fcnt_down(mut self, fcnt: u32) -> Session {
+        self.fcnt_down = fcnt;
+        self
+    }
+
+    /// Set the RX1 delay in seconds.
+    ///
+    /// Zero is treated as one second.
+    #[must_use = "set_rx_delay returns a modified Session"]
+    pub const fn set_rx_delay(mut self, secs: u8) -> Session {
+        self.rx_delay = match secs & 0x0F {
+            0 => 1,
+            x => x,
+        };
+        self
+    }
+
+    /// Set the RX1 data rate offset.
+    ///
+    /// This is set by the join accept DLSettings for OTAA, ABP devices must
+    /// use the offset configured in the network server.
+    /// Only the lower 3 bits are used.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::lorawan::Session;
+    ///
+    /// const SESSION: Session =
+    ///     Session::new(0x2601_1BDA, [0x11; 16], [0x22; 16]).set_rx1_dr_offset(2);
+    /// assert_eq!(SESSION.rx1_dr_offset(), 2);
+    /// ```
+    #[must_use = "set_rx1_dr_offset returns a modified Session"]
+    pub const fn set_rx1_dr_offset(mut self, offset: u8) -> Session {
+        self.rx1_dr_offset = offset & 0x7;
+        self
+    }
+
+    /// Set the RX2 data rate.
+    ///
+    /// This is set by the join accept DLSettings for OTAA, ABP devices must
+    /// use the RX2 data rate configured in the network server, or leave it
+    /// unset to use the regional default.
+    /// Only the lower 4 bits are used.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::lorawan::Session;
+    ///
+    /// const SESSION: Session = Session::new(0x2601_1BDA, [0x11; 16], [0x22; 16]);
+    /// assert_eq!(SESSION.rx2_dr(), None);
+    /// assert_eq!(SESSION.set_rx2_dr(3).rx2_dr(), Some(3));
+    /// ```
+    #[must_use = "set_rx2_dr returns a modified Session"]
+    pub const fn set_rx2_dr(mut self, dr: u8) -> Session {
+        self.rx2_dr = Some(dr & 0x0F);
+        self
+    }
+
+    /// Get the device address.
+    pub const fn dev_addr(&self) -> u32 {
+        self.dev_addr
+    }
+
+    /// Get the uplink frame counter of the next uplink.
+    pub const fn fcnt_up(&self) -> u32 {
+        self.fcnt_up
+    }
+
+    /// Get the lowest accepted downlink frame counter.
+    pub const fn fcnt_down(&self) -> u32 {
+        self.fcnt_down
+    }
+
+    /// Get the RX1 data rate offset from the join accept DLSettings.
+    ///
+    /// The mapping from data rate offset to modulation parameters is
+    /// regional.
+    pub const fn rx1_dr_offset(&self) -> u8 {
+        self.rx1_dr_offset
+    }
+
+    /// Get the RX2 data rate from the join accept DLSettings.
+    ///
+    /// Returns `None` if the regional default RX2 data rate is used, see
+    /// [`Region::rx2`].
+    /// The mapping from data rate to modulation parameters is regional.
+    pub const fn rx2_dr(&self) -> Option<u8> {
+        self.rx2_dr
+    }
+
+    /// Get the RX1 delay in seconds.
+    pub const fn rx_delay(&self) -> u8 {
+        self.rx_delay
+    }
+}
+
+/// Receive window timing.
+///
+/// Returned by [`Mac::rx_windows`].
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct RxWindow {
+    rf: RfCfg,
+    delay: Duration,
+    timeout: Duration,
+}
+
+impl RxWindow {
+    /// RF configuration of the window.
+    pub const fn rf(&self) -> &RfCfg {
+        &self.rf
+    }
+
+    /// Time from the end of the uplink to opening the receiver.
+    pub const fn delay(&self) -> Duration {
+        self.delay
+    }
+
+    /// Time to listen for a preamble.
+    pub const fn timeout(&self) -> Duration {
+        self.timeout
+    }
+}
+
+/// Received data frame.
+///
+/// Returned by [`Mac::downlink`].
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Downlink<'a> {
+    fport: Option<u8>,
+    payload: &'a [u8],
+    fopts: &'a [u8],
+    fcnt: u32,
+    fctrl: u8,
+    confirmed: bool,
+}
+
+impl<'a> Downlink<'a> {
+    /// Port of the payload, `None` if the frame has no payload.
+    ///
+    /// Port 0 carries MAC commands.
+    pub const fn fport(&self) -> Option<u8> {
+        self.fport
+    }
+
+    /// Decrypted payload.
+    pub const fn payload(&self) -> &'a [u8] {
+        self.payload
+    }
+
+    /// MAC commands piggybacked in the frame header.
+    pub const fn fopts(&self) -> &'a [u8] {
+        self.fopts
+    }
+
+    /// Downlink frame counter.
+    pub const fn fcnt(&self) -> u32 {
+        self.fcnt
+    }
+
+    /// Returns `true` if the network acknowledged the last confirmed uplink.
+    pub const fn ack(&self) -> bool {
+        self.fctrl & FCTRL_ACK != 0
+    }
+
+    /// Returns `true` if the network has more data pending.
+    pub const fn fpending(&self) -> bool {
+        self.fctrl & FCTRL_FPENDING != 0
+    }
+
+    /// Returns `true` if this is a confirmed downlink.
+    ///
+    /// The acknowledgement is sent in the next uplink.
+    pub const fn confirmed(&self) -> bool {
+        self.confirmed
+    }
+}
+
+/// Received frame.
+///
+/// Returned by [`Mac::downlink`].
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Rx<'a> {
+    /// A join accept was received, the device has joined.
+    JoinAccept,
+    /// A data frame was received.
+    Data(Downlink<'a>),
+}
+
+/// LoRaWAN 1.0.4 class A frame state machine.
+///
+/// The MAC does not perform any I/O, it builds uplink frames into a buffer,
+/// processes received frames, and provides the receive window timing.
+/// MAC commands are not processed or answered, see the
+/// [module documentation](super).
+/// See [`Device`](super::Device) for a driver that performs the I/O.
+///
+/// The [`counters`](Self::counters) must be persisted after every join
+/// request, uplink, and downlink to prevent nonce and frame counter reuse.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Mac {
+    otaa: Option<Otaa>,
+    session: Option<Session>,
+    dev_nonce: u16,
+    join_nonce: Option<u32>,
+    join_pending: Option<u16>,
+    ack_pending: bool,
+}
+
+impl Mac {
+    /// Create a MAC for over-the-air activation.
+    ///
+    /// `counters` are the persisted counters, or [`Counters::new`] for a
+    /// device that never joined.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::lorawan::{Counters, Mac, Otaa};
+    ///
+    /// const OTAA: Otaa = Otaa::new(0x0080_E115_0000_0001, 0x70B3_D57E_D000_0000, [0x2B; 16]);
+    /// const MAC: Mac = Mac::new_otaa(OTAA, Counters::new());
+    /// assert!(!MAC.is_joined());
+    /// ```
+    pub const fn new_otaa(otaa: Otaa, counters: Counters) -> Mac {
+        Mac {
+            otaa: Some(otaa),
+            session: None,
+            dev_nonce: counters.dev_nonce,
+            join_nonce: counters.join_nonce,
+            join_pending: None,
+            ack_pending: false,
+        }
+    }
+
+    /// Create a MAC for activation by personalization.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::lorawan::{Mac, Session};
+    ///
+    /// const MAC: Mac = Mac::new_abp(Session::new(0x2601_1BDA, [0x11; 16], [0x22; 16]));
+    /// assert!(MAC.is_joined());
+    /// ```
+    pub const fn new_abp(session: Session) -> Mac {
+        Mac {
+            otaa: None,
+            session: Some(session),
+            dev_nonce: 0,
+            join_nonce: None,
+            join_pending: None,
+            ack_pending: false,
+        }
+    }
+
+    /// Returns `true` if the device has a network session.
+    pub const fn is_joined(&self) -> bool {
+        self.session.is_some()
+    }
+
+    /// Get the network session.
+    pub const fn session(&self) -> Option<&Session> {
+        self.session.as_ref()
+    }
+
+    /// Get the counters to persist.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::lorawan::{Mac, Session};
+    ///
+    /// const MAC: Mac = Mac::new_abp(Session::new(0x2601_1BDA, [0x11; 16], [0x22; 16]).set_fcnt_up(7));
+    /// assert_eq!(MAC.counters().fcnt_up, 7);
+    /// ```
+    pub const fn counters(&self) -> Counters {
+        let (fcnt_up, fcnt_down): (u32, u32) = match self.session {
+            Some(session) => (session.fcnt_up, session.fcnt_down),
+            None => (0, 0),
+        };
+        Counters {
+            dev_nonce: self.dev_nonce,
+            join_nonce: self.join_nonce,
+            fcnt_up,
+            fcnt_down,
+        }
+    }
+
+    /// Build a join request.
+    ///
+    /// Returns the length of the frame written to `buf`.
+    ///
+    /// Each join request uses a new DevNonce, persist the
+    /// [`counters`](Self::counters) before transmitting.
+    pub fn join_request<C: BlockCipher>(
+        &mut self,
+        cipher: &mut C,
+        buf: &mut [u8],
+    ) -> Result<usize, Error<C::Error>> {
+        let otaa: Otaa = self.otaa.ok_or(Error::NoCredentials)?;
+        let dev_nonce: u16 = self.dev_nonce;
+        let next_dev_nonce: u16 = dev_nonce.checked_add(1).ok_or(Error::DevNonceExhausted)?;
+        let buf: &mut [u8] = buf.get_mut(..JOIN_REQUEST_LEN).ok_or(Error::BufTooSmall)?;
+
+        buf[0] = MType::JoinRequest.mhdr();
+        buf[1..9].copy_from_slice(&otaa.join_eui.to_le_bytes());
+        buf[9..17].copy_from_slice(&otaa.dev_eui.to_le_bytes());
+        buf[17..19].copy_from_slice(&dev_nonce.to_le_bytes());
+        let mic: [u8; 4] = crypto::join_mic(cipher, &otaa.app_key, &buf[..19])?;
+        buf[19..].copy_from_slice(&mic);
+
+        self.dev_nonce = next_dev_nonce;
+        self.join_pending = Some(dev_nonce);
+        Ok(JOIN_REQUEST_LEN)
+    }
+
+    /// Build a data uplink.
+    ///
+    /// An empty `payload` is sent without a port, for example to acknowledge
+    /// a confirmed downlink.
+    /// Port 0 is encrypted with the network session key for MAC commands.
+    ///
+    /// Returns the length of the frame written to `buf`.
+    pub fn uplink<C: BlockCipher>(
+        &mut self,
+        cipher: &mut C,
+        fport: u8,
+        payload: &[u8],
+        confirmed: bool,
+        buf: &mut [u8],
+    ) -> Result<usize, Error<C::Error>> {
+        let session: &mut Session = self.session.as_mut().ok_or(Error::NotJoined)?;
+        if fport > FPORT_MAX {
+            return Err(Error::FPort);
+        }
+        let len: usize = match payload.len() {
+            0 => DATA_OVERHEAD,
+            n => DATA_OVERHEAD + 1 + n,
+        };
+        if len > usize::from(u8::MAX) {
+            return Err(Error::PayloadLen);
+        }
+        let buf: &mut [u8] = buf.get_mut(..len).ok_or(Error::BufTooSmall)?;
+
+        let fcnt: u32 = session.fcnt_up;
+        let mtype: MType = if confirmed {
+            MType::ConfirmedDataUp
+        } else {
+            MType::UnconfirmedDataUp
+        };
+        buf[0] = mtype.mhdr();
+        buf[1..5].copy_from_slice(&session.dev_addr.to_le_bytes());
+        buf[5] = if self.ack_pending { FCTRL_ACK } else { 0 };
+        buf[6..8].copy_from_slice(&(fcnt as u16).to_le_bytes());
+        if !payload.is_empty() {
+            buf[8] = fport;
+            let frm_payload: &mut [u8] = &mut buf[9..len - MIC_LEN];
+            frm_payload.copy_from_slice(payload);
+            let key: &[u8; 16] = if fport == 0 {
+                &session.nwk_skey
+            } else {
+                &session.app_skey
+            };
+            crypto::crypt_payload(cipher, key, Dir::Up, session.dev_addr, fcnt, frm_payload)?;
+        }
+        let mic: [u8; 4] = crypto::data_mic(
+            cipher,
+            &session.nwk_skey,
+            Dir::Up,
+            session.dev_addr,
+            fcnt,
+            &buf[..len - MIC_LEN],
+        )?;
+        buf[len - MIC_LEN..].copy_from_slice(&mic);
+
+        session.fcnt_up = fcnt.wrapping_add(1);
+        self.ack_pending = false;
+        Ok(len)
+    }
+
+    /// Process a received frame.
+    ///
+    /// The frame is decrypted in-place, the returned [`Downlink`] borrows the
+    /// payload from `buf`.
+    pub fn downlink<'a, C: BlockCipher>(
+        &mut self,
+        cipher: &mut C,
+        buf: &'a mut [u8],
+    ) -> Result<Rx<'a>, Error<C::Error>> {
+        let mhdr: u8 = *buf.first().ok_or(Error::Frame)?;
+        if mhdr & 0b11 != 0 {
+            return Err(Error::Frame);
+        }
+        match MType::from_mhdr(mhdr) {
+            MType::JoinAccept => self.join_accept(cipher, buf).map(|_| Rx::JoinAccept),
+            MType::UnconfirmedDataDown => self.data_down(cipher, buf, false).map(Rx::Data),
+            MType::ConfirmedDataDown => self.data_down(cipher, buf, true).map(Rx::Data),
+            _ => Err(Error::Frame),
+        }
+    }
+
+    fn join_accept<C: BlockCipher>(
+        &mut self,
+        cipher: &mut C,
+        buf: &mut [u8],
+    ) -> Result<(), Error<C::Error>> {
+        let dev_nonce: u16 = self.join_pending.ok_or(Error::JoinNotPending)?;
+        let otaa: Otaa = self.otaa.ok_or(Error::NoCredentials)?;
+        // 16 bytes, or 32 bytes with a CFList, plus the MHDR
+        let len: usize = buf.len();
+        if len != 17 && len != 33 {
+            return Err(Error::Frame);
+        }
+
+        crypto::decrypt_join_accept(cipher, &otaa.app_key, &mut buf[1..])?;
+        let mic: [u8; 4] = crypto::join_mic(cipher, &otaa.app_key, &buf[..len - MIC_LEN])?;
+        if mic != buf[len - MIC_LEN..] {
+            return Err(Error::Mic);
+        }
+
+        let join_nonce: u32 = u32::from_le_bytes([buf[1], buf[2], buf[3], 0]);
+        let net_id: u32 = u32::from_le_bytes([buf[4], buf[5], buf[6], 0]);
+        let dev_addr: u32 = u32::from_le_bytes([buf[7], buf[8], buf[9], buf[10]]);
+        if matches!(self.join_nonce, Some(last) if join_nonce <= last) {
+            return Err(Error::Replay);
+        }
+
+        let nwk_skey: [u8; 16] =
+            crypto::session_key(cipher, &otaa.app_key, 0x01, join_nonce, net_id, dev_nonce)?;
+        let app_skey: [u8; 16] =
+            crypto::session_key(cipher, &otaa.app_key, 0x02, join_nonce, net_id, dev_nonce)?;
+
+        let session: Session = Session::new(dev_addr, nwk_skey, app_skey)
+            .set_rx1_dr_offset(buf[11] >> 4)
+            .set_rx2_dr(buf[11])
+            .set_rx_delay(buf[12]);
+        self.session = Some(session);
+        self.join_nonce = Some(join_nonce);
+        self.join_pending = None;
+        self.ack_pending = false;
+        Ok(())
+    }
+
+    fn data_down<'a, C: BlockCipher>(
+        &mut self,
+        cipher: &mut C,
+        buf: &'a mut [u8],
+        confirmed: bool,
+    ) -> Result<Downlink<'a>, Error<C::Error>> {
+        let session: &mut Session = self.session.as_mut().ok_or(Error::NotJoined)?;
+        let len: usize = buf.len();
+        if len < DATA_OVERHEAD {
+            return Err(Error::Frame);
+        }
+        let dev_addr: u32 = u32::from_le_bytes([buf[1], buf[2], buf[3], buf[4]]);
+        if dev_addr != session.dev_addr {
+            return Err(Error::DevAddr);
+        }
+        let fctrl: u8 = buf[5];
+        let fopts_end: usize = 8 + usize::from(fctrl & FCTRL_FOPTS_LEN);
+        if fopts_end + MIC_LEN > len {
+            return Err(Error::Frame);
+        }
+
+        // reconstruct the 32-bit counter from the 16 transmitted bits
+        let fcnt_lsb: u16 = u16::from_le_bytes([buf[6], buf[7]]);
+        let mut fcnt: u32 = (session.fcnt_down & 0xFFFF_0000) | u32::from(fcnt_lsb);
+        if fcnt < session.fcnt_down {
+            fcnt = fcnt.wrapping_add(0x1_0000);
+        }
+
+        let mic: [u8; 4] = crypto::data_mic(
+            cipher,
+            &session.nwk_skey,
+            Dir::Down,
+            dev_addr,
+            fcnt,
+            &buf[..len - MIC_LEN],
+        )?;
+        if mic != buf[len - MIC_LEN..] {
+            return Err(Error::Mic);
+        }
+
+        let fport: Option<u8> = if fopts_end + MIC_LEN < len {
+            Some(buf[fopts_end])
+        } else {
+            None
+        };
+        let payload_start: usize = fopts_end + usize::from(fport.is_some());
+        if let Some(fport) = fport {
+            if fport == 0 && fopts_end != 8 {
+                return Err(Error::Frame);
+            }
+            let key: &[u8; 16] = if fport == 0 {
+                &session.nwk_skey
+            } else {
+                &session.app_skey
+            };
+            crypto::crypt_payload(
+                cipher,
+                key,
+                Dir::Down,
+                dev_addr,
+                fcnt,
+                &mut buf[payload_start..len - MIC_LEN],
+            )?;
+        }
+
+        session.fcnt_down = fcnt.wrapping_add(1);
+        if confirmed {
+            self.ack_pending = true;
+        }
+
+        let buf: &'a [u8] = buf;
+        Ok(Downlink {
+            fport,
+            payload: &buf[payload_start..len - MIC_LEN],
+            fopts: &buf[8..fopts_end],
+            fcnt,
+            fctrl,
+            confirmed,
+        })
+    }
+
+    /// RX1 configuration for an uplink on channel `ch` of `region` with data
+    /// rate `dr`.
+    ///
+    /// The RX1 frequency is [`Region::rx1_channel`], and the RX1 data rate is
+    /// [`Region::rx1_dr`] with the RX1 data rate offset of the session.
+    /// The offset is zero while a join is pending.
+    ///
+    /// Returns `None` if the channel or data rate is not defined in the
+    /// region, or if the RX1 data rate is not a LoRa data rate.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::{
+    ///     lorawan::{Mac, RfCfg, Session},
+    ///     subghz::{region::Region, LoRaBandwidth, RfFreq, SpreadingFactor},
+    /// };
+    ///
+    /// const MAC: Mac = Mac::new_abp(Session::new(0x2601_1BDA, [0x11; 16], [0x22; 16]));
+    ///
+    /// // US915 uplink on channel 9 at DR0, downlink at 923.9 MHz with DR10
+    /// let rx1: RfCfg = MAC.rx1(Region::Us915, 9, 0).unwrap();
+    /// assert_eq!(rx1.rf_freq(), RfFreq::from_frequency(923_900_000));
+    /// assert_eq!(rx1.mod_params().sf(), SpreadingFactor::Sf10);
+    /// assert_eq!(rx1.mod_params().bw(), LoRaBandwidth::Bw500);
+    /// ```
+    pub fn rx1(&self, region: Region, ch: u8, dr: u8) -> Option<RfCfg> {
+        match region.channel_data_rates(ch) {
+            Some(drs) if drs.contains(&dr) => (),
+            _ => return None,
+        }
+        let offset: u8 = match (self.join_pending, self.session) {
+            (None, Some(session)) => session.rx1_dr_offset(),
+            _ => 0,
+        };
+        let rx1_dr: u8 = region.rx1_dr(dr, offset)?;
+        match region.data_rate(rx1_dr)? {
+            DataRate::LoRa(mod_params) => Some(RfCfg::new(region.rx1_channel(ch)?, mod_params)),
+            DataRate::Fsk(_) => None,
+        }
+    }
+
+    /// RX2 configuration of `region`.
+    ///
+    /// The RX2 frequency is the regional default, and the RX2 data rate is
+    /// [`Session::rx2_dr`], or the regional default while a join is pending
+    /// or if the session has no RX2 data rate, see [`Region::rx2`].
+    ///
+    /// Returns `None` if the RX2 data rate is not defined in the region, or
+    /// is not a LoRa data rate.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::{
+    ///     lorawan::{Mac, RfCfg, Session},
+    ///     subghz::{region::Region, RfFreq, SpreadingFactor},
+    /// };
+    ///
+    /// const SESSION: Session = Session::new(0x2601_1BDA, [0x11; 16], [0x22; 16]);
+    ///
+    /// // EU868 default RX2 at 869.525 MHz with DR0
+    /// let rx2: RfCfg = Mac::new_abp(SESSION).rx2(Region::Eu868).unwrap();
+    /// assert_eq!(rx2.rf_freq(), RfFreq::from_frequency(869_525_000));
+    /// assert_eq!(rx2.mod_params().sf(), SpreadingFactor::Sf12);
+    ///
+    /// // RX2 data rate DR3 from the network server
+    /// let rx2: RfCfg = Mac::new_abp(SESSION.set_rx2_dr(3))
+    ///     .rx2(Region::Eu868)
+    ///     .unwrap();
+    /// assert_eq!(rx2.rf_freq(), RfFreq::from_frequency(869_525_000));
+    /// assert_eq!(rx2.mod_params().sf(), SpreadingFactor::Sf9);
+    /// ```
+    pub fn rx2(&self, region: Region) -> Option<RfCfg> {
+        let (rf_freq, default_dr): (RfFreq, u8) = region.rx2();
+        let dr: u8 = match (self.join_pending, self.session) {
+            (None, Some(session)) => session.rx2_dr().unwrap_or(default_dr),
+            _ => default_dr,
+        };
+        match region.data_rate(dr)? {
+            DataRate::LoRa(mod_params) => Some(RfCfg::new(rf_freq, mod_params)),
+            DataRate::Fsk(_) => None,
+        }
+    }
+
+    /// Receive window timing for an uplink.
+    ///
+    /// RX1 uses `rx1`, which is the uplink frequency and modulation
+    /// parameters for regions without dedicated downlink channels when the
+    /// RX1 data rate offset is zero.
+    /// Use [`rx1`](Self::rx1) for the regional RX1 configuration.
+    /// RX2 uses `rx2`, use [`rx2`](Self::rx2) for the regional RX2
+    /// configuration.
+    ///
+    /// The windows open early and close late by a margin to allow for timer
+    /// error, and stay open for at least 6 symbols to detect the preamble.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use core::time::Duration;
+    /// use stm32wlxx_hal::{
+    ///     lorawan::{Mac, RfCfg, RxWindow, Session},
+    ///     subghz::{LoRaBandwidth, LoRaModParams, RfFreq, SpreadingFactor},
+    /// };
+    ///
+    /// const MAC: Mac = Mac::new_abp(Session::new(0x2601_1BDA, [0x11; 16], [0x22; 16]));
+    /// const TX: RfCfg = RfCfg::new(
+    ///     RfFreq::from_frequency(868_100_000),
+    ///     LoRaModParams::new()
+    ///         .set_sf(SpreadingFactor::Sf7)
+    ///         .set_bw(LoRaBandwidth::Bw125),
+    /// );
+    /// const RX2: RfCfg = RfCfg::new(
+    ///     RfFreq::from_frequency(869_525_000),
+    ///     LoRaModParams::new()
+    ///         .set_sf(SpreadingFactor::Sf12)
+    ///         .set_bw(LoRaBandwidth::Bw125)
+    ///         .set_ldro_en(true),
+    /// );
+    ///
+    /// let [rx1, rx2]: [RxWindow; 2] = MAC.rx_windows(&TX, &RX2);
+    /// assert_eq!(rx1.delay(), Duration::from_millis(980));
+    /// assert_eq!(rx2.delay(), Duration::from_millis(1980));
+    /// assert_eq!(rx2.rf(), &RX2);
+    /// ```
+    pub fn rx_windows(&self, rx1: &RfCfg, rx2: &RfCfg) -> [RxWindow; 2] {
+        let delay1: Duration = match (self.join_pending, self.session) {
+            (None, Some(session)) => Duration::from_secs(session.rx_delay.into()),
+            _ => JOIN_ACCEPT_DELAY1,
+        };
+        let window = |rf: &RfCfg, delay: Duration| RxWindow {
+            rf: *rf,
+            delay: delay - RX_WINDOW_MARGIN,
+            timeout: RX_WINDOW_MARGIN * 2 + rf.mod_params().symbol_time() * RX_MIN_SYMBOLS,
+        };
+        [window(rx1, delay1), window(rx2, delay1 + RX_DELAY2_OFFSET)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Error, Mac, Otaa, Rx, Session};
+    use crate::lorawan::{
+        crypto::{self, Dir},
+        soft_aes::SoftAes,
+        Counters, RfCfg,
+    };
+
+    const APP_KEY: [u8; 16] = [
+        0x2B, 0x7E, 0x15, 0x16, 0x28, 0xAE, 0xD2, 0xA6, 0xAB, 0xF7, 0x15, 0x88, 0x09, 0xCF, 0x4F,
+        0x3C,
+    ];
+    const OTAA: Otaa = Otaa::new(0x0080_E115_0000_0001, 0x70B3_D57E_D000_0000, APP_KEY);
+    const DEV_ADDR: u32 = 0x2601_1BDA;
+    const NET_ID: u32 = 0x00_0013;
+
+    /// Build a join accept as the network server.
+    fn join_accept(join_nonce: u32, dl_settings: u8, rx_delay: u8) -> [u8; 17] {
+        let mut buf: [u8; 17] = [0; 17];
+        buf[0] = 0x20;
+        buf[1..4].copy_from_slice(&join_nonce.to_le_bytes()[..3]);
+        buf[4..7].copy_from_slice(&NET_ID.to_le_bytes()[..3]);
+        buf[7..11].copy_from_slice(&DEV_ADDR.to_le_bytes());
+        buf[11] = dl_settings;
+        buf[12] = rx_delay;
+        let mic: [u8; 4] = crypto::join_mic(&mut SoftAes, &APP_KEY, &buf[..13]).unwrap();
+        buf[13..].copy_from_slice(&mic);
+        let mut block: [u8; 16] = [0; 16];
+        block.copy_from_slice(&buf[1..]);
+        SoftAes::decrypt_block(&APP_KEY, &mut block);
+        buf[1..].copy_from_slice(&block);
+        buf
+    }
+
+    /// Build a data downlink as the network server.
+    fn data_down(
+        session: &Session,
+        fcnt: u32,
+        confirmed: bool,
+        fctrl: u8,
+        fport: u8,
+        payload: &[u8],
+        buf: &mut [u8],
+    ) -> usize {
+        let len: usize = 13 + payload.len();
+        buf[0] = if confirmed { 0xA0 } else { 0x60 };
+        buf[1..5].copy_from_slice(&session.dev_addr.to_le_bytes());
+        buf[5] = fctrl;
+        buf[6..8].copy_from_slice(&(fcnt as u16).to_le_bytes());
+        buf[8] = fport;
+        buf[9..len - 4].copy_from_slice(payload);
+        let key: [u8; 16] = if fport == 0 {
+            session.nwk_skey
+        } else {
+            session.app_skey
+        };
+        crypto::crypt_payload(
+            &mut SoftAes,
+            &key,
+            Dir::Down,
+            session.dev_addr,
+            fcnt,
+            &mut buf[9..len - 4],
+        )
+        .unwrap();
+        let mic: [u8; 4] = crypto::data_mic(
+            &mut SoftAes,
+            &session.nwk_skey,
+            Dir::Down,
+            session.dev_addr,
+            fcnt,
+            &buf[..len - 4],
+        )
+        .unwrap();
+        buf[len - 4..len].copy_from_slice(&mic);
+        len
+    }
+
+    fn joined_mac() -> Mac {
+        let mut mac: Mac = Mac::new_otaa(OTAA, Counters::new());
+        let mut buf: [u8; 255] = [0; 255];
+        mac.join_request(&mut SoftAes, &mut buf).unwrap();
+        let mut accept: [u8; 17] = join_accept(1, 0x00, 1);
+        assert_eq!(mac.downlink(&mut SoftAes, &mut accept), Ok(Rx::JoinAccept));
+        mac
+    }
+
+    #[test]
+    fn join_request() {
+        let mut mac: Mac = Mac::new_otaa(OTAA, Counters::new());
+        let mut buf: [u8; 23] = [0; 23];
+        assert_eq!(mac.join_request(&mut SoftAes, &mut buf), Ok(23));
+        assert_eq!(buf[0], 0x00);
+        assert_eq!(buf[1..9], 0x70B3_D57E_D000_0000_u64.to_le_bytes());
+        assert_eq!(buf[9..17], 0x0080_E115_0000_0001_u64.to_le_bytes());
+        assert_eq!(buf[17..19], [0, 0]);
+        let mic: [u8; 4] = crypto::join_mic(&mut SoftAes, &APP_KEY, &buf[..19]).unwrap();
+        assert_eq!(buf[19..], mic);
+
+        // DevNonce increments with every request
+        mac.join_request(&mut SoftAes, &mut buf).unwrap();
+        assert_eq!(buf[17..19], [1, 0]);
+        assert_eq!(mac.counters().dev_nonce, 2);
+
+        assert_eq!(
+            mac.join_request(&mut SoftAes, &mut [0; 22]),
+            Err(Error::BufTooSmall)
+        );
+    }
+
+    #[test]
+    fn dev_nonce_exhausted() {
+        let counters: Counters = Counters {
+            dev_nonce: u16::MAX,
+            ..Counters::new()
+        };
+        let mut mac: Mac = Mac::new_otaa(OTAA, counters);
+        assert_eq!(
+            mac.join_request(&mut SoftAes, &mut [0; 23]),
+            Err(Error::DevNonceExhausted)
+        );
+    }
+
+    #[test]
+    fn join() {
+        let mut mac: Mac = Mac::new_otaa(OTAA, Counters::new());
+        let mut accept: [u8; 17] = join_accept(1, 0x12, 3);
+        assert_eq!(
+            mac.downlink(&mut SoftAes, &mut accept.clone()),
+            Err(Error::JoinNotPending)
+        );
+
+        let mut buf: [u8; 23] = [0; 23];
+        mac.join_request(&mut SoftAes, &mut buf).unwrap();
+
+        let mut bad_mic: [u8; 17] = accept;
+        bad_mic[16] ^= 1;
+        assert_eq!(mac.downlink(&mut SoftAes, &mut bad_mic), Err(Error::Mic));
+        assert!(!mac.is_joined());
+
+        assert_eq!(mac.downlink(&mut SoftAes, &mut accept), Ok(Rx::JoinAccept));
+        let session: &Session = mac.session().unwrap();
+        assert_eq!(session.dev_addr(), DEV_ADDR);
+        assert_eq!(session.rx1_dr_offset(), 1);
+        assert_eq!(session.rx2_dr(), Some(2));
+        assert_eq!(session.rx_delay(), 3);
+        assert_eq!(
+            session.nwk_skey,
+            crypto::session_key(&mut SoftAes, &APP_KEY, 0x01, 1, NET_ID, 0).unwrap()
+        );
+        assert_eq!(
+            session.app_skey,
+            crypto::session_key(&mut SoftAes, &APP_KEY, 0x02, 1, NET_ID, 0).unwrap()
+        );
+        assert_eq!(mac.counters().join_nonce, Some(1));
+
+        // the same JoinNonce cannot be used twice
+        mac.join_request(&mut SoftAes, &mut buf).unwrap();
+        let mut replay: [u8; 17] = join_accept(1, 0x12, 3);
+        assert_eq!(mac.downlink(&mut SoftAes, &mut replay), Err(Error::Replay));
+    }
+
+    #[test]
+    fn uplink() {
+        let mut mac: Mac = joined_mac();
+        let session: Session = *mac.session().unwrap();
+        let mut buf: [u8; 255] = [0; 255];
+
+        let len: usize = mac
+            .uplink(&mut SoftAes, 10, b"hello", true, &mut buf)
+            .unwrap();
+        assert_eq!(len, 18);
+        assert_eq!(buf[0], 0x80);
+        assert_eq!(buf[1..5], DEV_ADDR.to_le_bytes());
+        assert_eq!(buf[5], 0x00);
+        assert_eq!(buf[6..8], [0, 0]);
+        assert_eq!(buf[8], 10);
+        let mic: [u8; 4] = crypto::data_mic(
+            &mut SoftAes,
+            &session.nwk_skey,
+            Dir::Up,
+            DEV_ADDR,
+            0,
+            &buf[..14],
+        )
+        .unwrap();
+        assert_eq!(buf[14..18], mic);
+        crypto::crypt_payload(
+            &mut SoftAes,
+            &session.app_skey,
+            Dir::Up,
+            DEV_ADDR,
+            0,
+            &mut buf[9..14],
+        )
+        .unwrap();
+        assert_eq!(&buf[9..14], b"hello");
+        assert_eq!(mac.counters().fcnt_up, 1);
+
+        // empty uplink has no port
+        assert_eq!(mac.uplink(&mut SoftAes, 10, &[], false, &mut buf), Ok(12));
+        assert_eq!(buf[0], 0x40);
+        assert_eq!(buf[6..8], [1, 0]);
+
+        assert_eq!(
+            mac.uplink(&mut SoftAes, 224, b"x", false, &mut buf),
+            Err(Error::FPort)
+        );
+        assert_eq!(
+            mac.uplink(&mut SoftAes, 1, &[0; 243], false, &mut [0; 512]),
+            Err(Error::PayloadLen)
+        );
+        assert_eq!(
+            mac.uplink(&mut SoftAes, 1, b"hello", false, &mut [0; 17]),
+            Err(Error::BufTooSmall)
+        );
+        assert_eq!(mac.counters().fcnt_up, 2);
+    }
+
+    #[test]
+    fn uplink_not_joined() {
+        let mut mac: Mac = Mac::new_otaa(OTAA, Counters::new());
+        assert_eq!(
+            mac.uplink(&mut SoftAes, 1, b"x", false, &mut [0; 255]),
+            Err(Error::NotJoined)
+        );
+        let mut abp: Mac = Mac::new_abp(Session::new(DEV_ADDR, [1; 16], [2; 16]));
+        assert_eq!(
+            abp.join_request(&mut SoftAes, &mut [0; 23]),
+            Err(Error::NoCredentials)
+        );
+    }
+
+    #[test]
+    fn downlink() {
+        let mut mac: Mac = joined_mac();
+        let session: Session = *mac.session().unwrap();
+        let mut buf: [u8; 255] = [0; 255];
+
+        let len: usize = data_down(&session, 0, true, 0x20, 3, b"world", &mut buf);
+        match mac.downlink(&mut SoftAes, &mut buf[..len]) {
+            Ok(Rx::Data(dl)) => {
+                assert_eq!(dl.fport(), Some(3));
+                assert_eq!(dl.payload(), b"world");
+                assert!(dl.fopts().is_empty());
+                assert!(dl.ack());
+                assert!(!dl.fpending());
+                assert!(dl.confirmed());
+                assert_eq!(dl.fcnt(), 0);
+            }
+            x => panic!("unexpected {:?}", x),
+        }
+        assert_eq!(mac.counters().fcnt_down, 1);
+
+        // replayed frame counters are rolled into the next 16-bit epoch
+        let len: usize = data_down(&session, 0, false, 0, 3, b"world", &mut buf);
+        assert_eq!(mac.downlink(&mut SoftAes, &mut buf[..len]), Err(Error::Mic));
+
+        // the ACK for the confirmed downlink is sent in the next uplink
+        mac.uplink(&mut SoftAes, 1, b"x", false, &mut buf).unwrap();
+        assert_eq!(buf[5], 0x20);
+        mac.uplink(&mut SoftAes, 1, b"x", false, &mut buf).unwrap();
+        assert_eq!(buf[5], 0x00);
+    }
+
+    #[test]
+    fn downlink_fcnt_rollover() {
+        let mut mac: Mac = joined_mac();
+        let session: Session = *mac.session().unwrap();
+        mac.session = Some(session.set_fcnt_down(0xFFFF));
+        let mut buf: [u8; 255] = [0; 255];
+
+        let len: usize = data_down(&session, 0x1_0002, false, 0, 1, b"a", &mut buf);
+        match mac.downlink(&mut SoftAes, &mut buf[..len]) {
+            Ok(Rx::Data(dl)) => assert_eq!(dl.fcnt(), 0x1_0002),
+            x => panic!("unexpected {:?}", x),
+        }
+        assert_eq!(mac.counters().fcnt_down, 0x1_0003);
+    }
+
+    #[test]
+    fn downlink_errors() {
+        let mut mac: Mac = joined_mac();
+        let session: Session = *mac.session().unwrap();
+        let mut buf: [u8; 255] = [0; 255];
+
+        let other: Session = Session::new(DEV_ADDR + 1, session.nwk_skey, session.app_skey);
+        let len: usize = data_down(&other, 0, false, 0, 1, b"a", &mut buf);
+        assert_eq!(
+            mac.downlink(&mut SoftAes, &mut buf[..len]),
+            Err(Error::DevAddr)
+        );
+
+        let len: usize = data_down(&session, 0, false, 0, 1, b"a", &mut buf);
+        buf[len - 1] ^= 0xFF;
+        assert_eq!(mac.downlink(&mut SoftAes, &mut buf[..len]), Err(Error::Mic));
+
+        assert_eq!(
+            mac.downlink(&mut SoftAes, &mut buf[..11]),
+            Err(Error::Frame)
+        );
+        assert_eq!(mac.downlink(&mut SoftAes, &mut []), Err(Error::Frame));
+        // uplink MType
+        buf[0] = 0x40;
+        assert_eq!(
+            mac.downlink(&mut SoftAes, &mut buf[..len]),
+            Err(Error::Frame)
+        );
+        assert_eq!(mac.counters().fcnt_down, 0);
+    }
+
+    #[test]
+    fn rx1() {
+        use crate::subghz::{region::Region, LoRaBandwidth, RfFreq, SpreadingFactor};
+
+        let sf_bw = |rf: RfCfg| (rf.mod_params().sf(), rf.mod_params().bw());
+
+        let session: Session = Session::new(DEV_ADDR, [1; 16], [2; 16]);
+        let mac: Mac = Mac::new_abp(session);
+
+        // EU868 RX1 is the uplink channel
+        let rf: RfCfg = mac.rx1(Region::Eu868, 1, 5).unwrap();
+        assert_eq!(rf.rf_freq(), RfFreq::from_frequency(868_300_000));
+        assert_eq!(sf_bw(rf), (SpreadingFactor::Sf7, LoRaBandwidth::Bw125));
+
+        // US915 and AU915 have dedicated downlink channels
+        let rf: RfCfg = mac.rx1(Region::Us915, 63, 3).unwrap();
+        assert_eq!(rf.rf_freq(), RfFreq::from_frequency(927_500_000));
+        assert_eq!(sf_bw(rf), (SpreadingFactor::Sf7, LoRaBandwidth::Bw500));
+        let rf: RfCfg = mac.rx1(Region::Au915, 64, 6).unwrap();
+        assert_eq!(rf.rf_freq(), RfFreq::from_frequency(923_300_000));
+        assert_eq!(sf_bw(rf), (SpreadingFactor::Sf7, LoRaBandwidth::Bw500));
+
+        // RX1DROffset from the session
+        let mac: Mac = Mac::new_abp(session.set_rx1_dr_offset(2));
+        let rf: RfCfg = mac.rx1(Region::Eu868, 0, 5).unwrap();
+        assert_eq!(sf_bw(rf), (SpreadingFactor::Sf9, LoRaBandwidth::Bw125));
+        let rf: RfCfg = mac.rx1(Region::Us915, 0, 3).unwrap();
+        assert_eq!(rf.rf_freq(), RfFreq::from_frequency(923_300_000));
+        assert_eq!(sf_bw(rf), (SpreadingFactor::Sf9, LoRaBandwidth::Bw500));
+
+        // data rate not allowed on the channel
+        assert_eq!(mac.rx1(Region::Us915, 0, 4), None);
+        assert_eq!(mac.rx1(Region::Us915, 64, 0), None);
+        // channel does not exist
+        assert_eq!(mac.rx1(Region::Eu868, 3, 0), None);
+        // DR7 (FSK) is not allowed on the EU868 default channels
+        assert_eq!(mac.rx1(Region::Eu868, 0, 7), None);
+    }
+
+    #[test]
+    fn rx2() {
+        use crate::subghz::{region::Region, LoRaBandwidth, RfFreq, SpreadingFactor};
+
+        let sf_bw = |rf: RfCfg| (rf.mod_params().sf(), rf.mod_params().bw());
+
+        // regional default before joining and while a join is pending
+        let mut mac: Mac = Mac::new_otaa(OTAA, Counters::new());
+        let rf: RfCfg = mac.rx2(Region::Eu868).unwrap();
+        assert_eq!(rf.rf_freq(), RfFreq::from_frequency(869_525_000));
+        assert_eq!(sf_bw(rf), (SpreadingFactor::Sf12, LoRaBandwidth::Bw125));
+        mac.join_request(&mut SoftAes, &mut [0; 23]).unwrap();
+        let rf: RfCfg = mac.rx2(Region::Us915).unwrap();
+        assert_eq!(rf.rf_freq(), RfFreq::from_frequency(923_300_000));
+        assert_eq!(sf_bw(rf), (SpreadingFactor::Sf12, LoRaBandwidth::Bw500));
+
+        // RX2DataRate from the join accept DLSettings
+        let mut accept: [u8; 17] = join_accept(1, 0x12, 3);
+        assert_eq!(mac.downlink(&mut SoftAes, &mut accept), Ok(Rx::JoinAccept));
+        let rf: RfCfg = mac.rx2(Region::Eu868).unwrap();
+        assert_eq!(rf.rf_freq(), RfFreq::from_frequency(869_525_000));
+        assert_eq!(sf_bw(rf), (SpreadingFactor::Sf10, LoRaBandwidth::Bw125));
+
+        // ABP sessions use the regional default unless set
+        let session: Session = Session::new(DEV_ADDR, [1; 16], [2; 16]);
+        let rf: RfCfg = Mac::new_abp(session).rx2(Region::Us915).unwrap();
+        assert_eq!(sf_bw(rf), (SpreadingFactor::Sf12, LoRaBandwidth::Bw500));
+        let rf: RfCfg = Mac::new_abp(session.set_rx2_dr(10))
+            .rx2(Region::Us915)
+            .unwrap();
+        assert_eq!(sf_bw(rf), (SpreadingFactor::Sf10, LoRaBandwidth::Bw500));
+
+        // DR7 (FSK) and undefined data rates
+        assert_eq!(Mac::new_abp(session.set_rx2_dr(7)).rx2(Region::Eu868), None);
+        assert_eq!(
+            Mac::new_abp(session.set_rx2_dr(15)).rx2(Region::Eu868),
+            None
+        );
+    }
+}
@@ -0,0 +1,86 @@
+//! LoRaWAN 1.0.4 class A framing and receive windows
+//!
+//! This is not a complete LoRaWAN MAC: MAC commands are not answered and
+//! join accept CFLists are ignored, see the list of unsupported features
+//! below.
+//! Network servers expect the mandatory MAC commands to be answered, and
+//! may repeat them or stop sending downlinks to a device that does not.
+//!
+//! The MAC layer is split into a pure state machine, [`Mac`], which builds
+//! and parses frames without performing any I/O, and a [`Device`] driver that
+//! sends the frames with a [`Radio`] and times the receive windows.
+//!
+//! Supported:
+//!
+//! * Over-the-air activation (OTAA) and activation by personalization (ABP).
+//! * Confirmed and unconfirmed uplinks and downlinks.
+//! * 32-bit frame counters with replay protection.
+//! * RX1 and RX2 receive windows, with the RX2 data rate from the join
+//!   accept DLSettings, see [`Mac::rx2`].
+//! * Regional RX1 downlink channels and RX1 data rate offsets on the default
+//!   channels of a [`Region`](crate::subghz::region::Region), see
+//!   [`Mac::rx1`] and [`Device::send_region`].
+//! * Persistent DevNonce, JoinNonce, and frame counters with
+//!   [`CounterStore`].
+//!
+//! Not supported:
+//!
+//! * MAC commands, including the commands LoRaWAN 1.0.4 requires the end
+//!   device to answer, are not processed and are not answered.
+//!   They are available to the application in [`Downlink::fopts`] and in
+//!   the payload of port 0, but requests from the network server are
+//!   dropped, for example:
+//!   * LinkADRReq, the data rate, TX power, and channel mask are not changed.
+//!   * RXParamSetupReq, the RX1 data rate offset and RX2 settings are not
+//!     changed.
+//!   * RXTimingSetupReq, the RX1 delay is not changed.
+//!   * NewChannelReq and DlChannelReq, only the default channels are used.
+//!   * DutyCycleReq, see [`tx_gate`](crate::subghz::tx_gate) for duty cycle
+//!     limits.
+//! * Adaptive data rate (ADR), the application selects the channel and data
+//!   rate of each uplink.
+//! * Join accept CFLists are ignored.
+//! * Class B and class C.
+//!
+//! Cryptography uses the [`Aes`](crate::aes::Aes) peripheral through the
+//! [`BlockCipher`] trait.
+//!
+//! # Example
+//!
+//! Building and parsing frames with the MAC state machine:
+//!
+//! ```no_run
+//! use stm32wlxx_hal::{
+//!     aes::Aes,
+//!     lorawan::{Counters, Mac, Otaa, Rx},
+//!     pac,
+//! };
+//!
+//! const OTAA: Otaa = Otaa::new(0x0080_E115_0000_0001, 0x70B3_D57E_D000_0000, [0x2B; 16]);
+//!
+//! let mut dp: pac::Peripherals = pac::Peripherals::take().unwrap();
+//! let mut aes: Aes = Aes::new(dp.AES, &mut dp.RCC);
+//!
+//! let mut mac: Mac = Mac::new_otaa(OTAA, Counters::new());
+//! let mut buf: [u8; 255] = [0; 255];
+//! let len: usize = mac.join_request(&mut aes, &mut buf)?;
+//! // transmit &buf[..len], then receive into buf
+//! # let len: usize = 17;
+//! if mac.downlink(&mut aes, &mut buf[..len])? == Rx::JoinAccept {
+//!     let len: usize = mac.uplink(&mut aes, 1, b"hello", false, &mut buf)?;
+//!     // transmit &buf[..len]
+//! }
+//! # Ok::<(), stm32wlxx_hal::lorawan::Error<stm32wlxx_hal::aes::Error>>(())
+//! ```
+
+mod counters;
+mod crypto;
+mod device;
+mod mac;
+#[cfg(test)]
+mod soft_aes;
+
+pub use counters::{CounterStore, Counters};
+pub use crypto::BlockCipher;
+pub use device::{Device, DeviceError, Radio, RfCfg};
+pub use mac::{Downlink, Error, Mac, Otaa, Rx, RxWindow, Session};
@@ -0,0 +1,157 @@
+//! Software AES-128 for host tests.
+
+use super::BlockCipher;
+use core::convert::Infallible;
+
+fn xtime(b: u8) -> u8 {
+    (b << 1) ^ if b & 0x80 != 0 { 0x1B } else { 0 }
+}
+
+fn gmul(mut a: u8, mut b: u8) -> u8 {
+    let mut p: u8 = 0;
+    while b != 0 {
+        if b & 1 != 0 {
+            p ^= a;
+        }
+        a = xtime(a);
+        b >>= 1;
+    }
+    p
+}
+
+fn sbox() -> [u8; 256] {
+    let mut sbox: [u8; 256] = [0; 256];
+    let mut p: u8 = 1;
+    let mut q: u8 = 1;
+    loop {
+        // multiply p by 3
+        p ^= xtime(p);
+        // divide q by 3
+        q ^= q << 1;
+        q ^= q << 2;
+        q ^= q << 4;
+        if q & 0x80 != 0 {
+            q ^= 0x09;
+        }
+        let x: u8 = q ^ q.rotate_left(1) ^ q.rotate_left(2) ^ q.rotate_left(3) ^ q.rotate_left(4);
+        sbox[usize::from(p)] = x ^ 0x63;
+        if p == 1 {
+            break;
+        }
+    }
+    sbox[0] = 0x63;
+    sbox
+}
+
+fn inv_sbox() -> [u8; 256] {
+    let sbox: [u8; 256] = sbox();
+    let mut inv: [u8; 256] = [0; 256];
+    sbox.iter()
+        .enumerate()
+        .for_each(|(n, s)| inv[usize::from(*s)] = n as u8);
+    inv
+}
+
+fn round_keys(key: &[u8; 16]) -> [[u8; 16]; 11] {
+    let sbox: [u8; 256] = sbox();
+    let mut w: [[u8; 4]; 44] = [[0; 4]; 44];
+    for (i, word) in key.chunks_exact(4).enumerate() {
+        w[i].copy_from_slice(word);
+    }
+    let mut rcon: u8 = 1;
+    for i in 4..44 {
+        let mut temp: [u8; 4] = w[i - 1];
+        if i % 4 == 0 {
+            temp.rotate_left(1);
+            temp.iter_mut().for_each(|b| *b = sbox[usize::from(*b)]);
+            temp[0] ^= rcon;
+            rcon = xtime(rcon);
+        }
+        for n in 0..4 {
+            w[i][n] = w[i - 4][n] ^ temp[n];
+        }
+    }
+    let mut keys: [[u8; 16]; 11] = [[0; 16]; 11];
+    for (round, key) in keys.iter_mut().enumerate() {
+        for c in 0..4 {
+            key[c * 4..c * 4 + 4].copy_from_slice(&w[round * 4 + c]);
+        }
+    }
+    keys
+}
+
+fn add_round_key(state: &mut [u8; 16], key: &[u8; 16]) {
+    state.iter_mut().zip(key.iter()).for_each(|(s, k)| *s ^= k);
+}
+
+fn shift_rows(state: &mut [u8; 16]) {
+    let old: [u8; 16] = *state;
+    for c in 0..4 {
+        for r in 0..4 {
+            state[c * 4 + r] = old[((c + r) % 4) * 4 + r];
+        }
+    }
+}
+
+fn inv_shift_rows(state: &mut [u8; 16]) {
+    let old: [u8; 16] = *state;
+    for c in 0..4 {
+        for r in 0..4 {
+            state[((c + r) % 4) * 4 + r] = old[c * 4 + r];
+        }
+    }
+}
+
+fn mix_columns(state: &mut [u8; 16], m: [u8; 4]) {
+    for col in state.chunks_exact_mut(4) {
+        let a: [u8; 4] = [col[0], col[1], col[2], col[3]];
+        for (r, b) in col.iter_mut().enumerate() {
+            *b = (0..4).fold(0, |acc, n| acc ^ gmul(a[n], m[(4 + n - r) % 4]));
+        }
+    }
+}
+
+/// Software AES-128.
+#[derive(Debug, Default)]
+pub(crate) struct SoftAes;
+
+impl SoftAes {
+    /// Inverse cipher, used to encrypt join accepts as the network server.
+    pub(crate) fn decrypt_block(key: &[u8; 16], block: &mut [u8; 16]) {
+        let inv_sbox: [u8; 256] = inv_sbox();
+        let keys: [[u8; 16]; 11] = round_keys(key);
+        add_round_key(block, &keys[10]);
+        for round in (1..10).rev() {
+            inv_shift_rows(block);
+            block
+                .iter_mut()
+                .for_each(|b| *b = inv_sbox[usize::from(*b)]);
+            add_round_key(block, &keys[round]);
+            mix_columns(block, [14, 11, 13, 9]);
+        }
+        inv_shift_rows(block);
+        block
+            .iter_mut()
+            .for_each(|b| *b = inv_sbox[usize::from(*b)]);
+        add_round_key(block, &keys[0]);
+    }
+}
+
+impl BlockCipher for SoftAes {
+    type Error = Infallible;
+
+    fn encrypt_block(&mut self, key: &[u8; 16], block: &mut [u8; 16]) -> Result<(), Infallible> {
+        let sbox: [u8; 256] = sbox();
+        let keys: [[u8; 16]; 11] = round_keys(key);
+        add_round_key(block, &keys[0]);
+        for (round, key) in keys.iter().enumerate().skip(1) {
+            block.iter_mut().for_each(|b| *b = sbox[usize::from(*b)]);
+            shift_rows(block);
+            if round != 10 {
+                mix_columns(block, [2, 3, 1, 1]);
+            }
+            add_round_key(block, key);
+        }
+        Ok(())
+    }
+}
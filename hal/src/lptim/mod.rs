@@ -1,8 +1,10 @@
 //! Low-power timers
 //!
 //! The low power timers implements the `embedded-hal`
-//! [`PwmPin`](embedded_hal::PwmPin) and
-//! [`CountDown`](embedded_hal::timer::CountDown) traits.
+//! [`PwmPin`](embedded_hal::PwmPin),
+//! [`CountDown`](embedded_hal::timer::CountDown),
+//! [`DelayUs`](embedded_hal::blocking::delay::DelayUs), and
+//! [`DelayMs`](embedded_hal::blocking::delay::DelayMs) traits.
 //!
 //! # Example
 //!
@@ -764,6 +766,50 @@ impl_eh_countdown_for!(LpTim1);
 impl_eh_countdown_for!(LpTim2);
 impl_eh_countdown_for!(LpTim3);
 
+macro_rules! impl_eh_delay_for {
+    ($tim:ident) => {
+        impl $tim {
+            fn delay_ticks(&mut self, time: u32, per_sec: u64) {
+                use embedded_hal::timer::CountDown;
+
+                let hz: &Ratio<u32> = self.hz();
+                let mut ticks: u64 =
+                    u64::from(time) * u64::from(*hz.numer()) / (u64::from(*hz.denom()) * per_sec);
+                while ticks != 0 {
+                    let count: u16 = min(ticks, u64::from(u16::MAX)) as u16;
+                    self.start(count);
+                    while self.wait().is_err() {}
+                    ticks -= u64::from(count);
+                }
+            }
+        }
+
+        /// Blocking delay using the timer in one-shot mode.
+        ///
+        /// Delays longer than the timer period are split into multiple
+        /// periods.
+        impl embedded_hal::blocking::delay::DelayUs<u32> for $tim {
+            fn delay_us(&mut self, us: u32) {
+                self.delay_ticks(us, 1_000_000)
+            }
+        }
+
+        /// Blocking delay using the timer in one-shot mode.
+        ///
+        /// Delays longer than the timer period are split into multiple
+        /// periods.
+        impl embedded_hal::blocking::delay::DelayMs<u32> for $tim {
+            fn delay_ms(&mut self, ms: u32) {
+                self.delay_ticks(ms, 1_000)
+            }
+        }
+    };
+}
+
+impl_eh_delay_for!(LpTim1);
+impl_eh_delay_for!(LpTim2);
+impl_eh_delay_for!(LpTim3);
+
 macro_rules! impl_eh_pwmpin_for {
     ($tim:ident) => {
         impl embedded_hal::PwmPin for $tim {
@@ -5,7 +5,14 @@
 //! and there are many scenarios where you will want to adjust the clocks.
 //!
 //! Quickstart: [`set_sysclk_msi_max`]
+//!
+//! On the STM32WL5x both cores share the clock configuration.
+//! If both cores change clocks the `_hsem` variants of the sysclk functions
+//! hold the `hsem::Sem::RCC` hardware semaphore to prevent them from doing so
+//! at the same time, for example `set_sysclk_msi_max_hsem`.
 
+#[cfg(any(feature = "stm32wl5x_cm0p", feature = "stm32wl5x_cm4"))]
+use crate::hsem::{Hsem, Sem};
 use crate::{pac, Ratio};
 use cortex_m::{interrupt::CriticalSection, peripheral::syst::SystClkSource};
 
@@ -512,6 +519,154 @@ pub unsafe fn set_sysclk_msi_max(
     set_sysclk_msi(flash, pwr, rcc, MsiRange::Range48M, cs)
 }
 
+/// Set the sysclk to use the HSE 32MHz clock, holding the [`Sem::RCC`]
+/// hardware semaphore.
+///
+/// This is [`set_sysclk_hse`] with the clock switch protected from the other
+/// core, this spins until the other core releases the semaphore.
+/// Both cores must use the `_hsem` variants for the semaphore to be
+/// effective.
+///
+/// # Safety
+///
+/// 1. Peripherals must not be in-use before calling this function.
+/// 2. Peripherals may need their prescalers adjusted for the new sysclk frequency.
+///
+/// # Example
+///
+/// ```no_run
+/// use stm32wlxx_hal::{
+///     hsem::Hsem,
+///     pac,
+///     rcc::{set_sysclk_hse_hsem, Vos},
+/// };
+///
+/// let mut dp: pac::Peripherals = pac::Peripherals::take().unwrap();
+/// let hsem: Hsem = Hsem::new(dp.HSEM, &mut dp.RCC);
+/// cortex_m::interrupt::free(|cs| unsafe {
+///     set_sysclk_hse_hsem(&mut dp.FLASH, &mut dp.PWR, &mut dp.RCC, &hsem, Vos::V1_2, cs)
+/// });
+/// ```
+#[cfg(any(feature = "stm32wl5x_cm0p", feature = "stm32wl5x_cm4"))]
+pub unsafe fn set_sysclk_hse_hsem(
+    flash: &mut pac::FLASH,
+    pwr: &mut pac::PWR,
+    rcc: &mut pac::RCC,
+    hsem: &Hsem,
+    vos: Vos,
+    cs: &CriticalSection,
+) {
+    hsem.with_lock(Sem::RCC, || set_sysclk_hse(flash, pwr, rcc, vos, cs))
+}
+
+/// Set the sysclk to use the HSI 16MHz clock, holding the [`Sem::RCC`]
+/// hardware semaphore.
+///
+/// See [`set_sysclk_hse_hsem`] for details on the semaphore.
+///
+/// # Safety
+///
+/// 1. Peripherals must not be in-use before calling this function.
+/// 2. Peripherals may need their prescalers adjusted for the new sysclk frequency.
+///
+/// # Example
+///
+/// ```no_run
+/// use stm32wlxx_hal::{hsem::Hsem, pac, rcc::set_sysclk_hsi_hsem};
+///
+/// let mut dp: pac::Peripherals = pac::Peripherals::take().unwrap();
+/// let hsem: Hsem = Hsem::new(dp.HSEM, &mut dp.RCC);
+/// cortex_m::interrupt::free(|cs| unsafe {
+///     set_sysclk_hsi_hsem(&mut dp.FLASH, &mut dp.PWR, &mut dp.RCC, &hsem, cs)
+/// });
+/// ```
+#[cfg(any(feature = "stm32wl5x_cm0p", feature = "stm32wl5x_cm4"))]
+pub unsafe fn set_sysclk_hsi_hsem(
+    flash: &mut pac::FLASH,
+    pwr: &mut pac::PWR,
+    rcc: &mut pac::RCC,
+    hsem: &Hsem,
+    cs: &CriticalSection,
+) {
+    hsem.with_lock(Sem::RCC, || set_sysclk_hsi(flash, pwr, rcc, cs))
+}
+
+/// Set the sysclk from an MSI range, holding the [`Sem::RCC`] hardware
+/// semaphore.
+///
+/// See [`set_sysclk_hse_hsem`] for details on the semaphore.
+///
+/// # Safety
+///
+/// 1. Peripherals must not be in-use before calling this function.
+/// 2. Peripherals may need their prescalers adjusted for the new sysclk frequency.
+///
+/// # Example
+///
+/// ```no_run
+/// use stm32wlxx_hal::{
+///     hsem::Hsem,
+///     pac,
+///     rcc::{set_sysclk_msi_hsem, MsiRange},
+/// };
+///
+/// let mut dp: pac::Peripherals = pac::Peripherals::take().unwrap();
+/// let hsem: Hsem = Hsem::new(dp.HSEM, &mut dp.RCC);
+/// cortex_m::interrupt::free(|cs| unsafe {
+///     set_sysclk_msi_hsem(
+///         &mut dp.FLASH,
+///         &mut dp.PWR,
+///         &mut dp.RCC,
+///         &hsem,
+///         MsiRange::Range48M,
+///         cs,
+///     )
+/// });
+/// ```
+#[cfg(any(feature = "stm32wl5x_cm0p", feature = "stm32wl5x_cm4"))]
+pub unsafe fn set_sysclk_msi_hsem(
+    flash: &mut pac::FLASH,
+    pwr: &mut pac::PWR,
+    rcc: &mut pac::RCC,
+    hsem: &Hsem,
+    range: MsiRange,
+    cs: &CriticalSection,
+) {
+    hsem.with_lock(Sem::RCC, || set_sysclk_msi(flash, pwr, rcc, range, cs))
+}
+
+/// Set the sysclk to the MSI source at 48MHz, holding the [`Sem::RCC`]
+/// hardware semaphore.
+///
+/// See [`set_sysclk_hse_hsem`] for details on the semaphore.
+///
+/// # Safety
+///
+/// 1. Peripherals must not be in-use before calling this function.
+/// 2. Peripherals may need their prescalers adjusted for the new sysclk frequency.
+///
+/// # Example
+///
+/// ```no_run
+/// use stm32wlxx_hal::{hsem::Hsem, pac, rcc::set_sysclk_msi_max_hsem};
+///
+/// let mut dp: pac::Peripherals = pac::Peripherals::take().unwrap();
+/// let hsem: Hsem = Hsem::new(dp.HSEM, &mut dp.RCC);
+/// cortex_m::interrupt::free(|cs| unsafe {
+///     set_sysclk_msi_max_hsem(&mut dp.FLASH, &mut dp.PWR, &mut dp.RCC, &hsem, cs)
+/// });
+/// ```
+#[cfg(any(feature = "stm32wl5x_cm0p", feature = "stm32wl5x_cm4"))]
+pub unsafe fn set_sysclk_msi_max_hsem(
+    flash: &mut pac::FLASH,
+    pwr: &mut pac::PWR,
+    rcc: &mut pac::RCC,
+    hsem: &Hsem,
+    cs: &CriticalSection,
+) {
+    set_sysclk_msi_hsem(flash, pwr, rcc, hsem, MsiRange::Range48M, cs)
+}
+
 #[cfg_attr(feature = "stm32wl5x_cm0p", allow(dead_code))]
 fn pllclk(rcc: &pac::RCC, pllcfgr: &pac::rcc::pllcfgr::R) -> Ratio<u32> {
     use pac::rcc::{
@@ -733,6 +888,18 @@ pub(crate) fn pclk2(rcc: &pac::RCC, cfgr: &pac::rcc::cfgr::R) -> Ratio<u32> {
     hclk1(rcc, cfgr) / div
 }
 
+pub(crate) fn apb2timx(rcc: &pac::RCC) -> Ratio<u32> {
+    let cfgr: pac::rcc::cfgr::R = rcc.cfgr.read();
+    // see apb1timx
+    let div: u32 = match cfgr.ppre2().bits() {
+        0b101 => 2, // 4 / 2
+        0b110 => 4, // 8 / 2
+        0b111 => 8, // 16 / 2
+        _ => 1,     // 2 / 2 and all others
+    };
+    hclk1(rcc, &cfgr) / div
+}
+
 /// Calculate the current PCLK2 frequency in hertz
 ///
 /// Fractional frequencies will be rounded down.
@@ -637,6 +637,89 @@ impl Rtc {
             .modify(|_, w| w.alrbe().bit(en).alrbie().bit(irq_en));
     }
 
+    /// Set the timestamp on tamper event enable, and timestamp interrupt
+    /// enable.
+    ///
+    /// When enabled a tamper event detected by the [`tamp`](crate::tamp)
+    /// module saves the calendar to the timestamp registers and sets the
+    /// [`stat::TS`] flag.
+    /// The timestamp can be read with [`timestamp`](Self::timestamp).
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use stm32wlxx_hal::{
+    ///     pac,
+    ///     rtc::{self, Rtc},
+    ///     tamp::{Tamp, TamperCfg, Tamper},
+    /// };
+    ///
+    /// let mut dp: pac::Peripherals = pac::Peripherals::take().unwrap();
+    /// let mut rtc = Rtc::new(dp.RTC, rtc::Clk::Lse, &mut dp.PWR, &mut dp.RCC);
+    /// let mut tamp: Tamp = Tamp::new(dp.TAMP, &mut dp.PWR, &mut dp.RCC);
+    ///
+    /// rtc.set_tamper_timestamp_en(true, false);
+    /// tamp.setup_tamper(Tamper::In1, &TamperCfg::DEFAULT.set_erase(false));
+    ///
+    /// // ... tamper event occurs
+    ///
+    /// if let Some(date_time) = rtc.timestamp() {
+    ///     // handle tamper event
+    ///     Rtc::clear_status(rtc::stat::TS | rtc::stat::TSOV);
+    /// }
+    /// ```
+    #[inline]
+    pub fn set_tamper_timestamp_en(&mut self, en: bool, irq_en: bool) {
+        self.rtc
+            .cr
+            .modify(|_, w| w.tampts().bit(en).tsie().bit(irq_en));
+    }
+
+    /// Returns `true` if timestamp on tamper event is enabled.
+    #[inline]
+    #[must_use]
+    pub fn is_tamper_timestamp_en(&self) -> bool {
+        self.rtc.cr.read().tampts().bit_is_set()
+    }
+
+    /// Timestamp of the last timestamp event.
+    ///
+    /// Returns `None` if the [`stat::TS`] flag is not set, or if the calendar
+    /// has not been initialized.
+    ///
+    /// The timestamp registers do not store the year, the current year is
+    /// used.
+    /// Clear the [`stat::TS`] flag with [`clear_status`](Self::clear_status)
+    /// to allow the next timestamp to be recorded, [`stat::TSOV`] indicates
+    /// that an event occurred while the flag was set.
+    pub fn timestamp(&self) -> Option<NaiveDateTime> {
+        if Self::status().tsf().bit_is_clear() {
+            return None;
+        }
+
+        let year: i32 = self.date()?.year();
+        let ss: u32 = self.rtc.tsssr.read().ss().bits();
+        let dr = self.rtc.tsdr.read();
+        let tr = self.rtc.tstr.read();
+
+        let month: u8 = dr.mt().bit() as u8 * 10 + dr.mu().bits();
+        let day: u8 = dr.dt().bits() * 10 + dr.du().bits();
+        let date: NaiveDate = NaiveDate::from_ymd_opt(year, month as u32, day as u32)?;
+
+        let mut hour: u8 = tr.ht().bits() * 10 + tr.hu().bits();
+        if tr.pm().bit_is_set() {
+            hour += 12;
+        }
+        let minute: u8 = tr.mnt().bits() * 10 + tr.mnu().bits();
+        let second: u8 = tr.st().bits() * 10 + tr.su().bits();
+        let micro: u32 = self.ss_to_us(ss);
+
+        let time: NaiveTime =
+            NaiveTime::from_hms_micro_opt(hour as u32, minute as u32, second as u32, micro)?;
+
+        Some(date.and_time(time))
+    }
+
     /// Disable the RTC write protection.
     #[inline]
     pub fn disable_write_protect(&mut self) {
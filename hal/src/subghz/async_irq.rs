@@ -0,0 +1,404 @@
+use super::{Error, Irq, SubGhz, Timeout, Transport};
+use core::{
+    cell::RefCell,
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll, Waker},
+};
+use cortex_m::interrupt::Mutex;
+#[cfg(not(test))]
+use {
+    super::{mask_irq, unmask_irq},
+    cortex_m::interrupt::free,
+};
+
+static WAKER: Mutex<RefCell<Option<Waker>>> = Mutex::new(RefCell::new(None));
+
+// the host has no NVIC, and no interrupts to disable, the tests call `on_irq`
+// in place of the interrupt handler
+#[cfg(test)]
+fn mask_irq() {}
+
+#[cfg(test)]
+unsafe fn unmask_irq() {}
+
+#[cfg(test)]
+fn free<F, R>(f: F) -> R
+where
+    F: FnOnce(&cortex_m::interrupt::CriticalSection) -> R,
+{
+    // safety: the tests do not have interrupts
+    f(unsafe { &cortex_m::interrupt::CriticalSection::new() })
+}
+
+/// Sub-GHz radio interrupt handler for the async methods.
+///
+/// This must be called from the `RADIO_IRQ_BUSY` interrupt handler to use the
+/// async methods of [`SubGhz`] such as [`SubGhz::tx_async`].
+///
+/// The radio IRQ is level triggered, this masks the IRQ in the NVIC and wakes
+/// the task waiting on the radio.
+/// The IRQ is unmasked again by the async method after the IRQ status is
+/// cleared.
+///
+/// # Example
+///
+/// ```ignore
+/// use stm32wlxx_hal::pac::interrupt;
+///
+/// #[interrupt]
+/// #[allow(non_snake_case)]
+/// fn RADIO_IRQ_BUSY() {
+///     stm32wlxx_hal::subghz::on_irq();
+/// }
+/// ```
+pub fn on_irq() {
+    mask_irq();
+    let waker: Option<Waker> = free(|cs| WAKER.borrow(cs).borrow_mut().take());
+    if let Some(waker) = waker {
+        waker.wake()
+    }
+}
+
+/// Event that completed an async radio operation.
+///
+/// Returned by [`SubGhz::tx_async`], [`SubGhz::rx_async`], and
+/// [`SubGhz::cad_async`].
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum RadioEvent {
+    /// Packet transmission finished.
+    TxDone,
+    /// Packet reception finished.
+    ///
+    /// The packet can be read with [`SubGhz::rx_buffer_status`] and
+    /// [`SubGhz::read_buffer`].
+    RxDone,
+    /// A packet was received with a header CRC error.
+    ///
+    /// The radio keeps searching for a packet after a header error.
+    HeaderErr,
+    /// A packet was received with a CRC error, or for (G)FSK a preamble,
+    /// sync word, address, CRC, or length error.
+    Err,
+    /// Channel activity detection finished.
+    CadDone {
+        /// `true` if channel activity was detected.
+        detected: bool,
+    },
+    /// The RX or TX timeout elapsed.
+    Timeout,
+}
+
+struct IrqFuture<'a, MISO, MOSI, T> {
+    sg: &'a mut SubGhz<MISO, MOSI, T>,
+    mask: u16,
+}
+
+impl<'a, MISO, MOSI, T: Transport> Future for IrqFuture<'a, MISO, MOSI, T> {
+    type Output = Result<u16, Error>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this: &mut Self = self.get_mut();
+
+        // register the waker before reading the status to avoid missing an
+        // IRQ that fires between the read and the unmask
+        free(|cs| {
+            WAKER.borrow(cs).replace(Some(cx.waker().clone()));
+        });
+
+        let (_, irq_status) = this.sg.irq_status()?;
+        if irq_status != 0 {
+            // the IRQ is level triggered, clear everything to deassert it
+            this.sg.clear_irq_status(irq_status)?;
+            if irq_status & this.mask != 0 {
+                return Poll::Ready(Ok(irq_status));
+            }
+        }
+
+        // safety: the IRQ is masked again by `on_irq`
+        unsafe { unmask_irq() };
+        Poll::Pending
+    }
+}
+
+impl<MISO, MOSI, T: Transport> SubGhz<MISO, MOSI, T> {
+    /// Wait for any of the IRQs in `mask`.
+    ///
+    /// Returns the IRQ status, all IRQs are cleared before returning.
+    /// IRQs that are not in `mask` are cleared while waiting.
+    ///
+    /// The IRQs must be enabled with [`set_irq_cfg`](Self::set_irq_cfg), and
+    /// [`on_irq`] must be called from the radio interrupt handler.
+    ///
+    /// This unmasks the radio IRQ in the NVIC, which can break mask-based
+    /// critical sections.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # async fn doctest() -> Result<(), stm32wlxx_hal::subghz::Error> {
+    /// # let mut sg = unsafe { stm32wlxx_hal::subghz::SubGhz::steal() };
+    /// use stm32wlxx_hal::subghz::Irq;
+    ///
+    /// let irq_status: u16 = sg
+    ///     .wait_irq(Irq::RxDone.mask() | Irq::Timeout.mask())
+    ///     .await?;
+    /// # Ok(()) }
+    /// ```
+    pub async fn wait_irq(&mut self, mask: u16) -> Result<u16, Error> {
+        IrqFuture { sg: self, mask }.await
+    }
+
+    /// Transmit the packet in the buffer, completing on
+    /// [`Irq::TxDone`] or [`Irq::Timeout`].
+    ///
+    /// The payload must be written to the buffer and the packet parameters
+    /// set beforehand, this replaces [`set_tx`](Self::set_tx) followed by
+    /// polling [`irq_status`](Self::irq_status).
+    /// The TX done and timeout IRQ flags are cleared before transmitting.
+    ///
+    /// See [`wait_irq`](Self::wait_irq) for the interrupt requirements.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # async fn doctest() -> Result<(), stm32wlxx_hal::subghz::Error> {
+    /// # let mut sg = unsafe { stm32wlxx_hal::subghz::SubGhz::steal() };
+    /// use stm32wlxx_hal::subghz::{RadioEvent, Timeout};
+    ///
+    /// match sg.tx_async(Timeout::DISABLED).await? {
+    ///     RadioEvent::TxDone => (),
+    ///     _ => panic!("TX timeout"),
+    /// }
+    /// # Ok(()) }
+    /// ```
+    pub async fn tx_async(&mut self, timeout: Timeout) -> Result<RadioEvent, Error> {
+        const MASK: u16 = Irq::TxDone.mask() | Irq::Timeout.mask();
+        // a stale flag from a previous operation would complete immediately
+        self.clear_irq_status(MASK)?;
+        self.set_tx(timeout)?;
+        let irq_status: u16 = self.wait_irq(MASK).await?;
+        if irq_status & Irq::TxDone.mask() != 0 {
+            Ok(RadioEvent::TxDone)
+        } else {
+            Ok(RadioEvent::Timeout)
+        }
+    }
+
+    /// Receive a packet, completing on [`Irq::RxDone`], [`Irq::HeaderErr`],
+    /// [`Irq::Err`], or [`Irq::Timeout`].
+    ///
+    /// This replaces [`set_rx`](Self::set_rx) followed by polling
+    /// [`irq_status`](Self::irq_status).
+    /// The IRQ flags listed above are cleared before receiving.
+    ///
+    /// See [`wait_irq`](Self::wait_irq) for the interrupt requirements.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # async fn doctest() -> Result<(), stm32wlxx_hal::subghz::Error> {
+    /// # let mut sg = unsafe { stm32wlxx_hal::subghz::SubGhz::steal() };
+    /// use stm32wlxx_hal::subghz::{RadioEvent, Timeout};
+    ///
+    /// let mut buf: [u8; 255] = [0; 255];
+    /// if sg.rx_async(Timeout::DISABLED).await? == RadioEvent::RxDone {
+    ///     let (_, len, ptr) = sg.rx_buffer_status()?;
+    ///     sg.read_buffer(ptr, &mut buf[..usize::from(len)])?;
+    /// }
+    /// # Ok(()) }
+    /// ```
+    pub async fn rx_async(&mut self, timeout: Timeout) -> Result<RadioEvent, Error> {
+        const MASK: u16 =
+            Irq::RxDone.mask() | Irq::HeaderErr.mask() | Irq::Err.mask() | Irq::Timeout.mask();
+        // a stale flag from a previous operation would complete immediately
+        self.clear_irq_status(MASK)?;
+        self.set_rx(timeout)?;
+        let irq_status: u16 = self.wait_irq(MASK).await?;
+        if irq_status & Irq::HeaderErr.mask() != 0 {
+            Ok(RadioEvent::HeaderErr)
+        } else if irq_status & Irq::Err.mask() != 0 {
+            Ok(RadioEvent::Err)
+        } else if irq_status & Irq::RxDone.mask() != 0 {
+            Ok(RadioEvent::RxDone)
+        } else {
+            Ok(RadioEvent::Timeout)
+        }
+    }
+
+    /// Perform channel activity detection, completing on [`Irq::CadDone`].
+    ///
+    /// This replaces [`set_cad`](Self::set_cad) followed by polling
+    /// [`irq_status`](Self::irq_status).
+    /// The CAD done and CAD detected IRQ flags are cleared before starting.
+    ///
+    /// See [`wait_irq`](Self::wait_irq) for the interrupt requirements.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # async fn doctest() -> Result<(), stm32wlxx_hal::subghz::Error> {
+    /// # let mut sg = unsafe { stm32wlxx_hal::subghz::SubGhz::steal() };
+    /// use stm32wlxx_hal::subghz::RadioEvent;
+    ///
+    /// let busy: bool = matches!(
+    ///     sg.cad_async().await?,
+    ///     RadioEvent::CadDone { detected: true }
+    /// );
+    /// # Ok(()) }
+    /// ```
+    pub async fn cad_async(&mut self) -> Result<RadioEvent, Error> {
+        // a stale flag from a previous operation would complete immediately
+        self.clear_irq_status(Irq::CadDone.mask() | Irq::CadDetected.mask())?;
+        self.set_cad()?;
+        let irq_status: u16 = self.wait_irq(Irq::CadDone.mask()).await?;
+        Ok(RadioEvent::CadDone {
+            detected: irq_status & Irq::CadDetected.mask() != 0,
+        })
+    }
+}
+
+#[cfg(all(test, feature = "subghz-sim"))]
+mod test {
+    use super::{on_irq, IrqFuture, RadioEvent};
+    use crate::{
+        spi::{SgMiso, SgMosi},
+        subghz::{
+            sim::{SimMode, SimRadio},
+            CfgIrq, Irq, SubGhz, Timeout,
+        },
+    };
+    use core::{
+        future::Future,
+        pin::Pin,
+        ptr,
+        task::{Context, Poll, RawWaker, RawWakerVTable, Waker},
+    };
+    use std::boxed::Box;
+
+    type SimSubGhz = SubGhz<SgMiso, SgMosi, SimRadio>;
+
+    static NOOP_VTABLE: RawWakerVTable =
+        RawWakerVTable::new(|_| noop_raw_waker(), |_| (), |_| (), |_| ());
+
+    fn noop_raw_waker() -> RawWaker {
+        RawWaker::new(ptr::null(), &NOOP_VTABLE)
+    }
+
+    fn noop_waker() -> Waker {
+        // safety: the vtable functions do nothing
+        unsafe { Waker::from_raw(noop_raw_waker()) }
+    }
+
+    fn new_sg(responder: Option<fn(&mut SimRadio)>) -> SimSubGhz {
+        let mut sim: SimRadio = SimRadio::new();
+        sim.set_responder(responder);
+        let mut sg: SimSubGhz = SubGhz::new_with_transport(sim);
+        let cfg: CfgIrq = [
+            Irq::TxDone,
+            Irq::RxDone,
+            Irq::PreambleDetected,
+            Irq::CadDone,
+            Irq::CadDetected,
+            Irq::Timeout,
+        ]
+        .into_iter()
+        .fold(CfgIrq::new(), CfgIrq::irq_enable_all);
+        sg.set_irq_cfg(&cfg).unwrap();
+        sg
+    }
+
+    #[test]
+    fn pending_until_irq() {
+        let waker: Waker = noop_waker();
+        let mut cx: Context = Context::from_waker(&waker);
+        let mut sg: SimSubGhz = new_sg(None);
+        sg.set_tx(Timeout::DISABLED).unwrap();
+
+        let mut fut = IrqFuture {
+            sg: &mut sg,
+            mask: Irq::TxDone.mask() | Irq::Timeout.mask(),
+        };
+        assert_eq!(Pin::new(&mut fut).poll(&mut cx), Poll::Pending);
+
+        // IRQs outside of the mask are cleared without completing
+        fut.sg.transport_mut().raise_irq(Irq::PreambleDetected);
+        on_irq();
+        assert_eq!(Pin::new(&mut fut).poll(&mut cx), Poll::Pending);
+        assert_eq!(fut.sg.transport().irq_status(), 0);
+
+        assert!(fut.sg.transport_mut().tx_done());
+        on_irq();
+        assert_eq!(
+            Pin::new(&mut fut).poll(&mut cx),
+            Poll::Ready(Ok(Irq::TxDone.mask()))
+        );
+        assert_eq!(sg.transport().irq_status(), 0);
+    }
+
+    #[test]
+    fn stale_flag() {
+        let waker: Waker = noop_waker();
+        let mut cx: Context = Context::from_waker(&waker);
+
+        // flags left over from a previous operation
+        let mut sg: SimSubGhz = new_sg(None);
+        sg.transport_mut().raise_irq(Irq::TxDone);
+        sg.transport_mut().raise_irq(Irq::Timeout);
+        assert_eq!(
+            Box::pin(sg.tx_async(Timeout::DISABLED))
+                .as_mut()
+                .poll(&mut cx),
+            Poll::Pending
+        );
+        assert_eq!(sg.transport().mode(), SimMode::Tx);
+        assert_eq!(sg.transport().irq_status(), 0);
+
+        let mut sg: SimSubGhz = new_sg(None);
+        sg.transport_mut().raise_irq(Irq::RxDone);
+        assert_eq!(
+            Box::pin(sg.rx_async(Timeout::DISABLED))
+                .as_mut()
+                .poll(&mut cx),
+            Poll::Pending
+        );
+        assert_eq!(sg.transport().mode(), SimMode::Rx);
+        assert_eq!(sg.transport().irq_status(), 0);
+
+        let mut sg: SimSubGhz = new_sg(None);
+        sg.transport_mut().raise_irq(Irq::CadDone);
+        sg.transport_mut().raise_irq(Irq::CadDetected);
+        assert_eq!(
+            Box::pin(sg.cad_async()).as_mut().poll(&mut cx),
+            Poll::Pending
+        );
+        assert_eq!(sg.transport().mode(), SimMode::Cad);
+        assert_eq!(sg.transport().irq_status(), 0);
+    }
+
+    #[test]
+    fn timeout() {
+        let waker: Waker = noop_waker();
+        let mut cx: Context = Context::from_waker(&waker);
+
+        // the radio times out as soon as TX or RX starts
+        let mut sg: SimSubGhz = new_sg(Some(|sim| {
+            sim.timeout();
+        }));
+        assert_eq!(
+            Box::pin(sg.tx_async(Timeout::from_millis_sat(10)))
+                .as_mut()
+                .poll(&mut cx),
+            Poll::Ready(Ok(RadioEvent::Timeout))
+        );
+        assert_eq!(
+            Box::pin(sg.rx_async(Timeout::from_millis_sat(10)))
+                .as_mut()
+                .poll(&mut cx),
+            Poll::Ready(Ok(RadioEvent::Timeout))
+        );
+        assert_eq!(sg.transport().mode(), SimMode::StandbyRc);
+        assert_eq!(sg.transport().irq_status(), 0);
+    }
+}
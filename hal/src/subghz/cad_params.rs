@@ -58,6 +58,9 @@ pub enum ExitMode {
 /// |                    11 |             0x24 |            0x10 |
 /// |                    12 |             0x25 |            0x10 |
 ///
+/// [`LoRaModParams::cad_params`] returns these values for a spreading factor.
+///
+/// [`LoRaModParams::cad_params`]: crate::subghz::LoRaModParams::cad_params
 /// [`set_cad_params`]: crate::subghz::SubGhz::set_cad_params
 /// [`set_det_peak`]: crate::subghz::CadParams::set_det_peak
 /// [`set_det_min`]: crate::subghz::CadParams::set_det_min
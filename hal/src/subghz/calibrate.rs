@@ -1,3 +1,5 @@
+use super::{Error, OpError, RfFreq};
+
 /// Image calibration.
 ///
 /// Argument of [`calibrate_image`].
@@ -37,6 +39,83 @@ impl CalibrateImage {
         CalibrateImage(f1, f2)
     }
 
+    /// Image calibration for an RF frequency.
+    ///
+    /// Returns the ISM band constant that covers the frequency, for other
+    /// frequencies this returns a calibration from 4 MHz below to 4 MHz
+    /// above the frequency, rounded outwards to multiples of 4 MHz.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::subghz::{CalibrateImage, RfFreq};
+    ///
+    /// assert_eq!(
+    ///     CalibrateImage::for_freq(&RfFreq::F915),
+    ///     CalibrateImage::ISM_902_928
+    /// );
+    /// assert_eq!(
+    ///     CalibrateImage::for_freq(&RfFreq::from_frequency(868_100_000)),
+    ///     CalibrateImage::ISM_863_870
+    /// );
+    /// assert_eq!(
+    ///     CalibrateImage::for_freq(&RfFreq::from_frequency(169_400_000)),
+    ///     CalibrateImage::new(41, 44)
+    /// );
+    /// ```
+    pub const fn for_freq(rf_freq: &RfFreq) -> CalibrateImage {
+        const BANDS: [CalibrateImage; 5] = [
+            CalibrateImage::ISM_430_440,
+            CalibrateImage::ISM_470_510,
+            CalibrateImage::ISM_779_787,
+            CalibrateImage::ISM_863_870,
+            CalibrateImage::ISM_902_928,
+        ];
+
+        let freq: u32 = rf_freq.freq();
+        let mut idx: usize = 0;
+        while idx < BANDS.len() {
+            let band: CalibrateImage = BANDS[idx];
+            if freq >= band.0 as u32 * 4_000_000 && freq <= band.1 as u32 * 4_000_000 {
+                return band;
+            }
+            idx += 1;
+        }
+
+        const STEP: u32 = 4_000_000;
+        let f1: u32 = freq.saturating_sub(STEP) / STEP;
+        let f2: u32 = freq.saturating_add(2 * STEP - 1) / STEP;
+        CalibrateImage(
+            if f1 > u8::MAX as u32 {
+                u8::MAX
+            } else {
+                f1 as u8
+            },
+            if f2 > u8::MAX as u32 {
+                u8::MAX
+            } else {
+                f2 as u8
+            },
+        )
+    }
+
+    /// Returns `true` if the image calibration covers the calibration
+    /// required for the RF frequency, see [`for_freq`](Self::for_freq).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::subghz::{CalibrateImage, RfFreq};
+    ///
+    /// assert!(CalibrateImage::ISM_863_870.covers(&RfFreq::from_frequency(868_100_000)));
+    /// assert!(!CalibrateImage::ISM_902_928.covers(&RfFreq::from_frequency(868_100_000)));
+    /// assert!(CalibrateImage::new(0xD7, 0xE9).covers(&RfFreq::F915));
+    /// ```
+    pub const fn covers(&self, rf_freq: &RfFreq) -> bool {
+        let required: CalibrateImage = CalibrateImage::for_freq(rf_freq);
+        self.0 <= required.0 && self.1 >= required.1
+    }
+
     /// Create a new `CalibrateImage` structure from two frequencies.
     ///
     /// # Arguments
@@ -120,3 +199,146 @@ impl Calibrate {
         self as u8
     }
 }
+
+/// Calibration errors.
+///
+/// Returned by [`calibrate_for`].
+///
+/// [`calibrate_for`]: crate::subghz::SubGhz::calibrate_for
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum CalibrationError {
+    /// HSE32 clock startup failed, check the TCXO configuration.
+    Xosc,
+    /// Sub-GHz radio RC 64 kHz oscillator calibration failed.
+    Rc64K,
+    /// Sub-GHz radio RC 13 MHz oscillator calibration failed.
+    Rc13M,
+    /// RF-PLL calibration failed.
+    Pll,
+    /// RF-ADC calibration failed.
+    Adc,
+    /// Image calibration failed.
+    Image,
+    /// SPI error.
+    Spi(Error),
+}
+
+impl CalibrationError {
+    /// Get the first calibration error from the bits returned by
+    /// [`op_error`].
+    ///
+    /// Errors are checked in calibration order, the HSE32 clock first and
+    /// the image last.
+    /// Errors not caused by calibration are ignored.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::subghz::{CalibrationError, OpError};
+    ///
+    /// assert_eq!(CalibrationError::from_op_error(0), None);
+    /// assert_eq!(
+    ///     CalibrationError::from_op_error(OpError::PaRampError.mask()),
+    ///     None
+    /// );
+    /// assert_eq!(
+    ///     CalibrationError::from_op_error(
+    ///         OpError::ImageCalibrationError.mask() | OpError::PllCalibrationError.mask()
+    ///     ),
+    ///     Some(CalibrationError::Pll)
+    /// );
+    /// ```
+    ///
+    /// [`op_error`]: crate::subghz::SubGhz::op_error
+    pub const fn from_op_error(op_error: u16) -> Option<CalibrationError> {
+        const ERRORS: [(OpError, CalibrationError); 6] = [
+            (OpError::XoscStartError, CalibrationError::Xosc),
+            (OpError::RC64KCalibrationError, CalibrationError::Rc64K),
+            (OpError::RC13MCalibrationError, CalibrationError::Rc13M),
+            (OpError::PllCalibrationError, CalibrationError::Pll),
+            (OpError::AdcCalibrationError, CalibrationError::Adc),
+            (OpError::ImageCalibrationError, CalibrationError::Image),
+        ];
+
+        let mut idx: usize = 0;
+        while idx < ERRORS.len() {
+            let (op, err) = ERRORS[idx];
+            if op_error & op.mask() != 0 {
+                return Some(err);
+            }
+            idx += 1;
+        }
+        None
+    }
+}
+
+impl From<Error> for CalibrationError {
+    fn from(e: Error) -> Self {
+        CalibrationError::Spi(e)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{CalibrateImage, CalibrationError};
+    use crate::subghz::{OpError, RfFreq};
+
+    fn cal(hz: u32) -> CalibrateImage {
+        CalibrateImage::for_freq(&RfFreq::from_frequency(hz))
+    }
+
+    #[test]
+    fn for_freq_ism_bands() {
+        assert_eq!(cal(433_920_000), CalibrateImage::ISM_430_440);
+        assert_eq!(cal(490_000_000), CalibrateImage::ISM_470_510);
+        assert_eq!(cal(780_500_000), CalibrateImage::ISM_779_787);
+        assert_eq!(cal(863_000_000), CalibrateImage::ISM_863_870);
+        assert_eq!(cal(869_525_000), CalibrateImage::ISM_863_870);
+        assert_eq!(cal(902_300_000), CalibrateImage::ISM_902_928);
+        assert_eq!(cal(927_500_000), CalibrateImage::ISM_902_928);
+    }
+
+    #[test]
+    fn for_freq_other() {
+        for hz in (150_000_000..=960_000_000).step_by(1_250_000) {
+            let CalibrateImage(f1, f2) = cal(hz);
+            let (low, high): (u32, u32) = (u32::from(f1) * 4_000_000, u32::from(f2) * 4_000_000);
+            assert!(low <= hz && hz <= high, "{} Hz", hz);
+            if high - low < 16_000_000 {
+                // not an ISM band, covers at least 4 MHz on either side
+                assert!(low + 4_000_000 <= hz, "{} Hz", hz);
+                assert!(high >= hz + 4_000_000, "{} Hz", hz);
+            }
+        }
+        assert_eq!(cal(600_000_000), CalibrateImage::new(149, 151));
+        assert_eq!(cal(0), CalibrateImage::new(0, 1));
+        assert_eq!(cal(1_100_000_000), CalibrateImage::new(255, 255));
+    }
+
+    #[test]
+    fn from_op_error() {
+        const CAL_ERRORS: [(OpError, CalibrationError); 6] = [
+            (OpError::XoscStartError, CalibrationError::Xosc),
+            (OpError::RC64KCalibrationError, CalibrationError::Rc64K),
+            (OpError::RC13MCalibrationError, CalibrationError::Rc13M),
+            (OpError::PllCalibrationError, CalibrationError::Pll),
+            (OpError::AdcCalibrationError, CalibrationError::Adc),
+            (OpError::ImageCalibrationError, CalibrationError::Image),
+        ];
+        for (op, err) in CAL_ERRORS {
+            assert_eq!(CalibrationError::from_op_error(op.mask()), Some(err));
+        }
+        assert_eq!(CalibrationError::from_op_error(0), None);
+        assert_eq!(
+            CalibrationError::from_op_error(
+                OpError::PaRampError.mask() | OpError::PllLockError.mask()
+            ),
+            None
+        );
+        assert_eq!(
+            CalibrationError::from_op_error(0x01FF),
+            Some(CalibrationError::Xosc)
+        );
+    }
+}
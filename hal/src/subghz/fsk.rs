@@ -0,0 +1,967 @@
+//! High-level (G)FSK transceiver
+//!
+//! [`FskRadio`] owns a [`SubGhz`] radio and an RF switch callback, and
+//! configures the generic packet engine from a declarative [`FskCfg`].
+//!
+//! # Packet length
+//!
+//! Payloads are limited to 255 bytes, the maximum of the generic packet
+//! engine length field.
+//! Long packets (longer than 255 bytes with [`PktCtrl`] continuous mode and
+//! [`SubGhz::set_generic_pkt_len`]) are **not** supported by [`FskRadio`],
+//! they need the data buffer to be refilled or drained while the packet is
+//! on the air, which requires timing that this driver does not provide.
+//! Use the low-level [`SubGhz`] methods for long packets.
+//!
+//! [`PktCtrl`]: super::PktCtrl
+//!
+//! # Example
+//!
+//! ```no_run
+//! use stm32wlxx_hal::{
+//!     pac,
+//!     spi::{SgMiso, SgMosi},
+//!     subghz::{
+//!         fsk::{FskCfg, FskRadio},
+//!         AddrComp, CalibrateImage, FskBandwidth, FskBitrate, FskFdev, FskModParams,
+//!         FskPulseShape, RfFreq, RfState, SubGhz, Timeout,
+//!     },
+//! };
+//!
+//! const CFG: FskCfg = FskCfg::new()
+//!     .set_rf_freq(RfFreq::F433)
+//!     .set_cal_image(CalibrateImage::ISM_430_440)
+//!     .set_mod_params(
+//!         FskModParams::new()
+//!             .set_bitrate(FskBitrate::from_bps(20_000))
+//!             .set_pulse_shape(FskPulseShape::Bt05)
+//!             .set_bandwidth(FskBandwidth::Bw58)
+//!             .set_fdev(FskFdev::from_hertz(10_000)),
+//!     )
+//!     .set_whitening(Some(0x01FF))
+//!     .set_addr_comp(AddrComp::Broadcast, 0x12, 0xFF);
+//!
+//! let mut dp: pac::Peripherals = pac::Peripherals::take().unwrap();
+//!
+//! let sg: SubGhz<SgMiso, SgMosi> = SubGhz::new(dp.SPI3, &mut dp.RCC);
+//! // forward the state to the RF switch on this line
+//! let rfs = |_: RfState| ();
+//! let mut radio = FskRadio::new(sg, rfs, CFG)?;
+//!
+//! radio.transmit(b"Hello, World!")?;
+//!
+//! let mut buf: [u8; 255] = [0; 255];
+//! let (len, status) = radio.receive(&mut buf, Timeout::from_millis_sat(1000))?;
+//! # Ok::<(), stm32wlxx_hal::subghz::fsk::Error>(())
+//! ```
+
+use super::{
+    AddrComp, CalibrateImage, CfgIrq, CrcType, FallbackMode, FskBandwidth, FskModParams,
+    FskPacketStatus, GenericPacketParams, HeaderType, Irq, PaConfig, PacketType, PreambleDetection,
+    RegMode, RfFreq, RfState, SleepCfg, StandbyClk, Startup, SubGhz, TcxoMode, Timeout, Transport,
+    TxParams,
+};
+use crate::spi::Spi3;
+use embedded_hal::blocking::delay::DelayUs;
+
+const TX_BUF_OFFSET: u8 = 0;
+const RX_BUF_OFFSET: u8 = 0;
+
+const IRQ_CFG: CfgIrq = CfgIrq::new()
+    .irq_enable_all(Irq::TxDone)
+    .irq_enable_all(Irq::RxDone)
+    .irq_enable_all(Irq::Timeout)
+    .irq_enable_all(Irq::Err);
+
+/// Invalid parameter combinations in a [`FskCfg`].
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum CfgError {
+    /// The bandwidth is too narrow for the bitrate and frequency deviation,
+    /// see [`FskModParams::is_valid_worst_case`].
+    ModParams,
+    /// The sync word is longer than 8 bytes.
+    SyncWordLen,
+    /// The header type is fixed, but the payload length is zero.
+    FixedPayloadLen,
+    /// The RF frequency is outside of the 150 - 960 MHz band.
+    RfFreq,
+    /// The image calibration does not cover the RF frequency.
+    ///
+    /// Use [`CalibrateImage::for_freq`] to get the image calibration for
+    /// the RF frequency.
+    CalImage,
+}
+
+/// (G)FSK radio errors.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Error {
+    /// SPI error.
+    Spi(super::Error),
+    /// Invalid configuration.
+    Cfg(CfgError),
+    /// The operation timed out.
+    Timeout,
+    /// A packet was received with an invalid CRC.
+    Crc,
+    /// A packet was received with an invalid length.
+    Length,
+    /// Invalid payload length.
+    ///
+    /// The payload is empty, longer than 255 bytes (long packets are not
+    /// supported), or does not match the payload length of a fixed length
+    /// header.
+    PayloadLen,
+    /// The receive buffer is too small, contains the received packet length.
+    BufTooSmall(u8),
+}
+
+impl From<super::Error> for Error {
+    fn from(e: super::Error) -> Self {
+        Error::Spi(e)
+    }
+}
+
+impl From<CfgError> for Error {
+    fn from(e: CfgError) -> Self {
+        Error::Cfg(e)
+    }
+}
+
+/// (G)FSK radio configuration.
+///
+/// Argument of [`FskRadio::new`].
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct FskCfg {
+    rf_freq: RfFreq,
+    mod_params: FskModParams,
+    preamble_len: u16,
+    preamble_det: PreambleDetection,
+    sync_word: [u8; 8],
+    sync_word_len: u8,
+    header_type: HeaderType,
+    payload_len: u8,
+    crc_type: CrcType,
+    crc_init: u16,
+    crc_poly: u16,
+    whitening: Option<u16>,
+    addr_comp: AddrComp,
+    node_addr: u8,
+    broadcast_addr: u8,
+    pa_config: PaConfig,
+    tx_params: TxParams,
+    reg_mode: RegMode,
+    tcxo_mode: Option<TcxoMode>,
+    cal_image: CalibrateImage,
+    tx_timeout: Timeout,
+}
+
+impl FskCfg {
+    /// Create a new `FskCfg`.
+    ///
+    /// This is the same as `default`, but in a `const` function.
+    ///
+    /// The defaults are 915 MHz, 50 kb/s with a 25 kHz deviation and a
+    /// 117 kHz bandwidth, a 32 bit preamble with 8 bit detection, a 4 byte
+    /// sync word, a variable length header, a 2 byte CCITT CRC, no whitening,
+    /// no address filtering, the low-power PA at 14 dBm, the LDO regulator,
+    /// no TCXO, and no TX timeout.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::subghz::fsk::FskCfg;
+    ///
+    /// const CFG: FskCfg = FskCfg::new();
+    /// assert_eq!(CFG, FskCfg::default());
+    /// assert_eq!(CFG.validate(), Ok(()));
+    /// ```
+    pub const fn new() -> FskCfg {
+        FskCfg {
+            rf_freq: RfFreq::F915,
+            mod_params: FskModParams::new().set_bandwidth(FskBandwidth::Bw117),
+            preamble_len: 32,
+            preamble_det: PreambleDetection::Bit8,
+            sync_word: [0x79, 0x80, 0x0C, 0xC0, 0, 0, 0, 0],
+            sync_word_len: 4,
+            header_type: HeaderType::Variable,
+            payload_len: 0xFF,
+            crc_type: CrcType::Byte2,
+            crc_init: 0x1D0F,
+            crc_poly: 0x1021,
+            whitening: None,
+            addr_comp: AddrComp::Disabled,
+            node_addr: 0,
+            broadcast_addr: 0,
+            pa_config: PaConfig::LP_14,
+            tx_params: TxParams::LP_14,
+            reg_mode: RegMode::Ldo,
+            tcxo_mode: None,
+            cal_image: CalibrateImage::ISM_902_928,
+            tx_timeout: Timeout::DISABLED,
+        }
+    }
+
+    /// Set the RF frequency.
+    ///
+    /// The image calibration should be set to match with
+    /// [`set_cal_image`](Self::set_cal_image).
+    #[must_use = "set_rf_freq returns a modified FskCfg"]
+    pub const fn set_rf_freq(mut self, rf_freq: RfFreq) -> FskCfg {
+        self.rf_freq = rf_freq;
+        self
+    }
+
+    /// Set the modulation parameters; bitrate, pulse shape, bandwidth, and
+    /// frequency deviation.
+    #[must_use = "set_mod_params returns a modified FskCfg"]
+    pub const fn set_mod_params(mut self, mod_params: FskModParams) -> FskCfg {
+        self.mod_params = mod_params;
+        self
+    }
+
+    /// Set the preamble length in bits, and the preamble detection length.
+    #[must_use = "set_preamble returns a modified FskCfg"]
+    pub const fn set_preamble(mut self, len: u16, det: PreambleDetection) -> FskCfg {
+        self.preamble_len = len;
+        self.preamble_det = det;
+        self
+    }
+
+    /// Set the sync word, up to 8 bytes.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::subghz::fsk::{CfgError, FskCfg};
+    ///
+    /// const CFG: FskCfg = FskCfg::new().set_sync_word(&[0xC1, 0x94, 0xC1]);
+    /// assert_eq!(CFG.sync_word(), &[0xC1, 0x94, 0xC1]);
+    ///
+    /// const TOO_LONG: FskCfg = FskCfg::new().set_sync_word(&[0; 9]);
+    /// assert_eq!(TOO_LONG.validate(), Err(CfgError::SyncWordLen));
+    /// ```
+    #[must_use = "set_sync_word returns a modified FskCfg"]
+    pub const fn set_sync_word(mut self, sync_word: &[u8]) -> FskCfg {
+        self.sync_word = [0; 8];
+        let mut idx: usize = 0;
+        while idx < sync_word.len() && idx < self.sync_word.len() {
+            self.sync_word[idx] = sync_word[idx];
+            idx += 1;
+        }
+        self.sync_word_len = if sync_word.len() > u8::MAX as usize {
+            u8::MAX
+        } else {
+            sync_word.len() as u8
+        };
+        self
+    }
+
+    /// Set the header type, and the payload length of a fixed length header.
+    ///
+    /// The payload length is ignored for a variable length header.
+    #[must_use = "set_header_type returns a modified FskCfg"]
+    pub const fn set_header_type(mut self, header_type: HeaderType, payload_len: u8) -> FskCfg {
+        self.header_type = header_type;
+        self.payload_len = payload_len;
+        self
+    }
+
+    /// Set the CRC type, initial value, and polynomial.
+    ///
+    /// # Example
+    ///
+    /// IBM CRC.
+    ///
+    /// ```
+    /// use stm32wlxx_hal::subghz::{fsk::FskCfg, CrcType};
+    ///
+    /// const CFG: FskCfg = FskCfg::new().set_crc(CrcType::Byte2, 0xFFFF, 0x8005);
+    /// ```
+    #[must_use = "set_crc returns a modified FskCfg"]
+    pub const fn set_crc(mut self, crc_type: CrcType, init: u16, poly: u16) -> FskCfg {
+        self.crc_type = crc_type;
+        self.crc_init = init;
+        self.crc_poly = poly;
+        self
+    }
+
+    /// Set the 9 bit whitening seed, `None` disables whitening.
+    #[must_use = "set_whitening returns a modified FskCfg"]
+    pub const fn set_whitening(mut self, seed: Option<u16>) -> FskCfg {
+        self.whitening = seed;
+        self
+    }
+
+    /// Set the address filtering, node address, and broadcast address.
+    ///
+    /// The addresses can be changed at runtime with
+    /// [`FskRadio::set_addrs`].
+    #[must_use = "set_addr_comp returns a modified FskCfg"]
+    pub const fn set_addr_comp(mut self, addr_comp: AddrComp, node: u8, broadcast: u8) -> FskCfg {
+        self.addr_comp = addr_comp;
+        self.node_addr = node;
+        self.broadcast_addr = broadcast;
+        self
+    }
+
+    /// Set the power amplifier configuration and TX parameters.
+    #[must_use = "set_pa returns a modified FskCfg"]
+    pub const fn set_pa(mut self, pa_config: PaConfig, tx_params: TxParams) -> FskCfg {
+        self.pa_config = pa_config;
+        self.tx_params = tx_params;
+        self
+    }
+
+    /// Set the radio power supply.
+    #[must_use = "set_reg_mode returns a modified FskCfg"]
+    pub const fn set_reg_mode(mut self, reg_mode: RegMode) -> FskCfg {
+        self.reg_mode = reg_mode;
+        self
+    }
+
+    /// Set the TCXO mode, `None` if the board does not use a TCXO.
+    #[must_use = "set_tcxo_mode returns a modified FskCfg"]
+    pub const fn set_tcxo_mode(mut self, tcxo_mode: Option<TcxoMode>) -> FskCfg {
+        self.tcxo_mode = tcxo_mode;
+        self
+    }
+
+    /// Set the image calibration.
+    #[must_use = "set_cal_image returns a modified FskCfg"]
+    pub const fn set_cal_image(mut self, cal_image: CalibrateImage) -> FskCfg {
+        self.cal_image = cal_image;
+        self
+    }
+
+    /// Set the TX timeout.
+    ///
+    /// [`Timeout::DISABLED`] disables the timeout.
+    #[must_use = "set_tx_timeout returns a modified FskCfg"]
+    pub const fn set_tx_timeout(mut self, tx_timeout: Timeout) -> FskCfg {
+        self.tx_timeout = tx_timeout;
+        self
+    }
+
+    /// Get the RF frequency.
+    pub const fn rf_freq(&self) -> RfFreq {
+        self.rf_freq
+    }
+
+    /// Get the modulation parameters.
+    pub const fn mod_params(&self) -> FskModParams {
+        self.mod_params
+    }
+
+    /// Get the sync word.
+    pub fn sync_word(&self) -> &[u8] {
+        &self.sync_word[..usize::from(self.sync_word_len.min(8))]
+    }
+
+    /// Get the power amplifier configuration.
+    pub const fn pa_config(&self) -> PaConfig {
+        self.pa_config
+    }
+
+    /// Get the packet parameters for a payload length.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::subghz::{
+    ///     fsk::FskCfg, AddrComp, CrcType, GenericPacketParams, HeaderType, PreambleDetection,
+    /// };
+    ///
+    /// const EXPECTED: GenericPacketParams = GenericPacketParams::new()
+    ///     .set_preamble_len(32)
+    ///     .set_preamble_detection(PreambleDetection::Bit8)
+    ///     .set_sync_word_len(32)
+    ///     .set_addr_comp(AddrComp::Disabled)
+    ///     .set_header_type(HeaderType::Variable)
+    ///     .set_payload_len(16)
+    ///     .set_crc_type(CrcType::Byte2)
+    ///     .set_whitening_enable(false);
+    ///
+    /// assert_eq!(FskCfg::new().packet_params(16), EXPECTED);
+    /// ```
+    pub const fn packet_params(&self, payload_len: u8) -> GenericPacketParams {
+        GenericPacketParams::new()
+            .set_preamble_len(self.preamble_len)
+            .set_preamble_detection(self.preamble_det)
+            .set_sync_word_len(self.sync_word_len.saturating_mul(8))
+            .set_addr_comp(self.addr_comp)
+            .set_header_type(self.header_type)
+            .set_payload_len(payload_len)
+            .set_crc_type(self.crc_type)
+            .set_whitening_enable(self.whitening.is_some())
+    }
+
+    /// Check the configuration for invalid parameter combinations.
+    ///
+    /// This is called by [`FskRadio::new`], it is `const` so the
+    /// configuration can also be checked at compile time.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::subghz::{
+    ///     fsk::{CfgError, FskCfg},
+    ///     FskBandwidth, FskBitrate, FskFdev, FskModParams,
+    /// };
+    ///
+    /// const MOD_PARAMS: FskModParams = FskModParams::new()
+    ///     .set_bitrate(FskBitrate::from_bps(100_000))
+    ///     .set_bandwidth(FskBandwidth::Bw58)
+    ///     .set_fdev(FskFdev::from_hertz(25_000));
+    ///
+    /// const CFG: FskCfg = FskCfg::new().set_mod_params(MOD_PARAMS);
+    /// assert_eq!(CFG.validate(), Err(CfgError::ModParams));
+    /// ```
+    pub const fn validate(&self) -> Result<(), CfgError> {
+        if !self.rf_freq.is_valid() {
+            return Err(CfgError::RfFreq);
+        }
+
+        if !self.cal_image.covers(&self.rf_freq) {
+            return Err(CfgError::CalImage);
+        }
+
+        if !self.mod_params.is_valid_worst_case() {
+            return Err(CfgError::ModParams);
+        }
+
+        if self.sync_word_len as usize > self.sync_word.len() {
+            return Err(CfgError::SyncWordLen);
+        }
+
+        if matches!(self.header_type, HeaderType::Fixed) && self.payload_len == 0 {
+            return Err(CfgError::FixedPayloadLen);
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for FskCfg {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// High-level (G)FSK transceiver.
+///
+/// The RF switch callback is called with the required [`RfState`] before each
+/// operation, and with [`RfState::Off`] after each operation.
+///
+/// All operations block until completion.
+/// If the radio is in sleep mode it is woken up before the operation, and
+/// reconfigured if it was put to sleep with a cold startup.
+#[derive(Debug)]
+pub struct FskRadio<MISO, MOSI, RFS, T = Spi3<MISO, MOSI>> {
+    sg: SubGhz<MISO, MOSI, T>,
+    rfs: RFS,
+    cfg: FskCfg,
+    sleep: Option<SleepCfg>,
+}
+
+impl<MISO, MOSI, RFS, T> FskRadio<MISO, MOSI, RFS, T>
+where
+    T: Transport,
+    RFS: FnMut(RfState),
+{
+    /// Create a new (G)FSK radio.
+    ///
+    /// This validates the configuration, then configures the radio for
+    /// (G)FSK and leaves it in standby mode.
+    ///
+    /// # Example
+    ///
+    /// See the [module-level documentation](self).
+    pub fn new(sg: SubGhz<MISO, MOSI, T>, rfs: RFS, cfg: FskCfg) -> Result<Self, Error> {
+        cfg.validate()?;
+        let mut radio: Self = FskRadio {
+            sg,
+            rfs,
+            cfg,
+            sleep: None,
+        };
+        (radio.rfs)(RfState::Off);
+        radio.init()?;
+        Ok(radio)
+    }
+
+    /// Free the radio and RF switch callback from the driver.
+    ///
+    /// The radio is left in its current mode, except for sleep mode.
+    /// If the radio is in sleep mode it is woken up, after a cold startup the
+    /// radio must be configured again.
+    pub fn free(mut self) -> (SubGhz<MISO, MOSI, T>, RFS) {
+        if self.sleep.take().is_some() {
+            self.sg.wake_in_place();
+        }
+        (self.sg, self.rfs)
+    }
+
+    /// Get the radio configuration.
+    #[inline]
+    pub const fn cfg(&self) -> &FskCfg {
+        &self.cfg
+    }
+
+    /// Returns `true` if the radio is in sleep mode.
+    #[inline]
+    pub const fn is_asleep(&self) -> bool {
+        self.sleep.is_some()
+    }
+
+    fn init(&mut self) -> Result<(), Error> {
+        let cfg: FskCfg = self.cfg;
+        self.sg.set_standby(StandbyClk::Rc)?;
+        if let Some(tcxo_mode) = cfg.tcxo_mode {
+            self.sg.set_tcxo_mode(&tcxo_mode)?;
+        }
+        self.sg.set_standby(StandbyClk::Hse)?;
+        self.sg.set_tx_rx_fallback_mode(FallbackMode::StandbyHse)?;
+        self.sg.set_regulator_mode(cfg.reg_mode)?;
+        self.sg
+            .set_buffer_base_address(TX_BUF_OFFSET, RX_BUF_OFFSET)?;
+        self.sg.set_pa_config(&cfg.pa_config)?;
+        self.sg.set_tx_params(&cfg.tx_params)?;
+        self.sg.set_packet_type(PacketType::Fsk)?;
+        self.sg.set_sync_word(&cfg.sync_word)?;
+        // the register names are swapped in these methods
+        self.sg.set_crc_polynomial(cfg.crc_init)?;
+        self.sg.set_initial_crc_polynomial(cfg.crc_poly)?;
+        if let Some(seed) = cfg.whitening {
+            self.sg.set_whitening_seed(seed)?;
+        }
+        self.sg.set_addrs(cfg.node_addr, cfg.broadcast_addr)?;
+        self.sg.set_fsk_mod_params(&cfg.mod_params)?;
+        self.sg
+            .set_packet_params(&cfg.packet_params(cfg.payload_len))?;
+        self.sg.calibrate_image(cfg.cal_image)?;
+        self.sg.set_rf_frequency(&cfg.rf_freq)?;
+        self.sg.set_irq_cfg(&IRQ_CFG)?;
+        Ok(())
+    }
+
+    /// Set the node and broadcast addresses used for address filtering.
+    ///
+    /// Address filtering is enabled with [`FskCfg::set_addr_comp`].
+    pub fn set_addrs(&mut self, node: u8, broadcast: u8) -> Result<(), Error> {
+        self.wakeup()?;
+        self.sg.set_addrs(node, broadcast)?;
+        self.cfg.node_addr = node;
+        self.cfg.broadcast_addr = broadcast;
+        Ok(())
+    }
+
+    /// Wakeup the radio from sleep mode.
+    ///
+    /// This does nothing if the radio is not in sleep mode.
+    ///
+    /// This is called automatically by all operations, calling it manually
+    /// is only required to move the wakeup latency out of an operation.
+    pub fn wakeup(&mut self) -> Result<(), Error> {
+        if let Some(cfg) = self.sleep.take() {
+            self.sg.wake_in_place();
+            if cfg.startup() == Startup::Cold {
+                self.reinit()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Reconfigure the radio after a cold startup.
+    #[cfg(feature = "subghz-snapshot")]
+    fn reinit(&mut self) -> Result<(), Error> {
+        let cfg: FskCfg = self.cfg;
+        self.sg.restore_snapshot()?;
+        // registers and calibration are not part of the snapshot
+        self.sg.set_standby(StandbyClk::Hse)?;
+        self.sg.set_tx_rx_fallback_mode(FallbackMode::StandbyHse)?;
+        self.sg
+            .set_buffer_base_address(TX_BUF_OFFSET, RX_BUF_OFFSET)?;
+        self.sg.set_sync_word(&cfg.sync_word)?;
+        // the register names are swapped in these methods
+        self.sg.set_crc_polynomial(cfg.crc_init)?;
+        self.sg.set_initial_crc_polynomial(cfg.crc_poly)?;
+        if let Some(seed) = cfg.whitening {
+            self.sg.set_whitening_seed(seed)?;
+        }
+        self.sg.set_addrs(cfg.node_addr, cfg.broadcast_addr)?;
+        self.sg.calibrate_image(cfg.cal_image)?;
+        Ok(())
+    }
+
+    /// Reconfigure the radio after a cold startup.
+    #[cfg(not(feature = "subghz-snapshot"))]
+    fn reinit(&mut self) -> Result<(), Error> {
+        self.init()
+    }
+
+    /// Put the radio into sleep mode.
+    ///
+    /// This does nothing if the radio is already in sleep mode.
+    ///
+    /// The delay is used to wait the 500 μs required after sleep before the
+    /// radio can be accessed again, see [`SubGhz::sleep`].
+    ///
+    /// With [`Startup::Cold`] the radio configuration is lost, and restored
+    /// on wakeup.
+    pub fn sleep<D: DelayUs<u32>>(&mut self, cfg: SleepCfg, delay: &mut D) -> Result<(), Error> {
+        if self.sleep.is_some() {
+            return Ok(());
+        }
+        (self.rfs)(RfState::Off);
+        self.sg.sleep_in_place(cfg, delay)?;
+        self.sleep = Some(cfg);
+        Ok(())
+    }
+
+    /// Transmit a packet of up to 255 bytes.
+    ///
+    /// This blocks until the packet is sent, or the TX timeout set in
+    /// [`FskCfg::set_tx_timeout`] elapses.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::PayloadLen`] if the payload is empty, longer than 255
+    ///   bytes, or does not match the payload length of a fixed length header.
+    /// * [`Error::Timeout`] if the TX timeout elapsed.
+    pub fn transmit(&mut self, data: &[u8]) -> Result<(), Error> {
+        let len: u8 = match u8::try_from(data.len()) {
+            Ok(0) | Err(_) => return Err(Error::PayloadLen),
+            Ok(len) => len,
+        };
+        if self.cfg.header_type == HeaderType::Fixed && len != self.cfg.payload_len {
+            return Err(Error::PayloadLen);
+        }
+
+        self.wakeup()?;
+        self.sg.set_packet_params(&self.cfg.packet_params(len))?;
+        self.sg.write_buffer(TX_BUF_OFFSET, data)?;
+        (self.rfs)(RfState::tx(self.cfg.pa_config.pa()));
+        self.sg.set_tx(self.cfg.tx_timeout)?;
+        let result: Result<u16, Error> = self.poll_irq(Irq::TxDone.mask() | Irq::Timeout.mask());
+        (self.rfs)(RfState::Off);
+
+        if result? & Irq::TxDone.mask() != 0 {
+            Ok(())
+        } else {
+            Err(Error::Timeout)
+        }
+    }
+
+    /// Receive a packet of up to 255 bytes.
+    ///
+    /// This blocks until a packet is received, or the timeout elapses.
+    /// [`Timeout::DISABLED`] waits forever.
+    ///
+    /// Packets rejected by address filtering are dropped by the radio, and
+    /// reception continues.
+    ///
+    /// Returns the length of the received packet, and the packet status.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::Timeout`] if the timeout elapsed.
+    /// * [`Error::Crc`] if a packet with an invalid CRC was received.
+    /// * [`Error::Length`] if a packet with an invalid length was received.
+    /// * [`Error::BufTooSmall`] if the packet does not fit in `buf`.
+    pub fn receive(
+        &mut self,
+        buf: &mut [u8],
+        timeout: Timeout,
+    ) -> Result<(u8, FskPacketStatus), Error> {
+        self.wakeup()?;
+        let max_len: u8 = match self.cfg.header_type {
+            HeaderType::Fixed => self.cfg.payload_len,
+            HeaderType::Variable => u8::MAX,
+        };
+        self.sg
+            .set_packet_params(&self.cfg.packet_params(max_len))?;
+        (self.rfs)(RfState::Rx);
+        self.sg.set_rx(timeout)?;
+        let result: Result<u16, Error> =
+            self.poll_irq(Irq::RxDone.mask() | Irq::Timeout.mask() | Irq::Err.mask());
+        (self.rfs)(RfState::Off);
+        let irq_status: u16 = result?;
+
+        if irq_status & (Irq::RxDone.mask() | Irq::Err.mask()) != 0 {
+            let status: FskPacketStatus = self.sg.fsk_packet_status()?;
+            if status.crc_err() {
+                Err(Error::Crc)
+            } else if status.length_err() {
+                Err(Error::Length)
+            } else {
+                let (_, len, ptr) = self.sg.rx_buffer_status()?;
+                let data: &mut [u8] = buf
+                    .get_mut(..usize::from(len))
+                    .ok_or(Error::BufTooSmall(len))?;
+                self.sg.read_buffer(ptr, data)?;
+                Ok((len, status))
+            }
+        } else {
+            Err(Error::Timeout)
+        }
+    }
+
+    /// Poll the IRQ status until any IRQ in `mask` is set.
+    ///
+    /// Returns the cleared IRQ status.
+    fn poll_irq(&mut self, mask: u16) -> Result<u16, Error> {
+        loop {
+            let (_, irq_status) = self.sg.irq_status()?;
+            if irq_status & mask != 0 {
+                self.sg.clear_irq_status(irq_status)?;
+                return Ok(irq_status);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{CfgError, FskCfg};
+    use crate::subghz::{CalibrateImage, RfFreq};
+    #[cfg(feature = "subghz-sim")]
+    use {
+        super::{Error, FskRadio},
+        crate::{
+            spi::{SgMiso, SgMosi},
+            subghz::{
+                sim::{SimMode, SimRadio, REG_LEN},
+                FskPacketStatus, HeaderType, PacketType, RfState, SleepCfg, Startup, SubGhz,
+                Timeout,
+            },
+        },
+        std::vec::Vec,
+    };
+
+    #[cfg(feature = "subghz-sim")]
+    type SimSubGhz = SubGhz<SgMiso, SgMosi, SimRadio>;
+
+    #[cfg(feature = "subghz-sim")]
+    fn new_sg(responder: fn(&mut SimRadio)) -> SimSubGhz {
+        let mut sim: SimRadio = SimRadio::new();
+        sim.set_responder(Some(responder));
+        SubGhz::new_with_transport(sim)
+    }
+
+    #[cfg(feature = "subghz-sim")]
+    struct NoDelay;
+
+    #[cfg(feature = "subghz-sim")]
+    impl embedded_hal::blocking::delay::DelayUs<u32> for NoDelay {
+        fn delay_us(&mut self, _us: u32) {}
+    }
+
+    #[test]
+    fn validate_cal_image() {
+        let cfg: FskCfg = FskCfg::new().set_rf_freq(RfFreq::F433);
+        assert_eq!(cfg.validate(), Err(CfgError::CalImage));
+        let cfg: FskCfg = cfg.set_cal_image(CalibrateImage::ISM_430_440);
+        assert_eq!(cfg.validate(), Ok(()));
+    }
+
+    #[test]
+    #[cfg(feature = "subghz-sim")]
+    fn new() {
+        let mut states: Vec<RfState> = Vec::new();
+        let radio = FskRadio::new(new_sg(|_| {}), |s| states.push(s), FskCfg::new()).unwrap();
+        let (sg, _) = radio.free();
+        let sim: &SimRadio = sg.transport();
+        assert_eq!(sim.mode(), SimMode::StandbyHse);
+        assert_eq!(sim.packet_type(), Ok(PacketType::Fsk));
+        assert_eq!(sim.rf_freq(), RfFreq::F915);
+        assert_eq!(sim.register(0x06C0), 0x79);
+        assert_eq!(sim.register(0x06C3), 0xC0);
+        assert_eq!(states, [RfState::Off]);
+
+        let cfg: FskCfg = FskCfg::new().set_rf_freq(RfFreq::F433);
+        assert_eq!(
+            FskRadio::new(new_sg(|_| {}), |_| {}, cfg).err(),
+            Some(Error::Cfg(CfgError::CalImage))
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "subghz-sim")]
+    fn set_addrs() {
+        let mut radio = FskRadio::new(new_sg(|_| {}), |_| {}, FskCfg::new()).unwrap();
+        radio.set_addrs(0x12, 0xFF).unwrap();
+        assert_eq!(radio.cfg().node_addr, 0x12);
+        assert_eq!(radio.cfg().broadcast_addr, 0xFF);
+        let (sg, _) = radio.free();
+        assert_eq!(sg.transport().register(0x06CD), 0x12);
+        assert_eq!(sg.transport().register(0x06CE), 0xFF);
+    }
+
+    #[test]
+    #[cfg(feature = "subghz-sim")]
+    fn transmit() {
+        let cfg: FskCfg = FskCfg::new();
+        let mut states: Vec<RfState> = Vec::new();
+        let sg: SimSubGhz = new_sg(|sim| {
+            sim.tx_done();
+        });
+        let mut radio = FskRadio::new(sg, |s| states.push(s), cfg).unwrap();
+        assert_eq!(radio.transmit(b"hello"), Ok(()));
+        assert_eq!(radio.transmit(&[]), Err(Error::PayloadLen));
+        assert_eq!(radio.transmit(&[0; 256]), Err(Error::PayloadLen));
+        let (sg, _) = radio.free();
+        let sim: &SimRadio = sg.transport();
+        assert_eq!(&sim.buffer()[..5], b"hello");
+        // payload length
+        assert_eq!(sim.packet_params()[6], 5);
+        assert_eq!(sim.mode(), SimMode::StandbyHse);
+        assert_eq!(sim.irq_status(), 0);
+        assert_eq!(
+            states,
+            [RfState::Off, RfState::tx(cfg.pa_config.pa()), RfState::Off]
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "subghz-sim")]
+    fn transmit_fixed_len() {
+        let cfg: FskCfg = FskCfg::new().set_header_type(HeaderType::Fixed, 4);
+        let sg: SimSubGhz = new_sg(|sim| {
+            sim.tx_done();
+        });
+        let mut radio = FskRadio::new(sg, |_| {}, cfg).unwrap();
+        assert_eq!(radio.transmit(b"hello"), Err(Error::PayloadLen));
+        assert_eq!(radio.transmit(b"hell"), Ok(()));
+    }
+
+    #[test]
+    #[cfg(feature = "subghz-sim")]
+    fn transmit_timeout() {
+        let mut states: Vec<RfState> = Vec::new();
+        let sg: SimSubGhz = new_sg(|sim| {
+            sim.timeout();
+        });
+        let mut radio = FskRadio::new(sg, |s| states.push(s), FskCfg::new()).unwrap();
+        assert_eq!(radio.transmit(b"hello"), Err(Error::Timeout));
+        let (sg, _) = radio.free();
+        assert_eq!(sg.transport().mode(), SimMode::StandbyHse);
+        assert_eq!(states.last(), Some(&RfState::Off));
+    }
+
+    #[test]
+    #[cfg(feature = "subghz-sim")]
+    fn receive() {
+        let mut states: Vec<RfState> = Vec::new();
+        let sg: SimSubGhz = new_sg(|sim| {
+            sim.rx_done(b"hello", -40, 0);
+        });
+        let mut radio = FskRadio::new(sg, |s| states.push(s), FskCfg::new()).unwrap();
+        let mut buf: [u8; 255] = [0; 255];
+        let (len, status): (u8, FskPacketStatus) =
+            radio.receive(&mut buf, Timeout::DISABLED).unwrap();
+        assert_eq!(&buf[..usize::from(len)], b"hello");
+        assert_eq!(status.rssi_sync().to_integer(), -40);
+        assert!(!status.any_err());
+
+        let mut buf: [u8; 4] = [0; 4];
+        assert_eq!(
+            radio.receive(&mut buf, Timeout::DISABLED),
+            Err(Error::BufTooSmall(5))
+        );
+        let (sg, _) = radio.free();
+        // variable length packets are received up to the maximum length
+        assert_eq!(sg.transport().packet_params()[6], u8::MAX);
+        assert_eq!(sg.transport().mode(), SimMode::StandbyHse);
+        assert_eq!(
+            states,
+            [
+                RfState::Off,
+                RfState::Rx,
+                RfState::Off,
+                RfState::Rx,
+                RfState::Off
+            ]
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "subghz-sim")]
+    fn receive_fixed_len() {
+        let cfg: FskCfg = FskCfg::new().set_header_type(HeaderType::Fixed, 4);
+        let sg: SimSubGhz = new_sg(|sim| {
+            sim.rx_done(b"hell", -40, 0);
+        });
+        let mut radio = FskRadio::new(sg, |_| {}, cfg).unwrap();
+        let mut buf: [u8; 4] = [0; 4];
+        let (len, _) = radio.receive(&mut buf, Timeout::DISABLED).unwrap();
+        assert_eq!(&buf[..usize::from(len)], b"hell");
+        let (sg, _) = radio.free();
+        assert_eq!(sg.transport().packet_params()[6], 4);
+    }
+
+    #[test]
+    #[cfg(feature = "subghz-sim")]
+    fn receive_timeout() {
+        let sg: SimSubGhz = new_sg(|sim| {
+            sim.timeout();
+        });
+        let mut radio = FskRadio::new(sg, |_| {}, FskCfg::new()).unwrap();
+        let mut buf: [u8; 255] = [0; 255];
+        assert_eq!(
+            radio.receive(&mut buf, Timeout::from_millis_sat(10)),
+            Err(Error::Timeout)
+        );
+        let (sg, _) = radio.free();
+        assert_eq!(sg.transport().irq_status(), 0);
+    }
+
+    #[test]
+    #[cfg(feature = "subghz-sim")]
+    fn sleep_wake() {
+        for startup in [Startup::Warm, Startup::Cold] {
+            let sleep_cfg: SleepCfg = SleepCfg::new().set_startup(startup);
+            let sg: SimSubGhz = new_sg(|sim| {
+                sim.tx_done();
+            });
+            let mut radio = FskRadio::new(sg, |_| {}, FskCfg::new()).unwrap();
+            let before: SimRadio = radio.sg.transport().clone();
+
+            radio.sleep(sleep_cfg, &mut NoDelay).unwrap();
+            assert!(radio.is_asleep());
+            assert_eq!(radio.sg.transport().mode(), SimMode::Sleep(startup));
+            // does nothing if the radio is already in sleep mode
+            radio.sleep(sleep_cfg, &mut NoDelay).unwrap();
+
+            radio.wakeup().unwrap();
+            assert!(!radio.is_asleep());
+            let sim: &SimRadio = radio.sg.transport();
+            let mode: SimMode = match startup {
+                Startup::Warm => SimMode::StandbyRc,
+                // reconfigured
+                Startup::Cold => SimMode::StandbyHse,
+            };
+            assert_eq!(sim.mode(), mode);
+            assert_eq!(sim.packet_type(), before.packet_type());
+            assert_eq!(sim.rf_freq(), before.rf_freq());
+            assert_eq!(sim.mod_params(), before.mod_params());
+            assert_eq!(sim.packet_params(), before.packet_params());
+            assert_eq!(sim.irq_mask(), before.irq_mask());
+            for addr in 0..REG_LEN as u16 {
+                assert_eq!(sim.register(addr), before.register(addr), "{addr:#06X}");
+            }
+
+            // operations wake the radio
+            radio.sleep(sleep_cfg, &mut NoDelay).unwrap();
+            assert_eq!(radio.transmit(b"hello"), Ok(()));
+            assert!(!radio.is_asleep());
+
+            // free wakes the radio
+            radio.sleep(sleep_cfg, &mut NoDelay).unwrap();
+            let (sg, _) = radio.free();
+            assert_eq!(sg.transport().mode(), SimMode::StandbyRc);
+        }
+    }
+}
@@ -0,0 +1,947 @@
+//! High-level LoRa transceiver
+//!
+//! [`LoRaRadio`] owns a [`SubGhz`] radio and an RF switch callback, and
+//! sequences the low-level commands for the common LoRa operations.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use stm32wlxx_hal::{
+//!     pac,
+//!     subghz::{
+//!         lora::{LoRaCfg, LoRaRadio},
+//!         CalibrateImage, LoRaBandwidth, LoRaModParams, RfFreq, RfState, SpreadingFactor,
+//!         SubGhz, Timeout,
+//!     },
+//!     spi::{SgMiso, SgMosi},
+//!     util::new_delay,
+//! };
+//!
+//! const CFG: LoRaCfg = LoRaCfg::new()
+//!     .set_rf_freq(RfFreq::F868)
+//!     .set_cal_image(CalibrateImage::ISM_863_870)
+//!     .set_mod_params(
+//!         LoRaModParams::new()
+//!             .set_sf(SpreadingFactor::Sf7)
+//!             .set_bw(LoRaBandwidth::Bw125),
+//!     );
+//!
+//! let mut dp: pac::Peripherals = pac::Peripherals::take().unwrap();
+//! let cp: pac::CorePeripherals = pac::CorePeripherals::take().unwrap();
+//! let mut delay = new_delay(cp.SYST, &dp.RCC);
+//!
+//! let sg: SubGhz<SgMiso, SgMosi> = SubGhz::new(dp.SPI3, &mut dp.RCC);
+//! // forward the state to the RF switch on this line
+//! let rfs = |_: RfState| ();
+//! let mut radio = LoRaRadio::new(sg, rfs, CFG)?;
+//!
+//! radio.transmit(b"Hello, World!")?;
+//!
+//! let mut buf: [u8; 255] = [0; 255];
+//! let (len, status) = radio.receive(&mut buf, Timeout::from_millis_sat(1000))?;
+//!
+//! radio.sleep(Default::default(), &mut delay)?;
+//! # Ok::<(), stm32wlxx_hal::subghz::lora::Error>(())
+//! ```
+
+use super::{
+    CadParams, CalibrateImage, CfgIrq, FallbackMode, HeaderType, Irq, LoRaModParams,
+    LoRaPacketParams, LoRaPacketStatus, LoRaSyncWord, PaConfig, PacketType, RegMode, RfFreq,
+    RfState, SleepCfg, StandbyClk, Startup, SubGhz, TcxoMode, Timeout, Transport, TxParams,
+};
+use crate::spi::Spi3;
+use embedded_hal::blocking::delay::DelayUs;
+
+const TX_BUF_OFFSET: u8 = 0;
+const RX_BUF_OFFSET: u8 = 0;
+
+const IRQ_CFG: CfgIrq = CfgIrq::new()
+    .irq_enable_all(Irq::TxDone)
+    .irq_enable_all(Irq::RxDone)
+    .irq_enable_all(Irq::Timeout)
+    .irq_enable_all(Irq::HeaderErr)
+    .irq_enable_all(Irq::Err)
+    .irq_enable_all(Irq::CadDone)
+    .irq_enable_all(Irq::CadDetected);
+
+/// Invalid parameter combinations in a [`LoRaCfg`].
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum CfgError {
+    /// The symbol time is 16.38 ms or longer, but low data rate optimization
+    /// is disabled.
+    ///
+    /// Use [`LoRaModParams::set_ldro_auto`] to enable it when required.
+    LdroRequired,
+    /// The header type is fixed, but the payload length is zero.
+    FixedPayloadLen,
+    /// The RF frequency is outside of the 150 - 960 MHz band.
+    RfFreq,
+    /// The image calibration does not cover the RF frequency.
+    ///
+    /// Use [`CalibrateImage::for_freq`] to get the image calibration for
+    /// the RF frequency.
+    CalImage,
+}
+
+/// LoRa radio errors.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Error {
+    /// SPI error.
+    Spi(super::Error),
+    /// Invalid configuration.
+    Cfg(CfgError),
+    /// The operation timed out.
+    Timeout,
+    /// A packet was received with an invalid CRC.
+    Crc,
+    /// A packet was received with an invalid header.
+    Header,
+    /// Invalid payload length.
+    ///
+    /// The payload is empty, or does not match the payload length of a fixed
+    /// length header.
+    PayloadLen,
+    /// The receive buffer is too small, contains the received packet length.
+    BufTooSmall(u8),
+}
+
+impl From<super::Error> for Error {
+    fn from(e: super::Error) -> Self {
+        Error::Spi(e)
+    }
+}
+
+impl From<CfgError> for Error {
+    fn from(e: CfgError) -> Self {
+        Error::Cfg(e)
+    }
+}
+
+/// LoRa radio configuration.
+///
+/// Argument of [`LoRaRadio::new`].
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct LoRaCfg {
+    rf_freq: RfFreq,
+    mod_params: LoRaModParams,
+    packet_params: LoRaPacketParams,
+    sync_word: LoRaSyncWord,
+    cad_params: CadParams,
+    pa_config: PaConfig,
+    tx_params: TxParams,
+    reg_mode: RegMode,
+    tcxo_mode: Option<TcxoMode>,
+    cal_image: CalibrateImage,
+    tx_timeout: Timeout,
+}
+
+impl LoRaCfg {
+    /// Create a new `LoRaCfg`.
+    ///
+    /// This is the same as `default`, but in a `const` function.
+    ///
+    /// The defaults are 915 MHz, the default modulation, packet, and CAD
+    /// parameters, the public sync word, the low-power PA at 14 dBm, the LDO
+    /// regulator, no TCXO, and no TX timeout.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::subghz::lora::LoRaCfg;
+    ///
+    /// const CFG: LoRaCfg = LoRaCfg::new();
+    /// assert_eq!(CFG, LoRaCfg::default());
+    /// assert_eq!(CFG.validate(), Ok(()));
+    /// ```
+    pub const fn new() -> LoRaCfg {
+        LoRaCfg {
+            rf_freq: RfFreq::F915,
+            mod_params: LoRaModParams::new(),
+            packet_params: LoRaPacketParams::new(),
+            sync_word: LoRaSyncWord::Public,
+            cad_params: CadParams::new(),
+            pa_config: PaConfig::LP_14,
+            tx_params: TxParams::LP_14,
+            reg_mode: RegMode::Ldo,
+            tcxo_mode: None,
+            cal_image: CalibrateImage::ISM_902_928,
+            tx_timeout: Timeout::DISABLED,
+        }
+    }
+
+    /// Set the RF frequency.
+    ///
+    /// The image calibration should be set to match with
+    /// [`set_cal_image`](Self::set_cal_image).
+    #[must_use = "set_rf_freq returns a modified LoRaCfg"]
+    pub const fn set_rf_freq(mut self, rf_freq: RfFreq) -> LoRaCfg {
+        self.rf_freq = rf_freq;
+        self
+    }
+
+    /// Set the modulation parameters.
+    #[must_use = "set_mod_params returns a modified LoRaCfg"]
+    pub const fn set_mod_params(mut self, mod_params: LoRaModParams) -> LoRaCfg {
+        self.mod_params = mod_params;
+        self
+    }
+
+    /// Set the packet parameters.
+    ///
+    /// With a variable length header the payload length is set for each
+    /// transmission.
+    #[must_use = "set_packet_params returns a modified LoRaCfg"]
+    pub const fn set_packet_params(mut self, packet_params: LoRaPacketParams) -> LoRaCfg {
+        self.packet_params = packet_params;
+        self
+    }
+
+    /// Set the sync word.
+    #[must_use = "set_sync_word returns a modified LoRaCfg"]
+    pub const fn set_sync_word(mut self, sync_word: LoRaSyncWord) -> LoRaCfg {
+        self.sync_word = sync_word;
+        self
+    }
+
+    /// Set the channel activity detection parameters.
+    #[must_use = "set_cad_params returns a modified LoRaCfg"]
+    pub const fn set_cad_params(mut self, cad_params: CadParams) -> LoRaCfg {
+        self.cad_params = cad_params;
+        self
+    }
+
+    /// Set the power amplifier configuration and TX parameters.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::subghz::{lora::LoRaCfg, PaConfig, TxParams};
+    ///
+    /// const CFG: LoRaCfg = LoRaCfg::new().set_pa(PaConfig::HP_22, TxParams::HP);
+    /// ```
+    #[must_use = "set_pa returns a modified LoRaCfg"]
+    pub const fn set_pa(mut self, pa_config: PaConfig, tx_params: TxParams) -> LoRaCfg {
+        self.pa_config = pa_config;
+        self.tx_params = tx_params;
+        self
+    }
+
+    /// Set the radio power supply.
+    #[must_use = "set_reg_mode returns a modified LoRaCfg"]
+    pub const fn set_reg_mode(mut self, reg_mode: RegMode) -> LoRaCfg {
+        self.reg_mode = reg_mode;
+        self
+    }
+
+    /// Set the TCXO mode, `None` if the board does not use a TCXO.
+    #[must_use = "set_tcxo_mode returns a modified LoRaCfg"]
+    pub const fn set_tcxo_mode(mut self, tcxo_mode: Option<TcxoMode>) -> LoRaCfg {
+        self.tcxo_mode = tcxo_mode;
+        self
+    }
+
+    /// Set the image calibration.
+    #[must_use = "set_cal_image returns a modified LoRaCfg"]
+    pub const fn set_cal_image(mut self, cal_image: CalibrateImage) -> LoRaCfg {
+        self.cal_image = cal_image;
+        self
+    }
+
+    /// Set the TX timeout.
+    ///
+    /// [`Timeout::DISABLED`] disables the timeout.
+    #[must_use = "set_tx_timeout returns a modified LoRaCfg"]
+    pub const fn set_tx_timeout(mut self, tx_timeout: Timeout) -> LoRaCfg {
+        self.tx_timeout = tx_timeout;
+        self
+    }
+
+    /// Get the RF frequency.
+    pub const fn rf_freq(&self) -> RfFreq {
+        self.rf_freq
+    }
+
+    /// Get the modulation parameters.
+    pub const fn mod_params(&self) -> LoRaModParams {
+        self.mod_params
+    }
+
+    /// Get the packet parameters.
+    pub const fn packet_params(&self) -> LoRaPacketParams {
+        self.packet_params
+    }
+
+    /// Get the power amplifier configuration.
+    pub const fn pa_config(&self) -> PaConfig {
+        self.pa_config
+    }
+
+    /// Check the configuration for invalid parameter combinations.
+    ///
+    /// This is called by [`LoRaRadio::new`], it is `const` so the
+    /// configuration can also be checked at compile time.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use static_assertions as sa;
+    /// use stm32wlxx_hal::subghz::{
+    ///     lora::{CfgError, LoRaCfg},
+    ///     LoRaBandwidth, LoRaModParams, SpreadingFactor,
+    /// };
+    ///
+    /// const MOD_PARAMS: LoRaModParams = LoRaModParams::new()
+    ///     .set_sf(SpreadingFactor::Sf12)
+    ///     .set_bw(LoRaBandwidth::Bw125);
+    ///
+    /// const CFG: LoRaCfg = LoRaCfg::new().set_mod_params(MOD_PARAMS);
+    /// assert_eq!(CFG.validate(), Err(CfgError::LdroRequired));
+    ///
+    /// const CFG_LDRO: LoRaCfg = LoRaCfg::new().set_mod_params(MOD_PARAMS.set_ldro_en(true));
+    /// sa::const_assert!(CFG_LDRO.validate().is_ok());
+    /// ```
+    pub const fn validate(&self) -> Result<(), CfgError> {
+        if !self.rf_freq.is_valid() {
+            return Err(CfgError::RfFreq);
+        }
+
+        if !self.cal_image.covers(&self.rf_freq) {
+            return Err(CfgError::CalImage);
+        }
+
+        if matches!(self.packet_params.header_type(), HeaderType::Fixed)
+            && self.packet_params.payload_len() == 0
+        {
+            return Err(CfgError::FixedPayloadLen);
+        }
+
+        if self.mod_params.ldro_required() && !self.mod_params.ldro_en() {
+            return Err(CfgError::LdroRequired);
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for LoRaCfg {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// High-level LoRa transceiver.
+///
+/// The RF switch callback is called with the required [`RfState`] before each
+/// operation, and with [`RfState::Off`] after each operation.
+///
+/// All operations block until completion.
+/// If the radio is in sleep mode it is woken up before the operation, and
+/// reconfigured if it was put to sleep with a cold startup.
+#[derive(Debug)]
+pub struct LoRaRadio<MISO, MOSI, RFS, T = Spi3<MISO, MOSI>> {
+    sg: SubGhz<MISO, MOSI, T>,
+    rfs: RFS,
+    cfg: LoRaCfg,
+    sleep: Option<SleepCfg>,
+}
+
+impl<MISO, MOSI, RFS, T> LoRaRadio<MISO, MOSI, RFS, T>
+where
+    T: Transport,
+    RFS: FnMut(RfState),
+{
+    /// Create a new LoRa radio.
+    ///
+    /// This validates the configuration, then configures the radio for LoRa
+    /// and leaves it in standby mode.
+    ///
+    /// # Example
+    ///
+    /// See the [module-level documentation](self).
+    pub fn new(sg: SubGhz<MISO, MOSI, T>, rfs: RFS, cfg: LoRaCfg) -> Result<Self, Error> {
+        cfg.validate()?;
+        let mut radio: Self = LoRaRadio {
+            sg,
+            rfs,
+            cfg,
+            sleep: None,
+        };
+        (radio.rfs)(RfState::Off);
+        radio.init()?;
+        Ok(radio)
+    }
+
+    /// Free the radio and RF switch callback from the driver.
+    ///
+    /// The radio is left in its current mode, except for sleep mode.
+    /// If the radio is in sleep mode it is woken up, after a cold startup the
+    /// radio must be configured again.
+    pub fn free(mut self) -> (SubGhz<MISO, MOSI, T>, RFS) {
+        if self.sleep.take().is_some() {
+            self.sg.wake_in_place();
+        }
+        (self.sg, self.rfs)
+    }
+
+    /// Get the radio configuration.
+    #[inline]
+    pub const fn cfg(&self) -> &LoRaCfg {
+        &self.cfg
+    }
+
+    /// Returns `true` if the radio is in sleep mode.
+    #[inline]
+    pub const fn is_asleep(&self) -> bool {
+        self.sleep.is_some()
+    }
+
+    fn init(&mut self) -> Result<(), Error> {
+        let cfg: LoRaCfg = self.cfg;
+        self.sg.set_standby(StandbyClk::Rc)?;
+        if let Some(tcxo_mode) = cfg.tcxo_mode {
+            self.sg.set_tcxo_mode(&tcxo_mode)?;
+        }
+        self.sg.set_standby(StandbyClk::Hse)?;
+        self.sg.set_tx_rx_fallback_mode(FallbackMode::StandbyHse)?;
+        self.sg.set_regulator_mode(cfg.reg_mode)?;
+        self.sg
+            .set_buffer_base_address(TX_BUF_OFFSET, RX_BUF_OFFSET)?;
+        self.sg.set_pa_config(&cfg.pa_config)?;
+        self.sg.set_tx_params(&cfg.tx_params)?;
+        self.sg.set_packet_type(PacketType::LoRa)?;
+        self.sg.set_lora_sync_word(cfg.sync_word)?;
+        self.sg.set_lora_mod_params(&cfg.mod_params)?;
+        self.sg.set_lora_packet_params(&cfg.packet_params)?;
+        self.sg.calibrate_image(cfg.cal_image)?;
+        self.sg.set_rf_frequency(&cfg.rf_freq)?;
+        self.sg.set_irq_cfg(&IRQ_CFG)?;
+        Ok(())
+    }
+
+    /// Wakeup the radio from sleep mode.
+    ///
+    /// This does nothing if the radio is not in sleep mode.
+    ///
+    /// This is called automatically by all operations, calling it manually
+    /// is only required to move the wakeup latency out of an operation.
+    pub fn wakeup(&mut self) -> Result<(), Error> {
+        if let Some(cfg) = self.sleep.take() {
+            self.sg.wake_in_place();
+            if cfg.startup() == Startup::Cold {
+                self.reinit()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Reconfigure the radio after a cold startup.
+    #[cfg(feature = "subghz-snapshot")]
+    fn reinit(&mut self) -> Result<(), Error> {
+        self.sg.restore_snapshot()?;
+        // registers and calibration are not part of the snapshot
+        self.sg.set_standby(StandbyClk::Hse)?;
+        self.sg.set_tx_rx_fallback_mode(FallbackMode::StandbyHse)?;
+        self.sg
+            .set_buffer_base_address(TX_BUF_OFFSET, RX_BUF_OFFSET)?;
+        self.sg.set_lora_sync_word(self.cfg.sync_word)?;
+        self.sg.calibrate_image(self.cfg.cal_image)?;
+        Ok(())
+    }
+
+    /// Reconfigure the radio after a cold startup.
+    #[cfg(not(feature = "subghz-snapshot"))]
+    fn reinit(&mut self) -> Result<(), Error> {
+        self.init()
+    }
+
+    /// Put the radio into sleep mode.
+    ///
+    /// This does nothing if the radio is already in sleep mode.
+    ///
+    /// The delay is used to wait the 500 μs required after sleep before the
+    /// radio can be accessed again, see [`SubGhz::sleep`].
+    ///
+    /// With [`Startup::Cold`] the radio configuration is lost, and restored
+    /// on wakeup.
+    pub fn sleep<D: DelayUs<u32>>(&mut self, cfg: SleepCfg, delay: &mut D) -> Result<(), Error> {
+        if self.sleep.is_some() {
+            return Ok(());
+        }
+        (self.rfs)(RfState::Off);
+        self.sg.sleep_in_place(cfg, delay)?;
+        self.sleep = Some(cfg);
+        Ok(())
+    }
+
+    /// Set the RF frequency.
+    ///
+    /// This changes the frequency without repeating the image calibration,
+    /// the new frequency should be in the calibrated band.
+    ///
+    /// # Errors
+    ///
+    /// * [`CfgError::RfFreq`] if the frequency is outside of the 150 - 960 MHz
+    ///   band, the configuration is unchanged.
+    pub fn set_rf_freq(&mut self, rf_freq: RfFreq) -> Result<(), Error> {
+        let cfg: LoRaCfg = self.cfg.set_rf_freq(rf_freq);
+        cfg.validate()?;
+        self.wakeup()?;
+        self.sg.set_rf_frequency(&rf_freq)?;
+        self.cfg = cfg;
+        Ok(())
+    }
+
+    /// Set the modulation parameters.
+    ///
+    /// # Errors
+    ///
+    /// * [`CfgError::LdroRequired`] if low data rate optimization is required
+    ///   but disabled, the configuration is unchanged.
+    pub fn set_mod_params(&mut self, mod_params: LoRaModParams) -> Result<(), Error> {
+        let cfg: LoRaCfg = self.cfg.set_mod_params(mod_params);
+        cfg.validate()?;
+        self.wakeup()?;
+        self.sg.set_lora_mod_params(&mod_params)?;
+        self.cfg = cfg;
+        Ok(())
+    }
+
+    /// Set the packet parameters.
+    ///
+    /// The packet parameters are written to the radio before each operation.
+    ///
+    /// # Errors
+    ///
+    /// * [`CfgError::FixedPayloadLen`] if the header is fixed with a zero
+    ///   payload length, the configuration is unchanged.
+    pub fn set_packet_params(&mut self, packet_params: LoRaPacketParams) -> Result<(), Error> {
+        let cfg: LoRaCfg = self.cfg.set_packet_params(packet_params);
+        cfg.validate()?;
+        self.cfg = cfg;
+        Ok(())
+    }
+
+    /// Transmit a packet.
+    ///
+    /// This blocks until the packet is sent, or the TX timeout set in
+    /// [`LoRaCfg::set_tx_timeout`] elapses.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::PayloadLen`] if the payload is empty, longer than 255
+    ///   bytes, or does not match the payload length of a fixed length header.
+    /// * [`Error::Timeout`] if the TX timeout elapsed.
+    pub fn transmit(&mut self, data: &[u8]) -> Result<(), Error> {
+        let len: u8 = match u8::try_from(data.len()) {
+            Ok(0) | Err(_) => return Err(Error::PayloadLen),
+            Ok(len) => len,
+        };
+        let packet_params: LoRaPacketParams = match self.cfg.packet_params.header_type() {
+            HeaderType::Fixed if len != self.cfg.packet_params.payload_len() => {
+                return Err(Error::PayloadLen)
+            }
+            HeaderType::Fixed => self.cfg.packet_params,
+            HeaderType::Variable => self.cfg.packet_params.set_payload_len(len),
+        };
+
+        self.wakeup()?;
+        self.sg.set_lora_packet_params(&packet_params)?;
+        self.sg.write_buffer(TX_BUF_OFFSET, data)?;
+        (self.rfs)(RfState::tx(self.cfg.pa_config.pa()));
+        self.sg.set_tx(self.cfg.tx_timeout)?;
+        let result: Result<u16, Error> = self.poll_irq(Irq::TxDone.mask() | Irq::Timeout.mask());
+        (self.rfs)(RfState::Off);
+
+        if result? & Irq::TxDone.mask() != 0 {
+            Ok(())
+        } else {
+            Err(Error::Timeout)
+        }
+    }
+
+    /// Receive a packet.
+    ///
+    /// This blocks until a packet is received, or the timeout elapses.
+    /// [`Timeout::DISABLED`] waits forever.
+    ///
+    /// Returns the length of the received packet, and the packet status.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::Timeout`] if the timeout elapsed.
+    /// * [`Error::Header`] if a packet with an invalid header was received.
+    /// * [`Error::Crc`] if a packet with an invalid CRC was received.
+    /// * [`Error::BufTooSmall`] if the packet does not fit in `buf`.
+    pub fn receive(
+        &mut self,
+        buf: &mut [u8],
+        timeout: Timeout,
+    ) -> Result<(u8, LoRaPacketStatus), Error> {
+        self.wakeup()?;
+        self.sg.set_lora_packet_params(&self.cfg.packet_params)?;
+        (self.rfs)(RfState::Rx);
+        self.sg.set_rx(timeout)?;
+        let result: Result<u16, Error> = self.poll_irq(
+            Irq::RxDone.mask() | Irq::Timeout.mask() | Irq::HeaderErr.mask() | Irq::Err.mask(),
+        );
+        (self.rfs)(RfState::Off);
+        let irq_status: u16 = result?;
+
+        if irq_status & Irq::HeaderErr.mask() != 0 {
+            // the radio keeps searching after a header error
+            self.sg.set_standby(StandbyClk::Hse)?;
+            Err(Error::Header)
+        } else if irq_status & Irq::Err.mask() != 0 {
+            Err(Error::Crc)
+        } else if irq_status & Irq::RxDone.mask() != 0 {
+            let (_, len, ptr) = self.sg.rx_buffer_status()?;
+            let data: &mut [u8] = buf
+                .get_mut(..usize::from(len))
+                .ok_or(Error::BufTooSmall(len))?;
+            self.sg.read_buffer(ptr, data)?;
+            let status: LoRaPacketStatus = self.sg.lora_packet_status()?;
+            Ok((len, status))
+        } else {
+            Err(Error::Timeout)
+        }
+    }
+
+    /// Perform channel activity detection.
+    ///
+    /// Returns `true` if LoRa activity was detected on the channel.
+    ///
+    /// The number of symbols and detection thresholds are set with
+    /// [`LoRaCfg::set_cad_params`].
+    pub fn cad(&mut self) -> Result<bool, Error> {
+        self.wakeup()?;
+        self.sg.set_cad_params(&self.cfg.cad_params)?;
+        (self.rfs)(RfState::Rx);
+        self.sg.set_cad()?;
+        let result: Result<u16, Error> = self.poll_irq(Irq::CadDone.mask());
+        (self.rfs)(RfState::Off);
+        Ok(result? & Irq::CadDetected.mask() != 0)
+    }
+
+    /// Poll the IRQ status until any IRQ in `mask` is set.
+    ///
+    /// Returns the cleared IRQ status.
+    fn poll_irq(&mut self, mask: u16) -> Result<u16, Error> {
+        loop {
+            let (_, irq_status) = self.sg.irq_status()?;
+            if irq_status & mask != 0 {
+                self.sg.clear_irq_status(irq_status)?;
+                return Ok(irq_status);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{CfgError, LoRaCfg};
+    use crate::subghz::{
+        CalibrateImage, HeaderType, LoRaBandwidth, LoRaModParams, LoRaPacketParams, RfFreq,
+        SpreadingFactor,
+    };
+    #[cfg(feature = "subghz-sim")]
+    use {
+        super::{Error, LoRaRadio},
+        crate::{
+            spi::{SgMiso, SgMosi},
+            subghz::{
+                sim::{SimMode, SimRadio, REG_LEN},
+                Irq, LoRaPacketStatus, PacketType, RfState, SleepCfg, Startup, SubGhz, Timeout,
+            },
+        },
+        std::vec::Vec,
+    };
+
+    #[cfg(feature = "subghz-sim")]
+    const VARIABLE: LoRaCfg = LoRaCfg::new().set_packet_params(
+        LoRaPacketParams::new()
+            .set_preamble_len(8)
+            .set_header_type(HeaderType::Variable),
+    );
+
+    #[cfg(feature = "subghz-sim")]
+    type SimSubGhz = SubGhz<SgMiso, SgMosi, SimRadio>;
+
+    #[cfg(feature = "subghz-sim")]
+    fn new_sg(responder: fn(&mut SimRadio)) -> SimSubGhz {
+        let mut sim: SimRadio = SimRadio::new();
+        sim.set_responder(Some(responder));
+        SubGhz::new_with_transport(sim)
+    }
+
+    #[cfg(feature = "subghz-sim")]
+    struct NoDelay;
+
+    #[cfg(feature = "subghz-sim")]
+    impl embedded_hal::blocking::delay::DelayUs<u32> for NoDelay {
+        fn delay_us(&mut self, _us: u32) {}
+    }
+
+    #[test]
+    fn validate() {
+        assert_eq!(LoRaCfg::new().validate(), Ok(()));
+
+        let cfg: LoRaCfg = LoRaCfg::new().set_rf_freq(RfFreq::from_frequency(100_000_000));
+        assert_eq!(cfg.validate(), Err(CfgError::RfFreq));
+
+        let cfg: LoRaCfg = LoRaCfg::new().set_packet_params(
+            LoRaPacketParams::new()
+                .set_header_type(HeaderType::Fixed)
+                .set_payload_len(0),
+        );
+        assert_eq!(cfg.validate(), Err(CfgError::FixedPayloadLen));
+
+        let mod_params: LoRaModParams = LoRaModParams::new()
+            .set_sf(SpreadingFactor::Sf11)
+            .set_bw(LoRaBandwidth::Bw125);
+        let cfg: LoRaCfg = LoRaCfg::new().set_mod_params(mod_params);
+        assert_eq!(cfg.validate(), Err(CfgError::LdroRequired));
+        let cfg: LoRaCfg = cfg.set_mod_params(mod_params.set_ldro_auto());
+        assert_eq!(cfg.validate(), Ok(()));
+    }
+
+    #[test]
+    fn validate_cal_image() {
+        let eu868: RfFreq = RfFreq::from_frequency(868_100_000);
+
+        // the default calibration is for 902 - 928 MHz
+        let cfg: LoRaCfg = LoRaCfg::new().set_rf_freq(eu868);
+        assert_eq!(cfg.validate(), Err(CfgError::CalImage));
+
+        let cfg: LoRaCfg = cfg.set_cal_image(CalibrateImage::ISM_863_870);
+        assert_eq!(cfg.validate(), Ok(()));
+
+        let cfg: LoRaCfg = cfg.set_cal_image(CalibrateImage::for_freq(&eu868));
+        assert_eq!(cfg.validate(), Ok(()));
+
+        // a wider calibration covers the frequency
+        let cfg: LoRaCfg = cfg.set_cal_image(CalibrateImage::new(0xD7, 0xE9));
+        assert_eq!(cfg.validate(), Ok(()));
+
+        // edge of the band
+        let cfg: LoRaCfg = LoRaCfg::new()
+            .set_rf_freq(RfFreq::from_frequency(169_400_000))
+            .set_cal_image(CalibrateImage::new(41, 43));
+        assert_eq!(cfg.validate(), Err(CfgError::CalImage));
+        let cfg: LoRaCfg = cfg.set_cal_image(CalibrateImage::new(41, 44));
+        assert_eq!(cfg.validate(), Ok(()));
+    }
+
+    #[test]
+    #[cfg(feature = "subghz-sim")]
+    fn new() {
+        let mut states: Vec<RfState> = Vec::new();
+        let radio = LoRaRadio::new(new_sg(|_| {}), |s| states.push(s), LoRaCfg::new()).unwrap();
+        let (sg, _) = radio.free();
+        let sim: &SimRadio = sg.transport();
+        assert_eq!(sim.mode(), SimMode::StandbyHse);
+        assert_eq!(sim.packet_type(), Ok(PacketType::LoRa));
+        assert_eq!(sim.rf_freq(), LoRaCfg::new().rf_freq);
+        assert_eq!(states, [RfState::Off]);
+
+        let cfg: LoRaCfg = LoRaCfg::new().set_rf_freq(RfFreq::F868);
+        assert_eq!(
+            LoRaRadio::new(new_sg(|_| {}), |_| {}, cfg).err(),
+            Some(Error::Cfg(CfgError::CalImage))
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "subghz-sim")]
+    fn transmit() {
+        let cfg: LoRaCfg = VARIABLE;
+        let mut states: Vec<RfState> = Vec::new();
+        let sg: SimSubGhz = new_sg(|sim| {
+            sim.tx_done();
+        });
+        let mut radio = LoRaRadio::new(sg, |s| states.push(s), cfg).unwrap();
+        assert_eq!(radio.transmit(b"hello"), Ok(()));
+        assert_eq!(radio.transmit(&[]), Err(Error::PayloadLen));
+        assert_eq!(radio.transmit(&[0; 256]), Err(Error::PayloadLen));
+        let (sg, _) = radio.free();
+        assert_eq!(&sg.transport().buffer()[..5], b"hello");
+        assert_eq!(sg.transport().mode(), SimMode::StandbyHse);
+        assert_eq!(sg.transport().irq_status(), 0);
+        assert_eq!(
+            states,
+            [RfState::Off, RfState::tx(cfg.pa_config.pa()), RfState::Off]
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "subghz-sim")]
+    fn transmit_fixed_len() {
+        let cfg: LoRaCfg = LoRaCfg::new().set_packet_params(
+            LoRaPacketParams::new()
+                .set_header_type(HeaderType::Fixed)
+                .set_payload_len(4),
+        );
+        let sg: SimSubGhz = new_sg(|sim| {
+            sim.tx_done();
+        });
+        let mut radio = LoRaRadio::new(sg, |_| {}, cfg).unwrap();
+        assert_eq!(radio.transmit(b"hello"), Err(Error::PayloadLen));
+        assert_eq!(radio.transmit(b"hell"), Ok(()));
+    }
+
+    #[test]
+    #[cfg(feature = "subghz-sim")]
+    fn transmit_timeout() {
+        let mut states: Vec<RfState> = Vec::new();
+        let sg: SimSubGhz = new_sg(|sim| {
+            sim.timeout();
+        });
+        let mut radio = LoRaRadio::new(sg, |s| states.push(s), VARIABLE).unwrap();
+        assert_eq!(radio.transmit(b"hello"), Err(Error::Timeout));
+        let (sg, _) = radio.free();
+        assert_eq!(sg.transport().mode(), SimMode::StandbyHse);
+        assert_eq!(states.last(), Some(&RfState::Off));
+    }
+
+    #[test]
+    #[cfg(feature = "subghz-sim")]
+    fn receive() {
+        let mut states: Vec<RfState> = Vec::new();
+        let sg: SimSubGhz = new_sg(|sim| {
+            sim.rx_done(b"hello", -50, 8);
+        });
+        let mut radio = LoRaRadio::new(sg, |s| states.push(s), LoRaCfg::new()).unwrap();
+        let mut buf: [u8; 255] = [0; 255];
+        let (len, status): (u8, LoRaPacketStatus) =
+            radio.receive(&mut buf, Timeout::DISABLED).unwrap();
+        assert_eq!(&buf[..usize::from(len)], b"hello");
+        assert_eq!(status.rssi_pkt().to_integer(), -50);
+        assert_eq!(status.snr_pkt().to_integer(), 8);
+
+        let mut buf: [u8; 4] = [0; 4];
+        assert_eq!(
+            radio.receive(&mut buf, Timeout::DISABLED),
+            Err(Error::BufTooSmall(5))
+        );
+        let (sg, _) = radio.free();
+        assert_eq!(sg.transport().mode(), SimMode::StandbyHse);
+        assert_eq!(
+            states,
+            [
+                RfState::Off,
+                RfState::Rx,
+                RfState::Off,
+                RfState::Rx,
+                RfState::Off
+            ]
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "subghz-sim")]
+    fn receive_errors() {
+        let mut buf: [u8; 255] = [0; 255];
+
+        let sg: SimSubGhz = new_sg(|sim| {
+            sim.timeout();
+        });
+        let mut radio = LoRaRadio::new(sg, |_| {}, LoRaCfg::new()).unwrap();
+        assert_eq!(
+            radio.receive(&mut buf, Timeout::from_millis_sat(10)),
+            Err(Error::Timeout)
+        );
+
+        let sg: SimSubGhz = new_sg(|sim| {
+            if sim.mode() == SimMode::Rx {
+                sim.raise_irq(Irq::Err);
+            }
+        });
+        let mut radio = LoRaRadio::new(sg, |_| {}, LoRaCfg::new()).unwrap();
+        assert_eq!(radio.receive(&mut buf, Timeout::DISABLED), Err(Error::Crc));
+
+        let sg: SimSubGhz = new_sg(|sim| {
+            if sim.mode() == SimMode::Rx {
+                sim.raise_irq(Irq::HeaderErr);
+            }
+        });
+        let mut radio = LoRaRadio::new(sg, |_| {}, LoRaCfg::new()).unwrap();
+        assert_eq!(
+            radio.receive(&mut buf, Timeout::DISABLED),
+            Err(Error::Header)
+        );
+        let (sg, _) = radio.free();
+        // the radio keeps searching after a header error unless stopped
+        assert_eq!(sg.transport().mode(), SimMode::StandbyHse);
+    }
+
+    #[test]
+    #[cfg(feature = "subghz-sim")]
+    fn cad() {
+        let mut states: Vec<RfState> = Vec::new();
+        let sg: SimSubGhz = new_sg(|sim| {
+            sim.cad_done(true);
+        });
+        let mut radio = LoRaRadio::new(sg, |s| states.push(s), LoRaCfg::new()).unwrap();
+        assert_eq!(radio.cad(), Ok(true));
+        let (sg, _) = radio.free();
+        assert_eq!(sg.transport().irq_status(), 0);
+        assert_eq!(states, [RfState::Off, RfState::Rx, RfState::Off]);
+
+        let sg: SimSubGhz = new_sg(|sim| {
+            sim.cad_done(false);
+        });
+        let mut radio = LoRaRadio::new(sg, |_| {}, LoRaCfg::new()).unwrap();
+        assert_eq!(radio.cad(), Ok(false));
+        let (sg, _) = radio.free();
+        assert_eq!(sg.transport().irq_status(), 0);
+    }
+
+    #[test]
+    #[cfg(feature = "subghz-sim")]
+    fn sleep_wake() {
+        for startup in [Startup::Warm, Startup::Cold] {
+            let sleep_cfg: SleepCfg = SleepCfg::new().set_startup(startup);
+            let sg: SimSubGhz = new_sg(|sim| {
+                sim.tx_done();
+            });
+            let mut radio = LoRaRadio::new(sg, |_| {}, VARIABLE).unwrap();
+            let before: SimRadio = radio.sg.transport().clone();
+
+            radio.sleep(sleep_cfg, &mut NoDelay).unwrap();
+            assert!(radio.is_asleep());
+            assert_eq!(radio.sg.transport().mode(), SimMode::Sleep(startup));
+            // does nothing if the radio is already in sleep mode
+            radio.sleep(sleep_cfg, &mut NoDelay).unwrap();
+
+            radio.wakeup().unwrap();
+            assert!(!radio.is_asleep());
+            let sim: &SimRadio = radio.sg.transport();
+            let mode: SimMode = match startup {
+                Startup::Warm => SimMode::StandbyRc,
+                // reconfigured
+                Startup::Cold => SimMode::StandbyHse,
+            };
+            assert_eq!(sim.mode(), mode);
+            assert_eq!(sim.packet_type(), before.packet_type());
+            assert_eq!(sim.rf_freq(), before.rf_freq());
+            assert_eq!(sim.mod_params(), before.mod_params());
+            assert_eq!(sim.packet_params(), before.packet_params());
+            assert_eq!(sim.irq_mask(), before.irq_mask());
+            for addr in 0..REG_LEN as u16 {
+                assert_eq!(sim.register(addr), before.register(addr), "{addr:#06X}");
+            }
+
+            // operations wake the radio
+            radio.sleep(sleep_cfg, &mut NoDelay).unwrap();
+            assert_eq!(radio.transmit(b"hello"), Ok(()));
+            assert!(!radio.is_asleep());
+
+            // free wakes the radio
+            radio.sleep(sleep_cfg, &mut NoDelay).unwrap();
+            let (sg, _) = radio.free();
+            assert_eq!(sg.transport().mode(), SimMode::StandbyRc);
+        }
+    }
+}
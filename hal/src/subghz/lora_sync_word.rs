@@ -13,7 +13,38 @@ pub enum LoRaSyncWord {
 }
 
 impl LoRaSyncWord {
-    pub(crate) const fn bytes(self) -> [u8; 2] {
+    /// Create a new `LoRaSyncWord` from the register values.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::subghz::LoRaSyncWord;
+    ///
+    /// assert_eq!(LoRaSyncWord::from_bytes([0x14, 0x24]), LoRaSyncWord::Private);
+    /// assert_eq!(LoRaSyncWord::from_bytes([0x34, 0x44]), LoRaSyncWord::Public);
+    /// assert_eq!(
+    ///     LoRaSyncWord::from_bytes([0x12, 0x34]),
+    ///     LoRaSyncWord::Custom([0x12, 0x34])
+    /// );
+    /// ```
+    pub const fn from_bytes(bytes: [u8; 2]) -> LoRaSyncWord {
+        match bytes {
+            [0x14, 0x24] => LoRaSyncWord::Private,
+            [0x34, 0x44] => LoRaSyncWord::Public,
+            _ => LoRaSyncWord::Custom(bytes),
+        }
+    }
+
+    /// Get the register values.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::subghz::LoRaSyncWord;
+    ///
+    /// assert_eq!(LoRaSyncWord::Public.bytes(), [0x34, 0x44]);
+    /// ```
+    pub const fn bytes(self) -> [u8; 2] {
         match self {
             LoRaSyncWord::Private => [0x14, 0x24],
             LoRaSyncWord::Public => [0x34, 0x44],
@@ -9,6 +9,7 @@
 //!
 //! [link]: https://community.st.com/s/question/0D53W00000hR8kpSAC/stm32wl55-erratum-clairification
 
+mod async_irq;
 mod bit_sync;
 mod cad_params;
 mod calibrate;
@@ -28,26 +29,41 @@ mod pmode;
 mod pwr_ctrl;
 mod reg_mode;
 mod rf_frequency;
+mod rf_state;
+mod rtc_ctrl;
 mod rx_timeout_stop;
 mod sleep_cfg;
+mod sleeping;
 mod smps;
+#[cfg(feature = "subghz-snapshot")]
+mod snapshot;
 mod standby_clk;
 mod stats;
 mod status;
 mod tcxo_mode;
+mod time_on_air;
 mod timeout;
+mod transport;
 mod tx_params;
 mod value_error;
 
+pub mod fsk;
+pub mod lora;
+pub mod region;
+#[cfg(feature = "subghz-sim")]
+pub mod sim;
+pub mod tx_gate;
+
 use crate::{
     dma::DmaCh,
     pac,
     spi::{BaudRate, SgMiso, SgMosi, Spi3},
 };
 
+pub use async_irq::{on_irq, RadioEvent};
 pub use bit_sync::BitSync;
 pub use cad_params::{CadParams, ExitMode, NbCadSymbol};
-pub use calibrate::{Calibrate, CalibrateImage};
+pub use calibrate::{Calibrate, CalibrateImage, CalibrationError};
 pub use fallback_mode::FallbackMode;
 pub use hse_trim::HseTrim;
 pub use irq::{CfgIrq, Irq, IrqLine};
@@ -69,52 +85,40 @@ pub use pmode::PMode;
 pub use pwr_ctrl::{CurrentLim, PwrCtrl};
 pub use reg_mode::RegMode;
 pub use rf_frequency::RfFreq;
+pub use rf_state::RfState;
+pub use rtc_ctrl::RtcCtrl;
 pub use rx_timeout_stop::RxTimeoutStop;
 pub use sleep_cfg::{SleepCfg, Startup};
+pub use sleeping::{SleepingSubGhz, Woken};
 pub use smps::SmpsDrv;
+#[cfg(feature = "subghz-snapshot")]
+pub use snapshot::Snapshot;
 pub use standby_clk::StandbyClk;
 pub use stats::{FskStats, LoRaStats, Stats};
 pub use status::{CmdStatus, Status, StatusMode};
 pub use tcxo_mode::{TcxoMode, TcxoTrim};
+pub use time_on_air::{bpsk_time_on_air, fsk_time_on_air, lora_time_on_air, Modulation};
 pub use timeout::Timeout;
+pub use transport::Transport;
 pub use tx_params::{RampTime, TxParams};
 pub use value_error::ValueError;
 
 use crate::Ratio;
-
-use embedded_hal::blocking::spi::{Transfer, Write};
+use core::marker::PhantomData;
 
 /// Passthrough for SPI errors (for now)
 pub type Error = crate::spi::Error;
 
-struct Nss {
-    _priv: (),
-}
-
-impl Nss {
-    #[inline(always)]
-    pub fn new() -> Nss {
-        Self::clear();
-        Nss { _priv: () }
-    }
-
-    /// Clear NSS, enabling SPI transactions
-    #[inline(always)]
-    fn clear() {
-        unsafe { (*pac::PWR::PTR).subghzspicr.write(|w| w.nss().clear_bit()) }
-    }
-
-    /// Set NSS, disabling SPI transactions
-    #[inline(always)]
-    fn set() {
-        unsafe { (*pac::PWR::PTR).subghzspicr.write(|w| w.nss().set_bit()) }
-    }
+/// Clear NSS, enabling SPI transactions
+#[inline(always)]
+fn nss_clear() {
+    unsafe { (*pac::PWR::PTR).subghzspicr.write(|w| w.nss().clear_bit()) }
 }
 
-impl Drop for Nss {
-    fn drop(&mut self) {
-        Self::set()
-    }
+/// Set NSS, disabling SPI transactions
+#[inline(always)]
+fn nss_set() {
+    unsafe { (*pac::PWR::PTR).subghzspicr.write(|w| w.nss().set_bit()) }
 }
 
 fn baud_rate(rcc: &pac::RCC) -> BaudRate {
@@ -131,6 +135,8 @@ fn baud_rate(rcc: &pac::RCC) -> BaudRate {
 
 /// Wakeup the radio from sleep mode.
 ///
+/// [`SubGhz::sleep`] is a safe alternative.
+///
 /// # Safety
 ///
 /// 1. This must not be called when the SubGHz radio is in use.
@@ -141,7 +147,7 @@ fn baud_rate(rcc: &pac::RCC) -> BaudRate {
 /// See [`SubGhz::set_sleep`]
 #[inline]
 pub unsafe fn wakeup() {
-    Nss::clear();
+    nss_clear();
     // RM0453 rev 2 page 171 section 5.7.2 "Sleep mode"
     // on a firmware request via the sub-GHz radio SPI NSS signal
     // (keeping sub-GHz radio SPI NSS low for at least 20 μs)
@@ -149,7 +155,7 @@ pub unsafe fn wakeup() {
     // I have found this to be a more reliable mechanism for ensuring NSS is
     // pulled low for long enough to wake the radio.
     while rfbusys() {}
-    Nss::set();
+    nss_set();
 }
 
 /// Unmask the SubGHz IRQ in the NVIC.
@@ -354,8 +360,11 @@ fn pulse_reset(rcc: &mut pac::RCC) {
 /// # Ok::<(), stm32wlxx_hal::subghz::Error>(())
 /// ```
 #[derive(Debug)]
-pub struct SubGhz<MISO, MOSI> {
-    spi: Spi3<MISO, MOSI>,
+pub struct SubGhz<MISO, MOSI, T = Spi3<MISO, MOSI>> {
+    bus: T,
+    #[cfg(feature = "subghz-snapshot")]
+    state: Snapshot,
+    _dma: PhantomData<(MISO, MOSI)>,
 }
 
 impl<MISO, MOSI> SubGhz<MISO, MOSI> {
@@ -379,23 +388,6 @@ impl<MISO, MOSI> SubGhz<MISO, MOSI> {
         Spi3::<SgMiso, SgMosi>::enable_clock(rcc)
     }
 
-    fn poll_not_busy(&self) {
-        // TODO: this is a terrible timeout
-        let mut count: u32 = 1_000_000;
-        while rfbusys() {
-            count -= 1;
-            if count == 0 {
-                let dp = unsafe { pac::Peripherals::steal() };
-                panic!(
-                    "rfbusys timeout pwr.sr2=0x{:X} pwr.subghzspicr=0x{:X} pwr.cr1=0x{:X}",
-                    dp.PWR.sr2.read().bits(),
-                    dp.PWR.subghzspicr.read().bits(),
-                    dp.PWR.cr1.read().bits(),
-                );
-            }
-        }
-    }
-
     /// Free the SPI3 peripheral and DMA channels from the SubGhz driver.
     ///
     /// # Example
@@ -408,34 +400,35 @@ impl<MISO, MOSI> SubGhz<MISO, MOSI> {
     /// let (spi, _, _) = sg.free();
     /// ```
     pub fn free(self) -> (pac::SPI3, MISO, MOSI) {
-        self.spi.free()
+        self.bus.free()
     }
 }
 
-impl<MISO, MOSI> SubGhz<MISO, MOSI>
-where
-    Spi3<MISO, MOSI>: embedded_hal::blocking::spi::Transfer<u8, Error = Error>
-        + embedded_hal::blocking::spi::Write<u8, Error = Error>,
-{
-    fn read(&mut self, opcode: OpCode, data: &mut [u8]) -> Result<(), Error> {
-        self.poll_not_busy();
-        {
-            let _nss: Nss = Nss::new();
-            self.spi.write(&[opcode as u8])?;
-            self.spi.transfer(data)?;
-        }
-        self.poll_not_busy();
+impl<MISO, MOSI, T: Transport> SubGhz<MISO, MOSI, T> {
+    /// Run a transaction with NSS low, waiting for the radio to be ready
+    /// before and after.
+    fn transaction<F>(&mut self, f: F) -> Result<(), Error>
+    where
+        F: FnOnce(&mut T) -> Result<(), Error>,
+    {
+        self.bus.wait_not_busy();
+        self.bus.select();
+        let result: Result<(), Error> = f(&mut self.bus);
+        self.bus.deselect();
+        result?;
+        self.bus.wait_not_busy();
         Ok(())
     }
 
+    fn read(&mut self, opcode: OpCode, data: &mut [u8]) -> Result<(), Error> {
+        self.transaction(|bus| {
+            bus.write_bytes(&[opcode as u8])?;
+            bus.transfer_bytes(data)
+        })
+    }
+
     fn write(&mut self, data: &[u8]) -> Result<(), Error> {
-        self.poll_not_busy();
-        {
-            let _nss: Nss = Nss::new();
-            self.spi.write(data)?;
-        }
-        self.poll_not_busy();
-        Ok(())
+        self.transaction(|bus| bus.write_bytes(data))
     }
 
     /// Read one byte from the sub-Ghz radio.
@@ -496,7 +489,12 @@ impl SubGhz<SgMiso, SgMosi> {
     pub unsafe fn new_no_reset(spi: pac::SPI3, rcc: &mut pac::RCC) -> SubGhz<SgMiso, SgMosi> {
         let spi: Spi3<SgMiso, SgMosi> = Spi3::new(spi, baud_rate(rcc), rcc);
         unsafe { wakeup() };
-        Self { spi }
+        Self {
+            bus: spi,
+            #[cfg(feature = "subghz-snapshot")]
+            state: Snapshot::new(),
+            _dma: PhantomData,
+        }
     }
 
     /// Steal the SubGHz peripheral from whatever is currently using it.
@@ -522,7 +520,12 @@ impl SubGhz<SgMiso, SgMosi> {
     ///
     /// [`new`]: SubGhz::new
     pub unsafe fn steal() -> SubGhz<SgMiso, SgMosi> {
-        SubGhz { spi: Spi3::steal() }
+        SubGhz {
+            bus: Spi3::steal(),
+            #[cfg(feature = "subghz-snapshot")]
+            state: Snapshot::new(),
+            _dma: PhantomData,
+        }
     }
 }
 
@@ -588,7 +591,12 @@ impl<MISO: DmaCh, MOSI: DmaCh> SubGhz<MISO, MOSI> {
         let spi: Spi3<MISO, MOSI> =
             Spi3::new_with_dma(spi, miso_dma, mosi_dma, baud_rate(rcc), rcc);
         unsafe { wakeup() };
-        SubGhz { spi }
+        SubGhz {
+            bus: spi,
+            #[cfg(feature = "subghz-snapshot")]
+            state: Snapshot::new(),
+            _dma: PhantomData,
+        }
     }
 
     /// Steal the SubGHz peripheral from whatever is currently using it.
@@ -622,29 +630,83 @@ impl<MISO: DmaCh, MOSI: DmaCh> SubGhz<MISO, MOSI> {
     /// [`new_with_dma`]: SubGhz::new_with_dma
     pub unsafe fn steal_with_dma(miso_dma: MISO, mosi_dma: MOSI) -> Self {
         SubGhz {
-            spi: Spi3::steal_with_dma(miso_dma, mosi_dma),
+            bus: Spi3::steal_with_dma(miso_dma, mosi_dma),
+            #[cfg(feature = "subghz-snapshot")]
+            state: Snapshot::new(),
+            _dma: PhantomData,
+        }
+    }
+}
+
+impl<T: Transport> SubGhz<SgMiso, SgMosi, T> {
+    /// Create a new sub-GHz radio driver from a custom transport.
+    ///
+    /// This runs the driver on something other than the SPI3 peripheral,
+    /// such as the simulated radio in the `sim` module (requires the
+    /// `subghz-sim` feature) for testing radio code on the host.
+    ///
+    /// The radio is not reset or woken up.
+    pub fn new_with_transport(transport: T) -> Self {
+        SubGhz {
+            bus: transport,
+            #[cfg(feature = "subghz-snapshot")]
+            state: Snapshot::new(),
+            _dma: PhantomData,
         }
     }
+
+    /// Free the transport from the SubGhz driver.
+    pub fn free_transport(self) -> T {
+        self.bus
+    }
+}
+
+impl<MISO, MOSI, T> SubGhz<MISO, MOSI, T> {
+    /// Get a reference to the transport.
+    pub fn transport(&self) -> &T {
+        &self.bus
+    }
+
+    /// Get a mutable reference to the transport.
+    ///
+    /// Commands sent directly to the transport bypass the driver.
+    pub fn transport_mut(&mut self) -> &mut T {
+        &mut self.bus
+    }
+
+    /// Get the radio configuration last applied by the driver.
+    ///
+    /// Requires the `subghz-snapshot` feature, which stores the
+    /// configuration in the driver at a cost of about 80 bytes of RAM.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # let mut sg = unsafe { stm32wlxx_hal::subghz::SubGhz::steal() };
+    /// use stm32wlxx_hal::subghz::{PacketType, RfFreq};
+    ///
+    /// sg.set_packet_type(PacketType::LoRa)?;
+    /// sg.set_rf_frequency(&RfFreq::F915)?;
+    ///
+    /// assert_eq!(sg.snapshot().packet_type(), Some(PacketType::LoRa));
+    /// assert_eq!(sg.snapshot().rf_freq(), Some(RfFreq::F915));
+    /// # Ok::<(), stm32wlxx_hal::subghz::Error>(())
+    /// ```
+    #[cfg(feature = "subghz-snapshot")]
+    pub fn snapshot(&self) -> Snapshot {
+        self.state
+    }
 }
 
 // 5.8.2
 /// Synchronous buffer access commands
-impl<MISO, MOSI> SubGhz<MISO, MOSI>
-where
-    Spi3<MISO, MOSI>: embedded_hal::blocking::spi::Transfer<u8, Error = Error>
-        + embedded_hal::blocking::spi::Write<u8, Error = Error>,
-{
+impl<MISO, MOSI, T: Transport> SubGhz<MISO, MOSI, T> {
     /// Write the radio buffer at the given offset.
     pub fn write_buffer(&mut self, offset: u8, data: &[u8]) -> Result<(), Error> {
-        self.poll_not_busy();
-        {
-            let _nss: Nss = Nss::new();
-            self.spi.write(&[OpCode::WriteBuffer as u8, offset])?;
-            self.spi.write(data)?;
-        }
-        self.poll_not_busy();
-
-        Ok(())
+        self.transaction(|bus| {
+            bus.write_bytes(&[OpCode::WriteBuffer as u8, offset])?;
+            bus.write_bytes(data)
+        })
     }
 
     /// Read the radio buffer at the given offset.
@@ -654,14 +716,11 @@ where
     pub fn read_buffer(&mut self, offset: u8, buf: &mut [u8]) -> Result<Status, Error> {
         let mut status_buf: [u8; 1] = [0];
 
-        self.poll_not_busy();
-        {
-            let _nss: Nss = Nss::new();
-            self.spi.write(&[OpCode::ReadBuffer as u8, offset])?;
-            self.spi.transfer(&mut status_buf)?;
-            self.spi.transfer(buf)?;
-        }
-        self.poll_not_busy();
+        self.transaction(|bus| {
+            bus.write_bytes(&[OpCode::ReadBuffer as u8, offset])?;
+            bus.transfer_bytes(&mut status_buf)?;
+            bus.transfer_bytes(buf)
+        })?;
 
         Ok(status_buf[0].into())
     }
@@ -682,42 +741,84 @@ macro_rules! wr_reg {
 
 // 5.8.2
 /// Register access
-impl<MISO, MOSI> SubGhz<MISO, MOSI>
-where
-    Spi3<MISO, MOSI>: embedded_hal::blocking::spi::Transfer<u8, Error = Error>
-        + embedded_hal::blocking::spi::Write<u8, Error = Error>,
-{
+impl<MISO, MOSI, T: Transport> SubGhz<MISO, MOSI, T> {
     // register write with variable length data
     fn write_register(&mut self, register: Register, data: &[u8]) -> Result<(), Error> {
-        let addr: [u8; 2] = register.address().to_be_bytes();
-
-        self.poll_not_busy();
-        {
-            let _nss: Nss = Nss::new();
-            self.spi
-                .write(&[OpCode::WriteRegister as u8, addr[0], addr[1]])?;
-            self.spi.write(data)?;
-        }
-        self.poll_not_busy();
-
-        Ok(())
+        unsafe { self.write_register_raw(register.address(), data) }
     }
 
     // register read with fixed (one) length data
     fn read_register(&mut self, register: Register) -> Result<u8, Error> {
-        let addr: [u8; 2] = register.address().to_be_bytes();
         let mut result = [0_u8];
+        unsafe { self.read_register_raw(register.address(), &mut result) }?;
+        Ok(result[0])
+    }
 
-        self.poll_not_busy();
-        {
-            let _nss: Nss = Nss::new();
-            self.spi
-                .write(&[OpCode::ReadRegister as u8, addr[0], addr[1], 0x00])?;
-            self.spi.transfer(&mut result)?;
-        }
-        self.poll_not_busy();
+    // register read with fixed length data
+    fn read_registers<const N: usize>(&mut self, register: Register) -> Result<[u8; N], Error> {
+        let mut result = [0_u8; N];
+        unsafe { self.read_register_raw(register.address(), &mut result) }?;
+        Ok(result)
+    }
 
-        Ok(result[0])
+    /// Write consecutive registers starting at a raw address.
+    ///
+    /// This is an escape hatch for registers without a typed method, such as
+    /// undocumented registers used in ST application notes.
+    ///
+    /// # Safety
+    ///
+    /// 1. The address and values must be valid for the radio, writing
+    ///    reserved registers or values can put the radio in an undefined
+    ///    state.
+    /// 2. Registers written with this method are not tracked by the driver,
+    ///    for example registers that are also written by typed methods.
+    ///
+    /// # Example
+    ///
+    /// Set the RX gain control register (`0x08AC`) to boost mode.
+    ///
+    /// ```no_run
+    /// # let mut sg = unsafe { stm32wlxx_hal::subghz::SubGhz::steal() };
+    /// unsafe { sg.write_register_raw(0x08AC, &[0x96])? };
+    /// # Ok::<(), stm32wlxx_hal::subghz::Error>(())
+    /// ```
+    pub unsafe fn write_register_raw(&mut self, addr: u16, data: &[u8]) -> Result<(), Error> {
+        let addr: [u8; 2] = addr.to_be_bytes();
+
+        self.transaction(|bus| {
+            bus.write_bytes(&[OpCode::WriteRegister as u8, addr[0], addr[1]])?;
+            bus.write_bytes(data)
+        })
+    }
+
+    /// Read consecutive registers starting at a raw address.
+    ///
+    /// This is an escape hatch for registers without a typed method, such as
+    /// undocumented registers used in ST application notes.
+    ///
+    /// # Safety
+    ///
+    /// 1. The address must be valid for the radio, reading reserved
+    ///    registers can have side effects.
+    ///
+    /// # Example
+    ///
+    /// Read the LoRa synchronization word registers (`0x0740` and `0x0741`).
+    ///
+    /// ```no_run
+    /// # let mut sg = unsafe { stm32wlxx_hal::subghz::SubGhz::steal() };
+    /// let mut buf: [u8; 2] = [0; 2];
+    /// unsafe { sg.read_register_raw(0x0740, &mut buf)? };
+    /// # Ok::<(), stm32wlxx_hal::subghz::Error>(())
+    /// ```
+    pub unsafe fn read_register_raw(&mut self, addr: u16, buf: &mut [u8]) -> Result<(), Error> {
+        let addr: [u8; 2] = addr.to_be_bytes();
+
+        self.transaction(|bus| {
+            bus.write_bytes(&[OpCode::ReadRegister as u8, addr[0], addr[1], 0x00])?;
+            bus.transfer_bytes(buf)
+        })
     }
 
     /// Reads the PktCtrl register (GPKTCTL1A)
@@ -736,6 +837,30 @@ where
         self.write(wr_reg![GBSYNC, bs.as_bits()])
     }
 
+    /// Reads the LoRa bit synchronization register (GBSYNC).
+    pub fn bit_sync(&mut self) -> Result<BitSync, Error> {
+        Ok(BitSync::from_raw(self.read_register(Register::GBSYNC)?))
+    }
+
+    /// Set the generic packet payload length register.
+    ///
+    /// This is the payload length of the packet being transmitted or
+    /// received, it is also set with
+    /// [`set_packet_params`](Self::set_packet_params).
+    /// The register is used to change the length of long packets while the
+    /// packet is transmitted or received.
+    pub fn set_generic_pkt_len(&mut self, len: u8) -> Result<(), Error> {
+        self.write(wr_reg![GRTXPLDLEN, len])
+    }
+
+    /// Reads the generic packet payload length register (GRTXPLDLEN).
+    ///
+    /// For packets with a variable length header this is the length received
+    /// in the header.
+    pub fn generic_pkt_len(&mut self) -> Result<u8, Error> {
+        self.read_register(Register::GRTXPLDLEN)
+    }
+
     /// Set the generic packet control register.
     pub fn set_pkt_ctrl(&mut self, pkt_ctrl: PktCtrl) -> Result<(), Error> {
         self.write(wr_reg![GPKTCTL1A, pkt_ctrl.as_bits()])
@@ -764,33 +889,89 @@ where
         self.write(wr_reg![GCRCINIRH, bytes[0], bytes[1]])
     }
 
+    /// Reads the generic packet CRC initial value registers (GCRCINIR).
+    ///
+    /// This is the value set with
+    /// [`set_crc_polynomial`](Self::set_crc_polynomial).
+    pub fn crc_init(&mut self) -> Result<u16, Error> {
+        Ok(u16::from_be_bytes(
+            self.read_registers(Register::GCRCINIRH)?,
+        ))
+    }
+
     /// Set the generic packet CRC polynomial.
     pub fn set_initial_crc_polynomial(&mut self, polynomial: u16) -> Result<(), Error> {
         let bytes: [u8; 2] = polynomial.to_be_bytes();
         self.write(wr_reg![GCRCPOLRH, bytes[0], bytes[1]])
     }
 
+    /// Reads the generic packet CRC polynomial registers (GCRCPOLR).
+    ///
+    /// This is the value set with
+    /// [`set_initial_crc_polynomial`](Self::set_initial_crc_polynomial).
+    pub fn crc_polynomial(&mut self) -> Result<u16, Error> {
+        Ok(u16::from_be_bytes(
+            self.read_registers(Register::GCRCPOLRH)?,
+        ))
+    }
+
     /// Set the synchronization word registers.
     pub fn set_sync_word(&mut self, sync_word: &[u8; 8]) -> Result<(), Error> {
         self.write_register(Register::GSYNC7, sync_word)
     }
 
+    /// Reads the synchronization word registers (GSYNC7 to GSYNC0).
+    pub fn sync_word(&mut self) -> Result<[u8; 8], Error> {
+        self.read_registers(Register::GSYNC7)
+    }
+
     /// Set the LoRa synchronization word registers.
     pub fn set_lora_sync_word(&mut self, sync_word: LoRaSyncWord) -> Result<(), Error> {
         let bytes: [u8; 2] = sync_word.bytes();
         self.write(wr_reg![LSYNCH, bytes[0], bytes[1]])
     }
 
+    /// Reads the LoRa synchronization word registers (LSYNCH and LSYNCL).
+    pub fn lora_sync_word(&mut self) -> Result<LoRaSyncWord, Error> {
+        Ok(LoRaSyncWord::from_bytes(
+            self.read_registers(Register::LSYNCH)?,
+        ))
+    }
+
     /// Set the RX gain control.
     pub fn set_rx_gain(&mut self, pmode: PMode) -> Result<(), Error> {
         self.write(wr_reg![RXGAINC, pmode as u8])
     }
 
+    /// Reads the RX gain control register (RXGAINC).
+    ///
+    /// Returns the raw value if it does not match a [`PMode`].
+    pub fn rx_gain(&mut self) -> Result<Result<PMode, u8>, Error> {
+        Ok(PMode::from_raw(self.read_register(Register::RXGAINC)?))
+    }
+
     /// Set the power amplifier over current protection.
     pub fn set_pa_ocp(&mut self, ocp: Ocp) -> Result<(), Error> {
         self.write(wr_reg![PAOCP, ocp as u8])
     }
 
+    /// Reads the power amplifier over current protection register (PAOCP).
+    ///
+    /// Returns the raw value if it does not match an [`Ocp`].
+    pub fn pa_ocp(&mut self) -> Result<Result<Ocp, u8>, Error> {
+        Ok(Ocp::from_raw(self.read_register(Register::PAOCP)?))
+    }
+
+    /// Set the radio real-time-clock control register.
+    pub fn set_rtc_ctrl(&mut self, rtc_ctrl: RtcCtrl) -> Result<(), Error> {
+        self.write(wr_reg![RTCCTLR, rtc_ctrl.as_bits()])
+    }
+
+    /// Reads the radio real-time-clock control register (RTCCTLR).
+    pub fn rtc_ctrl(&mut self) -> Result<RtcCtrl, Error> {
+        Ok(RtcCtrl::from_raw(self.read_register(Register::RTCCTLR)?))
+    }
+
     /// Restart the radio RTC.
     ///
     /// This is used to workaround an erratum for [`set_rx_duty_cycle`].
@@ -815,16 +996,35 @@ where
         ])
     }
 
+    /// Reads the radio real-time-clock period registers (RTCPRDR2 to
+    /// RTCPRDR0).
+    pub fn rtc_period(&mut self) -> Result<Timeout, Error> {
+        let [b2, b1, b0]: [u8; 3] = self.read_registers(Register::RTCPRDR2)?;
+        Ok(Timeout::from_raw(u32::from_be_bytes([0, b2, b1, b0])))
+    }
+
     /// Set the HSE32 crystal OSC_IN load capacitor trimming.
     pub fn set_hse_in_trim(&mut self, trim: HseTrim) -> Result<(), Error> {
         self.write(wr_reg![HSEINTRIM, trim.into()])
     }
 
+    /// Reads the HSE32 crystal OSC_IN load capacitor trimming register
+    /// (HSEINTRIM).
+    pub fn hse_in_trim(&mut self) -> Result<HseTrim, Error> {
+        Ok(HseTrim::from_raw(self.read_register(Register::HSEINTRIM)?))
+    }
+
     /// Set the HSE32 crystal OSC_OUT load capacitor trimming.
     pub fn set_hse_out_trim(&mut self, trim: HseTrim) -> Result<(), Error> {
         self.write(wr_reg![HSEOUTTRIM, trim.into()])
     }
 
+    /// Reads the HSE32 crystal OSC_OUT load capacitor trimming register
+    /// (HSEOUTTRIM).
+    pub fn hse_out_trim(&mut self) -> Result<HseTrim, Error> {
+        Ok(HseTrim::from_raw(self.read_register(Register::HSEOUTTRIM)?))
+    }
+
     /// Set the SMPS clock detection enabled.
     ///
     /// SMPS clock detection must be enabled fore enabling the SMPS.
@@ -832,16 +1032,33 @@ where
         self.write(wr_reg![SMPSC0, (en as u8) << 6])
     }
 
+    /// Returns `true` if SMPS clock detection is enabled (SMPSC0).
+    pub fn smps_clock_det_en(&mut self) -> Result<bool, Error> {
+        Ok(self.read_register(Register::SMPSC0)? & (1 << 6) != 0)
+    }
+
     /// Set the power current limiting.
     pub fn set_pwr_ctrl(&mut self, pwr_ctrl: PwrCtrl) -> Result<(), Error> {
         self.write(wr_reg![PC, pwr_ctrl.as_bits()])
     }
 
+    /// Reads the power control register (PC).
+    pub fn pwr_ctrl(&mut self) -> Result<PwrCtrl, Error> {
+        Ok(PwrCtrl::from_raw(self.read_register(Register::PC)?))
+    }
+
     /// Set the maximum SMPS drive capability.
     pub fn set_smps_drv(&mut self, drv: SmpsDrv) -> Result<(), Error> {
         self.write(wr_reg![SMPSC2, (drv as u8) << 1])
     }
 
+    /// Reads the maximum SMPS drive capability (SMPSC2).
+    pub fn smps_drv(&mut self) -> Result<SmpsDrv, Error> {
+        Ok(SmpsDrv::from_raw(
+            self.read_register(Register::SMPSC2)? >> 1,
+        ))
+    }
+
     /// Set the node address.
     ///
     /// Used with [`GenericPacketParams::set_addr_comp`] to filter packets based
@@ -850,6 +1067,11 @@ where
         self.write(wr_reg![NODE, addr])
     }
 
+    /// Reads the node address register (NODE).
+    pub fn node_addr(&mut self) -> Result<u8, Error> {
+        self.read_register(Register::NODE)
+    }
+
     /// Set the broadcast address.
     ///
     /// Used with [`GenericPacketParams::set_addr_comp`] to filter packets based
@@ -858,6 +1080,11 @@ where
         self.write(wr_reg![BROADCAST, addr])
     }
 
+    /// Reads the broadcast address register (BROADCAST).
+    pub fn broadcast_addr(&mut self) -> Result<u8, Error> {
+        self.read_register(Register::BROADCAST)
+    }
+
     /// Set both the broadcast address and node address.
     ///
     /// This is a combination of [`set_node_addr`] and [`set_broadcast_addr`]
@@ -872,13 +1099,11 @@ where
 
 // 5.8.3
 /// Operating mode commands
-impl<MISO, MOSI> SubGhz<MISO, MOSI>
-where
-    Spi3<MISO, MOSI>: embedded_hal::blocking::spi::Transfer<u8, Error = Error>
-        + embedded_hal::blocking::spi::Write<u8, Error = Error>,
-{
+impl<MISO, MOSI, T: Transport> SubGhz<MISO, MOSI, T> {
     /// Put the radio into sleep mode.
     ///
+    /// [`sleep`](Self::sleep) is a safe alternative.
+    ///
     /// This command is only accepted in standby mode.
     /// The cfg argument allows some optional functions to be maintained
     /// in sleep mode.
@@ -915,12 +1140,13 @@ where
     pub unsafe fn set_sleep(&mut self, cfg: SleepCfg) -> Result<(), Error> {
         // poll for busy before, but not after
         // radio idles with busy high while in sleep mode
-        self.poll_not_busy();
-        {
-            let _nss: Nss = Nss::new();
-            self.spi.write(&[OpCode::SetSleep as u8, u8::from(cfg)])?;
-        }
-        Ok(())
+        self.bus.wait_not_busy();
+        self.bus.select();
+        let result: Result<(), Error> = self
+            .bus
+            .write_bytes(&[OpCode::SetSleep as u8, u8::from(cfg)]);
+        self.bus.deselect();
+        result
     }
 
     /// Put the radio into standby mode.
@@ -1091,14 +1317,15 @@ where
 
 // 5.8.4
 /// Radio configuration commands
-impl<MISO, MOSI> SubGhz<MISO, MOSI>
-where
-    Spi3<MISO, MOSI>: embedded_hal::blocking::spi::Transfer<u8, Error = Error>
-        + embedded_hal::blocking::spi::Write<u8, Error = Error>,
-{
+impl<MISO, MOSI, T: Transport> SubGhz<MISO, MOSI, T> {
     /// Set the packet type (modulation scheme).
     pub fn set_packet_type(&mut self, packet_type: PacketType) -> Result<(), Error> {
-        self.write(&[OpCode::SetPacketType as u8, packet_type as u8])
+        self.write(&[OpCode::SetPacketType as u8, packet_type as u8])?;
+        #[cfg(feature = "subghz-snapshot")]
+        {
+            self.state.packet_type = Some(packet_type);
+        }
+        Ok(())
     }
 
     /// Get the packet type.
@@ -1109,19 +1336,34 @@ where
 
     /// Set the radio carrier frequency.
     pub fn set_rf_frequency(&mut self, freq: &RfFreq) -> Result<(), Error> {
-        self.write(freq.as_slice())
+        self.write(freq.as_slice())?;
+        #[cfg(feature = "subghz-snapshot")]
+        {
+            self.state.rf_freq = Some(*freq);
+        }
+        Ok(())
     }
 
     /// Set the transmit output power and the PA ramp-up time.
     pub fn set_tx_params(&mut self, params: &TxParams) -> Result<(), Error> {
-        self.write(params.as_slice())
+        self.write(params.as_slice())?;
+        #[cfg(feature = "subghz-snapshot")]
+        {
+            self.state.tx_params = Some(*params);
+        }
+        Ok(())
     }
 
     /// Power amplifier configuration.
     ///
     /// Used to customize the maximum output power and efficiency.
     pub fn set_pa_config(&mut self, pa_config: &PaConfig) -> Result<(), Error> {
-        self.write(pa_config.as_slice())
+        self.write(pa_config.as_slice())?;
+        #[cfg(feature = "subghz-snapshot")]
+        {
+            self.state.pa_config = Some(*pa_config);
+        }
+        Ok(())
     }
 
     /// Operating mode to enter after a successful packet transmission or
@@ -1147,32 +1389,62 @@ where
 
     /// Set the (G)FSK modulation parameters.
     pub fn set_fsk_mod_params(&mut self, params: &FskModParams) -> Result<(), Error> {
-        self.write(params.as_slice())
+        self.write(params.as_slice())?;
+        #[cfg(feature = "subghz-snapshot")]
+        {
+            self.state.fsk_mod_params = Some(*params);
+        }
+        Ok(())
     }
 
     /// Set the LoRa modulation parameters.
     pub fn set_lora_mod_params(&mut self, params: &LoRaModParams) -> Result<(), Error> {
-        self.write(params.as_slice())
+        self.write(params.as_slice())?;
+        #[cfg(feature = "subghz-snapshot")]
+        {
+            self.state.lora_mod_params = Some(*params);
+        }
+        Ok(())
     }
 
     /// Set the BPSK modulation parameters.
     pub fn set_bpsk_mod_params(&mut self, params: &BpskModParams) -> Result<(), Error> {
-        self.write(params.as_slice())
+        self.write(params.as_slice())?;
+        #[cfg(feature = "subghz-snapshot")]
+        {
+            self.state.bpsk_mod_params = Some(*params);
+        }
+        Ok(())
     }
 
     /// Set the generic (FSK) packet parameters.
     pub fn set_packet_params(&mut self, params: &GenericPacketParams) -> Result<(), Error> {
-        self.write(params.as_slice())
+        self.write(params.as_slice())?;
+        #[cfg(feature = "subghz-snapshot")]
+        {
+            self.state.generic_packet_params = Some(*params);
+        }
+        Ok(())
     }
 
     /// Set the BPSK packet parameters.
     pub fn set_bpsk_packet_params(&mut self, params: &BpskPacketParams) -> Result<(), Error> {
-        self.write(params.as_slice())
+        self.write(params.as_slice())?;
+        #[cfg(feature = "subghz-snapshot")]
+        {
+            self.state.bpsk_packet_params = Some(*params);
+        }
+        Ok(())
     }
 
     /// Set the LoRa packet parameters.
     pub fn set_lora_packet_params(&mut self, params: &LoRaPacketParams) -> Result<(), Error> {
-        self.write(params.as_slice())
+        self.write(params.as_slice())?;
+        #[cfg(feature = "subghz-snapshot")]
+        {
+            self.state.lora_packet_params = Some(*params);
+        }
+        Ok(())
     }
 
     /// Set the number of LoRa symbols to be received before starting the
@@ -1186,11 +1458,7 @@ where
 
 // 5.8.5
 /// Communication status and information commands
-impl<MISO, MOSI> SubGhz<MISO, MOSI>
-where
-    Spi3<MISO, MOSI>: embedded_hal::blocking::spi::Transfer<u8, Error = Error>
-        + embedded_hal::blocking::spi::Write<u8, Error = Error>,
-{
+impl<MISO, MOSI, T: Transport> SubGhz<MISO, MOSI, T> {
     /// Get the radio status.
     ///
     /// The hardware (or documentation) appears to have many bugs where this
@@ -1255,14 +1523,15 @@ where
 
 // 5.8.6
 /// IRQ commands
-impl<MISO, MOSI> SubGhz<MISO, MOSI>
-where
-    Spi3<MISO, MOSI>: embedded_hal::blocking::spi::Transfer<u8, Error = Error>
-        + embedded_hal::blocking::spi::Write<u8, Error = Error>,
-{
+impl<MISO, MOSI, T: Transport> SubGhz<MISO, MOSI, T> {
     /// Set the interrupt configuration.
     pub fn set_irq_cfg(&mut self, cfg: &CfgIrq) -> Result<(), Error> {
-        self.write(cfg.as_slice())
+        self.write(cfg.as_slice())?;
+        #[cfg(feature = "subghz-snapshot")]
+        {
+            self.state.irq_cfg = Some(*cfg);
+        }
+        Ok(())
     }
 
     /// Get the IRQ status.
@@ -1280,11 +1549,7 @@ where
 
 // 5.8.7
 /// Miscellaneous commands
-impl<MISO, MOSI> SubGhz<MISO, MOSI>
-where
-    Spi3<MISO, MOSI>: embedded_hal::blocking::spi::Transfer<u8, Error = Error>
-        + embedded_hal::blocking::spi::Write<u8, Error = Error>,
-{
+impl<MISO, MOSI, T: Transport> SubGhz<MISO, MOSI, T> {
     /// Calibrate one or several blocks at any time when in standby mode.
     pub fn calibrate(&mut self, cal: u8) -> Result<(), Error> {
         // bit 7 is reserved and must be kept at reset value.
@@ -1298,9 +1563,67 @@ where
         self.write(&[OpCode::CalibrateImage as u8, cal.0, cal.1])
     }
 
+    /// Calibrate all blocks, and the image for an RF frequency.
+    ///
+    /// This puts the radio into standby mode with the RC 13 MHz oscillator,
+    /// runs a full [`calibrate`](SubGhz::calibrate), runs
+    /// [`calibrate_image`](SubGhz::calibrate_image) with
+    /// [`CalibrateImage::for_freq`], then checks
+    /// [`op_error`](SubGhz::op_error) for calibration failures.
+    ///
+    /// The TCXO must be configured with
+    /// [`set_tcxo_mode`](SubGhz::set_tcxo_mode) before calling this method,
+    /// otherwise the RF-PLL and RF-ADC calibrations fail.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # let mut sg = unsafe { stm32wlxx_hal::subghz::SubGhz::steal() };
+    /// use stm32wlxx_hal::subghz::{
+    ///     CalibrationError, RfFreq, StandbyClk, TcxoMode, TcxoTrim, Timeout,
+    /// };
+    ///
+    /// const TCXO_MODE: TcxoMode = TcxoMode::new()
+    ///     .set_tcxo_trim(TcxoTrim::Volts1pt7)
+    ///     .set_timeout(Timeout::from_millis_sat(10));
+    ///
+    /// sg.set_standby(StandbyClk::Rc)?;
+    /// sg.set_tcxo_mode(&TCXO_MODE)?;
+    /// match sg.calibrate_for(&RfFreq::F915) {
+    ///     Ok(()) => (),
+    ///     Err(CalibrationError::Xosc) => panic!("TCXO failed to start"),
+    ///     Err(e) => return Err(e),
+    /// }
+    /// # Ok::<(), CalibrationError>(())
+    /// ```
+    pub fn calibrate_for(&mut self, rf_freq: &RfFreq) -> Result<(), CalibrationError> {
+        const CAL_ALL: u8 = Calibrate::Image.mask()
+            | Calibrate::AdcBulkP.mask()
+            | Calibrate::AdcBulkN.mask()
+            | Calibrate::AdcPulse.mask()
+            | Calibrate::Pll.mask()
+            | Calibrate::Rc13M.mask()
+            | Calibrate::Rc64K.mask();
+
+        self.set_standby(StandbyClk::Rc)?;
+        self.clear_error()?;
+        self.calibrate(CAL_ALL)?;
+        self.calibrate_image(CalibrateImage::for_freq(rf_freq))?;
+        let (_, op_error) = self.op_error()?;
+        match CalibrationError::from_op_error(op_error) {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+
     /// Set the radio power supply.
     pub fn set_regulator_mode(&mut self, reg_mode: RegMode) -> Result<(), Error> {
-        self.write(&[OpCode::SetRegulatorMode as u8, reg_mode as u8])
+        self.write(&[OpCode::SetRegulatorMode as u8, reg_mode as u8])?;
+        #[cfg(feature = "subghz-snapshot")]
+        {
+            self.state.reg_mode = Some(reg_mode);
+        }
+        Ok(())
     }
 
     /// Get the radio operational errors.
@@ -1317,14 +1640,110 @@ where
 
 // 5.8.8
 /// Set TCXO mode command
-impl<MISO, MOSI> SubGhz<MISO, MOSI>
-where
-    Spi3<MISO, MOSI>: embedded_hal::blocking::spi::Transfer<u8, Error = Error>
-        + embedded_hal::blocking::spi::Write<u8, Error = Error>,
-{
+impl<MISO, MOSI, T: Transport> SubGhz<MISO, MOSI, T> {
     /// Set the TCXO trim and HSE32 ready timeout.
     pub fn set_tcxo_mode(&mut self, tcxo_mode: &TcxoMode) -> Result<(), Error> {
-        self.write(tcxo_mode.as_slice())
+        self.write(tcxo_mode.as_slice())?;
+        #[cfg(feature = "subghz-snapshot")]
+        {
+            self.state.tcxo_mode = Some(*tcxo_mode);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "subghz-snapshot")]
+/// Configuration snapshot
+impl<MISO, MOSI, T: Transport> SubGhz<MISO, MOSI, T> {
+    /// Apply a radio configuration from [`snapshot`](Self::snapshot).
+    ///
+    /// This is used to reinitialise the radio after waking from sleep with a
+    /// [cold start](Startup::Cold), which does not retain the configuration.
+    ///
+    /// The settings are applied in this order, settings that are `None` are
+    /// skipped:
+    ///
+    /// 1. TCXO mode
+    /// 2. Regulator mode
+    /// 3. Packet type
+    /// 4. RF frequency
+    /// 5. Modulation parameters for the packet type
+    /// 6. Packet parameters for the packet type
+    /// 7. Power amplifier configuration
+    /// 8. TX parameters
+    /// 9. IRQ configuration
+    ///
+    /// Modulation and packet parameters are skipped if the packet type is
+    /// `None`.
+    /// Registers and calibration are not part of the snapshot.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # let mut sg = unsafe { stm32wlxx_hal::subghz::SubGhz::steal() };
+    /// use stm32wlxx_hal::subghz::{wakeup, PacketType, SleepCfg, Snapshot, StandbyClk};
+    ///
+    /// sg.set_packet_type(PacketType::LoRa)?;
+    /// // ... other configuration
+    ///
+    /// let snapshot: Snapshot = sg.snapshot();
+    /// sg.set_standby(StandbyClk::Rc)?;
+    /// unsafe { sg.set_sleep(SleepCfg::default())? };
+    /// // ... wait at least 500 µs
+    /// unsafe { wakeup() };
+    /// sg.restore(&snapshot)?;
+    /// # Ok::<(), stm32wlxx_hal::subghz::Error>(())
+    /// ```
+    pub fn restore(&mut self, snapshot: &Snapshot) -> Result<(), Error> {
+        if let Some(tcxo_mode) = snapshot.tcxo_mode() {
+            self.set_tcxo_mode(&tcxo_mode)?;
+        }
+        if let Some(reg_mode) = snapshot.reg_mode() {
+            self.set_regulator_mode(reg_mode)?;
+        }
+        if let Some(packet_type) = snapshot.packet_type() {
+            self.set_packet_type(packet_type)?;
+        }
+        if let Some(rf_freq) = snapshot.rf_freq() {
+            self.set_rf_frequency(&rf_freq)?;
+        }
+        match snapshot.packet_type() {
+            Some(PacketType::LoRa) => {
+                if let Some(mod_params) = snapshot.lora_mod_params() {
+                    self.set_lora_mod_params(&mod_params)?;
+                }
+                if let Some(packet_params) = snapshot.lora_packet_params() {
+                    self.set_lora_packet_params(&packet_params)?;
+                }
+            }
+            Some(PacketType::Fsk) | Some(PacketType::Msk) => {
+                if let Some(mod_params) = snapshot.fsk_mod_params() {
+                    self.set_fsk_mod_params(&mod_params)?;
+                }
+                if let Some(packet_params) = snapshot.generic_packet_params() {
+                    self.set_packet_params(&packet_params)?;
+                }
+            }
+            Some(PacketType::Bpsk) => {
+                if let Some(mod_params) = snapshot.bpsk_mod_params() {
+                    self.set_bpsk_mod_params(&mod_params)?;
+                }
+                if let Some(packet_params) = snapshot.bpsk_packet_params() {
+                    self.set_bpsk_packet_params(&packet_params)?;
+                }
+            }
+            None => (),
+        }
+        if let Some(pa_config) = snapshot.pa_config() {
+            self.set_pa_config(&pa_config)?;
+        }
+        if let Some(tx_params) = snapshot.tx_params() {
+            self.set_tx_params(&tx_params)?;
+        }
+        if let Some(irq_cfg) = snapshot.irq_cfg() {
+            self.set_irq_cfg(&irq_cfg)?;
+        }
+        Ok(())
     }
 }
 
@@ -1392,6 +1811,8 @@ pub(crate) enum Register {
     GPKTCTL1A = 0x06B8,
     /// Generic whitening.
     GWHITEINIRL = 0x06B9,
+    /// Generic payload length.
+    GRTXPLDLEN = 0x06BB,
     /// Generic CRC initial.
     GCRCINIRH = 0x06BC,
     /// Generic CRC polynomial.
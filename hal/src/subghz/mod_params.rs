@@ -1,3 +1,7 @@
+use super::{CadParams, NbCadSymbol};
+use crate::Ratio;
+use core::time::Duration;
+
 /// Bandwidth options for [`FskModParams`].
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
@@ -387,6 +391,20 @@ impl FskModParams {
         .set_fdev(FskFdev::from_hertz(25_000))
     }
 
+    /// Create from the command parameters, without the opcode.
+    ///
+    /// Parameters beyond the length of the command are ignored.
+    #[cfg(feature = "subghz-sim")]
+    pub(crate) const fn from_params(params: &[u8]) -> FskModParams {
+        let mut ret: FskModParams = FskModParams::new();
+        let mut idx: usize = 1;
+        while idx < ret.buf.len() && idx <= params.len() {
+            ret.buf[idx] = params[idx - 1];
+            idx += 1;
+        }
+        ret
+    }
+
     /// Get the bitrate.
     ///
     /// # Example
@@ -782,6 +800,20 @@ impl LoRaModParams {
         }
     }
 
+    /// Create from the command parameters, without the opcode.
+    ///
+    /// Parameters beyond the length of the command are ignored.
+    #[cfg(feature = "subghz-sim")]
+    pub(crate) const fn from_params(params: &[u8]) -> LoRaModParams {
+        let mut ret: LoRaModParams = LoRaModParams::new();
+        let mut idx: usize = 1;
+        while idx < ret.buf.len() && idx <= params.len() {
+            ret.buf[idx] = params[idx - 1];
+            idx += 1;
+        }
+        ret
+    }
+
     /// Set the spreading factor.
     ///
     /// # Example
@@ -798,6 +830,330 @@ impl LoRaModParams {
         self
     }
 
+    /// Get the spreading factor.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::subghz::{LoRaModParams, SpreadingFactor};
+    ///
+    /// const MOD_PARAMS: LoRaModParams = LoRaModParams::new().set_sf(SpreadingFactor::Sf9);
+    /// assert_eq!(MOD_PARAMS.sf(), SpreadingFactor::Sf9);
+    /// ```
+    pub const fn sf(&self) -> SpreadingFactor {
+        match self.buf[1] {
+            0x05 => SpreadingFactor::Sf5,
+            0x06 => SpreadingFactor::Sf6,
+            0x07 => SpreadingFactor::Sf7,
+            0x08 => SpreadingFactor::Sf8,
+            0x09 => SpreadingFactor::Sf9,
+            0x0A => SpreadingFactor::Sf10,
+            0x0B => SpreadingFactor::Sf11,
+            // only valid values can be set
+            _ => SpreadingFactor::Sf12,
+        }
+    }
+
+    /// Get the bandwidth.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::subghz::{LoRaBandwidth, LoRaModParams};
+    ///
+    /// const MOD_PARAMS: LoRaModParams = LoRaModParams::new().set_bw(LoRaBandwidth::Bw250);
+    /// assert_eq!(MOD_PARAMS.bw(), LoRaBandwidth::Bw250);
+    /// ```
+    pub const fn bw(&self) -> LoRaBandwidth {
+        match self.buf[2] {
+            0x00 => LoRaBandwidth::Bw7,
+            0x08 => LoRaBandwidth::Bw10,
+            0x01 => LoRaBandwidth::Bw15,
+            0x09 => LoRaBandwidth::Bw20,
+            0x02 => LoRaBandwidth::Bw31,
+            0x0A => LoRaBandwidth::Bw41,
+            0x03 => LoRaBandwidth::Bw62,
+            0x04 => LoRaBandwidth::Bw125,
+            0x05 => LoRaBandwidth::Bw250,
+            // only valid values can be set
+            _ => LoRaBandwidth::Bw500,
+        }
+    }
+
+    /// Get the forward error correction coding rate.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::subghz::{CodingRate, LoRaModParams};
+    ///
+    /// const MOD_PARAMS: LoRaModParams = LoRaModParams::new().set_cr(CodingRate::Cr47);
+    /// assert_eq!(MOD_PARAMS.cr(), CodingRate::Cr47);
+    /// ```
+    pub const fn cr(&self) -> CodingRate {
+        match self.buf[3] {
+            0x00 => CodingRate::Cr44,
+            0x01 => CodingRate::Cr45,
+            0x02 => CodingRate::Cr46,
+            0x03 => CodingRate::Cr47,
+            // only valid values can be set
+            _ => CodingRate::Cr48,
+        }
+    }
+
+    /// Returns `true` if low data rate optimization is enabled.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::subghz::LoRaModParams;
+    ///
+    /// const MOD_PARAMS: LoRaModParams = LoRaModParams::new().set_ldro_en(true);
+    /// assert!(MOD_PARAMS.ldro_en());
+    /// ```
+    pub const fn ldro_en(&self) -> bool {
+        self.buf[4] != 0
+    }
+
+    /// Enable low data rate optimization if it is required by the spreading
+    /// factor and bandwidth.
+    ///
+    /// This must be called after [`set_sf`](Self::set_sf) and
+    /// [`set_bw`](Self::set_bw).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::subghz::{LoRaBandwidth, LoRaModParams, SpreadingFactor};
+    ///
+    /// const MOD_PARAMS: LoRaModParams = LoRaModParams::new()
+    ///     .set_sf(SpreadingFactor::Sf11)
+    ///     .set_bw(LoRaBandwidth::Bw125)
+    ///     .set_ldro_auto();
+    /// assert!(MOD_PARAMS.ldro_en());
+    ///
+    /// const MOD_PARAMS_SF10: LoRaModParams = MOD_PARAMS.set_sf(SpreadingFactor::Sf10).set_ldro_auto();
+    /// assert!(!MOD_PARAMS_SF10.ldro_en());
+    /// ```
+    #[must_use = "set_ldro_auto returns a modified LoRaModParams"]
+    pub const fn set_ldro_auto(self) -> Self {
+        let required: bool = self.ldro_required();
+        self.set_ldro_en(required)
+    }
+
+    /// Returns `true` if low data rate optimization is required.
+    ///
+    /// Low data rate optimization is mandated when the symbol time is 16.38 ms
+    /// or longer.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::subghz::{LoRaBandwidth, LoRaModParams, SpreadingFactor};
+    ///
+    /// const MOD_PARAMS: LoRaModParams = LoRaModParams::new()
+    ///     .set_sf(SpreadingFactor::Sf11)
+    ///     .set_bw(LoRaBandwidth::Bw125);
+    /// assert!(MOD_PARAMS.ldro_required());
+    /// assert!(!MOD_PARAMS.set_bw(LoRaBandwidth::Bw250).ldro_required());
+    /// ```
+    pub const fn ldro_required(&self) -> bool {
+        const LDRO_SYMBOL_TIME_NANOS: u128 = 16_380_000;
+        self.symbol_time().as_nanos() >= LDRO_SYMBOL_TIME_NANOS
+    }
+
+    /// Get the duration of a symbol.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use core::time::Duration;
+    /// use stm32wlxx_hal::subghz::{LoRaBandwidth, LoRaModParams, SpreadingFactor};
+    ///
+    /// const MOD_PARAMS: LoRaModParams = LoRaModParams::new()
+    ///     .set_sf(SpreadingFactor::Sf7)
+    ///     .set_bw(LoRaBandwidth::Bw125);
+    /// assert_eq!(MOD_PARAMS.symbol_time(), Duration::from_micros(1_024));
+    /// ```
+    pub const fn symbol_time(&self) -> Duration {
+        // bandwidths are exactly 500 kHz divided by this value
+        let div: u64 = match self.bw() {
+            LoRaBandwidth::Bw7 => 64,
+            LoRaBandwidth::Bw10 => 48,
+            LoRaBandwidth::Bw15 => 32,
+            LoRaBandwidth::Bw20 => 24,
+            LoRaBandwidth::Bw31 => 16,
+            LoRaBandwidth::Bw41 => 12,
+            LoRaBandwidth::Bw62 => 8,
+            LoRaBandwidth::Bw125 => 4,
+            LoRaBandwidth::Bw250 => 2,
+            LoRaBandwidth::Bw500 => 1,
+        };
+        // 2^SF / (500 kHz / div)
+        Duration::from_nanos((2_000 << (self.sf() as u64)) * div)
+    }
+
+    /// Recommended argument for
+    /// [`set_lora_symb_timeout`](crate::subghz::SubGhz::set_lora_symb_timeout)
+    /// to search for a preamble for the given duration.
+    ///
+    /// This is the number of symbols in `window`, rounded up, with a minimum
+    /// of 6 symbols to lock on to the preamble, and saturating at
+    /// `u8::MAX`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use core::time::Duration;
+    /// use stm32wlxx_hal::subghz::{LoRaBandwidth, LoRaModParams, SpreadingFactor};
+    ///
+    /// const MOD_PARAMS: LoRaModParams = LoRaModParams::new()
+    ///     .set_sf(SpreadingFactor::Sf7)
+    ///     .set_bw(LoRaBandwidth::Bw125);
+    /// assert_eq!(MOD_PARAMS.symb_timeout(Duration::from_millis(20)), 20);
+    /// assert_eq!(MOD_PARAMS.symb_timeout(Duration::from_millis(1)), 6);
+    /// assert_eq!(MOD_PARAMS.symb_timeout(Duration::from_secs(1)), 255);
+    /// ```
+    pub const fn symb_timeout(&self, window: Duration) -> u8 {
+        const MIN_SYMBOLS: u128 = 6;
+        let symbol: u128 = self.symbol_time().as_nanos();
+        let symbols: u128 = (window.as_nanos() + symbol - 1) / symbol;
+        if symbols < MIN_SYMBOLS {
+            MIN_SYMBOLS as u8
+        } else if symbols > u8::MAX as u128 {
+            u8::MAX
+        } else {
+            symbols as u8
+        }
+    }
+
+    /// Recommended channel activity detection parameters.
+    ///
+    /// The detection thresholds are taken from the table in the [`CadParams`]
+    /// documentation.
+    /// The number of symbols follows the Semtech AN1200.48 application note,
+    /// 2 symbols for SF8 and below, and 4 symbols above SF8.
+    ///
+    /// The exit mode is [`ExitMode::Standby`](super::ExitMode::Standby).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::subghz::{CadParams, LoRaModParams, NbCadSymbol, SpreadingFactor};
+    ///
+    /// const MOD_PARAMS: LoRaModParams = LoRaModParams::new().set_sf(SpreadingFactor::Sf7);
+    /// assert_eq!(
+    ///     MOD_PARAMS.cad_params(),
+    ///     CadParams::new()
+    ///         .set_num_symbol(NbCadSymbol::S2)
+    ///         .set_det_peak(0x20)
+    ///         .set_det_min(0x10)
+    /// );
+    /// ```
+    pub const fn cad_params(&self) -> CadParams {
+        let (num_symbol, det_peak): (NbCadSymbol, u8) = match self.sf() {
+            SpreadingFactor::Sf5 => (NbCadSymbol::S2, 0x18),
+            SpreadingFactor::Sf6 => (NbCadSymbol::S2, 0x19),
+            SpreadingFactor::Sf7 => (NbCadSymbol::S2, 0x20),
+            SpreadingFactor::Sf8 => (NbCadSymbol::S2, 0x21),
+            SpreadingFactor::Sf9 => (NbCadSymbol::S4, 0x22),
+            SpreadingFactor::Sf10 => (NbCadSymbol::S4, 0x23),
+            SpreadingFactor::Sf11 => (NbCadSymbol::S4, 0x24),
+            SpreadingFactor::Sf12 => (NbCadSymbol::S4, 0x25),
+        };
+        CadParams::new()
+            .set_num_symbol(num_symbol)
+            .set_det_peak(det_peak)
+            .set_det_min(0x10)
+    }
+
+    /// Demodulator SNR limit in dB.
+    ///
+    /// This is the lowest SNR at which the spreading factor can be
+    /// demodulated.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::subghz::{LoRaModParams, SpreadingFactor};
+    ///
+    /// const MOD_PARAMS: LoRaModParams = LoRaModParams::new().set_sf(SpreadingFactor::Sf7);
+    /// assert_eq!(*MOD_PARAMS.snr_limit().numer(), -75);
+    /// assert_eq!(*MOD_PARAMS.snr_limit().denom(), 10);
+    /// ```
+    pub const fn snr_limit(&self) -> Ratio<i16> {
+        let tenths: i16 = match self.sf() {
+            SpreadingFactor::Sf5 => -25,
+            SpreadingFactor::Sf6 => -50,
+            SpreadingFactor::Sf7 => -75,
+            SpreadingFactor::Sf8 => -100,
+            SpreadingFactor::Sf9 => -125,
+            SpreadingFactor::Sf10 => -150,
+            SpreadingFactor::Sf11 => -175,
+            SpreadingFactor::Sf12 => -200,
+        };
+        Ratio::new_raw(tenths, 10)
+    }
+
+    /// Estimated receiver sensitivity in dBm.
+    ///
+    /// This is the thermal noise floor of the bandwidth, plus a 6 dB
+    /// receiver noise figure, plus the [SNR limit](Self::snr_limit).
+    /// The real sensitivity depends on the board, this is useful for
+    /// comparing modulation parameters and for link budget estimates.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::subghz::{LoRaBandwidth, LoRaModParams, SpreadingFactor};
+    ///
+    /// const MOD_PARAMS: LoRaModParams = LoRaModParams::new()
+    ///     .set_sf(SpreadingFactor::Sf12)
+    ///     .set_bw(LoRaBandwidth::Bw125);
+    /// assert_eq!(MOD_PARAMS.sensitivity().to_integer(), -137);
+    /// ```
+    pub const fn sensitivity(&self) -> Ratio<i16> {
+        // 10 * log10(bandwidth) in tenths of a dB
+        let bw: i16 = match self.bw() {
+            LoRaBandwidth::Bw7 => 389,
+            LoRaBandwidth::Bw10 => 402,
+            LoRaBandwidth::Bw15 => 419,
+            LoRaBandwidth::Bw20 => 432,
+            LoRaBandwidth::Bw31 => 449,
+            LoRaBandwidth::Bw41 => 462,
+            LoRaBandwidth::Bw62 => 480,
+            LoRaBandwidth::Bw125 => 510,
+            LoRaBandwidth::Bw250 => 540,
+            LoRaBandwidth::Bw500 => 570,
+        };
+        const THERMAL_NOISE: i16 = -1740;
+        const NOISE_FIGURE: i16 = 60;
+        Ratio::new_raw(
+            THERMAL_NOISE + bw + NOISE_FIGURE + *self.snr_limit().numer(),
+            10,
+        )
+    }
+
+    /// Estimated link budget in dB for the given transmit power in dBm.
+    ///
+    /// This is the transmit power minus the estimated
+    /// [sensitivity](Self::sensitivity), and does not include antenna gains.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::subghz::{LoRaBandwidth, LoRaModParams, SpreadingFactor};
+    ///
+    /// const MOD_PARAMS: LoRaModParams = LoRaModParams::new()
+    ///     .set_sf(SpreadingFactor::Sf7)
+    ///     .set_bw(LoRaBandwidth::Bw125);
+    /// assert_eq!(*MOD_PARAMS.link_budget(14).numer(), 1385);
+    /// assert_eq!(*MOD_PARAMS.link_budget(14).denom(), 10);
+    /// ```
+    pub const fn link_budget(&self, tx_dbm: i8) -> Ratio<i16> {
+        Ratio::new_raw((tx_dbm as i16) * 10 - *self.sensitivity().numer(), 10)
+    }
+
     /// Set the bandwidth.
     ///
     /// # Example
@@ -923,6 +1279,20 @@ impl BpskModParams {
         }
     }
 
+    /// Create from the command parameters, without the opcode.
+    ///
+    /// Parameters beyond the length of the command are ignored.
+    #[cfg(feature = "subghz-sim")]
+    pub(crate) const fn from_params(params: &[u8]) -> BpskModParams {
+        let mut ret: BpskModParams = BpskModParams::new();
+        let mut idx: usize = 1;
+        while idx < ret.buf.len() && idx <= params.len() {
+            ret.buf[idx] = params[idx - 1];
+            idx += 1;
+        }
+        ret
+    }
+
     /// Set the bitrate.
     ///
     /// # Example
@@ -947,6 +1317,22 @@ impl BpskModParams {
         self
     }
 
+    /// Get the bitrate.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::subghz::{BpskModParams, FskBitrate};
+    ///
+    /// const BITRATE: FskBitrate = FskBitrate::from_bps(600);
+    /// const MOD_PARAMS: BpskModParams = BpskModParams::new().set_bitrate(BITRATE);
+    /// assert_eq!(MOD_PARAMS.bitrate(), BITRATE);
+    /// ```
+    pub const fn bitrate(&self) -> FskBitrate {
+        let raw: u32 = u32::from_be_bytes([0, self.buf[1], self.buf[2], self.buf[3]]);
+        FskBitrate::from_raw(raw)
+    }
+
     /// Extracts a slice containing the packet.
     ///
     /// # Example
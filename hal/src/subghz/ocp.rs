@@ -12,3 +12,25 @@ pub enum Ocp {
     /// Maximum 140mA for HP PA mode.
     Max140m = 0x38,
 }
+
+impl Ocp {
+    /// Create a new `Ocp` from the over current protection register value.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::subghz::Ocp;
+    ///
+    /// assert_eq!(Ocp::from_raw(0x18), Ok(Ocp::Max60m));
+    /// assert_eq!(Ocp::from_raw(0x38), Ok(Ocp::Max140m));
+    /// // Other current limits do not have a variant
+    /// assert_eq!(Ocp::from_raw(0x20), Err(0x20));
+    /// ```
+    pub const fn from_raw(bits: u8) -> Result<Ocp, u8> {
+        match bits {
+            0x18 => Ok(Ocp::Max60m),
+            0x38 => Ok(Ocp::Max140m),
+            _ => Err(bits),
+        }
+    }
+}
@@ -1,3 +1,5 @@
+use super::{TxParams, ValueError};
+
 /// Power amplifier configuration parameters.
 ///
 /// Argument of [`set_pa_config`].
@@ -66,6 +68,80 @@ impl PaConfig {
         .set_hp_max(0x2)
         .set_pa(PaSel::Hp);
 
+    /// Optimal power amplifier configuration and transmit parameters for an
+    /// output power in dBm.
+    ///
+    /// This selects the most efficient optimal setting from the reference
+    /// manual that reaches the requested power, and reduces the power
+    /// register from that setting by the difference.
+    ///
+    /// | PA   | Output power (dBm) | Setting                          |
+    /// |------|--------------------|----------------------------------|
+    /// | `Lp` | -17 to +10         | [`LP_10`](Self::LP_10)           |
+    /// | `Lp` | +11 to +14         | [`LP_14`](Self::LP_14)           |
+    /// | `Lp` | +15                | [`LP_15`](Self::LP_15)           |
+    /// | `Hp` | -17 to +14         | [`HP_14`](Self::HP_14)           |
+    /// | `Hp` | +15 to +17         | [`HP_17`](Self::HP_17)           |
+    /// | `Hp` | +18 to +20         | [`HP_20`](Self::HP_20)           |
+    /// | `Hp` | +21 to +22         | [`HP_22`](Self::HP_22)           |
+    ///
+    /// The ramp time of the returned [`TxParams`](super::TxParams) is the
+    /// default, use [`set_ramp_time`](super::TxParams::set_ramp_time) to
+    /// change it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the power is below -17 dBm, or above +15 dBm for
+    /// the low-power PA, or above +22 dBm for the high-power PA.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::subghz::{PaConfig, PaSel, TxParams, ValueError};
+    ///
+    /// assert_eq!(
+    ///     PaConfig::for_dbm(PaSel::Hp, 22),
+    ///     Ok((PaConfig::HP_22, TxParams::HP))
+    /// );
+    /// assert_eq!(
+    ///     PaConfig::for_dbm(PaSel::Lp, 12),
+    ///     Ok((PaConfig::LP_14, TxParams::new().set_power(0x0C)))
+    /// );
+    /// assert_eq!(
+    ///     PaConfig::for_dbm(PaSel::Lp, 17),
+    ///     Err(ValueError::too_high(17, 15))
+    /// );
+    /// ```
+    pub const fn for_dbm(pa: PaSel, dbm: i8) -> Result<(PaConfig, TxParams), ValueError<i8>> {
+        const MIN: i8 = -17;
+        const LP_MAX: i8 = 15;
+        const HP_MAX: i8 = 22;
+
+        if dbm < MIN {
+            return Err(ValueError::too_low(dbm, MIN));
+        }
+
+        // (setting, power register at the maximum of the setting, maximum)
+        let (pa_config, max_power, max): (PaConfig, i8, i8) = match pa {
+            PaSel::Lp => match dbm {
+                i8::MIN..=10 => (PaConfig::LP_10, 0x0D, 10),
+                11..=14 => (PaConfig::LP_14, 0x0E, 14),
+                LP_MAX => (PaConfig::LP_15, 0x0E, 15),
+                _ => return Err(ValueError::too_high(dbm, LP_MAX)),
+            },
+            PaSel::Hp => match dbm {
+                i8::MIN..=14 => (PaConfig::HP_14, 0x16, 14),
+                15..=17 => (PaConfig::HP_17, 0x16, 17),
+                18..=20 => (PaConfig::HP_20, 0x16, 20),
+                21..=HP_MAX => (PaConfig::HP_22, 0x16, 22),
+                _ => return Err(ValueError::too_high(dbm, HP_MAX)),
+            },
+        };
+
+        let power: i8 = max_power - (max - dbm);
+        Ok((pa_config, TxParams::new().set_power(power as u8)))
+    }
+
     /// Create a new `PaConfig` struct.
     ///
     /// This is the same as `default`, but in a `const` function.
@@ -146,6 +222,24 @@ impl PaConfig {
         self
     }
 
+    /// Get the power amplifier selection.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::subghz::{PaConfig, PaSel};
+    ///
+    /// assert_eq!(PaConfig::HP_22.pa(), PaSel::Hp);
+    /// assert_eq!(PaConfig::LP_10.pa(), PaSel::Lp);
+    /// ```
+    pub const fn pa(&self) -> PaSel {
+        if self.buf[3] == PaSel::Hp as u8 {
+            PaSel::Hp
+        } else {
+            PaSel::Lp
+        }
+    }
+
     /// Extracts a slice containing the packet.
     ///
     /// # Example
@@ -202,11 +296,105 @@ impl Ord for PaSel {
 
 #[cfg(test)]
 mod test {
-    use super::PaSel;
+    use super::{PaConfig, PaSel};
+    use crate::subghz::{TxParams, ValueError};
 
     #[test]
     fn pa_sel_ord() {
         assert!(PaSel::Lp < PaSel::Hp);
         assert!(PaSel::Hp > PaSel::Lp);
     }
+
+    #[test]
+    fn for_dbm_optimal_settings() {
+        assert_eq!(
+            PaConfig::for_dbm(PaSel::Lp, 15),
+            Ok((PaConfig::LP_15, TxParams::LP_15))
+        );
+        assert_eq!(
+            PaConfig::for_dbm(PaSel::Lp, 14),
+            Ok((PaConfig::LP_14, TxParams::LP_14))
+        );
+        assert_eq!(
+            PaConfig::for_dbm(PaSel::Lp, 10),
+            Ok((PaConfig::LP_10, TxParams::LP_10))
+        );
+        assert_eq!(
+            PaConfig::for_dbm(PaSel::Hp, 22),
+            Ok((PaConfig::HP_22, TxParams::HP))
+        );
+        assert_eq!(
+            PaConfig::for_dbm(PaSel::Hp, 20),
+            Ok((PaConfig::HP_20, TxParams::HP))
+        );
+        assert_eq!(
+            PaConfig::for_dbm(PaSel::Hp, 17),
+            Ok((PaConfig::HP_17, TxParams::HP))
+        );
+        assert_eq!(
+            PaConfig::for_dbm(PaSel::Hp, 14),
+            Ok((PaConfig::HP_14, TxParams::HP))
+        );
+    }
+
+    #[test]
+    fn for_dbm_lp_table() {
+        for dbm in -17..=15 {
+            let (pa_config, tx_params) = PaConfig::for_dbm(PaSel::Lp, dbm).unwrap();
+            let (expected, power): (PaConfig, i8) = match dbm {
+                -17..=10 => (PaConfig::LP_10, dbm + 3),
+                11..=14 => (PaConfig::LP_14, dbm),
+                _ => (PaConfig::LP_15, dbm - 1),
+            };
+            assert_eq!(pa_config, expected, "{} dBm", dbm);
+            assert_eq!(pa_config.pa(), PaSel::Lp);
+            assert_eq!(tx_params, TxParams::new().set_power(power as u8));
+            // the LP power register range is -17 to +14
+            assert!((-17..=14).contains(&power), "{} dBm", dbm);
+        }
+    }
+
+    #[test]
+    fn for_dbm_hp_table() {
+        for dbm in -17..=22 {
+            let (pa_config, tx_params) = PaConfig::for_dbm(PaSel::Hp, dbm).unwrap();
+            let (expected, power): (PaConfig, i8) = match dbm {
+                -17..=14 => (PaConfig::HP_14, dbm + 8),
+                15..=17 => (PaConfig::HP_17, dbm + 5),
+                18..=20 => (PaConfig::HP_20, dbm + 2),
+                _ => (PaConfig::HP_22, dbm),
+            };
+            assert_eq!(pa_config, expected, "{} dBm", dbm);
+            assert_eq!(pa_config.pa(), PaSel::Hp);
+            assert_eq!(tx_params, TxParams::new().set_power(power as u8));
+            // the HP power register range is -9 to +22
+            assert!((-9..=22).contains(&power), "{} dBm", dbm);
+        }
+    }
+
+    #[test]
+    fn for_dbm_unreachable() {
+        for pa in [PaSel::Lp, PaSel::Hp] {
+            assert_eq!(
+                PaConfig::for_dbm(pa, -18),
+                Err(ValueError::too_low(-18, -17))
+            );
+            assert_eq!(
+                PaConfig::for_dbm(pa, i8::MIN),
+                Err(ValueError::too_low(i8::MIN, -17))
+            );
+        }
+        assert_eq!(
+            PaConfig::for_dbm(PaSel::Lp, 16),
+            Err(ValueError::too_high(16, 15))
+        );
+        assert_eq!(
+            PaConfig::for_dbm(PaSel::Hp, 23),
+            Err(ValueError::too_high(23, 22))
+        );
+        assert_eq!(
+            PaConfig::for_dbm(PaSel::Hp, i8::MAX),
+            Err(ValueError::too_high(i8::MAX, 22))
+        );
+    }
 }
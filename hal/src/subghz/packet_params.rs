@@ -112,6 +112,20 @@ impl GenericPacketParams {
         NEW
     }
 
+    /// Create from the command parameters, without the opcode.
+    ///
+    /// Parameters beyond the length of the command are ignored.
+    #[cfg(feature = "subghz-sim")]
+    pub(crate) const fn from_params(params: &[u8]) -> GenericPacketParams {
+        let mut ret: GenericPacketParams = GenericPacketParams::new();
+        let mut idx: usize = 1;
+        while idx < ret.buf.len() && idx <= params.len() {
+            ret.buf[idx] = params[idx - 1];
+            idx += 1;
+        }
+        ret
+    }
+
     /// Preamble length in number of symbols.
     ///
     /// Values of zero are invalid, and will automatically be set to 1.
@@ -274,6 +288,123 @@ impl GenericPacketParams {
         self
     }
 
+    /// Get the preamble length in number of symbols.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::subghz::GenericPacketParams;
+    ///
+    /// const PKT_PARAMS: GenericPacketParams = GenericPacketParams::new().set_preamble_len(40);
+    /// assert_eq!(PKT_PARAMS.preamble_len(), 40);
+    /// ```
+    pub const fn preamble_len(&self) -> u16 {
+        u16::from_be_bytes([self.buf[1], self.buf[2]])
+    }
+
+    /// Get the sync word length in bits.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::subghz::GenericPacketParams;
+    ///
+    /// const PKT_PARAMS: GenericPacketParams = GenericPacketParams::new().set_sync_word_len(16);
+    /// assert_eq!(PKT_PARAMS.sync_word_len(), 16);
+    /// ```
+    pub const fn sync_word_len(&self) -> u8 {
+        self.buf[4]
+    }
+
+    /// Get the address comparison/filtering.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::subghz::{AddrComp, GenericPacketParams};
+    ///
+    /// const PKT_PARAMS: GenericPacketParams =
+    ///     GenericPacketParams::new().set_addr_comp(AddrComp::Node);
+    /// assert_eq!(PKT_PARAMS.addr_comp(), AddrComp::Node);
+    /// ```
+    pub const fn addr_comp(&self) -> AddrComp {
+        match self.buf[5] {
+            0x0 => AddrComp::Disabled,
+            0x1 => AddrComp::Node,
+            // only valid values can be set
+            _ => AddrComp::Broadcast,
+        }
+    }
+
+    /// Get the header type.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::subghz::{GenericPacketParams, HeaderType};
+    ///
+    /// const PKT_PARAMS: GenericPacketParams =
+    ///     GenericPacketParams::new().set_header_type(HeaderType::Variable);
+    /// assert_eq!(PKT_PARAMS.header_type(), HeaderType::Variable);
+    /// ```
+    pub const fn header_type(&self) -> HeaderType {
+        if self.buf[6] == HeaderType::Variable.to_bits_generic() {
+            HeaderType::Variable
+        } else {
+            HeaderType::Fixed
+        }
+    }
+
+    /// Get the payload length in bytes.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::subghz::GenericPacketParams;
+    ///
+    /// const PKT_PARAMS: GenericPacketParams = GenericPacketParams::new().set_payload_len(12);
+    /// assert_eq!(PKT_PARAMS.payload_len(), 12);
+    /// ```
+    pub const fn payload_len(&self) -> u8 {
+        self.buf[7]
+    }
+
+    /// Get the CRC type.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::subghz::{CrcType, GenericPacketParams};
+    ///
+    /// const PKT_PARAMS: GenericPacketParams =
+    ///     GenericPacketParams::new().set_crc_type(CrcType::Byte2Inverted);
+    /// assert_eq!(PKT_PARAMS.crc_type(), CrcType::Byte2Inverted);
+    /// ```
+    pub const fn crc_type(&self) -> CrcType {
+        match self.buf[8] {
+            0x0 => CrcType::Byte1,
+            0x2 => CrcType::Byte2,
+            0x4 => CrcType::Byte1Inverted,
+            0x6 => CrcType::Byte2Inverted,
+            // only valid values can be set
+            _ => CrcType::Disabled,
+        }
+    }
+
+    /// Returns `true` if whitening is enabled.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::subghz::GenericPacketParams;
+    ///
+    /// const PKT_PARAMS: GenericPacketParams = GenericPacketParams::new().set_whitening_enable(true);
+    /// assert!(PKT_PARAMS.whitening_enable());
+    /// ```
+    pub const fn whitening_enable(&self) -> bool {
+        self.buf[9] != 0
+    }
+
     /// Extracts a slice containing the packet.
     ///
     /// # Example
@@ -313,6 +444,7 @@ impl Default for GenericPacketParams {
 ///
 /// [`set_lora_packet_params`]: super::SubGhz::set_lora_packet_params
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct LoRaPacketParams {
     buf: [u8; 7],
 }
@@ -345,6 +477,20 @@ impl LoRaPacketParams {
         NEW
     }
 
+    /// Create from the command parameters, without the opcode.
+    ///
+    /// Parameters beyond the length of the command are ignored.
+    #[cfg(feature = "subghz-sim")]
+    pub(crate) const fn from_params(params: &[u8]) -> LoRaPacketParams {
+        let mut ret: LoRaPacketParams = LoRaPacketParams::new();
+        let mut idx: usize = 1;
+        while idx < ret.buf.len() && idx <= params.len() {
+            ret.buf[idx] = params[idx - 1];
+            idx += 1;
+        }
+        ret
+    }
+
     /// Preamble length in number of symbols.
     ///
     /// Values of zero are invalid, and will automatically be set to 1.
@@ -438,6 +584,81 @@ impl LoRaPacketParams {
         self
     }
 
+    /// Get the preamble length in number of symbols.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::subghz::LoRaPacketParams;
+    ///
+    /// const PKT_PARAMS: LoRaPacketParams = LoRaPacketParams::new().set_preamble_len(12);
+    /// assert_eq!(PKT_PARAMS.preamble_len(), 12);
+    /// ```
+    pub const fn preamble_len(&self) -> u16 {
+        u16::from_be_bytes([self.buf[1], self.buf[2]])
+    }
+
+    /// Get the header type.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::subghz::{HeaderType, LoRaPacketParams};
+    ///
+    /// const PKT_PARAMS: LoRaPacketParams =
+    ///     LoRaPacketParams::new().set_header_type(HeaderType::Variable);
+    /// assert_eq!(PKT_PARAMS.header_type(), HeaderType::Variable);
+    /// ```
+    pub const fn header_type(&self) -> HeaderType {
+        if self.buf[3] == HeaderType::Fixed.to_bits_lora() {
+            HeaderType::Fixed
+        } else {
+            HeaderType::Variable
+        }
+    }
+
+    /// Get the payload length in bytes.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::subghz::LoRaPacketParams;
+    ///
+    /// const PKT_PARAMS: LoRaPacketParams = LoRaPacketParams::new().set_payload_len(64);
+    /// assert_eq!(PKT_PARAMS.payload_len(), 64);
+    /// ```
+    pub const fn payload_len(&self) -> u8 {
+        self.buf[4]
+    }
+
+    /// Returns `true` if the CRC is enabled.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::subghz::LoRaPacketParams;
+    ///
+    /// const PKT_PARAMS: LoRaPacketParams = LoRaPacketParams::new().set_crc_en(false);
+    /// assert!(!PKT_PARAMS.crc_en());
+    /// ```
+    pub const fn crc_en(&self) -> bool {
+        self.buf[5] != 0
+    }
+
+    /// Returns `true` if the IQ setup is inverted.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::subghz::LoRaPacketParams;
+    ///
+    /// const PKT_PARAMS: LoRaPacketParams = LoRaPacketParams::new().set_invert_iq(true);
+    /// assert!(PKT_PARAMS.invert_iq());
+    /// ```
+    pub const fn invert_iq(&self) -> bool {
+        self.buf[6] != 0
+    }
+
     /// Extracts a slice containing the packet.
     ///
     /// # Example
@@ -496,6 +717,20 @@ impl BpskPacketParams {
         }
     }
 
+    /// Create from the command parameters, without the opcode.
+    ///
+    /// Parameters beyond the length of the command are ignored.
+    #[cfg(feature = "subghz-sim")]
+    pub(crate) const fn from_params(params: &[u8]) -> BpskPacketParams {
+        let mut ret: BpskPacketParams = BpskPacketParams::new();
+        let mut idx: usize = 1;
+        while idx < ret.buf.len() && idx <= params.len() {
+            ret.buf[idx] = params[idx - 1];
+            idx += 1;
+        }
+        ret
+    }
+
     /// Set the payload length in bytes.
     ///
     /// The length includes preamble, sync word, device ID, and CRC.
@@ -514,6 +749,20 @@ impl BpskPacketParams {
         self
     }
 
+    /// Get the payload length in bytes.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::subghz::BpskPacketParams;
+    ///
+    /// const PKT_PARAMS: BpskPacketParams = BpskPacketParams::new().set_payload_len(26);
+    /// assert_eq!(PKT_PARAMS.payload_len(), 26);
+    /// ```
+    pub const fn payload_len(&self) -> u8 {
+        self.buf[1]
+    }
+
     /// Extracts a slice containing the packet.
     ///
     /// # Example
@@ -25,3 +25,29 @@ pub enum PMode {
     /// Best receiver sensitivity.
     Boost = (0x25 << 2) | 0b11,
 }
+
+impl PMode {
+    /// Create a new `PMode` from the RX gain control register value.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::subghz::PMode;
+    ///
+    /// assert_eq!(PMode::from_raw(0x94), Ok(PMode::PowerSaving));
+    /// assert_eq!(PMode::from_raw(0x95), Ok(PMode::Boost1));
+    /// assert_eq!(PMode::from_raw(0x96), Ok(PMode::Boost2));
+    /// assert_eq!(PMode::from_raw(0x97), Ok(PMode::Boost));
+    /// // Other values are undocumented
+    /// assert_eq!(PMode::from_raw(0x00), Err(0x00));
+    /// ```
+    pub const fn from_raw(bits: u8) -> Result<PMode, u8> {
+        match bits {
+            x if x == PMode::PowerSaving as u8 => Ok(PMode::PowerSaving),
+            x if x == PMode::Boost1 as u8 => Ok(PMode::Boost1),
+            x if x == PMode::Boost2 as u8 => Ok(PMode::Boost2),
+            x if x == PMode::Boost as u8 => Ok(PMode::Boost),
+            _ => Err(bits),
+        }
+    }
+}
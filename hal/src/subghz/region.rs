@@ -0,0 +1,775 @@
+//! LoRaWAN regional parameters
+//!
+//! Channel plans, data rates, transmit power limits, and RX2 defaults from
+//! the LoRa Alliance RP002-1.0.3 LoRaWAN regional parameters.
+//!
+//! Only the default channels are provided, channels added by the network
+//! with a join accept CFList or MAC commands are not covered.
+//! Dwell time limits are not applied, the AS923 tables are for AS923-1 with
+//! the downlink dwell time disabled.
+//!
+//! # Example
+//!
+//! ```
+//! use stm32wlxx_hal::subghz::{
+//!     region::{DataRate, Region},
+//!     LoRaBandwidth, RfFreq, SpreadingFactor,
+//! };
+//!
+//! const REGION: Region = Region::Eu868;
+//!
+//! let rf_freq: RfFreq = REGION.channel(0).unwrap();
+//! assert_eq!(rf_freq, RfFreq::from_frequency(868_100_000));
+//!
+//! match REGION.data_rate(5) {
+//!     Some(DataRate::LoRa(mod_params)) => {
+//!         assert_eq!(mod_params.sf(), SpreadingFactor::Sf7);
+//!         assert_eq!(mod_params.bw(), LoRaBandwidth::Bw125);
+//!     }
+//!     _ => unreachable!(),
+//! }
+//!
+//! assert_eq!(REGION.rx2(), (RfFreq::from_frequency(869_525_000), 0));
+//! ```
+
+use super::{
+    tx_gate::SubBand, CalibrateImage, CodingRate, FskBandwidth, FskBitrate, FskFdev, FskModParams,
+    FskPulseShape, LoRaBandwidth, LoRaModParams, PaConfig, RfFreq, SpreadingFactor, TxParams,
+};
+use core::ops::RangeInclusive;
+
+/// Antenna gain assumed by the regional parameters, rounded to a whole dB.
+const ANTENNA_GAIN_DBI: i8 = 2;
+
+/// LoRaWAN region.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Region {
+    /// Europe 863 - 870 MHz.
+    Eu868,
+    /// United States 902 - 928 MHz.
+    Us915,
+    /// Asia 915 - 928 MHz, AS923-1 frequency plan.
+    As923,
+    /// Australia 915 - 928 MHz.
+    Au915,
+    /// India 865 - 867 MHz.
+    In865,
+}
+
+/// Data rate modulation parameters.
+///
+/// Returned by [`Region::data_rate`].
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum DataRate {
+    /// LoRa modulation.
+    ///
+    /// Low data rate optimization is enabled when required.
+    LoRa(LoRaModParams),
+    /// (G)FSK modulation.
+    Fsk(FskModParams),
+}
+
+const fn lora(sf: SpreadingFactor, bw: LoRaBandwidth) -> Option<DataRate> {
+    Some(DataRate::LoRa(
+        LoRaModParams::new()
+            .set_sf(sf)
+            .set_bw(bw)
+            .set_cr(CodingRate::Cr45)
+            .set_ldro_auto(),
+    ))
+}
+
+// 50 kbps GFSK with 25 kHz deviation, used as DR7 in the EU-like regions
+const FSK_50K: Option<DataRate> = Some(DataRate::Fsk(
+    FskModParams::new()
+        .set_bitrate(FskBitrate::from_bps(50_000))
+        .set_pulse_shape(FskPulseShape::Bt10)
+        .set_bandwidth(FskBandwidth::Bw117)
+        .set_fdev(FskFdev::from_hertz(25_000)),
+));
+
+const fn clamp(x: i16, min: i16, max: i16) -> u8 {
+    if x < min {
+        min as u8
+    } else if x > max {
+        max as u8
+    } else {
+        x as u8
+    }
+}
+
+impl Region {
+    /// Number of default uplink channels.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::subghz::region::Region;
+    ///
+    /// assert_eq!(Region::Eu868.num_channels(), 3);
+    /// assert_eq!(Region::Us915.num_channels(), 72);
+    /// ```
+    pub const fn num_channels(self) -> u8 {
+        match self {
+            Region::Eu868 | Region::In865 => 3,
+            Region::Us915 | Region::Au915 => 72,
+            Region::As923 => 2,
+        }
+    }
+
+    /// Uplink frequency of a default channel.
+    ///
+    /// Returns `None` if the channel does not exist.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::subghz::{region::Region, RfFreq};
+    ///
+    /// assert_eq!(
+    ///     Region::Us915.channel(64),
+    ///     Some(RfFreq::from_frequency(903_000_000))
+    /// );
+    /// assert_eq!(Region::Us915.channel(72), None);
+    /// ```
+    pub const fn channel(self, ch: u8) -> Option<RfFreq> {
+        let hz: u32 = match (self, ch) {
+            (Region::Eu868, 0..=2) => 868_100_000 + 200_000 * ch as u32,
+            (Region::Us915, 0..=63) => 902_300_000 + 200_000 * ch as u32,
+            (Region::Us915, 64..=71) => 903_000_000 + 1_600_000 * (ch as u32 - 64),
+            (Region::Au915, 0..=63) => 915_200_000 + 200_000 * ch as u32,
+            (Region::Au915, 64..=71) => 915_900_000 + 1_600_000 * (ch as u32 - 64),
+            (Region::As923, 0..=1) => 923_200_000 + 200_000 * ch as u32,
+            (Region::In865, 0) => 865_062_500,
+            (Region::In865, 1) => 865_402_500,
+            (Region::In865, 2) => 865_985_000,
+            _ => return None,
+        };
+        Some(RfFreq::from_frequency(hz))
+    }
+
+    /// Uplink data rates allowed on a default channel.
+    ///
+    /// Returns `None` if the channel does not exist.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::subghz::region::Region;
+    ///
+    /// assert_eq!(Region::Us915.channel_data_rates(0), Some(0..=3));
+    /// assert_eq!(Region::Us915.channel_data_rates(64), Some(4..=4));
+    /// ```
+    pub const fn channel_data_rates(self, ch: u8) -> Option<RangeInclusive<u8>> {
+        if ch >= self.num_channels() {
+            return None;
+        }
+        Some(match (self, ch) {
+            (Region::Us915, 0..=63) => 0..=3,
+            (Region::Us915, _) => 4..=4,
+            (Region::Au915, 0..=63) => 0..=5,
+            (Region::Au915, _) => 6..=6,
+            _ => 0..=5,
+        })
+    }
+
+    /// RX1 downlink frequency for an uplink on a default channel.
+    ///
+    /// This is the uplink frequency, except for US915 and AU915 which have
+    /// eight dedicated 500 kHz downlink channels.
+    ///
+    /// Returns `None` if the channel does not exist.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::subghz::{region::Region, RfFreq};
+    ///
+    /// assert_eq!(
+    ///     Region::Us915.rx1_channel(9),
+    ///     Some(RfFreq::from_frequency(923_900_000))
+    /// );
+    /// ```
+    pub const fn rx1_channel(self, ch: u8) -> Option<RfFreq> {
+        match self {
+            Region::Us915 | Region::Au915 if ch < self.num_channels() => Some(
+                RfFreq::from_frequency(923_300_000 + 600_000 * (ch % 8) as u32),
+            ),
+            _ => self.channel(ch),
+        }
+    }
+
+    /// Modulation parameters of a data rate.
+    ///
+    /// This includes downlink only data rates, for example US915 DR8 to DR13.
+    ///
+    /// Returns `None` if the data rate is not defined, reserved for future
+    /// use, or uses LR-FHSS modulation.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::subghz::{
+    ///     region::{DataRate, Region},
+    ///     LoRaBandwidth, SpreadingFactor,
+    /// };
+    ///
+    /// match Region::Us915.data_rate(8) {
+    ///     Some(DataRate::LoRa(mod_params)) => {
+    ///         assert_eq!(mod_params.sf(), SpreadingFactor::Sf12);
+    ///         assert_eq!(mod_params.bw(), LoRaBandwidth::Bw500);
+    ///     }
+    ///     _ => unreachable!(),
+    /// }
+    /// ```
+    pub const fn data_rate(self, dr: u8) -> Option<DataRate> {
+        use LoRaBandwidth::{Bw125, Bw250, Bw500};
+        use SpreadingFactor::{Sf10, Sf11, Sf12, Sf7, Sf8, Sf9};
+
+        match (self, dr) {
+            (Region::Eu868 | Region::As923 | Region::In865 | Region::Au915, 0) => lora(Sf12, Bw125),
+            (Region::Eu868 | Region::As923 | Region::In865 | Region::Au915, 1) => lora(Sf11, Bw125),
+            (Region::Eu868 | Region::As923 | Region::In865 | Region::Au915, 2) => lora(Sf10, Bw125),
+            (Region::Eu868 | Region::As923 | Region::In865 | Region::Au915, 3) => lora(Sf9, Bw125),
+            (Region::Eu868 | Region::As923 | Region::In865 | Region::Au915, 4) => lora(Sf8, Bw125),
+            (Region::Eu868 | Region::As923 | Region::In865 | Region::Au915, 5) => lora(Sf7, Bw125),
+            (Region::Eu868 | Region::As923, 6) => lora(Sf7, Bw250),
+            (Region::Eu868 | Region::As923 | Region::In865, 7) => FSK_50K,
+            (Region::Au915, 6) => lora(Sf8, Bw500),
+            (Region::Us915, 0) => lora(Sf10, Bw125),
+            (Region::Us915, 1) => lora(Sf9, Bw125),
+            (Region::Us915, 2) => lora(Sf8, Bw125),
+            (Region::Us915, 3) => lora(Sf7, Bw125),
+            (Region::Us915, 4) => lora(Sf8, Bw500),
+            (Region::Us915 | Region::Au915, 8) => lora(Sf12, Bw500),
+            (Region::Us915 | Region::Au915, 9) => lora(Sf11, Bw500),
+            (Region::Us915 | Region::Au915, 10) => lora(Sf10, Bw500),
+            (Region::Us915 | Region::Au915, 11) => lora(Sf9, Bw500),
+            (Region::Us915 | Region::Au915, 12) => lora(Sf8, Bw500),
+            (Region::Us915 | Region::Au915, 13) => lora(Sf7, Bw500),
+            _ => None,
+        }
+    }
+
+    /// RX1 data rate for an uplink data rate and the RX1 data rate offset
+    /// from the join accept.
+    ///
+    /// Returns `None` if the uplink data rate or offset is invalid.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::subghz::region::Region;
+    ///
+    /// assert_eq!(Region::Eu868.rx1_dr(5, 0), Some(5));
+    /// assert_eq!(Region::Eu868.rx1_dr(1, 3), Some(0));
+    /// assert_eq!(Region::Us915.rx1_dr(0, 0), Some(10));
+    /// ```
+    pub const fn rx1_dr(self, dr: u8, offset: u8) -> Option<u8> {
+        let dr_i: i16 = dr as i16;
+        let offset_i: i16 = offset as i16;
+        match (self, dr, offset) {
+            (Region::Eu868, 0..=7, 0..=5) => Some(clamp(dr_i - offset_i, 0, 7)),
+            (Region::As923 | Region::In865, 0..=7, 0..=7) => {
+                if matches!(self, Region::In865) && dr == 6 {
+                    return None;
+                }
+                // offsets 6 and 7 increase the data rate
+                let eff: i16 = match offset {
+                    6 => -1,
+                    7 => -2,
+                    _ => offset_i,
+                };
+                match self {
+                    // RX1 DR = MIN(5, MAX(0, DR - offset))
+                    Region::As923 => Some(clamp(dr_i - eff, 0, 5)),
+                    _ => {
+                        let max: i16 = if dr <= 5 { 5 } else { 7 };
+                        match clamp(dr_i - eff, 0, max) {
+                            // DR6 is reserved in IN865
+                            6 => Some(5),
+                            x => Some(x),
+                        }
+                    }
+                }
+            }
+            (Region::Us915, 0..=4, 0..=3) => Some(clamp(10 + dr_i - offset_i, 8, 13)),
+            (Region::Au915, 0..=6, 0..=5) => Some(clamp(8 + dr_i - offset_i, 8, 13)),
+            _ => None,
+        }
+    }
+
+    /// Default RX2 frequency and data rate.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::subghz::{region::Region, RfFreq};
+    ///
+    /// assert_eq!(
+    ///     Region::Us915.rx2(),
+    ///     (RfFreq::from_frequency(923_300_000), 8)
+    /// );
+    /// ```
+    pub const fn rx2(self) -> (RfFreq, u8) {
+        let (hz, dr): (u32, u8) = match self {
+            Region::Eu868 => (869_525_000, 0),
+            Region::Us915 | Region::Au915 => (923_300_000, 8),
+            Region::As923 => (923_200_000, 2),
+            Region::In865 => (866_550_000, 2),
+        };
+        (RfFreq::from_frequency(hz), dr)
+    }
+
+    /// Default maximum EIRP in dBm.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::subghz::region::Region;
+    ///
+    /// assert_eq!(Region::Eu868.max_eirp(), 16);
+    /// assert_eq!(Region::Us915.max_eirp(), 30);
+    /// ```
+    pub const fn max_eirp(self) -> i8 {
+        match self {
+            Region::Eu868 | Region::As923 => 16,
+            Region::Us915 | Region::Au915 | Region::In865 => 30,
+        }
+    }
+
+    /// EIRP in dBm of a `TXPower` index from the `LinkADRReq` MAC command.
+    ///
+    /// Returns `None` if the index is not defined for the region.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::subghz::region::Region;
+    ///
+    /// assert_eq!(Region::Eu868.tx_power(0), Some(16));
+    /// assert_eq!(Region::Eu868.tx_power(7), Some(2));
+    /// assert_eq!(Region::Eu868.tx_power(8), None);
+    /// ```
+    pub const fn tx_power(self, idx: u8) -> Option<i8> {
+        let max_idx: u8 = match self {
+            Region::Eu868 | Region::As923 => 7,
+            Region::In865 => 10,
+            Region::Us915 | Region::Au915 => 14,
+        };
+        if idx > max_idx {
+            None
+        } else {
+            Some(self.max_eirp() - 2 * idx as i8)
+        }
+    }
+
+    /// Highest power amplifier configuration within the default maximum EIRP.
+    ///
+    /// This assumes an antenna gain of 2 dBi.
+    /// Regions allowing more than the +22 dBm of the high-power PA use the
+    /// high-power PA at +22 dBm.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::subghz::{region::Region, PaConfig, TxParams};
+    ///
+    /// assert_eq!(Region::Eu868.pa(), (PaConfig::LP_14, TxParams::LP_14));
+    /// assert_eq!(Region::Us915.pa(), (PaConfig::HP_22, TxParams::HP));
+    /// ```
+    pub const fn pa(self) -> (PaConfig, TxParams) {
+        match self.max_eirp() - ANTENNA_GAIN_DBI {
+            i8::MIN..=13 => (PaConfig::LP_10, TxParams::LP_10),
+            14 => (PaConfig::LP_14, TxParams::LP_14),
+            15..=16 => (PaConfig::LP_15, TxParams::LP_15),
+            17..=19 => (PaConfig::HP_17, TxParams::HP),
+            20..=21 => (PaConfig::HP_20, TxParams::HP),
+            _ => (PaConfig::HP_22, TxParams::HP),
+        }
+    }
+
+    /// Image calibration covering the default channels.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::subghz::{region::Region, CalibrateImage};
+    ///
+    /// assert_eq!(Region::Eu868.cal_image(), CalibrateImage::ISM_863_870);
+    /// ```
+    pub const fn cal_image(self) -> CalibrateImage {
+        match self {
+            Region::Eu868 | Region::In865 => CalibrateImage::ISM_863_870,
+            Region::Us915 | Region::As923 | Region::Au915 => CalibrateImage::ISM_902_928,
+        }
+    }
+
+    /// Duty-cycle limited sub-bands of the region.
+    ///
+    /// Only EU868 has duty cycle limits in the regional parameters, the
+    /// sub-bands are [`SubBand::EU868`] from ETSI EN 300 220.
+    /// Other regions return an empty slice.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::subghz::{
+    ///     region::Region,
+    ///     tx_gate::{SubBand, TxGate},
+    /// };
+    ///
+    /// assert_eq!(Region::Eu868.sub_bands(), &SubBand::EU868);
+    /// assert!(Region::Us915.sub_bands().is_empty());
+    ///
+    /// let gate: TxGate<6> = TxGate::new(SubBand::EU868);
+    /// ```
+    pub const fn sub_bands(self) -> &'static [SubBand] {
+        match self {
+            Region::Eu868 => &SubBand::EU868,
+            _ => &[],
+        }
+    }
+
+    /// Duty-cycle limited sub-band containing `rf_freq`.
+    ///
+    /// Returns `None` if `rf_freq` is not in any of the
+    /// [`sub_bands`](Self::sub_bands).
+    pub const fn sub_band(self, rf_freq: RfFreq) -> Option<SubBand> {
+        let bands: &[SubBand] = self.sub_bands();
+        let mut idx: usize = 0;
+        while idx < bands.len() {
+            if bands[idx].contains(&rf_freq) {
+                return Some(bands[idx]);
+            }
+            idx += 1;
+        }
+        None
+    }
+
+    /// Duty cycle limit of the sub-band containing `rf_freq`.
+    ///
+    /// Returns the denominator of the duty cycle, for example `Some(100)` for
+    /// 1%, see [`sub_band`](Self::sub_band).
+    ///
+    /// Returns `None` if the region has no duty cycle limit, or if `rf_freq`
+    /// is outside of the sub-bands of an EU868 device.
+    /// EU868 devices must not transmit outside of the sub-bands, a
+    /// [`TxGate`](super::tx_gate::TxGate) rejects these frequencies with
+    /// [`GateError::SubBand`](super::tx_gate::GateError::SubBand).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::subghz::{region::Region, RfFreq};
+    ///
+    /// let rf_freq: RfFreq = Region::Eu868.channel(0).unwrap();
+    /// assert_eq!(Region::Eu868.duty_cycle(rf_freq), Some(100));
+    /// assert_eq!(
+    ///     Region::Eu868.duty_cycle(RfFreq::from_frequency(869_525_000)),
+    ///     Some(10)
+    /// );
+    /// assert_eq!(Region::Us915.duty_cycle(rf_freq), None);
+    /// ```
+    pub const fn duty_cycle(self, rf_freq: RfFreq) -> Option<u16> {
+        match self.sub_band(rf_freq) {
+            Some(band) => Some(band.duty_cycle()),
+            None => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{DataRate, Region};
+    use crate::subghz::{
+        CalibrateImage, FskBandwidth, FskBitrate, FskFdev, FskModParams, FskPulseShape,
+        LoRaBandwidth, RfFreq, SpreadingFactor,
+    };
+
+    const REGIONS: [Region; 5] = [
+        Region::Eu868,
+        Region::Us915,
+        Region::As923,
+        Region::Au915,
+        Region::In865,
+    ];
+
+    fn lora(region: Region, dr: u8) -> (SpreadingFactor, LoRaBandwidth) {
+        match region.data_rate(dr) {
+            Some(DataRate::LoRa(mp)) => {
+                assert_eq!(mp.ldro_en(), mp.ldro_required());
+                (mp.sf(), mp.bw())
+            }
+            x => panic!("{:?} DR{} is not LoRa: {:?}", region, dr, x),
+        }
+    }
+
+    fn mhz(hz: u32) -> Option<RfFreq> {
+        Some(RfFreq::from_frequency(hz))
+    }
+
+    // RP002-1.0.3 tables 2.4.3 (EU868), 2.5.3 (US915), 2.7.3 (AU915),
+    // 2.9.3 (AS923), 2.10.3 (IN865)
+    #[test]
+    fn data_rates() {
+        use LoRaBandwidth::{Bw125, Bw250, Bw500};
+        use SpreadingFactor::{Sf10, Sf11, Sf12, Sf7, Sf8, Sf9};
+
+        const EU_LIKE: [(SpreadingFactor, LoRaBandwidth); 6] = [
+            (Sf12, Bw125),
+            (Sf11, Bw125),
+            (Sf10, Bw125),
+            (Sf9, Bw125),
+            (Sf8, Bw125),
+            (Sf7, Bw125),
+        ];
+        for region in [Region::Eu868, Region::As923, Region::Au915, Region::In865] {
+            for (dr, expected) in EU_LIKE.iter().enumerate() {
+                assert_eq!(lora(region, dr as u8), *expected);
+            }
+        }
+        assert_eq!(lora(Region::Eu868, 6), (Sf7, Bw250));
+        assert_eq!(lora(Region::As923, 6), (Sf7, Bw250));
+        assert_eq!(Region::In865.data_rate(6), None);
+        assert_eq!(lora(Region::Au915, 6), (Sf8, Bw500));
+        assert_eq!(Region::Au915.data_rate(7), None);
+
+        const FSK: FskModParams = FskModParams::new()
+            .set_bitrate(FskBitrate::from_bps(50_000))
+            .set_pulse_shape(FskPulseShape::Bt10)
+            .set_bandwidth(FskBandwidth::Bw117)
+            .set_fdev(FskFdev::from_hertz(25_000));
+        for region in [Region::Eu868, Region::As923, Region::In865] {
+            assert_eq!(region.data_rate(7), Some(DataRate::Fsk(FSK)));
+            assert_eq!(region.data_rate(8), None);
+        }
+
+        const US: [(SpreadingFactor, LoRaBandwidth); 5] = [
+            (Sf10, Bw125),
+            (Sf9, Bw125),
+            (Sf8, Bw125),
+            (Sf7, Bw125),
+            (Sf8, Bw500),
+        ];
+        for (dr, expected) in US.iter().enumerate() {
+            assert_eq!(lora(Region::Us915, dr as u8), *expected);
+        }
+        for dr in 5..=7 {
+            assert_eq!(Region::Us915.data_rate(dr), None);
+        }
+
+        const DOWNLINK_500: [SpreadingFactor; 6] = [Sf12, Sf11, Sf10, Sf9, Sf8, Sf7];
+        for region in [Region::Us915, Region::Au915] {
+            for (n, sf) in DOWNLINK_500.iter().enumerate() {
+                assert_eq!(lora(region, 8 + n as u8), (*sf, Bw500));
+            }
+            assert_eq!(region.data_rate(14), None);
+        }
+    }
+
+    #[test]
+    fn channels() {
+        assert_eq!(Region::Eu868.channel(0), mhz(868_100_000));
+        assert_eq!(Region::Eu868.channel(1), mhz(868_300_000));
+        assert_eq!(Region::Eu868.channel(2), mhz(868_500_000));
+
+        assert_eq!(Region::Us915.channel(0), mhz(902_300_000));
+        assert_eq!(Region::Us915.channel(63), mhz(914_900_000));
+        assert_eq!(Region::Us915.channel(64), mhz(903_000_000));
+        assert_eq!(Region::Us915.channel(71), mhz(914_200_000));
+
+        assert_eq!(Region::Au915.channel(0), mhz(915_200_000));
+        assert_eq!(Region::Au915.channel(63), mhz(927_800_000));
+        assert_eq!(Region::Au915.channel(64), mhz(915_900_000));
+        assert_eq!(Region::Au915.channel(71), mhz(927_100_000));
+
+        assert_eq!(Region::As923.channel(0), mhz(923_200_000));
+        assert_eq!(Region::As923.channel(1), mhz(923_400_000));
+
+        assert_eq!(Region::In865.channel(0), mhz(865_062_500));
+        assert_eq!(Region::In865.channel(1), mhz(865_402_500));
+        assert_eq!(Region::In865.channel(2), mhz(865_985_000));
+
+        for region in REGIONS {
+            for ch in 0..region.num_channels() {
+                let rf_freq: RfFreq = region.channel(ch).unwrap();
+                assert!(rf_freq.is_valid());
+                assert!(region.channel_data_rates(ch).is_some());
+                for dr in region.channel_data_rates(ch).unwrap() {
+                    assert!(region.data_rate(dr).is_some());
+                }
+            }
+            assert_eq!(region.channel(region.num_channels()), None);
+            assert_eq!(region.channel_data_rates(region.num_channels()), None);
+            assert_eq!(region.rx1_channel(region.num_channels()), None);
+        }
+    }
+
+    #[test]
+    fn rx1_channels() {
+        for ch in 0..3 {
+            assert_eq!(Region::Eu868.rx1_channel(ch), Region::Eu868.channel(ch));
+        }
+        for region in [Region::Us915, Region::Au915] {
+            assert_eq!(region.rx1_channel(0), mhz(923_300_000));
+            assert_eq!(region.rx1_channel(7), mhz(927_500_000));
+            assert_eq!(region.rx1_channel(8), mhz(923_300_000));
+            assert_eq!(region.rx1_channel(65), mhz(923_900_000));
+        }
+    }
+
+    #[test]
+    fn rx1_dr() {
+        // rows are uplink data rates, columns are offsets
+        const EU868: [[u8; 6]; 8] = [
+            [0, 0, 0, 0, 0, 0],
+            [1, 0, 0, 0, 0, 0],
+            [2, 1, 0, 0, 0, 0],
+            [3, 2, 1, 0, 0, 0],
+            [4, 3, 2, 1, 0, 0],
+            [5, 4, 3, 2, 1, 0],
+            [6, 5, 4, 3, 2, 1],
+            [7, 6, 5, 4, 3, 2],
+        ];
+        const AS923: [[u8; 8]; 8] = [
+            [0, 0, 0, 0, 0, 0, 1, 2],
+            [1, 0, 0, 0, 0, 0, 2, 3],
+            [2, 1, 0, 0, 0, 0, 3, 4],
+            [3, 2, 1, 0, 0, 0, 4, 5],
+            [4, 3, 2, 1, 0, 0, 5, 5],
+            [5, 4, 3, 2, 1, 0, 5, 5],
+            [5, 5, 4, 3, 2, 1, 5, 5],
+            [5, 5, 5, 4, 3, 2, 5, 5],
+        ];
+        const IN865_DR7: [u8; 8] = [7, 5, 5, 4, 3, 2, 7, 7];
+        const US915: [[u8; 4]; 5] = [
+            [10, 9, 8, 8],
+            [11, 10, 9, 8],
+            [12, 11, 10, 9],
+            [13, 12, 11, 10],
+            [13, 13, 12, 11],
+        ];
+        const AU915: [[u8; 6]; 7] = [
+            [8, 8, 8, 8, 8, 8],
+            [9, 8, 8, 8, 8, 8],
+            [10, 9, 8, 8, 8, 8],
+            [11, 10, 9, 8, 8, 8],
+            [12, 11, 10, 9, 8, 8],
+            [13, 12, 11, 10, 9, 8],
+            [13, 13, 12, 11, 10, 9],
+        ];
+
+        fn check<const N: usize>(region: Region, table: &[[u8; N]]) {
+            for (dr, row) in table.iter().enumerate() {
+                for (offset, expected) in row.iter().enumerate() {
+                    assert_eq!(
+                        region.rx1_dr(dr as u8, offset as u8),
+                        Some(*expected),
+                        "{:?} DR{} offset {}",
+                        region,
+                        dr,
+                        offset
+                    );
+                }
+                assert_eq!(region.rx1_dr(dr as u8, N as u8), None);
+            }
+            assert_eq!(region.rx1_dr(table.len() as u8, 0), None);
+        }
+
+        check(Region::Eu868, &EU868);
+        check(Region::As923, &AS923);
+        check(Region::In865, &AS923[..6]);
+        assert_eq!(Region::In865.rx1_dr(6, 0), None);
+        for (offset, expected) in IN865_DR7.iter().enumerate() {
+            assert_eq!(Region::In865.rx1_dr(7, offset as u8), Some(*expected));
+        }
+        check(Region::Us915, &US915);
+        check(Region::Au915, &AU915);
+    }
+
+    #[test]
+    fn rx2() {
+        assert_eq!(
+            Region::Eu868.rx2(),
+            (RfFreq::from_frequency(869_525_000), 0)
+        );
+        assert_eq!(
+            Region::Us915.rx2(),
+            (RfFreq::from_frequency(923_300_000), 8)
+        );
+        assert_eq!(
+            Region::Au915.rx2(),
+            (RfFreq::from_frequency(923_300_000), 8)
+        );
+        assert_eq!(
+            Region::As923.rx2(),
+            (RfFreq::from_frequency(923_200_000), 2)
+        );
+        assert_eq!(
+            Region::In865.rx2(),
+            (RfFreq::from_frequency(866_550_000), 2)
+        );
+        for region in REGIONS {
+            assert!(region.data_rate(region.rx2().1).is_some());
+        }
+    }
+
+    #[test]
+    fn tx_power() {
+        assert_eq!(Region::Us915.tx_power(0), Some(30));
+        assert_eq!(Region::Us915.tx_power(14), Some(2));
+        assert_eq!(Region::Us915.tx_power(15), None);
+        assert_eq!(Region::Au915.tx_power(14), Some(2));
+        assert_eq!(Region::As923.tx_power(7), Some(2));
+        assert_eq!(Region::As923.tx_power(8), None);
+        assert_eq!(Region::In865.tx_power(10), Some(10));
+        assert_eq!(Region::In865.tx_power(11), None);
+    }
+
+    #[test]
+    fn cal_image() {
+        for region in REGIONS {
+            let cal: CalibrateImage = region.cal_image();
+            for ch in 0..region.num_channels() {
+                let mhz: u32 = region.channel(ch).unwrap().freq() / 1_000_000;
+                assert!(
+                    (u32::from(cal.0) * 4..=u32::from(cal.1) * 4 + 4).contains(&mhz),
+                    "{:?} channel {} {} MHz",
+                    region,
+                    ch,
+                    mhz
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn duty_cycle() {
+        let eu = |hz: u32| Region::Eu868.duty_cycle(RfFreq::from_frequency(hz));
+        assert_eq!(eu(863_500_000), Some(1000));
+        assert_eq!(eu(865_500_000), Some(100));
+        assert_eq!(eu(868_100_000), Some(100));
+        assert_eq!(eu(868_500_000), Some(100));
+        assert_eq!(eu(868_800_000), Some(1000));
+        assert_eq!(eu(869_525_000), Some(10));
+        assert_eq!(eu(869_850_000), Some(100));
+        // outside of the sub-bands
+        assert_eq!(eu(862_000_000), None);
+        assert_eq!(eu(868_650_000), None);
+        assert_eq!(eu(869_300_000), None);
+        // every channel is in a sub-band
+        for ch in 0..Region::Eu868.num_channels() {
+            assert!(Region::Eu868
+                .duty_cycle(Region::Eu868.channel(ch).unwrap())
+                .is_some());
+        }
+        let (rx2, _) = Region::Eu868.rx2();
+        assert_eq!(Region::Eu868.duty_cycle(rx2), Some(10));
+        for region in [Region::Us915, Region::As923, Region::Au915, Region::In865] {
+            assert_eq!(region.duty_cycle(region.channel(0).unwrap()), None);
+        }
+    }
+}
@@ -104,10 +104,26 @@ impl RfFreq {
     ///
     /// assert_eq!(RfFreq::from_raw(0x39300000).freq(), 915_000_000);
     /// ```
-    pub fn freq(&self) -> u32 {
+    pub const fn freq(&self) -> u32 {
         (32_000_000 * (self.as_bits() as u64) / (1 << 25)) as u32
     }
 
+    /// Returns `true` if the frequency is within the 150 - 960 MHz band
+    /// supported by the radio.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::subghz::RfFreq;
+    ///
+    /// assert!(RfFreq::F433.is_valid());
+    /// assert!(!RfFreq::from_frequency(2_400_000_000).is_valid());
+    /// ```
+    pub const fn is_valid(&self) -> bool {
+        let freq: u32 = self.freq();
+        freq >= 150_000_000 && freq <= 960_000_000
+    }
+
     /// Extracts a slice containing the packet.
     ///
     /// # Example
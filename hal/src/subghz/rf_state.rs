@@ -0,0 +1,53 @@
+use super::PaSel;
+
+/// RF switch state.
+///
+/// The sub-GHz radio does not control the RF switch, this is passed to the
+/// RF switch callback of the high-level radio drivers such as
+/// [`LoRaRadio`](super::lora::LoRaRadio) before each operation.
+///
+/// # Example
+///
+/// Forward the state to the RF switch of the NUCLEO-WL55JC board.
+///
+/// ```ignore
+/// use nucleo_wl55jc_bsp::RfSwitch;
+/// use stm32wlxx_hal::subghz::RfState;
+///
+/// let rfs_cb = move |state: RfState| match state {
+///     RfState::Off | RfState::Rx => rfs.set_rx(),
+///     RfState::TxLp => rfs.set_tx_lp(),
+///     RfState::TxHp => rfs.set_tx_hp(),
+/// };
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum RfState {
+    /// Radio is idle or in sleep mode.
+    Off,
+    /// Receive, or channel activity detection.
+    Rx,
+    /// Transmit with the low-power PA.
+    TxLp,
+    /// Transmit with the high-power PA.
+    TxHp,
+}
+
+impl RfState {
+    /// RF switch state to transmit with the given PA.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::subghz::{PaSel, RfState};
+    ///
+    /// assert_eq!(RfState::tx(PaSel::Lp), RfState::TxLp);
+    /// assert_eq!(RfState::tx(PaSel::Hp), RfState::TxHp);
+    /// ```
+    pub const fn tx(pa: PaSel) -> RfState {
+        match pa {
+            PaSel::Lp => RfState::TxLp,
+            PaSel::Hp => RfState::TxHp,
+        }
+    }
+}
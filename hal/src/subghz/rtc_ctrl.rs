@@ -0,0 +1,77 @@
+/// Radio real-time-clock control.
+///
+/// Argument of [`set_rtc_ctrl`](crate::subghz::SubGhz::set_rtc_ctrl).
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct RtcCtrl {
+    val: u8,
+}
+
+impl RtcCtrl {
+    /// RTC control register reset value.
+    pub const RESET: RtcCtrl = RtcCtrl { val: 0x00 };
+
+    /// Create a new [`RtcCtrl`] structure from a raw value.
+    ///
+    /// Reserved bits will be masked.
+    pub const fn from_raw(raw: u8) -> Self {
+        Self { val: raw & 0x01 }
+    }
+
+    /// Get the raw value of the [`RtcCtrl`] register.
+    pub const fn as_bits(&self) -> u8 {
+        self.val
+    }
+
+    /// Radio RTC enable.
+    ///
+    /// Enabling the RTC when it is already enabled restarts the RTC.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::subghz::RtcCtrl;
+    ///
+    /// const RTC_CTRL: RtcCtrl = RtcCtrl::RESET.set_en(true);
+    /// # assert_eq!(u8::from(RTC_CTRL), 0x01u8);
+    /// ```
+    #[must_use = "set_en returns a modified RtcCtrl"]
+    pub const fn set_en(mut self, en: bool) -> RtcCtrl {
+        if en {
+            self.val |= 1;
+        } else {
+            self.val &= !1;
+        }
+        self
+    }
+
+    /// Returns `true` if the radio RTC is enabled.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::subghz::RtcCtrl;
+    ///
+    /// let rc: RtcCtrl = RtcCtrl::RESET;
+    /// assert_eq!(rc.en(), false);
+    /// let rc: RtcCtrl = rc.set_en(true);
+    /// assert_eq!(rc.en(), true);
+    /// let rc: RtcCtrl = rc.set_en(false);
+    /// assert_eq!(rc.en(), false);
+    /// ```
+    pub const fn en(&self) -> bool {
+        self.val & 1 != 0
+    }
+}
+
+impl From<RtcCtrl> for u8 {
+    fn from(rc: RtcCtrl) -> Self {
+        rc.val
+    }
+}
+
+impl Default for RtcCtrl {
+    fn default() -> Self {
+        Self::RESET
+    }
+}
@@ -0,0 +1,1544 @@
+//! Simulated sub-GHz radio for testing on the host.
+//!
+//! [`SimRadio`] is a [`Transport`] that decodes the command byte stream sent
+//! by [`SubGhz`](super::SubGhz) and keeps simulated radio state: the
+//! operating mode, the data buffer, IRQ flags, and the register file.
+//! Radio events such as a completed transmission or a received packet are
+//! triggered by the test with methods on [`SimRadio`].
+//!
+//! Radio timing is not simulated, the busy signal is never set and time
+//! passes only when the test triggers an event.
+//!
+//! Multiple simulated radios exchange packets over an [`Ether`].
+//!
+//! # Example
+//!
+//! ```
+//! use stm32wlxx_hal::subghz::{
+//!     sim::{SimMode, SimRadio},
+//!     CfgIrq, Irq, PacketType, SubGhz, Timeout,
+//! };
+//!
+//! let mut sg = SubGhz::new_with_transport(SimRadio::new());
+//! sg.set_packet_type(PacketType::LoRa)?;
+//! sg.set_irq_cfg(&CfgIrq::new().irq_enable_all(Irq::TxDone))?;
+//! sg.write_buffer(0, b"hello")?;
+//! sg.set_tx(Timeout::DISABLED)?;
+//!
+//! let sim: &mut SimRadio = sg.transport_mut();
+//! assert_eq!(sim.mode(), SimMode::Tx);
+//! assert_eq!(sim.packet_type(), Ok(PacketType::LoRa));
+//! assert!(sim.tx_done());
+//!
+//! let (_, irq_status) = sg.irq_status()?;
+//! assert_eq!(irq_status, Irq::TxDone.mask());
+//! # Ok::<(), stm32wlxx_hal::subghz::Error>(())
+//! ```
+
+use super::{
+    BpskModParams, BpskPacketParams, CmdStatus, Error, FallbackMode, FskModParams,
+    GenericPacketParams, Irq, LoRaModParams, LoRaPacketParams, Modulation, OpCode, PacketType,
+    RfFreq, Startup, Status, StatusMode, Transport,
+};
+use core::time::Duration;
+
+/// Length of the command header kept for decoding.
+const CMD_LEN: usize = 16;
+
+/// Size of the simulated register file, covering addresses `0x0000` to
+/// `0x0FFF`.
+pub const REG_LEN: usize = 0x1000;
+
+/// Reset value of the LoRa sync word registers.
+const LSYNC_RESET: [u8; 2] = [0x14, 0x24];
+const LSYNC_ADDR: usize = 0x0740;
+/// Address of the generic sync word registers.
+const GSYNC_ADDR: usize = 0x06C0;
+
+/// Continuous RX timeout.
+const RX_CONTINUOUS: u32 = 0x00FF_FFFF;
+
+/// Operating mode of the simulated radio.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum SimMode {
+    /// Sleep mode with the startup configuration used on wakeup.
+    Sleep(Startup),
+    /// Standby mode with RC 13 MHz clock.
+    StandbyRc,
+    /// Standby mode with HSE32 clock.
+    StandbyHse,
+    /// Frequency synthesis mode.
+    Fs,
+    /// Transmit mode.
+    Tx,
+    /// Receive mode.
+    Rx,
+    /// Channel activity detection.
+    Cad,
+}
+
+impl SimMode {
+    const fn status_mode(self) -> u8 {
+        match self {
+            SimMode::Sleep(_) | SimMode::StandbyRc => StatusMode::StandbyRc as u8,
+            SimMode::StandbyHse => StatusMode::StandbyHse as u8,
+            SimMode::Fs => StatusMode::Fs as u8,
+            SimMode::Rx | SimMode::Cad => StatusMode::Rx as u8,
+            SimMode::Tx => StatusMode::Tx as u8,
+        }
+    }
+}
+
+/// Simulated sub-GHz radio.
+///
+/// See the [module level documentation](self) for an example.
+#[derive(Debug, Clone)]
+pub struct SimRadio {
+    mode: SimMode,
+    cmd_status: u8,
+
+    // current transaction
+    selected: bool,
+    pos: usize,
+    cmd: [u8; CMD_LEN],
+
+    buf: [u8; 256],
+    regs: [u8; REG_LEN],
+    tx_base: u8,
+    rx_base: u8,
+    rx_len: u8,
+    tx: [u8; 256],
+    tx_len: Option<u8>,
+    rx_continuous: bool,
+
+    packet_type: u8,
+    rf_freq: u32,
+    mod_params: [u8; 8],
+    packet_params: [u8; 9],
+    fallback: u8,
+    irq_mask: u16,
+    irq_status: u16,
+    op_error: u16,
+    rssi_inst: u8,
+    packet_status: [u8; 3],
+    rx_pkts: u16,
+    responder: Option<fn(&mut SimRadio)>,
+}
+
+impl SimRadio {
+    /// Create a new simulated radio in standby mode with reset values.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::subghz::sim::{SimMode, SimRadio};
+    ///
+    /// let sim: SimRadio = SimRadio::new();
+    /// assert_eq!(sim.mode(), SimMode::StandbyRc);
+    /// ```
+    pub const fn new() -> SimRadio {
+        let mut regs: [u8; REG_LEN] = [0; REG_LEN];
+        regs[LSYNC_ADDR] = LSYNC_RESET[0];
+        regs[LSYNC_ADDR + 1] = LSYNC_RESET[1];
+        SimRadio {
+            mode: SimMode::StandbyRc,
+            cmd_status: 0,
+            selected: false,
+            pos: 0,
+            cmd: [0; CMD_LEN],
+            buf: [0; 256],
+            regs,
+            tx_base: 0,
+            rx_base: 0,
+            rx_len: 0,
+            tx: [0; 256],
+            tx_len: None,
+            rx_continuous: false,
+            packet_type: PacketType::Fsk as u8,
+            rf_freq: 0,
+            mod_params: [0; 8],
+            packet_params: [0; 9],
+            fallback: FallbackMode::Standby as u8,
+            irq_mask: 0,
+            irq_status: 0,
+            op_error: 0,
+            rssi_inst: 0xFF,
+            packet_status: [0; 3],
+            rx_pkts: 0,
+            responder: None,
+        }
+    }
+
+    /// Set a function that is called after each command.
+    ///
+    /// The responder triggers radio events for drivers that block until an
+    /// operation completes, such as [`LoRaRadio`](super::lora::LoRaRadio).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::subghz::{
+    ///     sim::{SimMode, SimRadio},
+    ///     CfgIrq, Irq, SubGhz, Timeout,
+    /// };
+    ///
+    /// let mut sim: SimRadio = SimRadio::new();
+    /// sim.set_responder(Some(|sim| {
+    ///     sim.tx_done();
+    /// }));
+    ///
+    /// let mut sg = SubGhz::new_with_transport(sim);
+    /// sg.set_irq_cfg(&CfgIrq::new().irq_enable_all(Irq::TxDone))?;
+    /// sg.set_tx(Timeout::DISABLED)?;
+    /// assert_eq!(sg.transport().mode(), SimMode::StandbyRc);
+    ///
+    /// let (_, irq_status) = sg.irq_status()?;
+    /// assert_eq!(irq_status, Irq::TxDone.mask());
+    /// # Ok::<(), stm32wlxx_hal::subghz::Error>(())
+    /// ```
+    pub fn set_responder(&mut self, responder: Option<fn(&mut SimRadio)>) {
+        self.responder = responder;
+    }
+
+    /// Operating mode.
+    pub const fn mode(&self) -> SimMode {
+        self.mode
+    }
+
+    /// Status byte returned by the radio.
+    pub const fn status(&self) -> Status {
+        Status::from_raw((self.mode.status_mode() << 4) | (self.cmd_status << 1))
+    }
+
+    /// IRQ status flags.
+    pub const fn irq_status(&self) -> u16 {
+        self.irq_status
+    }
+
+    /// IRQ mask set with [`set_irq_cfg`](super::SubGhz::set_irq_cfg).
+    pub const fn irq_mask(&self) -> u16 {
+        self.irq_mask
+    }
+
+    /// Packet type set with
+    /// [`set_packet_type`](super::SubGhz::set_packet_type).
+    pub const fn packet_type(&self) -> Result<PacketType, u8> {
+        PacketType::from_raw(self.packet_type)
+    }
+
+    /// RF frequency set with
+    /// [`set_rf_frequency`](super::SubGhz::set_rf_frequency).
+    pub const fn rf_freq(&self) -> RfFreq {
+        RfFreq::from_raw(self.rf_freq)
+    }
+
+    /// Raw modulation parameters, without the opcode.
+    pub const fn mod_params(&self) -> &[u8; 8] {
+        &self.mod_params
+    }
+
+    /// Raw packet parameters, without the opcode.
+    pub const fn packet_params(&self) -> &[u8; 9] {
+        &self.packet_params
+    }
+
+    /// Read a register.
+    ///
+    /// Returns `0` for addresses outside of the simulated register file.
+    pub const fn register(&self, addr: u16) -> u8 {
+        let addr: usize = addr as usize;
+        if addr < REG_LEN {
+            self.regs[addr]
+        } else {
+            0
+        }
+    }
+
+    /// Data buffer.
+    pub const fn buffer(&self) -> &[u8; 256] {
+        &self.buf
+    }
+
+    /// Packet being transmitted.
+    ///
+    /// Returns `None` if the radio is not transmitting a packet.
+    pub fn tx_packet(&self) -> Option<&[u8]> {
+        match (self.mode, self.tx_len) {
+            (SimMode::Tx, Some(len)) => Some(&self.tx[..usize::from(len)]),
+            _ => None,
+        }
+    }
+
+    /// Set the instantaneous RSSI in dBm returned by
+    /// [`rssi_inst`](super::SubGhz::rssi_inst).
+    pub fn set_rssi_inst(&mut self, rssi: i16) {
+        self.rssi_inst = rssi_raw(rssi);
+    }
+
+    /// Set errors returned by [`op_error`](super::SubGhz::op_error).
+    ///
+    /// The errors are set until cleared with
+    /// [`clear_error`](super::SubGhz::clear_error).
+    pub fn set_op_error(&mut self, op_error: u16) {
+        self.op_error |= op_error;
+    }
+
+    /// Set an IRQ flag if the IRQ is enabled.
+    pub fn raise_irq(&mut self, irq: Irq) {
+        self.irq_status |= irq.mask() & self.irq_mask;
+    }
+
+    fn fallback(&mut self) {
+        self.mode = match self.fallback {
+            x if x == FallbackMode::StandbyHse as u8 => SimMode::StandbyHse,
+            x if x == FallbackMode::Fs as u8 => SimMode::Fs,
+            _ => SimMode::StandbyRc,
+        };
+    }
+
+    /// Complete a transmission.
+    ///
+    /// Returns `false` if the radio is not in TX mode.
+    pub fn tx_done(&mut self) -> bool {
+        if self.mode != SimMode::Tx {
+            return false;
+        }
+        self.tx_len = None;
+        self.cmd_status = CmdStatus::Complete as u8;
+        self.raise_irq(Irq::TxDone);
+        self.fallback();
+        true
+    }
+
+    /// Receive a packet with a RSSI in dBm and a SNR in dB.
+    ///
+    /// The packet is written to the data buffer at the RX base address.
+    /// The radio stays in RX mode if the receive is continuous, otherwise it
+    /// enters the fallback mode.
+    ///
+    /// Returns `false` if the radio is not in RX mode.
+    pub fn rx_done(&mut self, data: &[u8], rssi: i16, snr: i8) -> bool {
+        if self.mode != SimMode::Rx {
+            return false;
+        }
+        for (n, byte) in data.iter().take(255).enumerate() {
+            self.buf[usize::from(self.rx_base.wrapping_add(n as u8))] = *byte;
+        }
+        self.rx_len = data.len().min(255) as u8;
+        self.packet_status = match self.packet_type() {
+            Ok(PacketType::LoRa) => [
+                rssi_raw(rssi),
+                snr.saturating_mul(4) as u8,
+                rssi_raw(rssi.saturating_sub(snr.min(0).into())),
+            ],
+            _ => [0, rssi_raw(rssi), rssi_raw(rssi)],
+        };
+        self.rx_pkts = self.rx_pkts.wrapping_add(1);
+        self.cmd_status = CmdStatus::Available as u8;
+        self.raise_irq(Irq::PreambleDetected);
+        if self.packet_type() == Ok(PacketType::LoRa) {
+            self.raise_irq(Irq::HeaderValid);
+        } else {
+            self.raise_irq(Irq::SyncDetected);
+        }
+        self.raise_irq(Irq::RxDone);
+        if !self.rx_continuous {
+            self.fallback();
+        }
+        true
+    }
+
+    /// Time out the current transmission or reception.
+    ///
+    /// Returns `false` if the radio is not in TX or RX mode.
+    pub fn timeout(&mut self) -> bool {
+        if !matches!(self.mode, SimMode::Tx | SimMode::Rx) {
+            return false;
+        }
+        self.tx_len = None;
+        self.cmd_status = CmdStatus::Timeout as u8;
+        self.raise_irq(Irq::Timeout);
+        self.fallback();
+        true
+    }
+
+    /// Complete channel activity detection.
+    ///
+    /// Returns `false` if the radio is not performing channel activity
+    /// detection.
+    pub fn cad_done(&mut self, detected: bool) -> bool {
+        if self.mode != SimMode::Cad {
+            return false;
+        }
+        self.raise_irq(Irq::CadDone);
+        if detected {
+            self.raise_irq(Irq::CadDetected);
+        }
+        self.mode = SimMode::StandbyRc;
+        true
+    }
+
+    fn wakeup(&mut self) {
+        if let SimMode::Sleep(startup) = self.mode {
+            if startup == Startup::Cold {
+                *self = SimRadio {
+                    responder: self.responder,
+                    ..SimRadio::new()
+                };
+            } else {
+                self.mode = SimMode::StandbyRc;
+            }
+        }
+    }
+
+    /// Response byte of a read command, `idx` is the index after the status.
+    fn response(&self, opcode: u8, idx: usize) -> u8 {
+        let data: [u8; 6] = match opcode {
+            x if x == OpCode::GetIrqStatus as u8 => {
+                let [hi, lo] = self.irq_status.to_be_bytes();
+                [hi, lo, 0, 0, 0, 0]
+            }
+            x if x == OpCode::GetError as u8 => {
+                let [hi, lo] = self.op_error.to_be_bytes();
+                [hi, lo, 0, 0, 0, 0]
+            }
+            x if x == OpCode::GetPacketType as u8 => [self.packet_type, 0, 0, 0, 0, 0],
+            x if x == OpCode::GetRxBufferStatus as u8 => [self.rx_len, self.rx_base, 0, 0, 0, 0],
+            x if x == OpCode::GetRssiInst as u8 => [self.rssi_inst, 0, 0, 0, 0, 0],
+            x if x == OpCode::GetPacketStatus as u8 => {
+                let [a, b, c] = self.packet_status;
+                [a, b, c, 0, 0, 0]
+            }
+            x if x == OpCode::GetStats as u8 => {
+                let [hi, lo] = self.rx_pkts.to_be_bytes();
+                [hi, lo, 0, 0, 0, 0]
+            }
+            _ => [0; 6],
+        };
+        data.get(idx).copied().unwrap_or(0)
+    }
+
+    /// Clock one byte through the radio.
+    fn clock(&mut self, mosi: u8) -> u8 {
+        let pos: usize = self.pos;
+        self.pos += 1;
+        if pos < CMD_LEN {
+            self.cmd[pos] = mosi;
+        }
+        let status: u8 = self.status().into();
+        if pos == 0 {
+            return status;
+        }
+
+        let opcode: u8 = self.cmd[0];
+        if opcode == OpCode::WriteBuffer as u8 {
+            if pos >= 2 {
+                let offset: u8 = self.cmd[1].wrapping_add((pos - 2) as u8);
+                self.buf[usize::from(offset)] = mosi;
+            }
+            status
+        } else if opcode == OpCode::ReadBuffer as u8 {
+            if pos >= 3 {
+                let offset: u8 = self.cmd[1].wrapping_add((pos - 3) as u8);
+                self.buf[usize::from(offset)]
+            } else {
+                status
+            }
+        } else if opcode == OpCode::WriteRegister as u8 {
+            if pos >= 3 {
+                let addr: usize = self.reg_addr() + (pos - 3);
+                if addr < REG_LEN {
+                    self.regs[addr] = mosi;
+                }
+            }
+            status
+        } else if opcode == OpCode::ReadRegister as u8 {
+            if pos >= 4 {
+                let addr: usize = self.reg_addr() + (pos - 4);
+                self.regs.get(addr).copied().unwrap_or(0)
+            } else {
+                status
+            }
+        } else if pos >= 2 {
+            self.response(opcode, pos - 2)
+        } else {
+            status
+        }
+    }
+
+    fn reg_addr(&self) -> usize {
+        usize::from(u16::from_be_bytes([self.cmd[1], self.cmd[2]]))
+    }
+
+    /// Command parameter, `0` if it was not sent.
+    fn param(&self, idx: usize) -> u8 {
+        if idx + 1 < self.pos.min(CMD_LEN) {
+            self.cmd[idx + 1]
+        } else {
+            0
+        }
+    }
+
+    fn param_u24(&self, idx: usize) -> u32 {
+        u32::from_be_bytes([0, self.param(idx), self.param(idx + 1), self.param(idx + 2)])
+    }
+
+    /// Execute the command when NSS goes high.
+    fn execute(&mut self) {
+        if self.pos == 0 {
+            return;
+        }
+
+        const STREAMED: [OpCode; 4] = [
+            OpCode::WriteBuffer,
+            OpCode::ReadBuffer,
+            OpCode::WriteRegister,
+            OpCode::ReadRegister,
+        ];
+        const READS: [OpCode; 8] = [
+            OpCode::GetError,
+            OpCode::GetIrqStatus,
+            OpCode::GetPacketStatus,
+            OpCode::GetPacketType,
+            OpCode::GetRssiInst,
+            OpCode::GetRxBufferStatus,
+            OpCode::GetStats,
+            OpCode::GetStatus,
+        ];
+
+        let opcode: u8 = self.cmd[0];
+        let is = |op: OpCode| opcode == op as u8;
+
+        if STREAMED.iter().chain(READS.iter()).any(|op| is(*op)) {
+            // executed while clocking
+        } else if is(OpCode::SetSleep) {
+            self.mode = SimMode::Sleep(if self.param(0) & (1 << 2) == 0 {
+                Startup::Cold
+            } else {
+                Startup::Warm
+            });
+        } else if is(OpCode::SetStandby) {
+            self.mode = if self.param(0) & 0b1 == 0 {
+                SimMode::StandbyRc
+            } else {
+                SimMode::StandbyHse
+            };
+        } else if is(OpCode::SetFs) {
+            self.mode = SimMode::Fs;
+        } else if is(OpCode::SetTx) {
+            let len: u8 = self.payload_len();
+            for n in 0..len {
+                self.tx[usize::from(n)] = self.buf[usize::from(self.tx_base.wrapping_add(n))];
+            }
+            self.tx_len = Some(len);
+            self.mode = SimMode::Tx;
+        } else if is(OpCode::SetTxContinuousWave) || is(OpCode::SetTxContinuousPreamble) {
+            self.tx_len = None;
+            self.mode = SimMode::Tx;
+        } else if is(OpCode::SetRx) {
+            self.rx_continuous = self.param_u24(0) == RX_CONTINUOUS;
+            self.mode = SimMode::Rx;
+        } else if is(OpCode::SetRxDutyCycle) {
+            self.rx_continuous = false;
+            self.mode = SimMode::Rx;
+        } else if is(OpCode::SetCad) {
+            self.mode = SimMode::Cad;
+        } else if is(OpCode::SetPacketType) {
+            self.packet_type = self.param(0);
+        } else if is(OpCode::SetRfFrequency) {
+            self.rf_freq =
+                u32::from_be_bytes([self.param(0), self.param(1), self.param(2), self.param(3)]);
+        } else if is(OpCode::SetModulationParams) {
+            for n in 0..self.mod_params.len() {
+                self.mod_params[n] = self.param(n);
+            }
+        } else if is(OpCode::SetPacketParams) {
+            for n in 0..self.packet_params.len() {
+                self.packet_params[n] = self.param(n);
+            }
+        } else if is(OpCode::SetBufferBaseAddress) {
+            self.tx_base = self.param(0);
+            self.rx_base = self.param(1);
+        } else if is(OpCode::SetTxRxFallbackMode) {
+            self.fallback = self.param(0);
+        } else if is(OpCode::CfgDioIrq) {
+            self.irq_mask = u16::from_be_bytes([self.param(0), self.param(1)]);
+        } else if is(OpCode::ClrIrqStatus) {
+            self.irq_status &= !u16::from_be_bytes([self.param(0), self.param(1)]);
+        } else if is(OpCode::ClrError) {
+            self.op_error = 0;
+        } else if is(OpCode::ResetStats) {
+            self.rx_pkts = 0;
+        } else if is(OpCode::Calibrate)
+            || is(OpCode::CalibrateImage)
+            || is(OpCode::SetCadParams)
+            || is(OpCode::SetLoRaSymbTimeout)
+            || is(OpCode::SetPaConfig)
+            || is(OpCode::SetRegulatorMode)
+            || is(OpCode::SetStopRxTimerOnPreamble)
+            || is(OpCode::SetTcxoMode)
+            || is(OpCode::SetTxParams)
+        {
+            // accepted without simulated effect
+        } else {
+            self.cmd_status = CmdStatus::ProcessingError as u8;
+        }
+    }
+
+    /// Payload length from the packet parameters.
+    fn payload_len(&self) -> u8 {
+        match self.packet_type() {
+            Ok(PacketType::LoRa) => self.packet_params[3],
+            Ok(PacketType::Bpsk) => self.packet_params[0],
+            _ => self.packet_params[6],
+        }
+    }
+
+    /// Modulation and packet parameters.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::subghz::{
+    ///     sim::SimRadio, LoRaModParams, LoRaPacketParams, Modulation, PacketType, SubGhz,
+    /// };
+    ///
+    /// const MOD_PARAMS: LoRaModParams = LoRaModParams::new();
+    /// const PKT_PARAMS: LoRaPacketParams = LoRaPacketParams::new().set_payload_len(12);
+    ///
+    /// let mut sg = SubGhz::new_with_transport(SimRadio::new());
+    /// sg.set_packet_type(PacketType::LoRa)?;
+    /// sg.set_lora_mod_params(&MOD_PARAMS)?;
+    /// sg.set_lora_packet_params(&PKT_PARAMS)?;
+    /// assert_eq!(
+    ///     sg.transport().modulation(),
+    ///     Some(Modulation::LoRa(MOD_PARAMS, PKT_PARAMS))
+    /// );
+    /// # Ok::<(), stm32wlxx_hal::subghz::Error>(())
+    /// ```
+    pub fn modulation(&self) -> Option<Modulation> {
+        match self.packet_type() {
+            Ok(PacketType::LoRa) => Some(Modulation::LoRa(
+                LoRaModParams::from_params(&self.mod_params),
+                LoRaPacketParams::from_params(&self.packet_params),
+            )),
+            Ok(PacketType::Fsk) | Ok(PacketType::Msk) => Some(Modulation::Fsk(
+                FskModParams::from_params(&self.mod_params),
+                GenericPacketParams::from_params(&self.packet_params),
+            )),
+            Ok(PacketType::Bpsk) => Some(Modulation::Bpsk(
+                BpskModParams::from_params(&self.mod_params),
+                BpskPacketParams::from_params(&self.packet_params),
+            )),
+            Err(_) => None,
+        }
+    }
+
+    /// Channel settings that must match for two radios to communicate.
+    fn channel(&self) -> Channel {
+        let mut sync_word: [u8; 8] = [0; 8];
+        match self.packet_type() {
+            Ok(PacketType::LoRa) => {
+                sync_word[..2].copy_from_slice(&self.regs[LSYNC_ADDR..LSYNC_ADDR + 2])
+            }
+            Ok(PacketType::Fsk) | Ok(PacketType::Msk) => {
+                sync_word.copy_from_slice(&self.regs[GSYNC_ADDR..GSYNC_ADDR + 8])
+            }
+            _ => (),
+        }
+        Channel {
+            rf_freq: self.rf_freq,
+            packet_type: self.packet_type,
+            mod_params: self.mod_params,
+            sync_word,
+        }
+    }
+}
+
+impl Default for SimRadio {
+    fn default() -> Self {
+        SimRadio::new()
+    }
+}
+
+/// Convert dBm to the RSSI register format.
+fn rssi_raw(rssi: i16) -> u8 {
+    rssi.saturating_mul(-2).clamp(0, 255) as u8
+}
+
+impl Transport for SimRadio {
+    fn wait_not_busy(&mut self) {}
+
+    fn select(&mut self) {
+        self.wakeup();
+        self.selected = true;
+        self.pos = 0;
+    }
+
+    fn deselect(&mut self) {
+        if self.selected {
+            self.execute();
+        }
+        self.selected = false;
+        self.pos = 0;
+        if let Some(responder) = self.responder {
+            responder(self);
+        }
+    }
+
+    fn write_bytes(&mut self, data: &[u8]) -> Result<(), Error> {
+        for byte in data {
+            self.clock(*byte);
+        }
+        Ok(())
+    }
+
+    fn transfer_bytes(&mut self, data: &mut [u8]) -> Result<(), Error> {
+        for byte in data.iter_mut() {
+            *byte = self.clock(*byte);
+        }
+        Ok(())
+    }
+}
+
+/// Channel settings that must match for two radios to communicate.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+struct Channel {
+    rf_freq: u32,
+    packet_type: u8,
+    mod_params: [u8; 8],
+    sync_word: [u8; 8],
+}
+
+/// Radio link from a transmitter to a receiver on an [`Ether`].
+///
+/// # Example
+///
+/// ```
+/// use stm32wlxx_hal::subghz::sim::Link;
+///
+/// const LINK: Link = Link::new().set_rssi(-110).set_snr(-5).set_loss(10);
+/// assert_eq!(LINK.rssi(), -110);
+/// assert_eq!(LINK.snr(), -5);
+/// assert_eq!(LINK.loss(), 10);
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Link {
+    rssi: i16,
+    snr: i8,
+    loss: u8,
+}
+
+impl Link {
+    /// Create a new `Link` with a RSSI of -60 dBm, a SNR of 10 dB, and no
+    /// packet loss.
+    ///
+    /// This is the same as `default`, but in a `const` function.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::subghz::sim::Link;
+    ///
+    /// const LINK: Link = Link::new();
+    /// assert_eq!(LINK, Link::default());
+    /// ```
+    pub const fn new() -> Link {
+        Link {
+            rssi: -60,
+            snr: 10,
+            loss: 0,
+        }
+    }
+
+    /// Set the RSSI of received packets in dBm.
+    #[must_use = "set_rssi returns a modified Link"]
+    pub const fn set_rssi(mut self, rssi: i16) -> Link {
+        self.rssi = rssi;
+        self
+    }
+
+    /// RSSI of received packets in dBm.
+    pub const fn rssi(&self) -> i16 {
+        self.rssi
+    }
+
+    /// Set the SNR of received packets in dB.
+    #[must_use = "set_snr returns a modified Link"]
+    pub const fn set_snr(mut self, snr: i8) -> Link {
+        self.snr = snr;
+        self
+    }
+
+    /// SNR of received packets in dB.
+    pub const fn snr(&self) -> i8 {
+        self.snr
+    }
+
+    /// Set the packet loss in percent.
+    ///
+    /// Values above 100 are saturated to 100.
+    #[must_use = "set_loss returns a modified Link"]
+    pub const fn set_loss(mut self, loss: u8) -> Link {
+        self.loss = if loss > 100 { 100 } else { loss };
+        self
+    }
+
+    /// Packet loss in percent.
+    pub const fn loss(&self) -> u8 {
+        self.loss
+    }
+}
+
+impl Default for Link {
+    fn default() -> Self {
+        Link::new()
+    }
+}
+
+/// Transmission on an [`Ether`].
+#[derive(Debug, Clone, Copy)]
+struct OnAir<const N: usize> {
+    end: Duration,
+    channel: Channel,
+    /// Receivers that were listening when the transmission started and have
+    /// not had a collision since.
+    listeners: [bool; N],
+}
+
+/// Simulated medium shared by `N` simulated radios.
+///
+/// A packet transmitted by one radio is received by the other radios if the
+/// RF frequency, packet type, modulation parameters, and sync word of both
+/// radios match.
+///
+/// Time is passed in by the test with [`poll`](Self::poll).
+/// A transmission is on air for the [time on air](Modulation::time_on_air)
+/// of the packet.
+/// A packet is received if the receiver was in RX mode for the entire
+/// transmission, and no other transmission on the same channel overlapped.
+///
+/// The radio links are configured with [`Link`] for packet loss, RSSI, and
+/// SNR.
+/// Packet loss uses a pseudo-random number generator with a fixed seed,
+/// so tests are repeatable.
+///
+/// RX timeouts are not simulated, use [`SimRadio::timeout`].
+///
+/// # Example
+///
+/// ```
+/// use core::time::Duration;
+/// use stm32wlxx_hal::subghz::{
+///     sim::{Ether, SimRadio},
+///     CfgIrq, Irq, LoRaPacketParams, PacketType, RfFreq, SubGhz, Timeout,
+/// };
+///
+/// let mut ether: Ether<2> = Ether::new();
+/// let mut a = SubGhz::new_with_transport(SimRadio::new());
+/// let mut b = SubGhz::new_with_transport(SimRadio::new());
+///
+/// for sg in [&mut a, &mut b] {
+///     sg.set_packet_type(PacketType::LoRa)?;
+///     sg.set_rf_frequency(&RfFreq::F915)?;
+///     sg.set_lora_packet_params(&LoRaPacketParams::new().set_payload_len(4))?;
+///     sg.set_irq_cfg(
+///         &CfgIrq::new()
+///             .irq_enable_all(Irq::TxDone)
+///             .irq_enable_all(Irq::RxDone),
+///     )?;
+/// }
+///
+/// b.set_rx(Timeout::DISABLED)?;
+/// a.write_buffer(0, b"ping")?;
+/// a.set_tx(Timeout::DISABLED)?;
+///
+/// let mut now: Duration = Duration::ZERO;
+/// while let Some(next) = ether.poll(now, &mut [a.transport_mut(), b.transport_mut()]) {
+///     now = next;
+/// }
+///
+/// assert_eq!(a.irq_status()?.1, Irq::TxDone.mask());
+/// assert_eq!(b.irq_status()?.1, Irq::RxDone.mask());
+/// let mut buf: [u8; 4] = [0; 4];
+/// b.read_buffer(0, &mut buf)?;
+/// assert_eq!(&buf, b"ping");
+/// # Ok::<(), stm32wlxx_hal::subghz::Error>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct Ether<const N: usize> {
+    links: [[Link; N]; N],
+    on_air: [Option<OnAir<N>>; N],
+    rng: u32,
+}
+
+impl<const N: usize> Ether<N> {
+    /// Create a new medium with [`Link::new`] between all radios.
+    pub const fn new() -> Ether<N> {
+        Ether {
+            links: [[Link::new(); N]; N],
+            on_air: [None; N],
+            rng: 0x2545_F491,
+        }
+    }
+
+    /// Set the seed of the pseudo-random number generator for packet loss.
+    pub fn set_seed(&mut self, seed: u32) {
+        // xorshift gets stuck at zero
+        self.rng = if seed == 0 { 1 } else { seed };
+    }
+
+    /// Set the link from the radio with index `tx` to the radio with index
+    /// `rx`.
+    ///
+    /// # Panics
+    ///
+    /// * `tx` or `rx` is not less than `N`.
+    pub fn set_link(&mut self, tx: usize, rx: usize, link: Link) {
+        self.links[tx][rx] = link;
+    }
+
+    /// Set the link between all radios.
+    pub fn set_links(&mut self, link: Link) {
+        self.links = [[link; N]; N];
+    }
+
+    /// Link from the radio with index `tx` to the radio with index `rx`.
+    ///
+    /// # Panics
+    ///
+    /// * `tx` or `rx` is not less than `N`.
+    pub fn link(&self, tx: usize, rx: usize) -> Link {
+        self.links[tx][rx]
+    }
+
+    /// Returns `true` if a transmission is on air.
+    pub fn is_busy(&self) -> bool {
+        self.on_air.iter().any(Option::is_some)
+    }
+
+    fn lost(&mut self, link: Link) -> bool {
+        // xorshift32
+        let mut x: u32 = self.rng;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.rng = x;
+        x % 100 < u32::from(link.loss)
+    }
+
+    /// Advance the medium to `now`.
+    ///
+    /// This completes transmissions that ended before `now`, starts new
+    /// transmissions, and completes channel activity detection.
+    ///
+    /// The radios must be passed in the same order on every call, the index
+    /// of a radio in `radios` is the index used for [`set_link`](Self::set_link).
+    ///
+    /// Returns the time at which the next transmission ends, `None` if no
+    /// transmissions are on air.
+    pub fn poll(&mut self, now: Duration, radios: &mut [&mut SimRadio; N]) -> Option<Duration> {
+        for tx in 0..N {
+            let on_air: OnAir<N> = match self.on_air[tx] {
+                Some(on_air) if on_air.end <= now => on_air,
+                _ => continue,
+            };
+            self.on_air[tx] = None;
+
+            let mut buf: [u8; 256] = [0; 256];
+            let len: usize = match radios[tx].tx_packet() {
+                Some(packet) => {
+                    buf[..packet.len()].copy_from_slice(packet);
+                    packet.len()
+                }
+                // transmission aborted
+                None => continue,
+            };
+            radios[tx].tx_done();
+
+            for (rx, radio) in radios.iter_mut().enumerate() {
+                if on_air.listeners[rx]
+                    && radio.mode() == SimMode::Rx
+                    && radio.channel() == on_air.channel
+                {
+                    let link: Link = self.links[tx][rx];
+                    if !self.lost(link) {
+                        radio.rx_done(&buf[..len], link.rssi, link.snr);
+                    }
+                }
+            }
+        }
+
+        for tx in 0..N {
+            if self.on_air[tx].is_some() || radios[tx].tx_packet().is_none() {
+                continue;
+            }
+            let time_on_air: Duration = radios[tx]
+                .modulation()
+                .map(|modulation| modulation.time_on_air())
+                .unwrap_or(Duration::ZERO);
+            let channel: Channel = radios[tx].channel();
+
+            let mut listeners: [bool; N] = [false; N];
+            for (rx, listener) in listeners.iter_mut().enumerate() {
+                *listener =
+                    rx != tx && radios[rx].mode() == SimMode::Rx && radios[rx].channel() == channel;
+            }
+            for other in self.on_air.iter_mut().flatten() {
+                if other.channel == channel {
+                    for (a, b) in other.listeners.iter_mut().zip(listeners.iter_mut()) {
+                        if *a && *b {
+                            *a = false;
+                            *b = false;
+                        }
+                    }
+                }
+            }
+
+            self.on_air[tx] = Some(OnAir {
+                end: now.saturating_add(time_on_air),
+                channel,
+                listeners,
+            });
+        }
+
+        for radio in radios.iter_mut() {
+            if radio.mode() == SimMode::Cad {
+                let channel: Channel = radio.channel();
+                let detected: bool = self
+                    .on_air
+                    .iter()
+                    .flatten()
+                    .any(|on_air| on_air.channel == channel);
+                radio.cad_done(detected);
+            }
+        }
+
+        self.on_air.iter().flatten().map(|on_air| on_air.end).min()
+    }
+}
+
+impl<const N: usize> Default for Ether<N> {
+    fn default() -> Self {
+        Ether::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Ether, Link, SimMode, SimRadio};
+    use crate::spi::{SgMiso, SgMosi};
+    use crate::subghz::{
+        BitSync, CfgIrq, CmdStatus, FallbackMode, GenericPacketParams, HseTrim, Irq, LoRaModParams,
+        LoRaPacketParams, LoRaSyncWord, Ocp, OpError, PMode, PacketType, PktCtrl, RfFreq, RtcCtrl,
+        SleepCfg, SleepingSubGhz, SmpsDrv, StandbyClk, Startup, StatusMode, SubGhz, Timeout, Woken,
+    };
+    #[cfg(feature = "subghz-snapshot")]
+    use crate::subghz::{FskModParams, Modulation, PaConfig, RegMode, Snapshot, TxParams};
+    use core::time::Duration;
+
+    type SimSubGhz = SubGhz<SgMiso, SgMosi, SimRadio>;
+
+    fn new_sg() -> SimSubGhz {
+        let mut sg: SimSubGhz = SubGhz::new_with_transport(SimRadio::new());
+        sg.set_irq_cfg(
+            &CfgIrq::new()
+                .irq_enable_all(Irq::TxDone)
+                .irq_enable_all(Irq::RxDone)
+                .irq_enable_all(Irq::Timeout)
+                .irq_enable_all(Irq::CadDone)
+                .irq_enable_all(Irq::CadDetected),
+        )
+        .unwrap();
+        sg
+    }
+
+    #[test]
+    fn config_decoding() {
+        const MOD_PARAMS: LoRaModParams = LoRaModParams::new();
+        const PKT_PARAMS: LoRaPacketParams = LoRaPacketParams::new().set_payload_len(12);
+
+        let mut sg: SimSubGhz = new_sg();
+        sg.set_packet_type(PacketType::LoRa).unwrap();
+        sg.set_rf_frequency(&RfFreq::F868).unwrap();
+        sg.set_lora_mod_params(&MOD_PARAMS).unwrap();
+        sg.set_lora_packet_params(&PKT_PARAMS).unwrap();
+
+        assert_eq!(sg.packet_type().unwrap(), Ok(PacketType::LoRa));
+        let sim: &SimRadio = sg.transport();
+        assert_eq!(sim.packet_type(), Ok(PacketType::LoRa));
+        assert_eq!(sim.rf_freq(), RfFreq::F868);
+        assert_eq!(&sim.mod_params()[..4], &MOD_PARAMS.as_slice()[1..]);
+        assert_eq!(&sim.packet_params()[..6], &PKT_PARAMS.as_slice()[1..]);
+        assert_eq!(
+            sim.irq_mask(),
+            Irq::TxDone.mask()
+                | Irq::RxDone.mask()
+                | Irq::Timeout.mask()
+                | Irq::CadDone.mask()
+                | Irq::CadDetected.mask()
+        );
+    }
+
+    #[test]
+    fn registers() {
+        let mut sg: SimSubGhz = new_sg();
+        assert_eq!(sg.transport().register(0x0740), 0x14);
+        sg.set_lora_sync_word(LoRaSyncWord::Public).unwrap();
+        assert_eq!(
+            [
+                sg.transport().register(0x0740),
+                sg.transport().register(0x0741)
+            ],
+            LoRaSyncWord::Public.bytes()
+        );
+
+        let pkt_ctrl: PktCtrl = PktCtrl::RESET.set_sync_det_en(false);
+        sg.set_pkt_ctrl(pkt_ctrl).unwrap();
+        assert_eq!(sg.pkt_ctrl().unwrap(), pkt_ctrl);
+        sg.set_init_whitening(0xA5).unwrap();
+        assert_eq!(sg.init_whitening().unwrap(), 0xA5);
+    }
+
+    #[test]
+    fn typed_registers() {
+        let mut sg: SimSubGhz = new_sg();
+        assert_eq!(sg.lora_sync_word().unwrap(), LoRaSyncWord::Private);
+        sg.set_lora_sync_word(LoRaSyncWord::Custom([0x12, 0x34]))
+            .unwrap();
+        assert_eq!(
+            sg.lora_sync_word().unwrap(),
+            LoRaSyncWord::Custom([0x12, 0x34])
+        );
+
+        assert_eq!(sg.rx_gain().unwrap(), Err(0));
+        sg.set_rx_gain(PMode::Boost).unwrap();
+        assert_eq!(sg.rx_gain().unwrap(), Ok(PMode::Boost));
+        sg.set_pa_ocp(Ocp::Max140m).unwrap();
+        assert_eq!(sg.pa_ocp().unwrap(), Ok(Ocp::Max140m));
+
+        sg.set_rtc_ctrl(RtcCtrl::RESET.set_en(true)).unwrap();
+        assert!(sg.rtc_ctrl().unwrap().en());
+        sg.set_rtc_period(Timeout::from_raw(0x123456)).unwrap();
+        assert_eq!(sg.rtc_period().unwrap(), Timeout::from_raw(0x123456));
+
+        sg.set_generic_pkt_len(200).unwrap();
+        assert_eq!(sg.generic_pkt_len().unwrap(), 200);
+        sg.set_crc_polynomial(0x1D0F).unwrap();
+        sg.set_initial_crc_polynomial(0x1021).unwrap();
+        assert_eq!(sg.crc_init().unwrap(), 0x1D0F);
+        assert_eq!(sg.crc_polynomial().unwrap(), 0x1021);
+        sg.set_sync_word(&[1, 2, 3, 4, 5, 6, 7, 8]).unwrap();
+        assert_eq!(sg.sync_word().unwrap(), [1, 2, 3, 4, 5, 6, 7, 8]);
+        sg.set_addrs(0x11, 0xFF).unwrap();
+        assert_eq!(sg.node_addr().unwrap(), 0x11);
+        assert_eq!(sg.broadcast_addr().unwrap(), 0xFF);
+
+        sg.set_hse_in_trim(HseTrim::MAX).unwrap();
+        sg.set_hse_out_trim(HseTrim::MIN).unwrap();
+        assert_eq!(sg.hse_in_trim().unwrap(), HseTrim::MAX);
+        assert_eq!(sg.hse_out_trim().unwrap(), HseTrim::MIN);
+        sg.set_smps_clock_det_en(true).unwrap();
+        assert!(sg.smps_clock_det_en().unwrap());
+        sg.set_smps_drv(SmpsDrv::Milli40).unwrap();
+        assert_eq!(sg.smps_drv().unwrap(), SmpsDrv::Milli40);
+        let bit_sync: BitSync = BitSync::RESET.set_simple_bit_sync_en(true);
+        sg.set_bit_sync(bit_sync).unwrap();
+        assert_eq!(sg.bit_sync().unwrap(), bit_sync);
+
+        let mut buf: [u8; 2] = [0; 2];
+        unsafe { sg.write_register_raw(0x0589, &[0xAB, 0xCD]) }.unwrap();
+        unsafe { sg.read_register_raw(0x0589, &mut buf) }.unwrap();
+        assert_eq!(buf, [0xAB, 0xCD]);
+    }
+
+    #[test]
+    fn tx() {
+        let mut sg: SimSubGhz = new_sg();
+        sg.set_packet_type(PacketType::Fsk).unwrap();
+        sg.set_packet_params(&GenericPacketParams::new().set_payload_len(5))
+            .unwrap();
+        sg.set_buffer_base_address(250, 0).unwrap();
+        sg.write_buffer(250, b"hello").unwrap();
+
+        let mut buf: [u8; 5] = [0; 5];
+        sg.read_buffer(250, &mut buf).unwrap();
+        assert_eq!(&buf, b"hello");
+
+        assert_eq!(sg.transport().tx_packet(), None);
+        sg.set_tx(Timeout::DISABLED).unwrap();
+        let (status, irq_status) = sg.irq_status().unwrap();
+        assert_eq!(status.mode(), Ok(StatusMode::Tx));
+        assert_eq!(irq_status, 0);
+
+        let sim: &mut SimRadio = sg.transport_mut();
+        assert_eq!(sim.tx_packet(), Some(&b"hello"[..]));
+        assert!(sim.tx_done());
+        assert!(!sim.tx_done());
+        assert_eq!(sim.mode(), SimMode::StandbyRc);
+
+        let (status, irq_status) = sg.irq_status().unwrap();
+        assert_eq!(status.mode(), Ok(StatusMode::StandbyRc));
+        assert_eq!(status.cmd(), Ok(CmdStatus::Complete));
+        assert_eq!(irq_status, Irq::TxDone.mask());
+        sg.clear_irq_status(irq_status).unwrap();
+        assert_eq!(sg.irq_status().unwrap().1, 0);
+    }
+
+    #[test]
+    fn rx() {
+        let mut sg: SimSubGhz = new_sg();
+        sg.set_packet_type(PacketType::LoRa).unwrap();
+        sg.set_buffer_base_address(0, 128).unwrap();
+        sg.set_tx_rx_fallback_mode(FallbackMode::StandbyHse)
+            .unwrap();
+
+        assert!(!sg.transport_mut().rx_done(b"early", -80, 5));
+        sg.set_rx(Timeout::DISABLED).unwrap();
+        assert!(sg.transport_mut().rx_done(b"world", -80, 5));
+        assert_eq!(sg.transport().mode(), SimMode::StandbyHse);
+
+        let (status, irq_status) = sg.irq_status().unwrap();
+        assert_eq!(status.cmd(), Ok(CmdStatus::Available));
+        assert_eq!(irq_status, Irq::RxDone.mask());
+
+        let (_, len, ptr) = sg.rx_buffer_status().unwrap();
+        assert_eq!((len, ptr), (5, 128));
+        let mut buf: [u8; 5] = [0; 5];
+        sg.read_buffer(ptr, &mut buf).unwrap();
+        assert_eq!(&buf, b"world");
+
+        let pkt_status = sg.lora_packet_status().unwrap();
+        assert_eq!(pkt_status.rssi_pkt().to_integer(), -80);
+        assert_eq!(pkt_status.snr_pkt().to_integer(), 5);
+
+        // continuous RX stays in RX mode
+        sg.set_rx(Timeout::MAX).unwrap();
+        assert!(sg.transport_mut().rx_done(b"again", -90, 0));
+        assert_eq!(sg.transport().mode(), SimMode::Rx);
+        assert!(sg.transport_mut().timeout());
+        assert_eq!(
+            sg.irq_status().unwrap().1 & Irq::Timeout.mask(),
+            Irq::Timeout.mask()
+        );
+        assert_eq!(sg.lora_stats().unwrap().pkt_rx(), 2);
+    }
+
+    #[test]
+    fn cad_and_rssi() {
+        let mut sg: SimSubGhz = new_sg();
+        sg.transport_mut().set_rssi_inst(-100);
+        assert_eq!(sg.rssi_inst().unwrap().1.to_integer(), -100);
+
+        sg.set_cad().unwrap();
+        assert!(sg.transport_mut().cad_done(true));
+        assert_eq!(
+            sg.irq_status().unwrap().1,
+            Irq::CadDone.mask() | Irq::CadDetected.mask()
+        );
+        assert_eq!(sg.transport().mode(), SimMode::StandbyRc);
+    }
+
+    #[test]
+    fn sleep() {
+        let mut sg: SimSubGhz = new_sg();
+        sg.set_packet_type(PacketType::LoRa).unwrap();
+        sg.set_standby(StandbyClk::Hse).unwrap();
+
+        unsafe { sg.set_sleep(SleepCfg::new().set_startup(Startup::Warm)) }.unwrap();
+        assert_eq!(sg.transport().mode(), SimMode::Sleep(Startup::Warm));
+        // the next command wakes the radio
+        assert_eq!(sg.packet_type().unwrap(), Ok(PacketType::LoRa));
+        assert_eq!(sg.transport().mode(), SimMode::StandbyRc);
+
+        unsafe { sg.set_sleep(SleepCfg::new().set_startup(Startup::Cold)) }.unwrap();
+        assert_eq!(sg.packet_type().unwrap(), Ok(PacketType::Fsk));
+        assert_eq!(sg.transport().irq_mask(), 0);
+    }
+
+    #[test]
+    #[cfg(feature = "subghz-snapshot")]
+    fn snapshot_restore() {
+        const MOD_PARAMS: LoRaModParams = LoRaModParams::new();
+        const PKT_PARAMS: LoRaPacketParams = LoRaPacketParams::new().set_payload_len(12);
+        const PA_CONFIG: PaConfig = PaConfig::HP_14;
+        const TX_PARAMS: TxParams = TxParams::new().set_power(0x0E);
+
+        let mut sg: SimSubGhz = SubGhz::new_with_transport(SimRadio::new());
+        assert_eq!(sg.snapshot(), Snapshot::new());
+
+        let irq_cfg: CfgIrq = CfgIrq::new().irq_enable_all(Irq::RxDone);
+        sg.set_packet_type(PacketType::LoRa).unwrap();
+        sg.set_rf_frequency(&RfFreq::F868).unwrap();
+        sg.set_lora_mod_params(&MOD_PARAMS).unwrap();
+        sg.set_lora_packet_params(&PKT_PARAMS).unwrap();
+        sg.set_fsk_mod_params(&FskModParams::new()).unwrap();
+        sg.set_lora_mod_params(&MOD_PARAMS).unwrap();
+        sg.set_pa_config(&PA_CONFIG).unwrap();
+        sg.set_tx_params(&TX_PARAMS).unwrap();
+        sg.set_irq_cfg(&irq_cfg).unwrap();
+
+        let snapshot: Snapshot = sg.snapshot();
+        assert_eq!(snapshot.packet_type(), Some(PacketType::LoRa));
+        assert_eq!(snapshot.rf_freq(), Some(RfFreq::F868));
+        assert_eq!(snapshot.pa_config(), Some(PA_CONFIG));
+        assert_eq!(snapshot.tx_params(), Some(TX_PARAMS));
+        assert_eq!(snapshot.irq_cfg(), Some(irq_cfg));
+        assert_eq!(snapshot.tcxo_mode(), None);
+        assert_eq!(
+            snapshot.modulation(),
+            Some(Modulation::LoRa(MOD_PARAMS, PKT_PARAMS))
+        );
+        let before: SimRadio = sg.transport().clone();
+
+        unsafe { sg.set_sleep(SleepCfg::new().set_startup(Startup::Cold)) }.unwrap();
+        assert_eq!(sg.packet_type().unwrap(), Ok(PacketType::Fsk));
+        sg.restore(&snapshot).unwrap();
+        assert_eq!(sg.snapshot(), snapshot);
+
+        let sim: &SimRadio = sg.transport();
+        assert_eq!(sim.packet_type(), before.packet_type());
+        assert_eq!(sim.rf_freq(), before.rf_freq());
+        assert_eq!(sim.mod_params(), before.mod_params());
+        assert_eq!(sim.packet_params(), before.packet_params());
+        assert_eq!(sim.irq_mask(), before.irq_mask());
+    }
+
+    struct NoDelay;
+
+    impl embedded_hal::blocking::delay::DelayUs<u32> for NoDelay {
+        fn delay_us(&mut self, _us: u32) {}
+    }
+
+    #[test]
+    fn sleep_wake() {
+        let mut sg: SimSubGhz = new_sg();
+        sg.set_packet_type(PacketType::LoRa).unwrap();
+
+        // warm start retains the configuration
+        let sleeping: SleepingSubGhz<SgMiso, SgMosi, SimRadio> = sg
+            .sleep(SleepCfg::new().set_startup(Startup::Warm), &mut NoDelay)
+            .unwrap();
+        assert_eq!(sleeping.cfg().startup(), Startup::Warm);
+        let woken: Woken<SgMiso, SgMosi, SimRadio> = sleeping.wake().unwrap();
+        assert!(!woken.needs_reconfig());
+        let mut sg: SimSubGhz = woken.into_inner();
+        assert_eq!(sg.transport().mode(), SimMode::StandbyRc);
+        assert_eq!(sg.packet_type().unwrap(), Ok(PacketType::LoRa));
+
+        // cold start loses the configuration
+        let sleeping: SleepingSubGhz<SgMiso, SgMosi, SimRadio> = sg
+            .sleep(SleepCfg::new().set_startup(Startup::Cold), &mut NoDelay)
+            .unwrap();
+        assert_eq!(sleeping.cfg().startup(), Startup::Cold);
+        let woken: Woken<SgMiso, SgMosi, SimRadio> = sleeping.wake().unwrap();
+        #[cfg(not(feature = "subghz-snapshot"))]
+        let mut sg: SimSubGhz = match woken {
+            Woken::NeedsReconfig(sg) => sg,
+            Woken::Ready(_) => panic!("cold start without a snapshot is not ready"),
+        };
+        #[cfg(feature = "subghz-snapshot")]
+        let mut sg: SimSubGhz = match woken {
+            Woken::Ready(sg) => sg,
+            Woken::NeedsReconfig(_) => panic!("cold start with a snapshot is ready"),
+        };
+        assert_eq!(sg.transport().mode(), SimMode::StandbyRc);
+        #[cfg(not(feature = "subghz-snapshot"))]
+        assert_eq!(sg.packet_type().unwrap(), Ok(PacketType::Fsk));
+        #[cfg(feature = "subghz-snapshot")]
+        assert_eq!(sg.packet_type().unwrap(), Ok(PacketType::LoRa));
+    }
+
+    #[test]
+    #[cfg(feature = "subghz-snapshot")]
+    fn sleep_wake_restore() {
+        let mut sg: SimSubGhz = new_sg();
+        sg.set_regulator_mode(RegMode::Smps).unwrap();
+        sg.set_packet_type(PacketType::LoRa).unwrap();
+        sg.set_rf_frequency(&RfFreq::F915).unwrap();
+        let snapshot: Snapshot = sg.snapshot();
+        let irq_mask: u16 = sg.transport().irq_mask();
+
+        // warm start retains the configuration
+        let sleeping: SleepingSubGhz<SgMiso, SgMosi, SimRadio> = sg
+            .sleep(SleepCfg::new().set_startup(Startup::Warm), &mut NoDelay)
+            .unwrap();
+        let sg: SimSubGhz = sleeping.wake().unwrap().into_inner();
+        assert_eq!(sg.snapshot(), snapshot);
+
+        // cold start restores the configuration
+        let sleeping: SleepingSubGhz<SgMiso, SgMosi, SimRadio> = sg
+            .sleep(SleepCfg::new().set_startup(Startup::Cold), &mut NoDelay)
+            .unwrap();
+        assert_eq!(sleeping.snapshot(), snapshot);
+        let woken: Woken<SgMiso, SgMosi, SimRadio> = sleeping.wake().unwrap();
+        assert!(!woken.needs_reconfig());
+        let mut sg: SimSubGhz = woken.into_inner();
+        assert_eq!(sg.transport().mode(), SimMode::StandbyRc);
+        assert_eq!(sg.snapshot(), snapshot);
+        assert_eq!(sg.packet_type().unwrap(), Ok(PacketType::LoRa));
+        assert_eq!(sg.transport().rf_freq(), RfFreq::F915);
+        assert_eq!(sg.transport().irq_mask(), irq_mask);
+    }
+
+    #[test]
+    fn calibration_errors() {
+        let mut sg: SimSubGhz = new_sg();
+        assert_eq!(sg.calibrate_for(&RfFreq::F915), Ok(()));
+
+        sg.transport_mut()
+            .set_op_error(OpError::PllCalibrationError.mask());
+        // errors from before the calibration are cleared
+        assert_eq!(sg.calibrate_for(&RfFreq::F915), Ok(()));
+        assert_eq!(sg.op_error().unwrap().1, 0);
+    }
+
+    #[test]
+    fn unknown_opcode() {
+        use crate::subghz::Transport;
+
+        let mut sim: SimRadio = SimRadio::new();
+        sim.select();
+        sim.write_bytes(&[0xFF, 0x00]).unwrap();
+        sim.deselect();
+        assert_eq!(sim.status().cmd(), Ok(CmdStatus::ProcessingError));
+    }
+
+    fn lora_pair() -> (SimSubGhz, SimSubGhz) {
+        let mut a: SimSubGhz = new_sg();
+        let mut b: SimSubGhz = new_sg();
+        for sg in [&mut a, &mut b] {
+            sg.set_packet_type(PacketType::LoRa).unwrap();
+            sg.set_rf_frequency(&RfFreq::F868).unwrap();
+            sg.set_lora_mod_params(&LoRaModParams::new()).unwrap();
+            sg.set_lora_packet_params(&LoRaPacketParams::new().set_payload_len(3))
+                .unwrap();
+            sg.write_buffer(0, b"abc").unwrap();
+        }
+        (a, b)
+    }
+
+    #[test]
+    fn ether_airtime() {
+        let mut ether: Ether<2> = Ether::new();
+        ether.set_links(Link::new().set_rssi(-100).set_snr(7));
+        let (mut a, mut b) = lora_pair();
+        let time_on_air: Duration = a.transport().modulation().unwrap().time_on_air();
+
+        b.set_rx(Timeout::DISABLED).unwrap();
+        a.set_tx(Timeout::DISABLED).unwrap();
+        let t0: Duration = Duration::from_millis(5);
+        let end: Option<Duration> = ether.poll(t0, &mut [a.transport_mut(), b.transport_mut()]);
+        assert_eq!(end, Some(t0 + time_on_air));
+        assert!(ether.is_busy());
+
+        // not received before the end of the transmission
+        let before: Duration = t0 + time_on_air - Duration::from_micros(1);
+        assert_eq!(
+            ether.poll(before, &mut [a.transport_mut(), b.transport_mut()]),
+            end
+        );
+        assert_eq!(b.irq_status().unwrap().1, 0);
+
+        assert_eq!(
+            ether.poll(end.unwrap(), &mut [a.transport_mut(), b.transport_mut()]),
+            None
+        );
+        assert!(!ether.is_busy());
+        assert_eq!(a.irq_status().unwrap().1, Irq::TxDone.mask());
+        assert_eq!(b.irq_status().unwrap().1, Irq::RxDone.mask());
+        let pkt_status = b.lora_packet_status().unwrap();
+        assert_eq!(pkt_status.rssi_pkt().to_integer(), -100);
+        assert_eq!(pkt_status.snr_pkt().to_integer(), 7);
+        let mut buf: [u8; 3] = [0; 3];
+        b.read_buffer(0, &mut buf).unwrap();
+        assert_eq!(&buf, b"abc");
+    }
+
+    #[test]
+    fn ether_channel_mismatch() {
+        let mut ether: Ether<3> = Ether::new();
+        let (mut a, mut b) = lora_pair();
+        let (_, mut c) = lora_pair();
+        b.set_rf_frequency(&RfFreq::F915).unwrap();
+        c.set_lora_sync_word(LoRaSyncWord::Public).unwrap();
+
+        b.set_rx(Timeout::DISABLED).unwrap();
+        c.set_rx(Timeout::DISABLED).unwrap();
+        a.set_tx(Timeout::DISABLED).unwrap();
+        let mut now: Duration = Duration::ZERO;
+        while let Some(next) = ether.poll(
+            now,
+            &mut [a.transport_mut(), b.transport_mut(), c.transport_mut()],
+        ) {
+            now = next;
+        }
+        assert_eq!(a.irq_status().unwrap().1, Irq::TxDone.mask());
+        assert_eq!(b.irq_status().unwrap().1, 0);
+        assert_eq!(c.irq_status().unwrap().1, 0);
+    }
+
+    #[test]
+    fn ether_loss_and_collision() {
+        let mut ether: Ether<3> = Ether::new();
+        ether.set_link(0, 1, Link::new().set_loss(100));
+        let (mut a, mut b) = lora_pair();
+        let (mut c, _) = lora_pair();
+
+        b.set_rx(Timeout::DISABLED).unwrap();
+        a.set_tx(Timeout::DISABLED).unwrap();
+        let end: Duration = ether
+            .poll(
+                Duration::ZERO,
+                &mut [a.transport_mut(), b.transport_mut(), c.transport_mut()],
+            )
+            .unwrap();
+        ether.poll(
+            end,
+            &mut [a.transport_mut(), b.transport_mut(), c.transport_mut()],
+        );
+        assert_eq!(b.irq_status().unwrap().1, 0);
+        assert_eq!(b.transport().mode(), SimMode::Rx);
+
+        // overlapping transmissions from A and C
+        ether.set_links(Link::new());
+        a.set_tx(Timeout::DISABLED).unwrap();
+        ether.poll(
+            Duration::ZERO,
+            &mut [a.transport_mut(), b.transport_mut(), c.transport_mut()],
+        );
+        c.set_tx(Timeout::DISABLED).unwrap();
+        let mut now: Duration = Duration::from_millis(1);
+        while let Some(next) = ether.poll(
+            now,
+            &mut [a.transport_mut(), b.transport_mut(), c.transport_mut()],
+        ) {
+            now = next;
+        }
+        assert_eq!(a.irq_status().unwrap().1, Irq::TxDone.mask());
+        assert_eq!(c.irq_status().unwrap().1, Irq::TxDone.mask());
+        assert_eq!(b.irq_status().unwrap().1, 0);
+    }
+
+    #[test]
+    fn ether_cad() {
+        let mut ether: Ether<2> = Ether::new();
+        let (mut a, mut b) = lora_pair();
+
+        b.set_cad().unwrap();
+        ether.poll(Duration::ZERO, &mut [a.transport_mut(), b.transport_mut()]);
+        assert_eq!(b.irq_status().unwrap().1, Irq::CadDone.mask());
+        b.clear_irq_status(Irq::CadDone.mask()).unwrap();
+
+        a.set_tx(Timeout::DISABLED).unwrap();
+        ether.poll(Duration::ZERO, &mut [a.transport_mut(), b.transport_mut()]);
+        b.set_cad().unwrap();
+        ether.poll(Duration::ZERO, &mut [a.transport_mut(), b.transport_mut()]);
+        assert_eq!(
+            b.irq_status().unwrap().1,
+            Irq::CadDone.mask() | Irq::CadDetected.mask()
+        );
+    }
+}
@@ -70,6 +70,27 @@ impl SleepCfg {
         self
     }
 
+    /// Get the startup mode.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::subghz::{SleepCfg, Startup};
+    ///
+    /// assert_eq!(SleepCfg::new().startup(), Startup::Warm);
+    /// assert_eq!(
+    ///     SleepCfg::new().set_startup(Startup::Cold).startup(),
+    ///     Startup::Cold
+    /// );
+    /// ```
+    pub const fn startup(&self) -> Startup {
+        if self.0 & (1 << 2) == 0 {
+            Startup::Cold
+        } else {
+            Startup::Warm
+        }
+    }
+
     /// Set the RTC wakeup enable.
     ///
     /// # Example
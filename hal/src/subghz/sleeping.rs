@@ -0,0 +1,205 @@
+#[cfg(feature = "subghz-snapshot")]
+use super::Snapshot;
+use super::{Error, SleepCfg, StandbyClk, Startup, SubGhz, Transport};
+use crate::spi::Spi3;
+use embedded_hal::blocking::delay::DelayUs;
+
+/// Sub-GHz radio in sleep mode.
+///
+/// Created by [`SubGhz::sleep`].
+/// The radio cannot be used while in sleep mode, it must be woken with
+/// [`wake`](Self::wake) to get the [`SubGhz`] driver back.
+#[derive(Debug)]
+pub struct SleepingSubGhz<MISO, MOSI, T = Spi3<MISO, MOSI>> {
+    sg: SubGhz<MISO, MOSI, T>,
+    cfg: SleepCfg,
+}
+
+/// Sleep mode
+impl<MISO, MOSI, T: Transport> SubGhz<MISO, MOSI, T> {
+    /// Put the radio into sleep mode.
+    ///
+    /// This is a safe alternative to [`set_sleep`](Self::set_sleep) and
+    /// [`wakeup`](super::wakeup).
+    ///
+    /// The radio is put into standby mode with the RC 13 MHz clock, then into
+    /// sleep mode.
+    /// The radio must not be woken for 500 μs after entering sleep mode,
+    /// `delay` is used to wait before returning.
+    ///
+    /// With a [warm start](super::Startup::Warm) the radio retains the configuration
+    /// in sleep mode.
+    /// With a [cold start](super::Startup::Cold) the configuration is lost.
+    /// With the `subghz-snapshot` feature [`SleepingSubGhz::wake`] restores
+    /// the configuration applied by the driver, otherwise it returns
+    /// [`Woken::NeedsReconfig`] and the radio must be configured again.
+    ///
+    /// If the [RTC wakeup](SleepCfg::set_rtc_wakeup_en) is enabled the radio
+    /// wakes up by itself, [`SleepingSubGhz::wake`] is still required to use
+    /// the radio.
+    ///
+    /// # Errors
+    ///
+    /// Returns the driver with the error if the sleep commands failed.
+    /// The radio may be in standby or sleep mode.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # let cp = unsafe { stm32wlxx_hal::pac::CorePeripherals::steal() };
+    /// # let sg = unsafe { stm32wlxx_hal::subghz::SubGhz::steal() };
+    /// # let mut delay = stm32wlxx_hal::cortex_m::delay::Delay::new(cp.SYST, 48_000_000);
+    /// use stm32wlxx_hal::subghz::{SleepCfg, SleepingSubGhz, Startup, SubGhz, Woken};
+    ///
+    /// let cfg: SleepCfg = SleepCfg::new().set_startup(Startup::Cold);
+    /// let sleeping: SleepingSubGhz<_, _> = sg.sleep(cfg, &mut delay).map_err(|(e, _)| e)?;
+    ///
+    /// // ... low power things
+    ///
+    /// let sg: SubGhz<_, _> = match sleeping.wake().map_err(|(e, _)| e)? {
+    ///     Woken::Ready(sg) => sg,
+    ///     Woken::NeedsReconfig(sg) => {
+    ///         // ... configure the radio again
+    ///         sg
+    ///     }
+    /// };
+    /// # Ok::<(), stm32wlxx_hal::subghz::Error>(())
+    /// ```
+    pub fn sleep<D: DelayUs<u32>>(
+        mut self,
+        cfg: SleepCfg,
+        delay: &mut D,
+    ) -> Result<SleepingSubGhz<MISO, MOSI, T>, (Error, Self)> {
+        match self.sleep_in_place(cfg, delay) {
+            Ok(()) => Ok(SleepingSubGhz { sg: self, cfg }),
+            Err(e) => Err((e, self)),
+        }
+    }
+
+    /// Put the radio into sleep mode without giving up the driver.
+    ///
+    /// For drivers that own a [`SubGhz`] and track the sleep mode
+    /// themselves, the radio must not be used until it is woken with
+    /// [`wake_in_place`](Self::wake_in_place).
+    pub(crate) fn sleep_in_place<D: DelayUs<u32>>(
+        &mut self,
+        cfg: SleepCfg,
+        delay: &mut D,
+    ) -> Result<(), Error> {
+        self.set_standby(StandbyClk::Rc)?;
+        // safety:
+        // 1. NSS does not go low until the delay has elapsed
+        // 2. the caller does not use the radio until it is woken
+        unsafe { self.set_sleep(cfg) }?;
+        delay.delay_us(500);
+        Ok(())
+    }
+
+    /// Wake the radio from sleep mode entered with
+    /// [`sleep_in_place`](Self::sleep_in_place).
+    ///
+    /// The configuration is not restored.
+    pub(crate) fn wake_in_place(&mut self) {
+        // RM0453 rev 2 page 171 section 5.7.2 "Sleep mode"
+        // on a firmware request via the sub-GHz radio SPI NSS signal
+        // (keeping sub-GHz radio SPI NSS low for at least 20 μs)
+        self.bus.select();
+        self.bus.wait_not_busy();
+        self.bus.deselect();
+    }
+
+    /// Restore the configuration from the snapshot after a cold start.
+    #[cfg(feature = "subghz-snapshot")]
+    pub(crate) fn restore_snapshot(&mut self) -> Result<(), Error> {
+        let snapshot: Snapshot = self.state;
+        self.state = Snapshot::new();
+        self.restore(&snapshot)
+    }
+}
+
+/// Sub-GHz radio woken from sleep mode.
+///
+/// Returned by [`SleepingSubGhz::wake`].
+#[derive(Debug)]
+#[must_use = "the radio may need to be configured again"]
+pub enum Woken<MISO, MOSI, T = Spi3<MISO, MOSI>> {
+    /// The radio has the configuration applied before sleep mode.
+    ///
+    /// The configuration was retained by a warm start, or restored from the
+    /// snapshot after a cold start.
+    Ready(SubGhz<MISO, MOSI, T>),
+    /// The configuration was lost by a cold start without the
+    /// `subghz-snapshot` feature, the radio must be configured again.
+    NeedsReconfig(SubGhz<MISO, MOSI, T>),
+}
+
+impl<MISO, MOSI, T> Woken<MISO, MOSI, T> {
+    /// Returns `true` if the radio must be configured again.
+    pub fn needs_reconfig(&self) -> bool {
+        matches!(self, Woken::NeedsReconfig(_))
+    }
+
+    /// Get the driver without checking if it must be configured again.
+    pub fn into_inner(self) -> SubGhz<MISO, MOSI, T> {
+        match self {
+            Woken::Ready(sg) | Woken::NeedsReconfig(sg) => sg,
+        }
+    }
+}
+
+impl<MISO, MOSI, T> SleepingSubGhz<MISO, MOSI, T> {
+    /// Sleep configuration used to enter sleep mode.
+    pub fn cfg(&self) -> SleepCfg {
+        self.cfg
+    }
+
+    /// Get the radio configuration last applied by the driver.
+    ///
+    /// This is restored by [`wake`](Self::wake) after a cold start.
+    #[cfg(feature = "subghz-snapshot")]
+    pub fn snapshot(&self) -> Snapshot {
+        self.sg.snapshot()
+    }
+}
+
+impl<MISO, MOSI, T: Transport> SleepingSubGhz<MISO, MOSI, T> {
+    /// Wake the radio from sleep mode.
+    ///
+    /// NSS is held low until the radio is ready, after waking the radio is in
+    /// standby mode with the RC 13 MHz clock.
+    ///
+    /// After a [cold start](super::Startup::Cold) with the `subghz-snapshot`
+    /// feature the configuration from `snapshot` is applied with `restore`.
+    /// Registers and calibration are not part of the snapshot, radios with a
+    /// TCXO may need to be recalibrated with
+    /// [`calibrate_for`](SubGhz::calibrate_for).
+    ///
+    /// After a cold start without the `subghz-snapshot` feature this returns
+    /// [`Woken::NeedsReconfig`].
+    ///
+    /// # Errors
+    ///
+    /// Returns the driver with the error if restoring the configuration
+    /// failed.
+    /// The radio is awake, the `snapshot` of the returned driver contains the
+    /// settings that were restored.
+    #[allow(clippy::type_complexity)]
+    pub fn wake(self) -> Result<Woken<MISO, MOSI, T>, (Error, SubGhz<MISO, MOSI, T>)> {
+        let SleepingSubGhz { mut sg, cfg } = self;
+
+        sg.wake_in_place();
+
+        if cfg.startup() == Startup::Warm {
+            return Ok(Woken::Ready(sg));
+        }
+
+        #[cfg(feature = "subghz-snapshot")]
+        match sg.restore_snapshot() {
+            Ok(()) => Ok(Woken::Ready(sg)),
+            Err(e) => Err((e, sg)),
+        }
+
+        #[cfg(not(feature = "subghz-snapshot"))]
+        Ok(Woken::NeedsReconfig(sg))
+    }
+}
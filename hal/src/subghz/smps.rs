@@ -17,6 +17,30 @@ pub enum SmpsDrv {
 }
 
 impl SmpsDrv {
+    /// Create a new `SmpsDrv` from bits.
+    ///
+    /// Only the 2 least significant bits are used.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::subghz::SmpsDrv;
+    ///
+    /// assert_eq!(SmpsDrv::from_raw(0), SmpsDrv::Milli20);
+    /// assert_eq!(SmpsDrv::from_raw(1), SmpsDrv::Milli40);
+    /// assert_eq!(SmpsDrv::from_raw(2), SmpsDrv::Milli60);
+    /// assert_eq!(SmpsDrv::from_raw(3), SmpsDrv::Milli100);
+    /// assert_eq!(SmpsDrv::from_raw(0xFF), SmpsDrv::Milli100);
+    /// ```
+    pub const fn from_raw(bits: u8) -> SmpsDrv {
+        match bits & 0b11 {
+            0 => SmpsDrv::Milli20,
+            1 => SmpsDrv::Milli40,
+            2 => SmpsDrv::Milli60,
+            _ => SmpsDrv::Milli100,
+        }
+    }
+
     /// Get the SMPS drive value as milliamps.
     ///
     /// # Example
@@ -0,0 +1,183 @@
+use super::{
+    BpskModParams, BpskPacketParams, CfgIrq, FskModParams, GenericPacketParams, LoRaModParams,
+    LoRaPacketParams, Modulation, PaConfig, PacketType, RegMode, RfFreq, TcxoMode, TxParams,
+};
+
+/// Radio configuration last applied by the [`SubGhz`](super::SubGhz) driver.
+///
+/// The radio configuration is write-only, the driver records each setting
+/// when the command is sent successfully.
+/// Settings that have not been applied since the driver was created are
+/// `None`.
+///
+/// Returned by [`SubGhz::snapshot`](super::SubGhz::snapshot), argument of
+/// [`SubGhz::restore`](super::SubGhz::restore).
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Snapshot {
+    pub(crate) packet_type: Option<PacketType>,
+    pub(crate) rf_freq: Option<RfFreq>,
+    pub(crate) lora_mod_params: Option<LoRaModParams>,
+    pub(crate) fsk_mod_params: Option<FskModParams>,
+    pub(crate) bpsk_mod_params: Option<BpskModParams>,
+    pub(crate) lora_packet_params: Option<LoRaPacketParams>,
+    pub(crate) generic_packet_params: Option<GenericPacketParams>,
+    pub(crate) bpsk_packet_params: Option<BpskPacketParams>,
+    pub(crate) pa_config: Option<PaConfig>,
+    pub(crate) tx_params: Option<TxParams>,
+    pub(crate) tcxo_mode: Option<TcxoMode>,
+    pub(crate) reg_mode: Option<RegMode>,
+    pub(crate) irq_cfg: Option<CfgIrq>,
+}
+
+impl Snapshot {
+    /// Create a new `Snapshot` with no settings applied.
+    ///
+    /// This is the same as `default`, but in a `const` function.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::subghz::Snapshot;
+    ///
+    /// const SNAPSHOT: Snapshot = Snapshot::new();
+    /// assert_eq!(SNAPSHOT, Snapshot::default());
+    /// assert_eq!(SNAPSHOT.packet_type(), None);
+    /// ```
+    pub const fn new() -> Snapshot {
+        Snapshot {
+            packet_type: None,
+            rf_freq: None,
+            lora_mod_params: None,
+            fsk_mod_params: None,
+            bpsk_mod_params: None,
+            lora_packet_params: None,
+            generic_packet_params: None,
+            bpsk_packet_params: None,
+            pa_config: None,
+            tx_params: None,
+            tcxo_mode: None,
+            reg_mode: None,
+            irq_cfg: None,
+        }
+    }
+
+    /// Packet type set with
+    /// [`set_packet_type`](super::SubGhz::set_packet_type).
+    pub const fn packet_type(&self) -> Option<PacketType> {
+        self.packet_type
+    }
+
+    /// RF frequency set with
+    /// [`set_rf_frequency`](super::SubGhz::set_rf_frequency).
+    pub const fn rf_freq(&self) -> Option<RfFreq> {
+        self.rf_freq
+    }
+
+    /// LoRa modulation parameters set with
+    /// [`set_lora_mod_params`](super::SubGhz::set_lora_mod_params).
+    pub const fn lora_mod_params(&self) -> Option<LoRaModParams> {
+        self.lora_mod_params
+    }
+
+    /// (G)FSK modulation parameters set with
+    /// [`set_fsk_mod_params`](super::SubGhz::set_fsk_mod_params).
+    pub const fn fsk_mod_params(&self) -> Option<FskModParams> {
+        self.fsk_mod_params
+    }
+
+    /// BPSK modulation parameters set with
+    /// [`set_bpsk_mod_params`](super::SubGhz::set_bpsk_mod_params).
+    pub const fn bpsk_mod_params(&self) -> Option<BpskModParams> {
+        self.bpsk_mod_params
+    }
+
+    /// LoRa packet parameters set with
+    /// [`set_lora_packet_params`](super::SubGhz::set_lora_packet_params).
+    pub const fn lora_packet_params(&self) -> Option<LoRaPacketParams> {
+        self.lora_packet_params
+    }
+
+    /// Generic packet parameters set with
+    /// [`set_packet_params`](super::SubGhz::set_packet_params).
+    pub const fn generic_packet_params(&self) -> Option<GenericPacketParams> {
+        self.generic_packet_params
+    }
+
+    /// BPSK packet parameters set with
+    /// [`set_bpsk_packet_params`](super::SubGhz::set_bpsk_packet_params).
+    pub const fn bpsk_packet_params(&self) -> Option<BpskPacketParams> {
+        self.bpsk_packet_params
+    }
+
+    /// Power amplifier configuration set with
+    /// [`set_pa_config`](super::SubGhz::set_pa_config).
+    pub const fn pa_config(&self) -> Option<PaConfig> {
+        self.pa_config
+    }
+
+    /// TX parameters set with [`set_tx_params`](super::SubGhz::set_tx_params).
+    pub const fn tx_params(&self) -> Option<TxParams> {
+        self.tx_params
+    }
+
+    /// TCXO mode set with [`set_tcxo_mode`](super::SubGhz::set_tcxo_mode).
+    pub const fn tcxo_mode(&self) -> Option<TcxoMode> {
+        self.tcxo_mode
+    }
+
+    /// Regulator mode set with
+    /// [`set_regulator_mode`](super::SubGhz::set_regulator_mode).
+    pub const fn reg_mode(&self) -> Option<RegMode> {
+        self.reg_mode
+    }
+
+    /// IRQ configuration set with [`set_irq_cfg`](super::SubGhz::set_irq_cfg).
+    pub const fn irq_cfg(&self) -> Option<CfgIrq> {
+        self.irq_cfg
+    }
+
+    /// Modulation and packet parameters for the packet type.
+    ///
+    /// Returns `None` if the packet type, or the modulation and packet
+    /// parameters for the packet type have not been set.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::subghz::Snapshot;
+    ///
+    /// assert_eq!(Snapshot::new().modulation(), None);
+    /// ```
+    pub const fn modulation(&self) -> Option<Modulation> {
+        match self.packet_type {
+            Some(PacketType::LoRa) => match (self.lora_mod_params, self.lora_packet_params) {
+                (Some(mod_params), Some(packet_params)) => {
+                    Some(Modulation::LoRa(mod_params, packet_params))
+                }
+                _ => None,
+            },
+            Some(PacketType::Fsk) | Some(PacketType::Msk) => {
+                match (self.fsk_mod_params, self.generic_packet_params) {
+                    (Some(mod_params), Some(packet_params)) => {
+                        Some(Modulation::Fsk(mod_params, packet_params))
+                    }
+                    _ => None,
+                }
+            }
+            Some(PacketType::Bpsk) => match (self.bpsk_mod_params, self.bpsk_packet_params) {
+                (Some(mod_params), Some(packet_params)) => {
+                    Some(Modulation::Bpsk(mod_params, packet_params))
+                }
+                _ => None,
+            },
+            None => None,
+        }
+    }
+}
+
+impl Default for Snapshot {
+    fn default() -> Self {
+        Snapshot::new()
+    }
+}
@@ -0,0 +1,347 @@
+use super::{
+    AddrComp, BpskModParams, BpskPacketParams, CrcType, FskBitrate, FskModParams,
+    GenericPacketParams, HeaderType, LoRaModParams, LoRaPacketParams, SpreadingFactor,
+};
+use core::time::Duration;
+
+/// Duration of `bits` at the given bitrate, rounded up to the nearest
+/// nanosecond.
+const fn bits_duration(bits: u64, bitrate: FskBitrate) -> Duration {
+    // the bitrate is 1_024_000_000 / bits
+    let num: u64 = bits * (bitrate.into_bits() as u64) * 1000;
+    Duration::from_nanos((num + 1023) / 1024)
+}
+
+/// Calculate the LoRa time on air.
+///
+/// This uses the formula from section 6.1.4 "LoRa® Modulation Time-on-Air"
+/// of the SX1261/2 datasheet, which is the same radio IP as the STM32WL.
+///
+/// # Example
+///
+/// ```
+/// use core::time::Duration;
+/// use stm32wlxx_hal::subghz::{
+///     lora_time_on_air, CodingRate, HeaderType, LoRaBandwidth, LoRaModParams, LoRaPacketParams,
+///     SpreadingFactor,
+/// };
+///
+/// const MOD_PARAMS: LoRaModParams = LoRaModParams::new()
+///     .set_sf(SpreadingFactor::Sf7)
+///     .set_bw(LoRaBandwidth::Bw125)
+///     .set_cr(CodingRate::Cr45)
+///     .set_ldro_en(false);
+///
+/// const PKT_PARAMS: LoRaPacketParams = LoRaPacketParams::new()
+///     .set_preamble_len(8)
+///     .set_header_type(HeaderType::Variable)
+///     .set_payload_len(10)
+///     .set_crc_en(true);
+///
+/// const TOA: Duration = lora_time_on_air(&MOD_PARAMS, &PKT_PARAMS);
+/// assert_eq!(TOA, Duration::from_micros(41_216));
+/// ```
+pub const fn lora_time_on_air(
+    mod_params: &LoRaModParams,
+    packet_params: &LoRaPacketParams,
+) -> Duration {
+    let sf: SpreadingFactor = mod_params.sf();
+    let sf_bits: i32 = sf as i32;
+    let cr: i32 = mod_params.cr() as i32;
+    let pl: i32 = packet_params.payload_len() as i32;
+    let crc: i32 = packet_params.crc_en() as i32;
+    let header: i32 = matches!(packet_params.header_type(), HeaderType::Variable) as i32;
+    let low_sf: bool = sf_bits < 7;
+
+    // all symbol counts are in quarter symbols
+    let (fixed_quarters, payload_bits): (i32, i32) = if low_sf {
+        (25, 8 * pl + 16 * crc - 4 * sf_bits + 20 * header)
+    } else {
+        (17, 8 * pl + 16 * crc - 4 * sf_bits + 8 + 20 * header)
+    };
+    let bits_per_symbol: i32 = if mod_params.ldro_en() && !low_sf {
+        4 * (sf_bits - 2)
+    } else {
+        4 * sf_bits
+    };
+    let payload_bits: i32 = if payload_bits < 0 { 0 } else { payload_bits };
+    let payload_symbols: i32 =
+        8 + ((payload_bits + bits_per_symbol - 1) / bits_per_symbol) * (cr + 4);
+
+    let quarters: u64 =
+        4 * (packet_params.preamble_len() as u64) + (fixed_quarters + 4 * payload_symbols) as u64;
+
+    // symbol times are a multiple of 4 ns
+    let quarter_nanos: u64 = (mod_params.symbol_time().as_nanos() as u64) / 4;
+    Duration::from_nanos(quarters * quarter_nanos)
+}
+
+/// Calculate the (G)FSK time on air.
+///
+/// This is the total number of bits in the packet divided by the bitrate,
+/// rounded up to the nearest nanosecond.
+/// The packet is made of the preamble, sync word, the optional length and
+/// address bytes, the payload, and the CRC.
+///
+/// # Example
+///
+/// ```
+/// use core::time::Duration;
+/// use stm32wlxx_hal::subghz::{
+///     fsk_time_on_air, CrcType, FskBitrate, FskModParams, GenericPacketParams, HeaderType,
+/// };
+///
+/// const MOD_PARAMS: FskModParams = FskModParams::new().set_bitrate(FskBitrate::from_bps(50_000));
+///
+/// const PKT_PARAMS: GenericPacketParams = GenericPacketParams::new()
+///     .set_preamble_len(32)
+///     .set_sync_word_len(32)
+///     .set_header_type(HeaderType::Variable)
+///     .set_payload_len(10)
+///     .set_crc_type(CrcType::Byte2);
+///
+/// const TOA: Duration = fsk_time_on_air(&MOD_PARAMS, &PKT_PARAMS);
+/// assert_eq!(TOA, Duration::from_micros(3_360));
+/// ```
+pub const fn fsk_time_on_air(
+    mod_params: &FskModParams,
+    packet_params: &GenericPacketParams,
+) -> Duration {
+    let header_bits: u64 = match packet_params.header_type() {
+        HeaderType::Fixed => 0,
+        HeaderType::Variable => 8,
+    };
+    let addr_bits: u64 = match packet_params.addr_comp() {
+        AddrComp::Disabled => 0,
+        AddrComp::Node | AddrComp::Broadcast => 8,
+    };
+    let crc_bits: u64 = match packet_params.crc_type() {
+        CrcType::Disabled => 0,
+        CrcType::Byte1 | CrcType::Byte1Inverted => 8,
+        CrcType::Byte2 | CrcType::Byte2Inverted => 16,
+    };
+    let bits: u64 = packet_params.preamble_len() as u64
+        + packet_params.sync_word_len() as u64
+        + header_bits
+        + addr_bits
+        + 8 * (packet_params.payload_len() as u64)
+        + crc_bits;
+
+    bits_duration(bits, mod_params.bitrate())
+}
+
+/// Calculate the BPSK time on air.
+///
+/// This is the number of payload bits divided by the bitrate, rounded up to
+/// the nearest nanosecond.
+///
+/// # Example
+///
+/// ```
+/// use core::time::Duration;
+/// use stm32wlxx_hal::subghz::{bpsk_time_on_air, BpskModParams, BpskPacketParams, FskBitrate};
+///
+/// const MOD_PARAMS: BpskModParams = BpskModParams::new().set_bitrate(FskBitrate::from_bps(100));
+/// const PKT_PARAMS: BpskPacketParams = BpskPacketParams::new().set_payload_len(26);
+///
+/// const TOA: Duration = bpsk_time_on_air(&MOD_PARAMS, &PKT_PARAMS);
+/// assert_eq!(TOA, Duration::from_millis(2_080));
+/// ```
+pub const fn bpsk_time_on_air(
+    mod_params: &BpskModParams,
+    packet_params: &BpskPacketParams,
+) -> Duration {
+    bits_duration(
+        8 * (packet_params.payload_len() as u64),
+        mod_params.bitrate(),
+    )
+}
+
+/// Modulation and packet parameters of a transmission.
+///
+/// Argument of [`SubGhz::set_tx_gated`](super::SubGhz::set_tx_gated).
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Modulation {
+    /// LoRa modulation.
+    LoRa(LoRaModParams, LoRaPacketParams),
+    /// (G)FSK modulation.
+    Fsk(FskModParams, GenericPacketParams),
+    /// BPSK modulation.
+    Bpsk(BpskModParams, BpskPacketParams),
+}
+
+impl Modulation {
+    /// Calculate the time on air.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use core::time::Duration;
+    /// use stm32wlxx_hal::subghz::{BpskModParams, BpskPacketParams, FskBitrate, Modulation};
+    ///
+    /// const MODULATION: Modulation = Modulation::Bpsk(
+    ///     BpskModParams::new().set_bitrate(FskBitrate::from_bps(100)),
+    ///     BpskPacketParams::new().set_payload_len(26),
+    /// );
+    /// assert_eq!(MODULATION.time_on_air(), Duration::from_millis(2_080));
+    /// ```
+    pub const fn time_on_air(&self) -> Duration {
+        match self {
+            Modulation::LoRa(mod_params, packet_params) => {
+                lora_time_on_air(mod_params, packet_params)
+            }
+            Modulation::Fsk(mod_params, packet_params) => {
+                fsk_time_on_air(mod_params, packet_params)
+            }
+            Modulation::Bpsk(mod_params, packet_params) => {
+                bpsk_time_on_air(mod_params, packet_params)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{bpsk_time_on_air, fsk_time_on_air, lora_time_on_air};
+    use crate::subghz::{
+        AddrComp, BpskModParams, BpskPacketParams, CodingRate, CrcType, FskBitrate, FskModParams,
+        GenericPacketParams, HeaderType, LoRaBandwidth, LoRaModParams, LoRaPacketParams,
+        SpreadingFactor,
+    };
+    use core::time::Duration;
+
+    const fn lora(
+        sf: SpreadingFactor,
+        bw: LoRaBandwidth,
+        cr: CodingRate,
+        ldro: bool,
+    ) -> LoRaModParams {
+        LoRaModParams::new()
+            .set_sf(sf)
+            .set_bw(bw)
+            .set_cr(cr)
+            .set_ldro_en(ldro)
+    }
+
+    const fn lora_pkt(preamble: u16, header: HeaderType, len: u8, crc: bool) -> LoRaPacketParams {
+        LoRaPacketParams::new()
+            .set_preamble_len(preamble)
+            .set_header_type(header)
+            .set_payload_len(len)
+            .set_crc_en(crc)
+    }
+
+    #[test]
+    fn lora_sf7_bw125() {
+        let mp = lora(
+            SpreadingFactor::Sf7,
+            LoRaBandwidth::Bw125,
+            CodingRate::Cr45,
+            false,
+        );
+        let pp = lora_pkt(8, HeaderType::Variable, 10, true);
+        assert_eq!(lora_time_on_air(&mp, &pp), Duration::from_micros(41_216));
+    }
+
+    #[test]
+    fn lora_sf12_bw125_ldro() {
+        // LoRaWAN EU868 DR0 with the maximum payload
+        let mp = lora(
+            SpreadingFactor::Sf12,
+            LoRaBandwidth::Bw125,
+            CodingRate::Cr45,
+            true,
+        );
+        let pp = lora_pkt(8, HeaderType::Variable, 51, true);
+        assert_eq!(lora_time_on_air(&mp, &pp), Duration::from_micros(2_465_792));
+    }
+
+    #[test]
+    fn lora_sf9_bw125() {
+        // LoRaWAN US915 DR1 with the maximum payload
+        let mp = lora(
+            SpreadingFactor::Sf9,
+            LoRaBandwidth::Bw125,
+            CodingRate::Cr45,
+            false,
+        );
+        let pp = lora_pkt(8, HeaderType::Variable, 53, true);
+        // 8 + 4.25 + 8 + ceil((424 + 16 - 36 + 8 + 20) / 36) * 5 = 80.25 symbols
+        assert_eq!(lora_time_on_air(&mp, &pp), Duration::from_micros(328_704));
+    }
+
+    #[test]
+    fn lora_sf5_implicit_empty() {
+        // the payload symbol count saturates at zero
+        let mp = lora(
+            SpreadingFactor::Sf5,
+            LoRaBandwidth::Bw500,
+            CodingRate::Cr45,
+            false,
+        );
+        let pp = lora_pkt(8, HeaderType::Fixed, 1, false);
+        // 8 + 6.25 + 8 symbols of 64 μs
+        assert_eq!(lora_time_on_air(&mp, &pp), Duration::from_micros(1_424));
+    }
+
+    #[test]
+    fn lora_cr48_bw7() {
+        let mp = lora(
+            SpreadingFactor::Sf7,
+            LoRaBandwidth::Bw7,
+            CodingRate::Cr48,
+            false,
+        );
+        let pp = lora_pkt(12, HeaderType::Variable, 1, false);
+        // 12 + 4.25 + 8 + ceil((8 - 28 + 8 + 20) / 28) * 8 = 32.25 symbols
+        // of 16.384 ms
+        assert_eq!(lora_time_on_air(&mp, &pp), Duration::from_micros(528_384));
+    }
+
+    #[test]
+    fn fsk() {
+        let mp = FskModParams::new().set_bitrate(FskBitrate::from_bps(50_000));
+        let pp = GenericPacketParams::new()
+            .set_preamble_len(32)
+            .set_sync_word_len(32)
+            .set_addr_comp(AddrComp::Disabled)
+            .set_header_type(HeaderType::Variable)
+            .set_payload_len(10)
+            .set_crc_type(CrcType::Byte2);
+        // 32 + 32 + 8 + 80 + 16 = 168 bits
+        assert_eq!(fsk_time_on_air(&mp, &pp), Duration::from_micros(3_360));
+
+        let pp = pp
+            .set_addr_comp(AddrComp::Broadcast)
+            .set_header_type(HeaderType::Fixed)
+            .set_crc_type(CrcType::Byte1Inverted);
+        // 32 + 32 + 8 + 80 + 8 = 160 bits
+        assert_eq!(fsk_time_on_air(&mp, &pp), Duration::from_micros(3_200));
+
+        let pp = pp.set_crc_type(CrcType::Disabled);
+        assert_eq!(fsk_time_on_air(&mp, &pp), Duration::from_micros(3_040));
+    }
+
+    #[test]
+    fn fsk_rounding() {
+        // 600 b/s is not exact, 1_024_000_000 / 1_706_666 = 600.0002 b/s
+        let mp = FskModParams::new().set_bitrate(FskBitrate::from_bps(600));
+        let pp = GenericPacketParams::new()
+            .set_preamble_len(1)
+            .set_header_type(HeaderType::Fixed)
+            .set_payload_len(0)
+            .set_crc_type(CrcType::Disabled);
+        assert_eq!(fsk_time_on_air(&mp, &pp), Duration::from_nanos(1_666_667));
+    }
+
+    #[test]
+    fn bpsk() {
+        let mp = BpskModParams::new().set_bitrate(FskBitrate::from_bps(600));
+        let pp = BpskPacketParams::new().set_payload_len(26);
+        assert_eq!(
+            bpsk_time_on_air(&mp, &pp),
+            Duration::from_nanos(346_666_532)
+        );
+    }
+}
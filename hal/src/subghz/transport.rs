@@ -0,0 +1,86 @@
+use super::{nss_clear, nss_set, rfbusys, Error};
+use crate::{pac, spi::Spi3};
+use embedded_hal::blocking::spi::{Transfer, Write};
+
+/// Byte transport between the [`SubGhz`](super::SubGhz) command layer and
+/// the radio.
+///
+/// On hardware this is the SPI3 peripheral, with NSS and busy signals in the
+/// PWR peripheral.
+/// Other transports allow the command layer to run without the radio, for
+/// example the simulated radio in the `sim` module (requires the
+/// `subghz-sim` feature).
+///
+/// Each command is sent as a transaction:
+///
+/// 1. [`wait_not_busy`](Transport::wait_not_busy)
+/// 2. [`select`](Transport::select)
+/// 3. One or more [`write_bytes`](Transport::write_bytes) and
+///    [`transfer_bytes`](Transport::transfer_bytes), the first byte written
+///    is the command opcode.
+/// 4. [`deselect`](Transport::deselect)
+/// 5. [`wait_not_busy`](Transport::wait_not_busy), except after the
+///    `SetSleep` command.
+pub trait Transport {
+    /// Wait for the radio to be ready to accept a command.
+    fn wait_not_busy(&mut self);
+
+    /// Select the radio, driving NSS low.
+    ///
+    /// This wakes the radio if it is in sleep mode.
+    fn select(&mut self);
+
+    /// Deselect the radio, driving NSS high.
+    ///
+    /// The radio executes the command when it is deselected.
+    fn deselect(&mut self);
+
+    /// Write bytes to the radio, discarding the bytes read.
+    fn write_bytes(&mut self, data: &[u8]) -> Result<(), Error>;
+
+    /// Write bytes to the radio, replacing them with the bytes read.
+    fn transfer_bytes(&mut self, data: &mut [u8]) -> Result<(), Error>;
+}
+
+impl<MISO, MOSI> Transport for Spi3<MISO, MOSI>
+where
+    Spi3<MISO, MOSI>: Transfer<u8, Error = Error> + Write<u8, Error = Error>,
+{
+    fn wait_not_busy(&mut self) {
+        // TODO: this is a terrible timeout
+        let mut count: u32 = 1_000_000;
+        while rfbusys() {
+            count -= 1;
+            if count == 0 {
+                let dp = unsafe { pac::Peripherals::steal() };
+                panic!(
+                    "rfbusys timeout pwr.sr2=0x{:X} pwr.subghzspicr=0x{:X} pwr.cr1=0x{:X}",
+                    dp.PWR.sr2.read().bits(),
+                    dp.PWR.subghzspicr.read().bits(),
+                    dp.PWR.cr1.read().bits(),
+                );
+            }
+        }
+    }
+
+    #[inline(always)]
+    fn select(&mut self) {
+        nss_clear()
+    }
+
+    #[inline(always)]
+    fn deselect(&mut self) {
+        nss_set()
+    }
+
+    #[inline]
+    fn write_bytes(&mut self, data: &[u8]) -> Result<(), Error> {
+        self.write(data)
+    }
+
+    #[inline]
+    fn transfer_bytes(&mut self, data: &mut [u8]) -> Result<(), Error> {
+        self.transfer(data)?;
+        Ok(())
+    }
+}
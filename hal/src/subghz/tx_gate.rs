@@ -0,0 +1,514 @@
+//! Duty-cycle and listen-before-talk (LBT) transmit gating.
+//!
+//! Regulations such as ETSI EN 300 220 limit the fraction of time a device
+//! may transmit in a sub-band.
+//! [`TxGate`] tracks the airtime spent in each sub-band against a monotonic
+//! clock provided by the caller, and
+//! [`SubGhz::set_tx_gated`](super::SubGhz::set_tx_gated) refuses to transmit
+//! until the sub-band is available again.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use core::time::Duration;
+//! use stm32wlxx_hal::subghz::{
+//!     tx_gate::{GateError, Lbt, SubBand, TxGate},
+//!     LoRaModParams, LoRaPacketParams, Modulation, RfFreq, SubGhz, Timeout,
+//! };
+//! # let mut sg = unsafe { stm32wlxx_hal::subghz::SubGhz::steal() };
+//! # let cp = unsafe { stm32wlxx_hal::pac::CorePeripherals::steal() };
+//! # let mut delay = stm32wlxx_hal::cortex_m::delay::Delay::new(cp.SYST, 48_000_000);
+//! # fn now() -> Duration { Duration::ZERO }
+//!
+//! const RF_FREQ: RfFreq = RfFreq::from_frequency(868_100_000);
+//! const MODULATION: Modulation = Modulation::LoRa(
+//!     LoRaModParams::new(),
+//!     LoRaPacketParams::new().set_payload_len(16),
+//! );
+//!
+//! let mut gate: TxGate<6> = TxGate::new(SubBand::EU868).set_lbt(Some(Lbt::Rssi {
+//!     threshold: -80,
+//!     listen: Duration::from_millis(5),
+//! }));
+//!
+//! match sg.set_tx_gated(&mut gate, now(), &RF_FREQ, &MODULATION, Timeout::DISABLED, &mut delay) {
+//!     Ok(()) => (),
+//!     Err(GateError::DutyCycle { retry_after }) => {
+//!         // wait for retry_after, or pick another sub-band
+//!     }
+//!     Err(e) => return Err(e),
+//! }
+//! # Ok::<(), GateError>(())
+//! ```
+
+use super::{Error, Irq, Modulation, RfFreq, StandbyClk, SubGhz, Timeout, Transport};
+use core::time::Duration;
+use embedded_hal::blocking::delay::DelayUs;
+
+/// Interval between RSSI samples during [`Lbt::Rssi`], and between status
+/// reads during [`Lbt::Cad`].
+const LBT_INTERVAL_US: u32 = 100;
+
+/// A frequency range with a duty-cycle limit.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SubBand {
+    start: u32,
+    end: u32,
+    duty: u16,
+}
+
+impl SubBand {
+    /// ETSI EN 300 220 sub-bands used by the LoRaWAN EU863-870 region.
+    ///
+    /// These are also the [`Region::sub_bands`](super::region::Region::sub_bands)
+    /// of [`Region::Eu868`](super::region::Region::Eu868).
+    ///
+    /// | Frequency (MHz) | Duty cycle |
+    /// |-----------------|------------|
+    /// | 863.0 - 865.0   | 0.1 %      |
+    /// | 865.0 - 868.0   | 1 %        |
+    /// | 868.0 - 868.6   | 1 %        |
+    /// | 868.7 - 869.2   | 0.1 %      |
+    /// | 869.4 - 869.65  | 10 %       |
+    /// | 869.7 - 870.0   | 1 %        |
+    pub const EU868: [SubBand; 6] = [
+        SubBand::new(863_000_000, 864_999_999, 1000),
+        SubBand::new(865_000_000, 867_999_999, 100),
+        SubBand::new(868_000_000, 868_600_000, 100),
+        SubBand::new(868_700_000, 869_200_000, 1000),
+        SubBand::new(869_400_000, 869_650_000, 10),
+        SubBand::new(869_700_000, 870_000_000, 100),
+    ];
+
+    /// Create a new sub-band.
+    ///
+    /// The frequency range is inclusive, and the duty cycle is the
+    /// denominator of the allowed fraction, for example `100` for 1 %.
+    /// A duty cycle of `0` or `1` allows back-to-back transmissions, the
+    /// sub-band is only unavailable for the time on air of each
+    /// transmission.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::subghz::{tx_gate::SubBand, RfFreq};
+    ///
+    /// const BAND: SubBand = SubBand::new(869_400_000, 869_650_000, 10);
+    /// assert!(BAND.contains(&RfFreq::from_frequency(869_525_000)));
+    /// assert!(!BAND.contains(&RfFreq::from_frequency(868_100_000)));
+    /// assert_eq!(BAND.duty_cycle(), 10);
+    /// ```
+    pub const fn new(start_hz: u32, end_hz: u32, duty_cycle: u16) -> SubBand {
+        SubBand {
+            start: start_hz,
+            end: end_hz,
+            duty: duty_cycle,
+        }
+    }
+
+    /// Returns `true` if the RF frequency is in the sub-band.
+    pub const fn contains(&self, rf_freq: &RfFreq) -> bool {
+        let freq: u32 = rf_freq.freq();
+        freq >= self.start && freq <= self.end
+    }
+
+    /// Duty cycle denominator.
+    pub const fn duty_cycle(&self) -> u16 {
+        self.duty
+    }
+}
+
+/// Listen-before-talk method.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Lbt {
+    /// Sample the instantaneous RSSI in continuous RX mode.
+    ///
+    /// The packet type and RX bandwidth must be configured before
+    /// transmitting.
+    Rssi {
+        /// The channel is busy if the RSSI is at or above this level in dBm.
+        threshold: i16,
+        /// Time to listen for.
+        listen: Duration,
+    },
+    /// LoRa channel activity detection.
+    ///
+    /// The CAD parameters must be set with
+    /// [`set_cad_params`](super::SubGhz::set_cad_params), and the
+    /// [`CadDone`](Irq::CadDone) and [`CadDetected`](Irq::CadDetected)
+    /// IRQs must be enabled with [`set_irq_cfg`](super::SubGhz::set_irq_cfg).
+    Cad,
+}
+
+/// Transmit gating errors.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum GateError {
+    /// Transmitting would exceed the duty cycle of the sub-band.
+    DutyCycle {
+        /// Time until the sub-band is available.
+        retry_after: Duration,
+    },
+    /// Listen-before-talk detected activity on the channel.
+    ChannelBusy,
+    /// The RF frequency is not in any sub-band of the gate.
+    SubBand,
+    /// SPI error.
+    Spi(Error),
+}
+
+impl From<Error> for GateError {
+    fn from(e: Error) -> Self {
+        GateError::Spi(e)
+    }
+}
+
+/// Duty-cycle tracker for `N` sub-bands.
+///
+/// Time is provided by the caller as a [`Duration`] from a monotonic clock,
+/// the epoch does not matter as long as it does not change.
+///
+/// # Example
+///
+/// ```
+/// use core::time::Duration;
+/// use stm32wlxx_hal::subghz::{
+///     tx_gate::{GateError, SubBand, TxGate},
+///     RfFreq,
+/// };
+///
+/// const RF_FREQ: RfFreq = RfFreq::from_frequency(868_100_000);
+///
+/// let mut gate: TxGate<6> = TxGate::new(SubBand::EU868);
+/// assert_eq!(gate.check(&RF_FREQ, Duration::ZERO), Ok(()));
+///
+/// // 50 ms at a 1 % duty cycle blocks the sub-band for 5 s
+/// gate.record(&RF_FREQ, Duration::ZERO, Duration::from_millis(50))?;
+/// assert_eq!(
+///     gate.check(&RF_FREQ, Duration::from_secs(1)),
+///     Err(GateError::DutyCycle {
+///         retry_after: Duration::from_secs(4)
+///     })
+/// );
+/// assert_eq!(gate.check(&RF_FREQ, Duration::from_secs(5)), Ok(()));
+/// # Ok::<(), GateError>(())
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct TxGate<const N: usize> {
+    bands: [SubBand; N],
+    ready: [Duration; N],
+    lbt: Option<Lbt>,
+}
+
+impl<const N: usize> TxGate<N> {
+    /// Create a new transmit gate without listen-before-talk.
+    pub const fn new(bands: [SubBand; N]) -> TxGate<N> {
+        TxGate {
+            bands,
+            ready: [Duration::ZERO; N],
+            lbt: None,
+        }
+    }
+
+    /// Set the listen-before-talk method, `None` to disable.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::subghz::tx_gate::{Lbt, SubBand, TxGate};
+    ///
+    /// const GATE: TxGate<6> = TxGate::new(SubBand::EU868).set_lbt(Some(Lbt::Cad));
+    /// assert_eq!(GATE.lbt(), Some(Lbt::Cad));
+    /// ```
+    #[must_use = "set_lbt returns a modified TxGate"]
+    pub const fn set_lbt(mut self, lbt: Option<Lbt>) -> TxGate<N> {
+        self.lbt = lbt;
+        self
+    }
+
+    /// Listen-before-talk method.
+    pub const fn lbt(&self) -> Option<Lbt> {
+        self.lbt
+    }
+
+    fn band(&self, rf_freq: &RfFreq) -> Result<usize, GateError> {
+        self.bands
+            .iter()
+            .position(|band| band.contains(rf_freq))
+            .ok_or(GateError::SubBand)
+    }
+
+    /// Check if a transmission on the RF frequency is allowed at `now`.
+    pub fn check(&self, rf_freq: &RfFreq, now: Duration) -> Result<(), GateError> {
+        let idx: usize = self.band(rf_freq)?;
+        match self.ready[idx].checked_sub(now) {
+            Some(retry_after) if !retry_after.is_zero() => {
+                Err(GateError::DutyCycle { retry_after })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Record a transmission of `air` time on air that started at `now`.
+    ///
+    /// The sub-band is unavailable until `now + air * duty_cycle`.
+    pub fn record(
+        &mut self,
+        rf_freq: &RfFreq,
+        now: Duration,
+        air: Duration,
+    ) -> Result<(), GateError> {
+        let idx: usize = self.band(rf_freq)?;
+        let duty: u32 = self.bands[idx].duty_cycle().max(1).into();
+        self.ready[idx] = now.saturating_add(air.saturating_mul(duty));
+        Ok(())
+    }
+}
+
+/// Gated transmit
+impl<MISO, MOSI, T: Transport> SubGhz<MISO, MOSI, T> {
+    /// Set the RF frequency and start a transmission if the duty cycle and
+    /// listen-before-talk allow it.
+    ///
+    /// The packet type, modulation parameters, packet parameters, and TX
+    /// buffer must be configured before calling this method.
+    /// `modulation` must match the configured parameters, it is used to
+    /// calculate the time on air recorded in the gate.
+    ///
+    /// `now` is the current time of a monotonic clock, `delay` is used to
+    /// pace the RSSI samples of [`Lbt::Rssi`] and the status reads of
+    /// [`Lbt::Cad`].
+    /// The transmission is recorded in the gate as starting after the
+    /// listen-before-talk time, which is measured with `delay`.
+    ///
+    /// # Errors
+    ///
+    /// * [`GateError::DutyCycle`] if the sub-band is still unavailable.
+    /// * [`GateError::ChannelBusy`] if listen-before-talk detected activity,
+    ///   the radio is left in standby mode.
+    /// * [`GateError::SubBand`] if the RF frequency is not in a sub-band.
+    ///
+    /// Nothing is recorded in the gate if an error is returned.
+    pub fn set_tx_gated<const N: usize, D: DelayUs<u32>>(
+        &mut self,
+        gate: &mut TxGate<N>,
+        now: Duration,
+        rf_freq: &RfFreq,
+        modulation: &Modulation,
+        timeout: Timeout,
+        delay: &mut D,
+    ) -> Result<(), GateError> {
+        const INTERVAL: Duration = Duration::from_micros(LBT_INTERVAL_US as u64);
+
+        gate.check(rf_freq, now)?;
+        self.set_rf_frequency(rf_freq)?;
+
+        // time spent listening before the transmission starts
+        let mut elapsed: Duration = Duration::ZERO;
+        match gate.lbt() {
+            Some(Lbt::Rssi { threshold, listen }) => {
+                self.set_rx(Timeout::MAX)?;
+                let busy: bool = loop {
+                    delay.delay_us(LBT_INTERVAL_US);
+                    elapsed += INTERVAL;
+                    let (_, rssi) = self.rssi_inst()?;
+                    if rssi.to_integer() >= threshold {
+                        break true;
+                    }
+                    if elapsed >= listen {
+                        break false;
+                    }
+                };
+                self.set_standby(StandbyClk::Rc)?;
+                if busy {
+                    return Err(GateError::ChannelBusy);
+                }
+            }
+            Some(Lbt::Cad) => {
+                const CAD_MASK: u16 = Irq::CadDone.mask() | Irq::CadDetected.mask();
+                self.clear_irq_status(CAD_MASK)?;
+                self.set_cad()?;
+                let irq_status: u16 = loop {
+                    delay.delay_us(LBT_INTERVAL_US);
+                    elapsed += INTERVAL;
+                    let (_, irq_status) = self.irq_status()?;
+                    if irq_status & Irq::CadDone.mask() != 0 {
+                        break irq_status;
+                    }
+                };
+                self.clear_irq_status(CAD_MASK)?;
+                if irq_status & Irq::CadDetected.mask() != 0 {
+                    return Err(GateError::ChannelBusy);
+                }
+            }
+            None => (),
+        }
+
+        self.set_tx(timeout)?;
+        gate.record(
+            rf_freq,
+            now.saturating_add(elapsed),
+            modulation.time_on_air(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{GateError, SubBand, TxGate};
+    use crate::subghz::RfFreq;
+    use core::time::Duration;
+    #[cfg(feature = "subghz-sim")]
+    use {
+        super::Lbt,
+        crate::{
+            spi::{SgMiso, SgMosi},
+            subghz::{
+                sim::{SimMode, SimRadio},
+                CfgIrq, Irq, LoRaModParams, LoRaPacketParams, Modulation, PacketType, SubGhz,
+                Timeout,
+            },
+        },
+    };
+
+    fn mhz(hz: u32) -> RfFreq {
+        RfFreq::from_frequency(hz)
+    }
+
+    #[test]
+    fn sub_bands_independent() {
+        let mut gate: TxGate<6> = TxGate::new(SubBand::EU868);
+        let air: Duration = Duration::from_millis(100);
+        gate.record(&mhz(868_100_000), Duration::ZERO, air).unwrap();
+
+        // 1 % duty cycle, 100 ms on air -> 10 s
+        assert_eq!(
+            gate.check(&mhz(868_300_000), Duration::from_secs(2)),
+            Err(GateError::DutyCycle {
+                retry_after: Duration::from_secs(8)
+            })
+        );
+        assert_eq!(
+            gate.check(&mhz(868_300_000), Duration::from_secs(10)),
+            Ok(())
+        );
+        // other sub-bands are not affected
+        assert_eq!(gate.check(&mhz(869_525_000), Duration::ZERO), Ok(()));
+        assert_eq!(gate.check(&mhz(867_100_000), Duration::ZERO), Ok(()));
+
+        // 10 % duty cycle
+        gate.record(&mhz(869_525_000), Duration::ZERO, air).unwrap();
+        assert_eq!(
+            gate.check(&mhz(869_525_000), Duration::from_millis(500)),
+            Err(GateError::DutyCycle {
+                retry_after: Duration::from_millis(500)
+            })
+        );
+    }
+
+    #[test]
+    fn outside_sub_bands() {
+        let mut gate: TxGate<6> = TxGate::new(SubBand::EU868);
+        for hz in [862_000_000, 868_650_000, 869_300_000, 915_000_000] {
+            assert_eq!(
+                gate.check(&mhz(hz), Duration::ZERO),
+                Err(GateError::SubBand)
+            );
+            assert_eq!(
+                gate.record(&mhz(hz), Duration::ZERO, Duration::from_millis(1)),
+                Err(GateError::SubBand)
+            );
+        }
+    }
+
+    #[test]
+    fn unlimited() {
+        let mut gate: TxGate<1> = TxGate::new([SubBand::new(902_000_000, 928_000_000, 1)]);
+        let rf_freq: RfFreq = mhz(915_000_000);
+        gate.record(&rf_freq, Duration::ZERO, Duration::from_millis(400))
+            .unwrap();
+        assert!(gate.check(&rf_freq, Duration::from_millis(399)).is_err());
+        assert_eq!(gate.check(&rf_freq, Duration::from_millis(400)), Ok(()));
+
+        // saturates instead of overflowing
+        gate.record(&rf_freq, Duration::MAX, Duration::MAX).unwrap();
+        assert!(gate.check(&rf_freq, Duration::ZERO).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "subghz-sim")]
+    fn record_after_lbt() {
+        struct NoDelay;
+
+        impl embedded_hal::blocking::delay::DelayUs<u32> for NoDelay {
+            fn delay_us(&mut self, _us: u32) {}
+        }
+
+        const MODULATION: Modulation = Modulation::LoRa(
+            LoRaModParams::new(),
+            LoRaPacketParams::new().set_payload_len(16),
+        );
+        let rf_freq: RfFreq = mhz(868_100_000);
+        let now: Duration = Duration::from_secs(1);
+        // 1 % duty cycle
+        let off: Duration = MODULATION.time_on_air() * 100;
+
+        // the channel is quiet for the whole listen time
+        let mut sim: SimRadio = SimRadio::new();
+        sim.set_rssi_inst(-120);
+        let mut sg: SubGhz<SgMiso, SgMosi, SimRadio> = SubGhz::new_with_transport(sim);
+        sg.set_packet_type(PacketType::LoRa).unwrap();
+        let listen: Duration = Duration::from_millis(5);
+        let mut gate: TxGate<6> = TxGate::new(SubBand::EU868).set_lbt(Some(Lbt::Rssi {
+            threshold: -80,
+            listen,
+        }));
+        sg.set_tx_gated(
+            &mut gate,
+            now,
+            &rf_freq,
+            &MODULATION,
+            Timeout::DISABLED,
+            &mut NoDelay,
+        )
+        .unwrap();
+        assert_eq!(sg.transport().mode(), SimMode::Tx);
+        let ready: Duration = now + listen + off;
+        assert_eq!(
+            gate.check(&rf_freq, ready - Duration::from_micros(1)),
+            Err(GateError::DutyCycle {
+                retry_after: Duration::from_micros(1)
+            })
+        );
+        assert_eq!(gate.check(&rf_freq, ready), Ok(()));
+
+        // CAD completes on the first status read
+        let mut sim: SimRadio = SimRadio::new();
+        sim.set_responder(Some(|sim| {
+            sim.cad_done(false);
+        }));
+        let mut sg: SubGhz<SgMiso, SgMosi, SimRadio> = SubGhz::new_with_transport(sim);
+        sg.set_packet_type(PacketType::LoRa).unwrap();
+        sg.set_irq_cfg(&CfgIrq::new().irq_enable_all(Irq::CadDone))
+            .unwrap();
+        let mut gate: TxGate<6> = TxGate::new(SubBand::EU868).set_lbt(Some(Lbt::Cad));
+        sg.set_tx_gated(
+            &mut gate,
+            now,
+            &rf_freq,
+            &MODULATION,
+            Timeout::DISABLED,
+            &mut NoDelay,
+        )
+        .unwrap();
+        assert_eq!(sg.transport().mode(), SimMode::Tx);
+        let ready: Duration = now + Duration::from_micros(100) + off;
+        assert!(gate
+            .check(&rf_freq, ready - Duration::from_micros(1))
+            .is_err());
+        assert_eq!(gate.check(&rf_freq, ready), Ok(()));
+    }
+}
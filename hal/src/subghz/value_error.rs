@@ -1,8 +1,9 @@
 /// Error for a value that is out-of-bounds.
 ///
-/// Used by [`Timeout::from_duration`].
+/// Used by [`Timeout::from_duration`] and [`PaConfig::for_dbm`].
 ///
 /// [`Timeout::from_duration`]: super::Timeout::from_duration
+/// [`PaConfig::for_dbm`]: super::PaConfig::for_dbm
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct ValueError<T> {
@@ -0,0 +1,863 @@
+//! Tamper and backup registers
+//!
+//! The TAMP peripheral is in the backup domain with the RTC, it provides:
+//!
+//! * 20 32-bit backup registers that retain their contents in Standby and
+//!   Shutdown modes, and while V<sub>BAT</sub> is present.
+//! * 3 external tamper inputs, with optional digital filtering.
+//! * Internal tamper detection for LSE failure, RTC calendar overflow,
+//!   debug access, and monotonic counter overflow.
+//!
+//! By default a tamper event erases the backup registers, this can be
+//! disabled per tamper with [`TamperCfg::set_erase`].
+//! A tamper event can also save the calendar, see
+//! `rtc::Rtc::set_tamper_timestamp_en` (requires the `chrono` feature).
+//!
+//! Active tampers (pseudo-random pattern output on TAMP_OUTx compared
+//! with TAMP_INx) are not available on the STM32WL, only passive external
+//! tampers are supported.
+//!
+//! # Example
+//!
+//! Persist a boot counter across resets and low-power modes.
+//!
+//! ```no_run
+//! use stm32wlxx_hal::{
+//!     pac,
+//!     tamp::{Bkp, Tamp},
+//! };
+//!
+//! let mut dp: pac::Peripherals = pac::Peripherals::take().unwrap();
+//!
+//! let mut tamp: Tamp = Tamp::new(dp.TAMP, &mut dp.PWR, &mut dp.RCC);
+//! let boot_count: u32 = tamp.bkp(Bkp::BKP0).wrapping_add(1);
+//! tamp.set_bkp(Bkp::BKP0, boot_count);
+//! ```
+
+use crate::pac;
+
+// read-only in the PAC, any write increments the counter, offset from the
+// TAMP base address
+const COUNTR: usize = 0x40;
+
+// CR2 bits
+const CR2_TAMPNOER_SHIFT: u8 = 0;
+const CR2_TAMPMSK_SHIFT: u8 = 16;
+const CR2_BKERASE: u32 = 1 << 23;
+const CR2_TAMPTRG_SHIFT: u8 = 24;
+
+/// Number of backup registers.
+pub const NUM_BKP: u8 = 20;
+
+/// Pointer to a TAMP register that is read-only in the PAC.
+#[inline(always)]
+fn pac_gap(offset: usize) -> *mut u32 {
+    (pac::TAMP::PTR as *mut u8).wrapping_add(offset) as *mut u32
+}
+
+/// # Safety
+///
+/// `offset` must be one of the register offsets that are read-only in the
+/// PAC.
+#[inline(always)]
+unsafe fn write_gap(offset: usize, val: u32) {
+    pac_gap(offset).write_volatile(val)
+}
+
+/// Status (interrupt) masks.
+///
+/// Used for [`Tamp::status`], [`Tamp::masked_status`], and
+/// [`Tamp::clear_status`].
+pub mod stat {
+    /// Internal tamper 8 (monotonic counter overflow) flag
+    pub const ITAMP8: u32 = 1 << 23;
+    /// Internal tamper 6 (debug access) flag
+    pub const ITAMP6: u32 = 1 << 21;
+    /// Internal tamper 5 (RTC calendar overflow) flag
+    pub const ITAMP5: u32 = 1 << 20;
+    /// Internal tamper 3 (LSE monitoring) flag
+    pub const ITAMP3: u32 = 1 << 18;
+    /// External tamper 3 flag
+    pub const TAMP3: u32 = 1 << 2;
+    /// External tamper 2 flag
+    pub const TAMP2: u32 = 1 << 1;
+    /// External tamper 1 flag
+    pub const TAMP1: u32 = 1 << 0;
+
+    /// All internal tamper flags.
+    pub const ITAMP_ALL: u32 = ITAMP3 | ITAMP5 | ITAMP6 | ITAMP8;
+
+    /// All external tamper flags.
+    pub const TAMP_ALL: u32 = TAMP1 | TAMP2 | TAMP3;
+
+    /// All status flags.
+    pub const ALL: u32 = ITAMP_ALL | TAMP_ALL;
+}
+
+/// Backup register index.
+///
+/// # Example
+///
+/// ```
+/// use stm32wlxx_hal::tamp::Bkp;
+///
+/// assert_eq!(Bkp::new(19).map(Bkp::idx), Some(19));
+/// assert_eq!(Bkp::new(20), None);
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone, Copy, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Bkp(u8);
+
+impl Bkp {
+    /// Backup register 0.
+    pub const BKP0: Bkp = Bkp(0);
+    /// Backup register 1.
+    pub const BKP1: Bkp = Bkp(1);
+    /// Backup register 2.
+    pub const BKP2: Bkp = Bkp(2);
+    /// Backup register 3.
+    pub const BKP3: Bkp = Bkp(3);
+
+    /// Create a backup register index.
+    ///
+    /// Returns `None` if `idx` is greater than or equal to [`NUM_BKP`].
+    pub const fn new(idx: u8) -> Option<Bkp> {
+        if idx < NUM_BKP {
+            Some(Bkp(idx))
+        } else {
+            None
+        }
+    }
+
+    /// Get the backup register index.
+    pub const fn idx(self) -> u8 {
+        self.0
+    }
+
+    const fn usize(self) -> usize {
+        self.0 as usize
+    }
+}
+
+/// External tamper input.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[repr(u8)]
+pub enum Tamper {
+    /// TAMP_IN1
+    In1 = 0,
+    /// TAMP_IN2
+    In2 = 1,
+    /// TAMP_IN3
+    In3 = 2,
+}
+
+impl Tamper {
+    /// Status and interrupt mask for this tamper, see [`stat`].
+    pub const fn mask(self) -> u32 {
+        1 << (self as u8)
+    }
+}
+
+/// Internal tamper source.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[repr(u8)]
+pub enum Internal {
+    /// LSE monitoring (internal tamper 3).
+    Lse = 3,
+    /// RTC calendar overflow (internal tamper 5).
+    RtcOverflow = 5,
+    /// JTAG or SWD access when readout protection is above level 0
+    /// (internal tamper 6).
+    Debug = 6,
+    /// Monotonic counter overflow (internal tamper 8).
+    CounterOverflow = 8,
+}
+
+impl Internal {
+    /// Status and interrupt mask for this internal tamper, see [`stat`].
+    pub const fn mask(self) -> u32 {
+        1 << (15 + self as u8)
+    }
+
+    const fn noer(self) -> u32 {
+        1 << (self as u8 - 1)
+    }
+}
+
+/// External tamper trigger.
+///
+/// The meaning depends on the [`Filter`], edge detection is used when
+/// filtering is disabled, and level detection is used otherwise.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Trigger {
+    /// Rising edge, or low level with filtering.
+    RisingOrLow,
+    /// Falling edge, or high level with filtering.
+    FallingOrHigh,
+}
+
+/// External tamper configuration.
+///
+/// Used for [`Tamp::setup_tamper`].
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct TamperCfg {
+    trigger: Trigger,
+    erase: bool,
+    masked: bool,
+    irq_en: bool,
+}
+
+impl TamperCfg {
+    /// Default tamper configuration.
+    ///
+    /// * Rising edge or low level trigger
+    /// * Backup registers are erased on a tamper event
+    /// * Not masked
+    /// * Interrupt disabled
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::tamp::TamperCfg;
+    ///
+    /// assert_eq!(TamperCfg::DEFAULT, TamperCfg::default());
+    /// ```
+    pub const DEFAULT: TamperCfg = TamperCfg {
+        trigger: Trigger::RisingOrLow,
+        erase: true,
+        masked: false,
+        irq_en: false,
+    };
+
+    /// Set the trigger.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::tamp::{TamperCfg, Trigger};
+    ///
+    /// const CFG: TamperCfg = TamperCfg::DEFAULT.set_trigger(Trigger::FallingOrHigh);
+    /// ```
+    #[must_use = "set_trigger returns a modified TamperCfg"]
+    pub const fn set_trigger(mut self, trigger: Trigger) -> Self {
+        self.trigger = trigger;
+        self
+    }
+
+    /// Set the erase of the backup registers on a tamper event.
+    ///
+    /// When disabled the backup registers are preserved and the tamper event
+    /// only sets the status flag (and timestamp if enabled).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::tamp::TamperCfg;
+    ///
+    /// const CFG: TamperCfg = TamperCfg::DEFAULT.set_erase(false);
+    /// ```
+    #[must_use = "set_erase returns a modified TamperCfg"]
+    pub const fn set_erase(mut self, erase: bool) -> Self {
+        self.erase = erase;
+        self
+    }
+
+    /// Set the tamper masked.
+    ///
+    /// A masked tamper event does not erase the backup registers and the
+    /// status flag is cleared by hardware when the tamper condition is
+    /// removed.
+    /// This is only valid with level detection, and it forces
+    /// [`set_erase(false)`](Self::set_erase).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::tamp::TamperCfg;
+    ///
+    /// const CFG: TamperCfg = TamperCfg::DEFAULT.set_masked(true);
+    /// ```
+    #[must_use = "set_masked returns a modified TamperCfg"]
+    pub const fn set_masked(mut self, masked: bool) -> Self {
+        self.masked = masked;
+        if masked {
+            self.erase = false;
+        }
+        self
+    }
+
+    /// Set the tamper interrupt enable.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::tamp::TamperCfg;
+    ///
+    /// const CFG: TamperCfg = TamperCfg::DEFAULT.set_irq_en(true);
+    /// ```
+    #[must_use = "set_irq_en returns a modified TamperCfg"]
+    pub const fn set_irq_en(mut self, irq_en: bool) -> Self {
+        self.irq_en = irq_en;
+        self
+    }
+}
+
+impl Default for TamperCfg {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// External tamper filter.
+///
+/// This is the number of consecutive samples at the active level required
+/// to detect a tamper event.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[repr(u8)]
+pub enum Filter {
+    /// Filtering disabled, tampers are edge triggered.
+    Edge = 0b00,
+    /// Level detection with 2 samples.
+    Samples2 = 0b01,
+    /// Level detection with 4 samples.
+    Samples4 = 0b10,
+    /// Level detection with 8 samples.
+    Samples8 = 0b11,
+}
+
+/// External tamper sampling frequency.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[repr(u8)]
+pub enum SampleFreq {
+    /// RTCCLK / 32768
+    Div32768 = 0b000,
+    /// RTCCLK / 16384
+    Div16384 = 0b001,
+    /// RTCCLK / 8192
+    Div8192 = 0b010,
+    /// RTCCLK / 4096
+    Div4096 = 0b011,
+    /// RTCCLK / 2048
+    Div2048 = 0b100,
+    /// RTCCLK / 1024
+    Div1024 = 0b101,
+    /// RTCCLK / 512
+    Div512 = 0b110,
+    /// RTCCLK / 256
+    Div256 = 0b111,
+}
+
+/// External tamper precharge duration.
+///
+/// The inputs are precharged with the internal pull-up before each sample
+/// when level detection is used.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[repr(u8)]
+pub enum Precharge {
+    /// 1 RTCCLK cycle
+    Cycles1 = 0b00,
+    /// 2 RTCCLK cycles
+    Cycles2 = 0b01,
+    /// 4 RTCCLK cycles
+    Cycles4 = 0b10,
+    /// 8 RTCCLK cycles
+    Cycles8 = 0b11,
+}
+
+/// External tamper filter configuration.
+///
+/// This is shared by all passive external tampers.
+///
+/// Used for [`Tamp::set_filter_cfg`].
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct FilterCfg {
+    val: u8,
+}
+
+impl FilterCfg {
+    /// Reset filter configuration.
+    ///
+    /// * [`Filter::Edge`]
+    /// * [`SampleFreq::Div32768`]
+    /// * [`Precharge::Cycles1`]
+    /// * Pull-up precharge enabled
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::tamp::FilterCfg;
+    ///
+    /// assert_eq!(FilterCfg::RESET, FilterCfg::default());
+    /// ```
+    pub const RESET: FilterCfg = FilterCfg { val: 0 };
+
+    /// Set the filter.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::tamp::{Filter, FilterCfg};
+    ///
+    /// const CFG: FilterCfg = FilterCfg::RESET.set_filter(Filter::Samples4);
+    /// ```
+    #[must_use = "set_filter returns a modified FilterCfg"]
+    pub const fn set_filter(mut self, filter: Filter) -> Self {
+        self.val = (self.val & !(0b11 << 3)) | ((filter as u8) << 3);
+        self
+    }
+
+    /// Set the sampling frequency.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::tamp::{FilterCfg, SampleFreq};
+    ///
+    /// const CFG: FilterCfg = FilterCfg::RESET.set_sample_freq(SampleFreq::Div256);
+    /// ```
+    #[must_use = "set_sample_freq returns a modified FilterCfg"]
+    pub const fn set_sample_freq(mut self, freq: SampleFreq) -> Self {
+        self.val = (self.val & !0b111) | (freq as u8);
+        self
+    }
+
+    /// Set the precharge duration.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::tamp::{FilterCfg, Precharge};
+    ///
+    /// const CFG: FilterCfg = FilterCfg::RESET.set_precharge(Precharge::Cycles8);
+    /// ```
+    #[must_use = "set_precharge returns a modified FilterCfg"]
+    pub const fn set_precharge(mut self, precharge: Precharge) -> Self {
+        self.val = (self.val & !(0b11 << 5)) | ((precharge as u8) << 5);
+        self
+    }
+
+    /// Enable or disable the pull-up precharge.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::tamp::FilterCfg;
+    ///
+    /// const CFG: FilterCfg = FilterCfg::RESET.set_pull_up_en(false);
+    /// ```
+    #[must_use = "set_pull_up_en returns a modified FilterCfg"]
+    pub const fn set_pull_up_en(mut self, en: bool) -> Self {
+        if en {
+            self.val &= !(1 << 7);
+        } else {
+            self.val |= 1 << 7;
+        }
+        self
+    }
+}
+
+impl Default for FilterCfg {
+    fn default() -> Self {
+        Self::RESET
+    }
+}
+
+/// Tamper and backup register driver.
+#[derive(Debug)]
+pub struct Tamp {
+    tamp: pac::TAMP,
+}
+
+impl Tamp {
+    /// Create a new tamper driver.
+    ///
+    /// This will disable the backup domain write protection and enable the
+    /// TAMP APB clock, like `Rtc::new`.
+    ///
+    /// The TAMP is in the backup domain; system resets will not reset the
+    /// backup registers or the tamper configuration.
+    ///
+    /// **Note:** The tamper filters are clocked by RTCCLK, which must be
+    /// enabled for them to operate.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use stm32wlxx_hal::{pac, tamp::Tamp};
+    ///
+    /// let mut dp: pac::Peripherals = pac::Peripherals::take().unwrap();
+    /// let tamp: Tamp = Tamp::new(dp.TAMP, &mut dp.PWR, &mut dp.RCC);
+    /// ```
+    pub fn new(tamp: pac::TAMP, pwr: &mut pac::PWR, rcc: &mut pac::RCC) -> Tamp {
+        pwr.cr1.modify(|_, w| w.dbp().enabled());
+        Self::apbclken(rcc);
+        Tamp { tamp }
+    }
+
+    #[inline(always)]
+    fn apbclken(rcc: &mut pac::RCC) {
+        #[cfg(not(feature = "stm32wl5x_cm0p"))]
+        rcc.apb1enr1.modify(|_, w| w.rtcapben().set_bit());
+        #[cfg(feature = "stm32wl5x_cm0p")]
+        rcc.c2apb1enr1.modify(|_, w| w.rtcapben().set_bit());
+    }
+
+    /// Free the TAMP peripheral from the driver.
+    #[inline]
+    pub fn free(self) -> pac::TAMP {
+        self.tamp
+    }
+
+    /// Steal the TAMP peripheral from whatever is currently using it.
+    ///
+    /// # Safety
+    ///
+    /// 1. Ensure that the code stealing the TAMP has exclusive access to the
+    ///    peripheral. Singleton checks are bypassed with this method.
+    /// 2. You are responsible for enabling the TAMP APB clock and disabling
+    ///    the backup domain write protection.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::tamp::Tamp;
+    ///
+    /// // ... setup happens here
+    ///
+    /// let tamp: Tamp = unsafe { Tamp::steal() };
+    /// ```
+    #[inline]
+    pub unsafe fn steal() -> Tamp {
+        Tamp {
+            tamp: pac::Peripherals::steal().TAMP,
+        }
+    }
+
+    /// Read a backup register.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use stm32wlxx_hal::{
+    ///     pac,
+    ///     tamp::{Bkp, Tamp},
+    /// };
+    ///
+    /// let mut dp: pac::Peripherals = pac::Peripherals::take().unwrap();
+    /// let tamp: Tamp = Tamp::new(dp.TAMP, &mut dp.PWR, &mut dp.RCC);
+    /// let magic: u32 = tamp.bkp(Bkp::new(12).unwrap());
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn bkp(&self, bkp: Bkp) -> u32 {
+        self.tamp.bkpr[bkp.usize()].read().bits()
+    }
+
+    /// Write a backup register.
+    ///
+    /// The write is ignored if the backup domain write protection is
+    /// enabled.
+    #[inline]
+    pub fn set_bkp(&mut self, bkp: Bkp, val: u32) {
+        self.tamp.bkpr[bkp.usize()].write(|w| w.bits(val))
+    }
+
+    /// Erase all backup registers.
+    ///
+    /// This is the same erase that is performed on a tamper event.
+    #[inline]
+    pub fn erase_bkp(&mut self) {
+        // safety: BKERASE is cleared by hardware after the erase
+        self.tamp
+            .cr2
+            .modify(|r, w| unsafe { w.bits(r.bits() | CR2_BKERASE) })
+    }
+
+    /// Setup an external tamper.
+    ///
+    /// This will enable the tamper with passive detection.
+    ///
+    /// # Example
+    ///
+    /// Detect a case opening switch pulling TAMP_IN1 low, and keep the backup
+    /// registers.
+    ///
+    /// ```no_run
+    /// use stm32wlxx_hal::{
+    ///     pac,
+    ///     tamp::{Filter, FilterCfg, Tamp, TamperCfg, Tamper, Trigger},
+    /// };
+    ///
+    /// let mut dp: pac::Peripherals = pac::Peripherals::take().unwrap();
+    /// let mut tamp: Tamp = Tamp::new(dp.TAMP, &mut dp.PWR, &mut dp.RCC);
+    ///
+    /// tamp.set_filter_cfg(&FilterCfg::RESET.set_filter(Filter::Samples4));
+    /// tamp.setup_tamper(
+    ///     Tamper::In1,
+    ///     &TamperCfg::DEFAULT
+    ///         .set_trigger(Trigger::RisingOrLow)
+    ///         .set_erase(false),
+    /// );
+    /// ```
+    pub fn setup_tamper(&mut self, tamper: Tamper, cfg: &TamperCfg) {
+        let n: u8 = tamper as u8;
+        let mask: u32 = tamper.mask();
+
+        self.disable_tamper(tamper);
+
+        let mut cr2_set: u32 = 0;
+        if !cfg.erase {
+            cr2_set |= mask << CR2_TAMPNOER_SHIFT;
+        }
+        if cfg.masked {
+            cr2_set |= mask << CR2_TAMPMSK_SHIFT;
+        }
+        if matches!(cfg.trigger, Trigger::FallingOrHigh) {
+            cr2_set |= 1 << (CR2_TAMPTRG_SHIFT + n);
+        }
+        let cr2_clear: u32 = (mask << CR2_TAMPNOER_SHIFT)
+            | (mask << CR2_TAMPMSK_SHIFT)
+            | (1 << (CR2_TAMPTRG_SHIFT + n));
+
+        // safety: only bits for this tamper are modified
+        unsafe {
+            self.tamp
+                .cr2
+                .modify(|r, w| w.bits((r.bits() & !cr2_clear) | cr2_set));
+            self.tamp.ier.modify(|r, w| {
+                w.bits(if cfg.irq_en {
+                    r.bits() | mask
+                } else {
+                    r.bits() & !mask
+                })
+            });
+            self.tamp.cr1.modify(|r, w| w.bits(r.bits() | mask));
+        }
+    }
+
+    /// Disable an external tamper.
+    #[inline]
+    pub fn disable_tamper(&mut self, tamper: Tamper) {
+        // safety: only the enable bit for this tamper is modified
+        self.tamp
+            .cr1
+            .modify(|r, w| unsafe { w.bits(r.bits() & !tamper.mask()) })
+    }
+
+    /// Returns `true` if an external tamper is enabled.
+    #[inline]
+    #[must_use]
+    pub fn is_tamper_en(&self, tamper: Tamper) -> bool {
+        self.tamp.cr1.read().bits() & tamper.mask() != 0
+    }
+
+    /// Set the external tamper filter configuration.
+    ///
+    /// This applies to all passive external tampers.
+    /// Disable the external tampers before changing the filter.
+    #[inline]
+    pub fn set_filter_cfg(&mut self, cfg: &FilterCfg) {
+        // safety: all fields of the register are covered by FilterCfg
+        self.tamp
+            .fltcr
+            .write(|w| unsafe { w.bits(u32::from(cfg.val)) })
+    }
+
+    /// Setup an internal tamper.
+    ///
+    /// # Example
+    ///
+    /// Erase the backup registers if the LSE fails.
+    ///
+    /// ```no_run
+    /// use stm32wlxx_hal::{
+    ///     pac,
+    ///     tamp::{Internal, Tamp},
+    /// };
+    ///
+    /// let mut dp: pac::Peripherals = pac::Peripherals::take().unwrap();
+    /// let mut tamp: Tamp = Tamp::new(dp.TAMP, &mut dp.PWR, &mut dp.RCC);
+    /// tamp.setup_internal(Internal::Lse, true, false);
+    /// ```
+    pub fn setup_internal(&mut self, internal: Internal, erase: bool, irq_en: bool) {
+        let mask: u32 = internal.mask();
+        let noer: u32 = internal.noer();
+
+        // safety: only bits for this internal tamper are modified
+        unsafe {
+            self.tamp.cr3.modify(|r, w| {
+                w.bits(if erase {
+                    r.bits() & !noer
+                } else {
+                    r.bits() | noer
+                })
+            });
+            self.tamp.ier.modify(|r, w| {
+                w.bits(if irq_en {
+                    r.bits() | mask
+                } else {
+                    r.bits() & !mask
+                })
+            });
+            self.tamp.cr1.modify(|r, w| w.bits(r.bits() | mask));
+        }
+    }
+
+    /// Disable an internal tamper.
+    #[inline]
+    pub fn disable_internal(&mut self, internal: Internal) {
+        // safety: only the enable bit for this internal tamper is modified
+        self.tamp
+            .cr1
+            .modify(|r, w| unsafe { w.bits(r.bits() & !internal.mask()) })
+    }
+
+    /// Returns `true` if an internal tamper is enabled.
+    #[inline]
+    #[must_use]
+    pub fn is_internal_en(&self, internal: Internal) -> bool {
+        self.tamp.cr1.read().bits() & internal.mask() != 0
+    }
+
+    /// Read the monotonic counter.
+    #[inline]
+    #[must_use]
+    pub fn counter(&self) -> u32 {
+        self.tamp.countr.read().bits()
+    }
+
+    /// Increment the monotonic counter.
+    ///
+    /// The counter is reset to zero by a tamper event or a backup domain
+    /// reset, overflow is detected with [`Internal::CounterOverflow`].
+    #[inline]
+    pub fn increment_counter(&mut self) {
+        // safety: any write increments the counter
+        unsafe { write_gap(COUNTR, 0) }
+    }
+
+    /// Read the tamper status register.
+    ///
+    /// Status flag masks can be found in [`stat`].
+    #[inline]
+    pub fn status() -> u32 {
+        // safety: atomic read with no side effects
+        unsafe { (*pac::TAMP::PTR).sr.read().bits() }
+    }
+
+    /// Read the tamper masked status (interrupt) register.
+    ///
+    /// Status flag masks can be found in [`stat`].
+    #[inline]
+    pub fn masked_status() -> u32 {
+        // safety: atomic read with no side effects
+        unsafe { (*pac::TAMP::PTR).misr.read().bits() }
+    }
+
+    /// Clear status (interrupt) flags.
+    ///
+    /// Status flag masks can be found in [`stat`].
+    #[inline]
+    pub fn clear_status(mask: u32) {
+        // safety: mask is masked with valid register fields
+        unsafe { (*pac::TAMP::PTR).scr.write(|w| w.bits(mask & stat::ALL)) }
+    }
+
+    /// Unmask the TAMP IRQ in the NVIC.
+    ///
+    /// This interrupt is shared with the RTC timestamp, LSE CSS, and RTC
+    /// SSR underflow.
+    ///
+    /// # Safety
+    ///
+    /// This can break mask-based critical sections.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # #[cfg(all(not(feature = "stm32wl5x_cm0p"), feature = "rt"))]
+    /// unsafe { stm32wlxx_hal::tamp::Tamp::unmask_irq() };
+    /// ```
+    #[cfg(all(not(feature = "stm32wl5x_cm0p"), feature = "rt"))]
+    #[inline]
+    pub unsafe fn unmask_irq() {
+        pac::NVIC::unmask(pac::Interrupt::TAMP_STAMP_LSE_CSS_SSRU)
+    }
+
+    /// Mask the TAMP IRQ in the NVIC.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # #[cfg(all(not(feature = "stm32wl5x_cm0p"), feature = "rt"))]
+    /// stm32wlxx_hal::tamp::Tamp::mask_irq()
+    /// ```
+    #[cfg(all(not(feature = "stm32wl5x_cm0p"), feature = "rt"))]
+    #[inline]
+    pub fn mask_irq() {
+        pac::NVIC::mask(pac::Interrupt::TAMP_STAMP_LSE_CSS_SSRU)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Filter, FilterCfg, Precharge, SampleFreq};
+
+    #[test]
+    fn filter_cfg_fltcr() {
+        assert_eq!(FilterCfg::RESET.val, 0x00);
+
+        // TAMPFREQ[2:0]
+        assert_eq!(
+            FilterCfg::RESET.set_sample_freq(SampleFreq::Div256).val,
+            0b111
+        );
+        assert_eq!(
+            FilterCfg::RESET.set_sample_freq(SampleFreq::Div2048).val,
+            0b100
+        );
+        // TAMPFLT[4:3]
+        assert_eq!(FilterCfg::RESET.set_filter(Filter::Samples2).val, 0b01 << 3);
+        assert_eq!(FilterCfg::RESET.set_filter(Filter::Samples8).val, 0b11 << 3);
+        // TAMPPRCH[6:5]
+        assert_eq!(
+            FilterCfg::RESET.set_precharge(Precharge::Cycles4).val,
+            0b10 << 5
+        );
+        assert_eq!(
+            FilterCfg::RESET.set_precharge(Precharge::Cycles8).val,
+            0b11 << 5
+        );
+        // TAMPPUDIS
+        assert_eq!(FilterCfg::RESET.set_pull_up_en(false).val, 1 << 7);
+        assert_eq!(
+            FilterCfg::RESET
+                .set_pull_up_en(false)
+                .set_pull_up_en(true)
+                .val,
+            0x00
+        );
+
+        let cfg: FilterCfg = FilterCfg::RESET
+            .set_sample_freq(SampleFreq::Div1024)
+            .set_filter(Filter::Samples4)
+            .set_precharge(Precharge::Cycles2)
+            .set_pull_up_en(false);
+        assert_eq!(cfg.val, 0b1011_0101);
+
+        // setters replace the field without affecting the others
+        let cfg: FilterCfg = cfg
+            .set_sample_freq(SampleFreq::Div32768)
+            .set_filter(Filter::Edge)
+            .set_precharge(Precharge::Cycles1);
+        assert_eq!(cfg.val, 1 << 7);
+    }
+}
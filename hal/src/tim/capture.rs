@@ -0,0 +1,715 @@
+use super::{modify_reg, sealed::SlaveMode, Ch, Error, Tim, Tim1, Tim2, Timing, CR1_DIR};
+use crate::{
+    dma::{self, DmaCh},
+    gpio::sealed::{Tim1Ch1, Tim1Ch2, Tim1Ch3, Tim1Ch4, Tim2Ch1, Tim2Ch2, Tim2Ch3, Tim2Ch4},
+};
+use core::{
+    marker::PhantomData,
+    sync::atomic::{compiler_fence, Ordering::SeqCst},
+};
+use cortex_m::interrupt::CriticalSection;
+use paste::paste;
+
+/// Input capture edge.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Edge {
+    /// Capture on the rising edge.
+    Rising,
+    /// Capture on the falling edge.
+    Falling,
+    /// Capture on both edges.
+    Both,
+}
+
+impl Edge {
+    /// CCxNP and CCxP bits.
+    const fn bits(self) -> u32 {
+        match self {
+            Edge::Rising => 0b0000,
+            Edge::Falling => 0b0010,
+            Edge::Both => 0b1010,
+        }
+    }
+}
+
+/// Input filter.
+///
+/// An event is valid after `N` consecutive samples at the sampling
+/// frequency.
+/// `CkInt` samples at the timer clock frequency, `Dts` samples at the
+/// timer clock frequency divided by the given factor.
+#[repr(u8)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Filter {
+    /// No filter.
+    None = 0b0000,
+    /// Timer clock, N = 2.
+    CkIntN2 = 0b0001,
+    /// Timer clock, N = 4.
+    CkIntN4 = 0b0010,
+    /// Timer clock, N = 8.
+    CkIntN8 = 0b0011,
+    /// Timer clock / 2, N = 6.
+    Dts2N6 = 0b0100,
+    /// Timer clock / 2, N = 8.
+    Dts2N8 = 0b0101,
+    /// Timer clock / 4, N = 6.
+    Dts4N6 = 0b0110,
+    /// Timer clock / 4, N = 8.
+    Dts4N8 = 0b0111,
+    /// Timer clock / 8, N = 6.
+    Dts8N6 = 0b1000,
+    /// Timer clock / 8, N = 8.
+    Dts8N8 = 0b1001,
+    /// Timer clock / 16, N = 5.
+    Dts16N5 = 0b1010,
+    /// Timer clock / 16, N = 6.
+    Dts16N6 = 0b1011,
+    /// Timer clock / 16, N = 8.
+    Dts16N8 = 0b1100,
+    /// Timer clock / 32, N = 5.
+    Dts32N5 = 0b1101,
+    /// Timer clock / 32, N = 6.
+    Dts32N6 = 0b1110,
+    /// Timer clock / 32, N = 8.
+    Dts32N8 = 0b1111,
+}
+
+/// Input capture prescaler.
+///
+/// Number of events required for a capture.
+#[repr(u8)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum CapturePrescaler {
+    /// Capture every event.
+    Div1 = 0b00,
+    /// Capture every 2 events.
+    Div2 = 0b01,
+    /// Capture every 4 events.
+    Div4 = 0b10,
+    /// Capture every 8 events.
+    Div8 = 0b11,
+}
+
+/// Input capture channel configuration.
+///
+/// # Example
+///
+/// ```
+/// use stm32wlxx_hal::tim::{CaptureCfg, Edge, Filter};
+///
+/// const CFG: CaptureCfg = CaptureCfg::new()
+///     .set_edge(Edge::Falling)
+///     .set_filter(Filter::CkIntN8);
+/// assert_eq!(CFG.edge(), Edge::Falling);
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct CaptureCfg {
+    edge: Edge,
+    filter: Filter,
+    pre: CapturePrescaler,
+}
+
+impl CaptureCfg {
+    /// Create a new capture configuration.
+    ///
+    /// This captures every rising edge without a filter, and it is the same
+    /// as `default`, but in a `const` fn.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::tim::CaptureCfg;
+    ///
+    /// assert_eq!(CaptureCfg::new(), CaptureCfg::default());
+    /// ```
+    #[inline]
+    pub const fn new() -> Self {
+        CaptureCfg {
+            edge: Edge::Rising,
+            filter: Filter::None,
+            pre: CapturePrescaler::Div1,
+        }
+    }
+
+    /// Set the capture edge.
+    #[must_use = "set_edge returns a modified CaptureCfg"]
+    pub const fn set_edge(mut self, edge: Edge) -> CaptureCfg {
+        self.edge = edge;
+        self
+    }
+
+    /// Get the capture edge.
+    pub const fn edge(&self) -> Edge {
+        self.edge
+    }
+
+    /// Set the input filter.
+    #[must_use = "set_filter returns a modified CaptureCfg"]
+    pub const fn set_filter(mut self, filter: Filter) -> CaptureCfg {
+        self.filter = filter;
+        self
+    }
+
+    /// Get the input filter.
+    pub const fn filter(&self) -> Filter {
+        self.filter
+    }
+
+    /// Set the input capture prescaler.
+    #[must_use = "set_prescaler returns a modified CaptureCfg"]
+    pub const fn set_prescaler(mut self, pre: CapturePrescaler) -> CaptureCfg {
+        self.pre = pre;
+        self
+    }
+
+    /// Get the input capture prescaler.
+    pub const fn prescaler(&self) -> CapturePrescaler {
+        self.pre
+    }
+
+    /// Value of the CCMR register half for this channel.
+    const fn ccmr(&self) -> u32 {
+        // CCxS = 0b01, ICx is mapped on TIx
+        0b01 | ((self.pre as u32) << 2) | ((self.filter as u32) << 4)
+    }
+}
+
+impl Default for CaptureCfg {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Extends a free-running counter to 32 bits.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+struct Extender {
+    mask: u32,
+    last: u32,
+    ticks: u32,
+}
+
+impl Extender {
+    const fn new(mask: u32, cnt: u32) -> Self {
+        Extender {
+            mask,
+            last: cnt,
+            ticks: cnt,
+        }
+    }
+
+    /// Advance to a new counter value.
+    ///
+    /// At most one counter period can have elapsed since the last call.
+    fn advance(&mut self, cnt: u32) -> u32 {
+        let delta: u32 = cnt.wrapping_sub(self.last) & self.mask;
+        self.last = cnt;
+        self.ticks = self.ticks.wrapping_add(delta);
+        self.ticks
+    }
+
+    /// Extend a raw capture, `cnt` was read after the capture occurred.
+    fn extend(&mut self, raw: u32, cnt: u32) -> u32 {
+        let now: u32 = self.advance(cnt);
+        now.wrapping_sub(cnt.wrapping_sub(raw) & self.mask)
+    }
+
+    /// Extend a sequence of raw captures that occurred after the last
+    /// counter value, in place.
+    fn extend_in_place(&mut self, buf: &mut [u32]) {
+        buf.iter_mut().for_each(|raw| *raw = self.advance(*raw))
+    }
+
+    /// Signed difference from the last counter value.
+    fn advance_signed(&mut self, cnt: u32) -> i32 {
+        let delta: u32 = cnt.wrapping_sub(self.last) & self.mask;
+        self.last = cnt;
+        if delta > self.mask / 2 {
+            (i64::from(delta) - i64::from(self.mask) - 1) as i32
+        } else {
+            delta as i32
+        }
+    }
+}
+
+/// Input capture timer.
+///
+/// The counter is free-running over the full range of the timer, and the
+/// captured values are extended to 32 bits in software, see
+/// [`ticks`](Self::ticks) for the requirements.
+///
+/// # Example
+///
+/// Measure the period of a signal on pin A8.
+///
+/// ```no_run
+/// use stm32wlxx_hal::{
+///     cortex_m,
+///     gpio::{pins, PortA},
+///     pac,
+///     tim::{CaptureCfg, CaptureCh, InputCapture, Tim, Tim1},
+/// };
+///
+/// let mut dp: pac::Peripherals = pac::Peripherals::take().unwrap();
+///
+/// let pa: PortA = PortA::split(dp.GPIOA, &mut dp.RCC);
+/// let tim1: Tim1 = Tim1::new(dp.TIM1, &mut dp.RCC);
+/// let mut ic: InputCapture<Tim1> = InputCapture::new(tim1, 0);
+/// let ch1: CaptureCh<Tim1, pins::A8> =
+///     cortex_m::interrupt::free(|cs| ic.new_ch1(pa.a8, CaptureCfg::new(), cs));
+///
+/// let first: u32 = nb::block!(ic.capture(&ch1))?;
+/// let second: u32 = nb::block!(ic.capture(&ch1))?;
+/// let period_ticks: u32 = second.wrapping_sub(first);
+/// let hz: u32 = ic.tick_hz() / period_ticks;
+/// # Ok::<(), stm32wlxx_hal::tim::Error>(())
+/// ```
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct InputCapture<TIM> {
+    tim: TIM,
+    ext: Extender,
+}
+
+/// Input capture channel bound to a pin.
+///
+/// Constructed with the `new_ch*` methods on [`InputCapture`], for example
+/// [`InputCapture::<Tim1>::new_ch1`](InputCapture::<Tim1>::new_ch1).
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct CaptureCh<TIM, P> {
+    pin: P,
+    ch: Ch,
+    _tim: PhantomData<TIM>,
+}
+
+impl<TIM, P> CaptureCh<TIM, P> {
+    /// Get the timer channel.
+    #[inline]
+    pub fn ch(&self) -> Ch {
+        self.ch
+    }
+}
+
+impl<TIM: Tim> InputCapture<TIM> {
+    /// Create a new input capture timer.
+    ///
+    /// This starts the counter at the timer clock divided by `psc + 1`.
+    pub fn new(mut tim: TIM, psc: u16) -> Self {
+        tim.stop_counter();
+        tim.set_timing(Timing::from_raw(psc, TIM::MAX_ARR));
+        tim.start_counter();
+        let ext: Extender = Extender::new(TIM::MAX_ARR, tim.cnt());
+        Self { tim, ext }
+    }
+
+    /// Stop the counter and free the timer.
+    ///
+    /// Channels should be freed with [`free_ch`](Self::free_ch) first.
+    #[inline]
+    pub fn free(mut self) -> TIM {
+        self.tim.stop_counter();
+        self.tim
+    }
+
+    /// Disable a capture channel and free the pin.
+    pub fn free_ch<P>(&mut self, ch: CaptureCh<TIM, P>) -> P {
+        modify_reg(TIM::read_ccer, TIM::write_ccer, |ccer| ccer & !ch.ch.cce());
+        ch.pin
+    }
+
+    /// Tick frequency in hertz.
+    ///
+    /// This is the timer clock divided by the prescaler.
+    #[inline]
+    pub fn tick_hz(&self) -> u32 {
+        self.tim.hz().to_integer() / (u32::from(self.tim.timing().psc()) + 1)
+    }
+
+    /// Get the counter value extended to 32 bits.
+    ///
+    /// The 16-bit counters overflow every 65536 ticks, this method (or
+    /// [`capture`](Self::capture)) must be called at least once per overflow
+    /// to keep track of the overflows, for example from the update
+    /// interrupt ([`irq::UI`](super::irq::UI)).
+    ///
+    /// The extended value wraps around at `u32::MAX`, use
+    /// [`u32::wrapping_sub`] to compute differences.
+    #[inline]
+    pub fn ticks(&mut self) -> u32 {
+        let cnt: u32 = self.tim.cnt();
+        self.ext.advance(cnt)
+    }
+
+    fn setup_ch(&mut self, ch: Ch, cfg: &CaptureCfg) {
+        debug_assert!((ch as u8) < TIM::NUM_CH);
+        let shift: u8 = ch.ccmr_shift();
+        let polarity: u32 = cfg.edge.bits() << ((ch as u8) * 4);
+        let polarity_mask: u32 = Edge::Both.bits() << ((ch as u8) * 4);
+
+        modify_reg(TIM::read_ccer, TIM::write_ccer, |ccer| ccer & !ch.cce());
+        modify_reg(
+            || TIM::read_ccmr(ch),
+            |ccmr| TIM::write_ccmr(ch, ccmr),
+            |ccmr| (ccmr & !(0xFF << shift) & !(1 << (16 + shift))) | (cfg.ccmr() << shift),
+        );
+        modify_reg(TIM::read_ccer, TIM::write_ccer, |ccer| {
+            (ccer & !polarity_mask) | polarity | ch.cce()
+        });
+    }
+
+    /// Read a capture extended to 32 bits.
+    ///
+    /// The capture must be read within one counter period of occurring
+    /// for the extension to be correct.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::Overcapture`] a capture occurred before the previous
+    ///   capture was read, the capture is discarded.
+    pub fn capture<P>(&mut self, ch: &CaptureCh<TIM, P>) -> nb::Result<u32, Error> {
+        let sr: u32 = TIM::sr();
+        if sr & ch.ch.ccif() == 0 {
+            return Err(nb::Error::WouldBlock);
+        }
+
+        // reading CCR clears the CCxIF flag
+        let raw: u32 = TIM::read_ccr(ch.ch);
+        let cnt: u32 = TIM::read_cnt();
+        let ticks: u32 = self.ext.extend(raw, cnt);
+
+        if sr & ch.ch.ccof() != 0 {
+            // flags are cleared by writing 0, writing 1 has no effect
+            TIM::write_sr(!ch.ch.ccof());
+            Err(nb::Error::Other(Error::Overcapture))
+        } else {
+            Ok(ticks)
+        }
+    }
+
+    /// Fill a buffer with captures using DMA.
+    ///
+    /// This is blocking, it returns when the buffer is full.
+    ///
+    /// Captures are extended to 32 bits, consecutive captures must occur
+    /// within one counter period of each other, and the first capture must
+    /// occur within one counter period of calling this method.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::Dma`] DMA transfer error.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use stm32wlxx_hal::{
+    ///     cortex_m,
+    ///     dma::AllDma,
+    ///     gpio::{pins, PortA},
+    ///     pac,
+    ///     tim::{CaptureCfg, CaptureCh, InputCapture, Tim, Tim2},
+    /// };
+    ///
+    /// let mut dp: pac::Peripherals = pac::Peripherals::take().unwrap();
+    ///
+    /// let mut dma: AllDma = AllDma::split(dp.DMAMUX, dp.DMA1, dp.DMA2, &mut dp.RCC);
+    /// let pa: PortA = PortA::split(dp.GPIOA, &mut dp.RCC);
+    /// let tim2: Tim2 = Tim2::new(dp.TIM2, &mut dp.RCC);
+    /// let mut ic: InputCapture<Tim2> = InputCapture::new(tim2, 0);
+    /// let ch1: CaptureCh<Tim2, pins::A0> =
+    ///     cortex_m::interrupt::free(|cs| ic.new_ch1(pa.a0, CaptureCfg::new(), cs));
+    ///
+    /// let mut buf: [u32; 16] = [0; 16];
+    /// ic.capture_dma(&ch1, &mut dma.d1.c1, &mut buf)?;
+    /// # Ok::<(), stm32wlxx_hal::tim::Error>(())
+    /// ```
+    pub fn capture_dma<P, D: DmaCh>(
+        &mut self,
+        ch: &CaptureCh<TIM, P>,
+        dma: &mut D,
+        buf: &mut [u32],
+    ) -> Result<(), Error> {
+        if buf.is_empty() {
+            return Ok(());
+        }
+
+        const CR: dma::Cr = dma::Cr::RESET
+            .set_dir_from_periph()
+            .set_mem_inc(true)
+            .set_mem_size(dma::Size::Bits32)
+            .set_periph_size(dma::Size::Bits32)
+            .set_enable(true);
+
+        dma.set_cr(dma::Cr::DISABLE);
+        dma.clear_all_flags();
+        dma.set_periph_addr(TIM::ccr_addr(ch.ch));
+        dma.set_mux_cr_reqid(TIM::DMA_REQ_ID[ch.ch as usize]);
+        dma.set_mem_addr(buf.as_mut_ptr() as u32);
+        dma.set_num_data_xfer(buf.len() as u32);
+
+        // discard stale captures
+        TIM::read_ccr(ch.ch);
+        TIM::write_sr(!ch.ch.ccof());
+        self.ticks();
+
+        dma.set_cr(CR);
+        modify_reg(TIM::read_dier, TIM::write_dier, |dier| dier | ch.ch.ccde());
+
+        let ret: Result<(), Error> = loop {
+            let flags: u8 = dma.flags();
+            if flags & dma::flags::XFER_ERR != 0 {
+                break Err(Error::Dma);
+            }
+            if flags & dma::flags::XFER_CPL != 0 {
+                break Ok(());
+            }
+        };
+
+        modify_reg(TIM::read_dier, TIM::write_dier, |dier| dier & !ch.ch.ccde());
+        dma.set_cr(dma::Cr::DISABLE);
+        dma.clear_all_flags();
+        compiler_fence(SeqCst);
+
+        if ret.is_ok() {
+            self.ext.extend_in_place(buf);
+        }
+
+        ret
+    }
+}
+
+macro_rules! impl_capture_ch {
+    ($tim:ident, $n:expr, $ch:ident, $trt:ident, $method:ident) => {
+        paste! {
+            impl InputCapture<$tim> {
+                #[doc = "Setup a new input capture on channel " $n "."]
+                #[inline]
+                pub fn [<new_ $ch:lower>]<P: $trt>(
+                    &mut self,
+                    mut pin: P,
+                    cfg: CaptureCfg,
+                    cs: &CriticalSection,
+                ) -> CaptureCh<$tim, P> {
+                    pin.$method(cs);
+                    self.setup_ch(Ch::$ch, &cfg);
+                    CaptureCh {
+                        pin,
+                        ch: Ch::$ch,
+                        _tim: PhantomData,
+                    }
+                }
+            }
+        }
+    };
+}
+
+impl_capture_ch!(Tim1, 1, Ch1, Tim1Ch1, set_tim1_ch1_af);
+impl_capture_ch!(Tim1, 2, Ch2, Tim1Ch2, set_tim1_ch2_af);
+impl_capture_ch!(Tim1, 3, Ch3, Tim1Ch3, set_tim1_ch3_af);
+impl_capture_ch!(Tim1, 4, Ch4, Tim1Ch4, set_tim1_ch4_af);
+impl_capture_ch!(Tim2, 1, Ch1, Tim2Ch1, set_tim2_ch1_af);
+impl_capture_ch!(Tim2, 2, Ch2, Tim2Ch2, set_tim2_ch2_af);
+impl_capture_ch!(Tim2, 3, Ch3, Tim2Ch3, set_tim2_ch3_af);
+impl_capture_ch!(Tim2, 4, Ch4, Tim2Ch4, set_tim2_ch4_af);
+
+/// Quadrature encoder mode.
+///
+/// The discriminant is the SMCR SMS value, encoder mode 1 (`0b001`) counts
+/// on TI2 edges, and encoder mode 2 (`0b010`) counts on TI1 edges.
+#[repr(u8)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum EncoderMode {
+    /// Count on channel 1 edges, 2 counts per cycle.
+    Ch1 = 0b010,
+    /// Count on channel 2 edges, 2 counts per cycle.
+    Ch2 = 0b001,
+    /// Count on channel 1 and channel 2 edges, 4 counts per cycle.
+    Both = 0b011,
+}
+
+/// Quadrature encoder.
+///
+/// Implements the `embedded-hal` [`Qei`](embedded_hal::Qei) trait.
+///
+/// # Example
+///
+/// ```no_run
+/// use stm32wlxx_hal::{
+///     cortex_m,
+///     gpio::{pins, PortA},
+///     pac,
+///     tim::{Encoder, EncoderMode, Filter, Tim, Tim1},
+/// };
+///
+/// let mut dp: pac::Peripherals = pac::Peripherals::take().unwrap();
+///
+/// let pa: PortA = PortA::split(dp.GPIOA, &mut dp.RCC);
+/// let tim1: Tim1 = Tim1::new(dp.TIM1, &mut dp.RCC);
+/// let mut enc: Encoder<Tim1, pins::A8, pins::A9> = cortex_m::interrupt::free(|cs| {
+///     Encoder::<Tim1, _, _>::new(tim1, (pa.a8, pa.a9), EncoderMode::Both, Filter::CkIntN8, cs)
+/// });
+///
+/// let position: i32 = enc.position();
+/// ```
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Encoder<TIM, P1, P2> {
+    tim: TIM,
+    pins: (P1, P2),
+    ext: Extender,
+    pos: i32,
+}
+
+impl<TIM: Tim + SlaveMode, P1, P2> Encoder<TIM, P1, P2> {
+    fn setup(mut tim: TIM, pins: (P1, P2), mode: EncoderMode, filter: Filter) -> Self {
+        tim.stop_counter();
+        tim.set_timing(Timing::from_raw(0, TIM::MAX_ARR));
+
+        let ccmr: u32 = CaptureCfg::new().set_filter(filter).ccmr();
+        TIM::write_ccer(0);
+        TIM::write_ccmr(Ch::Ch1, ccmr | (ccmr << 8));
+        TIM::write_smcr(mode as u32);
+
+        tim.start_counter();
+        let ext: Extender = Extender::new(TIM::MAX_ARR, tim.cnt());
+        Self {
+            tim,
+            pins,
+            ext,
+            pos: 0,
+        }
+    }
+
+    /// Stop the encoder and free the timer and pins.
+    pub fn free(mut self) -> (TIM, (P1, P2)) {
+        self.tim.stop_counter();
+        TIM::write_smcr(0);
+        (self.tim, self.pins)
+    }
+
+    /// Get the position.
+    ///
+    /// This is the raw counter value extended to a signed 32-bit value.
+    ///
+    /// The counter must not move more than half of the counter range
+    /// (32768 counts for 16-bit timers) between calls.
+    #[inline]
+    pub fn position(&mut self) -> i32 {
+        let cnt: u32 = self.tim.cnt();
+        self.pos = self.pos.wrapping_add(self.ext.advance_signed(cnt));
+        self.pos
+    }
+
+    /// Set the position.
+    #[inline]
+    pub fn set_position(&mut self, pos: i32) {
+        self.position();
+        self.pos = pos;
+    }
+}
+
+impl<P1: Tim1Ch1, P2: Tim1Ch2> Encoder<Tim1, P1, P2> {
+    /// Create a new quadrature encoder on channels 1 and 2.
+    pub fn new(
+        tim: Tim1,
+        mut pins: (P1, P2),
+        mode: EncoderMode,
+        filter: Filter,
+        cs: &CriticalSection,
+    ) -> Self {
+        pins.0.set_tim1_ch1_af(cs);
+        pins.1.set_tim1_ch2_af(cs);
+        Self::setup(tim, pins, mode, filter)
+    }
+}
+
+impl<P1: Tim2Ch1, P2: Tim2Ch2> Encoder<Tim2, P1, P2> {
+    /// Create a new quadrature encoder on channels 1 and 2.
+    pub fn new(
+        tim: Tim2,
+        mut pins: (P1, P2),
+        mode: EncoderMode,
+        filter: Filter,
+        cs: &CriticalSection,
+    ) -> Self {
+        pins.0.set_tim2_ch1_af(cs);
+        pins.1.set_tim2_ch2_af(cs);
+        Self::setup(tim, pins, mode, filter)
+    }
+}
+
+impl<TIM: Tim, P1, P2> embedded_hal::Qei for Encoder<TIM, P1, P2> {
+    type Count = u32;
+
+    #[inline]
+    fn count(&self) -> Self::Count {
+        self.tim.cnt()
+    }
+
+    #[inline]
+    fn direction(&self) -> embedded_hal::Direction {
+        if TIM::read_cr1() & CR1_DIR == 0 {
+            embedded_hal::Direction::Upcounting
+        } else {
+            embedded_hal::Direction::Downcounting
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{EncoderMode, Extender};
+
+    #[test]
+    fn encoder_sms() {
+        assert_eq!(EncoderMode::Ch1 as u8, 0b010);
+        assert_eq!(EncoderMode::Ch2 as u8, 0b001);
+        assert_eq!(EncoderMode::Both as u8, 0b011);
+    }
+
+    #[test]
+    fn extend_16() {
+        let mut ext: Extender = Extender::new(0xFFFF, 0xFFF0);
+        assert_eq!(ext.advance(0xFFFF), 0xFFFF);
+        assert_eq!(ext.advance(0x0010), 0x1_0010);
+        // capture before the overflow, counter read after
+        assert_eq!(ext.extend(0xFFFE, 0x0020), 0xFFFE);
+        // capture and counter after the overflow
+        assert_eq!(ext.extend(0x0001, 0x0030), 0x1_0001);
+    }
+
+    #[test]
+    fn extend_32() {
+        let mut ext: Extender = Extender::new(u32::MAX, 0xFFFF_FFF0);
+        assert_eq!(ext.advance(0x10), 0x10);
+        assert_eq!(ext.extend(0xFFFF_FFFF, 0x20), 0xFFFF_FFFF);
+    }
+
+    #[test]
+    fn extend_in_place() {
+        let mut ext: Extender = Extender::new(0xFFFF, 0x8000);
+        let mut buf: [u32; 4] = [0xC000, 0x0000, 0x4000, 0x8000];
+        ext.extend_in_place(&mut buf);
+        assert_eq!(buf, [0xC000, 0x1_0000, 0x1_4000, 0x1_8000]);
+        assert_eq!(ext.advance(0x8001), 0x1_8001);
+    }
+
+    #[test]
+    fn signed() {
+        let mut ext: Extender = Extender::new(0xFFFF, 0);
+        assert_eq!(ext.advance_signed(5), 5);
+        assert_eq!(ext.advance_signed(0xFFFF), -6);
+        assert_eq!(ext.advance_signed(0x7FFE), 0x7FFF);
+        assert_eq!(ext.advance_signed(0xFFFF), -0x7FFF);
+
+        let mut ext: Extender = Extender::new(u32::MAX, 0);
+        assert_eq!(ext.advance_signed(u32::MAX), -1);
+        assert_eq!(ext.advance_signed(1), 2);
+    }
+}
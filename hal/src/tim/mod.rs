@@ -0,0 +1,1344 @@
+//! General-purpose and advanced-control timers
+//!
+//! * [`Tim1`] advanced-control 16-bit timer with 4 channels, complementary
+//!   outputs, and dead-time insertion
+//! * [`Tim2`] general-purpose 32-bit timer with 4 channels
+//! * [`Tim16`] and [`Tim17`] general-purpose 16-bit timers with 1 channel,
+//!   a complementary output, and dead-time insertion
+//!
+//! The timers implement the `embedded-hal`
+//! [`CountDown`](embedded_hal::timer::CountDown),
+//! [`Periodic`](embedded_hal::timer::Periodic), and
+//! [`Pwm`](embedded_hal::Pwm) traits.
+//! Channels bound to a pin implement [`PwmPin`](embedded_hal::PwmPin).
+//!
+//! [`Tim1`] and [`Tim2`] also support input capture with [`InputCapture`],
+//! and quadrature encoders with [`Encoder`].
+//!
+//! # Example
+//!
+//! Output a 1 kHz PWM with a 25% duty cycle on pin A0.
+//!
+//! ```no_run
+//! use stm32wlxx_hal::{
+//!     cortex_m,
+//!     embedded_hal::PwmPin,
+//!     gpio::{pins, PortA},
+//!     pac,
+//!     tim::{PwmCh, Tim, Tim2},
+//! };
+//!
+//! let mut dp: pac::Peripherals = pac::Peripherals::take().unwrap();
+//!
+//! let pa: PortA = PortA::split(dp.GPIOA, &mut dp.RCC);
+//! let mut tim2: Tim2 = Tim2::new(dp.TIM2, &mut dp.RCC);
+//! let mut ch1: PwmCh<Tim2, pins::A0> =
+//!     cortex_m::interrupt::free(|cs| tim2.new_pwm_ch1(pa.a0, cs));
+//!
+//! tim2.set_timing(tim2.timing_for_hz(1_000)?);
+//! ch1.set_duty(ch1.get_max_duty() / 4);
+//! ch1.enable();
+//! tim2.start_counter();
+//! # Ok::<(), stm32wlxx_hal::tim::Error>(())
+//! ```
+
+use crate::{
+    gpio::sealed::{
+        Tim16Ch1, Tim16Ch1n, Tim17Ch1, Tim17Ch1n, Tim1Ch1, Tim1Ch1n, Tim1Ch2, Tim1Ch2n, Tim1Ch3,
+        Tim1Ch3n, Tim1Ch4, Tim2Ch1, Tim2Ch2, Tim2Ch3, Tim2Ch4,
+    },
+    pac, Ratio,
+};
+use core::{cmp::min, marker::PhantomData, time::Duration};
+use cortex_m::interrupt::CriticalSection;
+use paste::paste;
+use void::Void;
+
+mod capture;
+
+pub use capture::{
+    CaptureCfg, CaptureCh, CapturePrescaler, Edge, Encoder, EncoderMode, Filter, InputCapture,
+};
+
+const CR1_CEN: u32 = 1;
+const CR1_URS: u32 = 1 << 2;
+const CR1_DIR: u32 = 1 << 4;
+const CR1_ARPE: u32 = 1 << 7;
+const EGR_UG: u32 = 1;
+
+/// Timer IRQs.
+pub mod irq {
+    /// Break interrupt.
+    ///
+    /// Only present on [`Tim1`](super::Tim1), [`Tim16`](super::Tim16), and
+    /// [`Tim17`](super::Tim17).
+    pub const BI: u32 = 1 << 7;
+    /// Trigger interrupt.
+    pub const TI: u32 = 1 << 6;
+    /// Commutation interrupt.
+    ///
+    /// Only present on [`Tim1`](super::Tim1), [`Tim16`](super::Tim16), and
+    /// [`Tim17`](super::Tim17).
+    pub const COMI: u32 = 1 << 5;
+    /// Capture/compare 4 interrupt.
+    pub const CC4I: u32 = 1 << 4;
+    /// Capture/compare 3 interrupt.
+    pub const CC3I: u32 = 1 << 3;
+    /// Capture/compare 2 interrupt.
+    pub const CC2I: u32 = 1 << 2;
+    /// Capture/compare 1 interrupt.
+    pub const CC1I: u32 = 1 << 1;
+    /// Update interrupt.
+    ///
+    /// Set by hardware on counter overflow.
+    pub const UI: u32 = 1;
+
+    /// Mask of all IRQs.
+    pub const ALL: u32 = BI | TI | COMI | CC4I | CC3I | CC2I | CC1I | UI;
+}
+
+/// Timer errors.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Error {
+    /// Requested frequency is too high for the timer clock.
+    TooFast,
+    /// Requested frequency is too low for the timer clock.
+    TooSlow,
+    /// Requested dead-time is too long for the timer clock.
+    DeadTime,
+    /// A capture occurred before the previous capture was read.
+    Overcapture,
+    /// DMA transfer error.
+    Dma,
+}
+
+/// Timer channel.
+#[repr(u8)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Ch {
+    /// Channel 1.
+    Ch1 = 0,
+    /// Channel 2.
+    Ch2 = 1,
+    /// Channel 3.
+    Ch3 = 2,
+    /// Channel 4.
+    Ch4 = 3,
+}
+
+impl Ch {
+    const fn ccmr_shift(self) -> u8 {
+        ((self as u8) % 2) * 8
+    }
+
+    /// Capture/compare output enable.
+    const fn cce(self) -> u32 {
+        1 << ((self as u8) * 4)
+    }
+
+    /// Capture/compare complementary output enable.
+    const fn ccne(self) -> u32 {
+        1 << ((self as u8) * 4 + 2)
+    }
+
+    /// Capture/compare interrupt flag.
+    const fn ccif(self) -> u32 {
+        1 << ((self as u8) + 1)
+    }
+
+    /// Capture/compare overcapture flag.
+    const fn ccof(self) -> u32 {
+        1 << ((self as u8) + 9)
+    }
+
+    /// Capture/compare DMA request enable.
+    const fn ccde(self) -> u32 {
+        1 << ((self as u8) + 9)
+    }
+}
+
+/// Timer channel of the single channel timers, [`Tim16`] and [`Tim17`].
+#[repr(u8)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum SingleCh {
+    /// Channel 1.
+    Ch1 = 0,
+}
+
+impl From<SingleCh> for Ch {
+    #[inline]
+    fn from(ch: SingleCh) -> Self {
+        match ch {
+            SingleCh::Ch1 => Ch::Ch1,
+        }
+    }
+}
+
+/// Timer prescaler and autoreload.
+///
+/// The counter period is `(psc + 1) * (arr + 1)` timer clock cycles.
+///
+/// Created with [`Timing::from_hz`] or [`Tim::timing_for_hz`].
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Timing {
+    psc: u16,
+    arr: u32,
+}
+
+impl Timing {
+    /// Create a new timing from raw prescaler and autoreload values.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::tim::Timing;
+    ///
+    /// let timing: Timing = Timing::from_raw(47, 999);
+    /// assert_eq!(timing.psc(), 47);
+    /// assert_eq!(timing.arr(), 999);
+    /// assert_eq!(timing.hz(48_000_000), 1_000);
+    /// ```
+    pub const fn from_raw(psc: u16, arr: u32) -> Timing {
+        Timing { psc, arr }
+    }
+
+    /// Solve for the prescaler and autoreload values that best approximate
+    /// a frequency.
+    ///
+    /// The smallest prescaler that allows the period to fit in `max_arr`
+    /// is selected, this maximizes the resolution of the duty cycle.
+    /// The autoreload value is then rounded to the nearest timer clock cycle.
+    ///
+    /// # Arguments
+    ///
+    /// * `src_hz` timer clock frequency in hertz.
+    /// * `hz` requested counter overflow frequency in hertz.
+    /// * `max_arr` maximum autoreload value for the timer,
+    ///   `0xFFFF` for 16-bit timers and `0xFFFF_FFFF` for 32-bit timers.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::TooFast`] `hz` is greater than half of `src_hz`.
+    /// * [`Error::TooSlow`] `hz` is zero, or less than the timer can count
+    ///   with the maximum prescaler.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::tim::{Error, Timing};
+    ///
+    /// // 1 kHz from 48 MHz, no prescaler needed
+    /// let timing: Timing = Timing::from_hz(48_000_000, 1_000, 0xFFFF)?;
+    /// assert_eq!(timing, Timing::from_raw(0, 47_999));
+    ///
+    /// // 50 Hz from 48 MHz needs a prescaler for a 16-bit timer
+    /// let timing: Timing = Timing::from_hz(48_000_000, 50, 0xFFFF)?;
+    /// assert_eq!(timing, Timing::from_raw(14, 63_999));
+    ///
+    /// // but not for a 32-bit timer
+    /// let timing: Timing = Timing::from_hz(48_000_000, 50, 0xFFFF_FFFF)?;
+    /// assert_eq!(timing, Timing::from_raw(0, 959_999));
+    ///
+    /// assert_eq!(Timing::from_hz(48_000_000, 0, 0xFFFF), Err(Error::TooSlow));
+    /// assert_eq!(Timing::from_hz(48_000_000, 0xFFFF_FFFF, 0xFFFF), Err(Error::TooFast));
+    /// # Ok::<(), stm32wlxx_hal::tim::Error>(())
+    /// ```
+    pub const fn from_hz(src_hz: u32, hz: u32, max_arr: u32) -> Result<Timing, Error> {
+        if hz == 0 {
+            return Err(Error::TooSlow);
+        }
+        if hz > src_hz / 2 {
+            return Err(Error::TooFast);
+        }
+
+        let src_hz: u64 = src_hz as u64;
+        let hz: u64 = hz as u64;
+        let max_period: u64 = max_arr as u64 + 1;
+
+        // smallest divider where the period fits in the autoreload register
+        let div: u64 = (src_hz + hz * max_period - 1) / (hz * max_period);
+        if div > (u16::MAX as u64) + 1 {
+            return Err(Error::TooSlow);
+        }
+        let div: u64 = if div == 0 { 1 } else { div };
+
+        // period rounded to the nearest cycle
+        let period: u64 = (src_hz + (hz * div) / 2) / (hz * div);
+        if period < 2 {
+            return Err(Error::TooFast);
+        }
+
+        Ok(Timing {
+            psc: (div - 1) as u16,
+            arr: (period - 1) as u32,
+        })
+    }
+
+    /// Prescaler value.
+    ///
+    /// The timer clock is divided by `psc + 1`.
+    pub const fn psc(&self) -> u16 {
+        self.psc
+    }
+
+    /// Autoreload value.
+    ///
+    /// The counter counts from `0` to `arr` inclusive.
+    pub const fn arr(&self) -> u32 {
+        self.arr
+    }
+
+    /// Number of timer clock cycles in one counter period.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::tim::Timing;
+    ///
+    /// assert_eq!(Timing::from_raw(0xFFFF, 0xFFFF_FFFF).cycles(), 1 << 48);
+    /// ```
+    pub const fn cycles(&self) -> u64 {
+        (self.psc as u64 + 1) * (self.arr as u64 + 1)
+    }
+
+    /// Counter overflow frequency in hertz, rounded to the nearest hertz.
+    ///
+    /// # Arguments
+    ///
+    /// * `src_hz` timer clock frequency in hertz.
+    pub const fn hz(&self, src_hz: u32) -> u32 {
+        let cycles: u64 = self.cycles();
+        ((src_hz as u64 + cycles / 2) / cycles) as u32
+    }
+}
+
+/// Dead-time inserted between complementary outputs.
+///
+/// Argument of [`Tim1::set_dead_time`], [`Tim16::set_dead_time`], and
+/// [`Tim17::set_dead_time`].
+///
+/// The dead-time is measured in timer clock cycles, the encoding has reduced
+/// precision for longer durations:
+///
+/// | Cycles     | Step |
+/// |------------|------|
+/// | 0 to 127   | 1    |
+/// | 128 to 254 | 2    |
+/// | 256 to 504 | 8    |
+/// | 512 to 1008| 16   |
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct DeadTime {
+    dtg: u8,
+}
+
+impl DeadTime {
+    /// No dead-time.
+    pub const NONE: DeadTime = DeadTime { dtg: 0 };
+
+    /// Maximum dead-time, 1008 timer clock cycles.
+    pub const MAX: DeadTime = DeadTime { dtg: 0xFF };
+
+    /// Create a dead-time from the raw DTG field value.
+    pub const fn from_raw(dtg: u8) -> DeadTime {
+        DeadTime { dtg }
+    }
+
+    /// Get the raw DTG field value.
+    pub const fn as_bits(&self) -> u8 {
+        self.dtg
+    }
+
+    /// Create a dead-time from a number of timer clock cycles.
+    ///
+    /// The number of cycles is rounded up to the next value that can be
+    /// represented.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::DeadTime`] `cycles` is greater than 1008.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::tim::{DeadTime, Error};
+    ///
+    /// assert_eq!(DeadTime::from_cycles(0)?, DeadTime::NONE);
+    /// assert_eq!(DeadTime::from_cycles(100)?.cycles(), 100);
+    /// assert_eq!(DeadTime::from_cycles(129)?.cycles(), 130);
+    /// assert_eq!(DeadTime::from_cycles(257)?.cycles(), 264);
+    /// assert_eq!(DeadTime::from_cycles(1008)?, DeadTime::MAX);
+    /// assert_eq!(DeadTime::from_cycles(1009), Err(Error::DeadTime));
+    /// # Ok::<(), stm32wlxx_hal::tim::Error>(())
+    /// ```
+    pub const fn from_cycles(cycles: u16) -> Result<DeadTime, Error> {
+        let dtg: u8 = if cycles <= 127 {
+            cycles as u8
+        } else if cycles <= 254 {
+            0b1000_0000 | ((cycles + 1) / 2 - 64) as u8
+        } else if cycles <= 504 {
+            0b1100_0000 | ((cycles + 7) / 8 - 32) as u8
+        } else if cycles <= 1008 {
+            0b1110_0000 | ((cycles + 15) / 16 - 32) as u8
+        } else {
+            return Err(Error::DeadTime);
+        };
+        Ok(DeadTime { dtg })
+    }
+
+    /// Create a dead-time from a duration.
+    ///
+    /// The duration is rounded up to the next value that can be represented.
+    ///
+    /// # Arguments
+    ///
+    /// * `src_hz` timer clock frequency in hertz, see [`Tim::hz`].
+    /// * `dt` dead-time duration.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::DeadTime`] the duration is greater than 1008 timer clock
+    ///   cycles.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use core::time::Duration;
+    /// use stm32wlxx_hal::tim::DeadTime;
+    ///
+    /// // 1 µs at 48 MHz is 48 cycles
+    /// let dt: DeadTime = DeadTime::from_duration(48_000_000, Duration::from_micros(1))?;
+    /// assert_eq!(dt.cycles(), 48);
+    /// # Ok::<(), stm32wlxx_hal::tim::Error>(())
+    /// ```
+    pub fn from_duration(src_hz: u32, dt: Duration) -> Result<DeadTime, Error> {
+        const NANOS_PER_SEC: u128 = 1_000_000_000;
+        let cycles: u128 = (dt.as_nanos() * u128::from(src_hz) + NANOS_PER_SEC - 1) / NANOS_PER_SEC;
+        match u16::try_from(cycles) {
+            Ok(cycles) => Self::from_cycles(cycles),
+            Err(_) => Err(Error::DeadTime),
+        }
+    }
+
+    /// Dead-time in timer clock cycles.
+    pub const fn cycles(&self) -> u16 {
+        let dtg: u16 = self.dtg as u16;
+        if dtg & 0b1000_0000 == 0 {
+            dtg
+        } else if dtg & 0b0100_0000 == 0 {
+            (64 + (dtg & 0x3F)) * 2
+        } else if dtg & 0b0010_0000 == 0 {
+            (32 + (dtg & 0x1F)) * 8
+        } else {
+            (32 + (dtg & 0x1F)) * 16
+        }
+    }
+}
+
+impl Default for DeadTime {
+    fn default() -> Self {
+        DeadTime::NONE
+    }
+}
+
+use sealed::Tim as SealedTim;
+pub(crate) mod sealed {
+    use super::{Ch, Ratio};
+
+    pub trait Tim {
+        type Pac;
+        const MAX_ARR: u32;
+        const NUM_CH: u8;
+        const DMA_REQ_ID: [u8; 4];
+        fn _hz(&self) -> &Ratio<u32>;
+        fn read_cr1() -> u32;
+        fn write_cr1(cr1: u32);
+        fn read_dier() -> u32;
+        fn write_dier(dier: u32);
+        fn read_sr() -> u32;
+        fn write_sr(sr: u32);
+        fn write_egr(egr: u32);
+        fn read_ccmr(ch: Ch) -> u32;
+        fn write_ccmr(ch: Ch, ccmr: u32);
+        fn read_ccer() -> u32;
+        fn write_ccer(ccer: u32);
+        fn read_cnt() -> u32;
+        fn read_psc() -> u16;
+        fn write_psc(psc: u16);
+        fn read_arr() -> u32;
+        fn write_arr(arr: u32);
+        fn read_ccr(ch: Ch) -> u32;
+        fn write_ccr(ch: Ch, ccr: u32);
+        fn ccr_addr(ch: Ch) -> u32;
+    }
+
+    pub trait SlaveMode: Tim {
+        fn write_smcr(smcr: u32);
+    }
+}
+
+/// Read-modify-write of a register shared by multiple channel handles.
+#[inline(always)]
+fn modify_reg<R, W, F>(read: R, write: W, f: F)
+where
+    R: FnOnce() -> u32,
+    W: FnOnce(u32),
+    F: FnOnce(u32) -> u32,
+{
+    cortex_m::interrupt::free(|_| write(f(read())))
+}
+
+#[inline]
+fn setup_pwm<T: SealedTim>(ch: Ch) {
+    const OCM_PWM1: u32 = 0b110 << 4;
+    const OCPE: u32 = 1 << 3;
+    // CCxS (output), OCxFE, OCxPE, OCxM, OCxCE, and OCxM[3]
+    const MASK: u32 = 0xFF | (1 << 16);
+
+    debug_assert!((ch as u8) < T::NUM_CH);
+    let shift: u8 = ch.ccmr_shift();
+    modify_reg(
+        || T::read_ccmr(ch),
+        |ccmr| T::write_ccmr(ch, ccmr),
+        |ccmr| (ccmr & !(MASK << shift)) | ((OCM_PWM1 | OCPE) << shift),
+    )
+}
+
+#[inline]
+fn max_duty<T: SealedTim>() -> u32 {
+    T::read_arr().saturating_add(1)
+}
+
+#[inline]
+fn duty<T: SealedTim>(ch: Ch) -> u32 {
+    debug_assert!((ch as u8) < T::NUM_CH);
+    T::read_ccr(ch)
+}
+
+#[inline]
+fn set_duty<T: SealedTim>(ch: Ch, duty: u32) {
+    debug_assert!((ch as u8) < T::NUM_CH);
+    // the CCR register is only written by the channel owner
+    T::write_ccr(ch, min(duty, max_duty::<T>()))
+}
+
+macro_rules! paste_tim {
+    (
+        $n:expr,
+        $max_arr:expr,
+        $num_ch:expr,
+        $dma_req_id:expr,
+        [$($ccmr_ch:pat => $ccmr:ident),+],
+        [$($ccr_ch:pat => $ccr:ident),+]
+    ) => {
+        paste! {
+            #[doc = "Timer " $n " driver."]
+            #[derive(Debug)]
+            pub struct [<Tim $n>] {
+                tim: pac::[<TIM $n>],
+                hz: Ratio<u32>,
+            }
+
+            // safety for register access through the pointer:
+            // 1. reads are atomic with no side effects (CCR reads clear CCxIF)
+            // 2. writes to registers shared by channel handles are done in a
+            //    critical section, see modify_reg
+            // 3. other writes take place from the timer driver
+            impl sealed::Tim for [<Tim $n>] {
+                type Pac = pac::[<TIM $n>];
+                const MAX_ARR: u32 = $max_arr;
+                const NUM_CH: u8 = $num_ch;
+                const DMA_REQ_ID: [u8; 4] = $dma_req_id;
+
+                #[inline(always)]
+                fn _hz(&self) -> &Ratio<u32> {
+                    &self.hz
+                }
+
+                #[inline(always)]
+                fn read_cr1() -> u32 {
+                    unsafe { (*pac::[<TIM $n>]::PTR).cr1.read().bits() }
+                }
+
+                #[inline(always)]
+                fn write_cr1(cr1: u32) {
+                    unsafe { (*pac::[<TIM $n>]::PTR).cr1.write(|w| w.bits(cr1)) }
+                }
+
+                #[inline(always)]
+                fn read_dier() -> u32 {
+                    unsafe { (*pac::[<TIM $n>]::PTR).dier.read().bits() }
+                }
+
+                #[inline(always)]
+                fn write_dier(dier: u32) {
+                    unsafe { (*pac::[<TIM $n>]::PTR).dier.write(|w| w.bits(dier)) }
+                }
+
+                #[inline(always)]
+                fn read_sr() -> u32 {
+                    unsafe { (*pac::[<TIM $n>]::PTR).sr.read().bits() }
+                }
+
+                #[inline(always)]
+                fn write_sr(sr: u32) {
+                    unsafe { (*pac::[<TIM $n>]::PTR).sr.write(|w| w.bits(sr)) }
+                }
+
+                #[inline(always)]
+                fn write_egr(egr: u32) {
+                    unsafe { (*pac::[<TIM $n>]::PTR).egr.write(|w| w.bits(egr)) }
+                }
+
+                #[inline(always)]
+                fn read_ccmr(ch: Ch) -> u32 {
+                    unsafe {
+                        let tim = &*pac::[<TIM $n>]::PTR;
+                        match ch {
+                            $($ccmr_ch => tim.$ccmr().read().bits(),)+
+                        }
+                    }
+                }
+
+                #[inline(always)]
+                fn write_ccmr(ch: Ch, ccmr: u32) {
+                    unsafe {
+                        let tim = &*pac::[<TIM $n>]::PTR;
+                        match ch {
+                            $($ccmr_ch => tim.$ccmr().write(|w| w.bits(ccmr)),)+
+                        }
+                    }
+                }
+
+                #[inline(always)]
+                fn read_ccer() -> u32 {
+                    unsafe { (*pac::[<TIM $n>]::PTR).ccer.read().bits() }
+                }
+
+                #[inline(always)]
+                fn write_ccer(ccer: u32) {
+                    unsafe { (*pac::[<TIM $n>]::PTR).ccer.write(|w| w.bits(ccer)) }
+                }
+
+                #[inline(always)]
+                fn read_cnt() -> u32 {
+                    unsafe { (*pac::[<TIM $n>]::PTR).cnt.read().bits() }
+                }
+
+                #[inline(always)]
+                fn read_psc() -> u16 {
+                    unsafe { (*pac::[<TIM $n>]::PTR).psc.read().bits() as u16 }
+                }
+
+                #[inline(always)]
+                fn write_psc(psc: u16) {
+                    unsafe { (*pac::[<TIM $n>]::PTR).psc.write(|w| w.bits(psc.into())) }
+                }
+
+                #[inline(always)]
+                fn read_arr() -> u32 {
+                    unsafe { (*pac::[<TIM $n>]::PTR).arr.read().bits() }
+                }
+
+                #[inline(always)]
+                fn write_arr(arr: u32) {
+                    unsafe { (*pac::[<TIM $n>]::PTR).arr.write(|w| w.bits(arr)) }
+                }
+
+                #[inline(always)]
+                fn read_ccr(ch: Ch) -> u32 {
+                    unsafe {
+                        let tim = &*pac::[<TIM $n>]::PTR;
+                        match ch {
+                            $($ccr_ch => tim.$ccr.read().bits(),)+
+                        }
+                    }
+                }
+
+                #[inline(always)]
+                fn write_ccr(ch: Ch, ccr: u32) {
+                    unsafe {
+                        let tim = &*pac::[<TIM $n>]::PTR;
+                        match ch {
+                            $($ccr_ch => tim.$ccr.write(|w| w.bits(ccr)),)+
+                        }
+                    }
+                }
+
+                #[inline(always)]
+                fn ccr_addr(ch: Ch) -> u32 {
+                    unsafe {
+                        let tim = &*pac::[<TIM $n>]::PTR;
+                        match ch {
+                            $($ccr_ch => tim.$ccr.as_ptr() as u32,)+
+                        }
+                    }
+                }
+            }
+        }
+    };
+}
+
+// DMAMUX request IDs for the capture/compare channels,
+// see table "DMAMUX: assignment of multiplexer inputs to resources"
+paste_tim!(
+    1,
+    0xFFFF,
+    4,
+    [23, 24, 25, 26],
+    [Ch::Ch1 | Ch::Ch2 => ccmr1_output, Ch::Ch3 | Ch::Ch4 => ccmr2_output],
+    [Ch::Ch1 => ccr1, Ch::Ch2 => ccr2, Ch::Ch3 => ccr3, Ch::Ch4 => ccr4]
+);
+paste_tim!(
+    2,
+    0xFFFF_FFFF,
+    4,
+    [30, 31, 32, 33],
+    [Ch::Ch1 | Ch::Ch2 => ccmr1_output, Ch::Ch3 | Ch::Ch4 => ccmr2_output],
+    [Ch::Ch1 => ccr1, Ch::Ch2 => ccr2, Ch::Ch3 => ccr3, Ch::Ch4 => ccr4]
+);
+// single channel timers only have CCMR1 and CCR1, the channel is restricted
+// to channel 1 by SingleCh and the PWM channel constructors
+paste_tim!(16, 0xFFFF, 1, [35, 0, 0, 0], [_ => ccmr1_output], [_ => ccr1]);
+paste_tim!(17, 0xFFFF, 1, [37, 0, 0, 0], [_ => ccmr1_output], [_ => ccr1]);
+
+macro_rules! impl_slave_mode_for {
+    ($n:expr) => {
+        paste! {
+            impl sealed::SlaveMode for [<Tim $n>] {
+                #[inline(always)]
+                fn write_smcr(smcr: u32) {
+                    unsafe { (*pac::[<TIM $n>]::PTR).smcr.write(|w| w.bits(smcr)) }
+                }
+            }
+        }
+    };
+}
+
+impl_slave_mode_for!(1);
+impl_slave_mode_for!(2);
+
+/// Timer trait.
+pub trait Tim: sealed::Tim + Sized {
+    /// Create a new timer driver.
+    ///
+    /// This will enable clocks and reset the timer peripheral.
+    ///
+    /// The timer clock is derived from PCLK1 for [`Tim2`] and from PCLK2
+    /// for all other timers.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use stm32wlxx_hal::{
+    ///     pac,
+    ///     tim::{Tim, Tim1},
+    /// };
+    ///
+    /// let mut dp: pac::Peripherals = pac::Peripherals::take().unwrap();
+    /// let tim1: Tim1 = Tim1::new(dp.TIM1, &mut dp.RCC);
+    /// ```
+    fn new(tim: Self::Pac, rcc: &mut pac::RCC) -> Self;
+
+    /// Free the timer registers from the driver.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use stm32wlxx_hal::{
+    ///     pac,
+    ///     tim::{Tim, Tim1},
+    /// };
+    ///
+    /// let mut dp: pac::Peripherals = pac::Peripherals::take().unwrap();
+    /// let tim1: Tim1 = Tim1::new(dp.TIM1, &mut dp.RCC);
+    /// // ... use timer
+    /// let tim1: pac::TIM1 = tim1.free();
+    /// ```
+    fn free(self) -> Self::Pac;
+
+    /// Reset the timer peripheral.
+    ///
+    /// [`new`](Self::new) will pulse reset for you.
+    ///
+    /// # Safety
+    ///
+    /// 1. Ensure nothing is using the timer before calling this function.
+    /// 2. You are responsible for setting up the timer after a reset.
+    unsafe fn pulse_reset(rcc: &mut pac::RCC);
+
+    /// Enable clocks for the timer peripheral.
+    ///
+    /// [`new`](Self::new) will enable clocks for you.
+    fn enable_clock(rcc: &mut pac::RCC);
+
+    /// Disable the timer peripheral clock.
+    ///
+    /// # Safety
+    ///
+    /// 1. Ensure nothing is using the timer before disabling the clock.
+    /// 2. You are responsible for re-enabling the clock before using the timer.
+    unsafe fn disable_clock(rcc: &mut pac::RCC);
+
+    /// Get the timer clock speed in hertz.
+    ///
+    /// This is the frequency before the prescaler.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use stm32wlxx_hal::{
+    ///     pac,
+    ///     tim::{Tim, Tim2},
+    /// };
+    ///
+    /// let mut dp: pac::Peripherals = pac::Peripherals::take().unwrap();
+    /// let tim2: Tim2 = Tim2::new(dp.TIM2, &mut dp.RCC);
+    /// // without any initialization the timer clock is 4MHz
+    /// assert_eq!(tim2.hz().to_integer(), 4_000_000);
+    /// ```
+    #[inline]
+    fn hz(&self) -> &Ratio<u32> {
+        self._hz()
+    }
+
+    /// Solve for the prescaler and autoreload values that best approximate
+    /// a counter overflow frequency for this timer.
+    ///
+    /// See [`Timing::from_hz`] for details.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use stm32wlxx_hal::{
+    ///     pac,
+    ///     tim::{Tim, Tim16, Timing},
+    /// };
+    ///
+    /// let mut dp: pac::Peripherals = pac::Peripherals::take().unwrap();
+    /// let tim16: Tim16 = Tim16::new(dp.TIM16, &mut dp.RCC);
+    /// let timing: Timing = tim16.timing_for_hz(50)?;
+    /// # Ok::<(), stm32wlxx_hal::tim::Error>(())
+    /// ```
+    #[inline]
+    fn timing_for_hz(&self, hz: u32) -> Result<Timing, Error> {
+        Timing::from_hz(self.hz().to_integer(), hz, Self::MAX_ARR)
+    }
+
+    /// Set the prescaler and autoreload values.
+    ///
+    /// The new values take effect immediately and the counter is reset.
+    ///
+    /// # Panics
+    ///
+    /// * (debug) autoreload value is greater than the timer maximum.
+    #[inline]
+    fn set_timing(&mut self, timing: Timing) {
+        debug_assert!(timing.arr() <= Self::MAX_ARR);
+        Self::write_psc(timing.psc());
+        Self::write_arr(timing.arr());
+        // load the preloaded registers
+        Self::write_egr(EGR_UG);
+    }
+
+    /// Get the prescaler and autoreload values.
+    #[inline]
+    fn timing(&self) -> Timing {
+        Timing::from_raw(Self::read_psc(), Self::read_arr())
+    }
+
+    /// Get the counter value.
+    #[inline]
+    fn cnt(&self) -> u32 {
+        Self::read_cnt()
+    }
+
+    /// Returns `true` if the counter is enabled.
+    #[inline]
+    fn is_counting(&self) -> bool {
+        Self::read_cr1() & CR1_CEN != 0
+    }
+
+    /// Enable the counter.
+    ///
+    /// Only the counter enable bit is modified, the clock division,
+    /// alignment, direction, and one-pulse settings are kept.
+    #[inline]
+    fn start_counter(&mut self) {
+        modify_reg(Self::read_cr1, Self::write_cr1, |cr1| cr1 | CR1_CEN)
+    }
+
+    /// Disable the counter.
+    ///
+    /// Only the counter enable bit is modified.
+    #[inline]
+    fn stop_counter(&mut self) {
+        modify_reg(Self::read_cr1, Self::write_cr1, |cr1| cr1 & !CR1_CEN)
+    }
+
+    /// Get the interrupt status.
+    #[inline]
+    fn sr() -> u32 {
+        Self::read_sr()
+    }
+
+    /// Clear interrupt status flags.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use stm32wlxx_hal::{
+    ///     pac,
+    ///     tim::{self, Tim, Tim2},
+    /// };
+    ///
+    /// let mut dp: pac::Peripherals = pac::Peripherals::take().unwrap();
+    /// let mut tim2: Tim2 = Tim2::new(dp.TIM2, &mut dp.RCC);
+    /// if Tim2::sr() & tim::irq::UI != 0 {
+    ///     tim2.clear_sr(tim::irq::UI);
+    /// }
+    /// ```
+    #[inline]
+    fn clear_sr(&mut self, flags: u32) {
+        // flags are cleared by writing 0, writing 1 has no effect
+        Self::write_sr(!(flags & irq::ALL))
+    }
+
+    /// Enable and disable interrupts.
+    ///
+    /// # Example
+    ///
+    /// Enable the update interrupt.
+    ///
+    /// ```no_run
+    /// use stm32wlxx_hal::{
+    ///     pac,
+    ///     tim::{self, Tim, Tim2},
+    /// };
+    ///
+    /// let mut dp: pac::Peripherals = pac::Peripherals::take().unwrap();
+    /// let mut tim2: Tim2 = Tim2::new(dp.TIM2, &mut dp.RCC);
+    /// tim2.set_dier(tim::irq::UI);
+    /// ```
+    #[inline]
+    fn set_dier(&mut self, dier: u32) {
+        // reserved bits are masked
+        Self::write_dier(dier & irq::ALL)
+    }
+}
+
+macro_rules! impl_tim_for {
+    ($n:expr, $enr:ident, $rstr:ident, $apbtimx:ident $(, $bdtr:ident)?) => {
+        paste! {
+            impl Tim for [<Tim $n>] {
+                fn new(tim: Self::Pac, rcc: &mut pac::RCC) -> Self {
+                    Self::enable_clock(rcc);
+                    unsafe { Self::pulse_reset(rcc) };
+
+                    tim.cr1
+                        .modify(|r, w| unsafe { w.bits(r.bits() | CR1_ARPE | CR1_URS) });
+                    $(
+                        // main output enable, outputs are then
+                        // controlled by the CCxE and CCxNE bits
+                        tim.$bdtr.write(|w| w.moe().set_bit());
+                    )?
+
+                    Self {
+                        tim,
+                        hz: crate::rcc::$apbtimx(rcc),
+                    }
+                }
+
+                #[inline]
+                fn free(self) -> Self::Pac {
+                    self.tim
+                }
+
+                #[inline]
+                unsafe fn pulse_reset(rcc: &mut pac::RCC) {
+                    rcc.$rstr.modify(|_, w| w.[<tim $n rst>]().set_bit());
+                    rcc.$rstr.modify(|_, w| w.[<tim $n rst>]().clear_bit());
+                }
+
+                #[inline]
+                fn enable_clock(rcc: &mut pac::RCC) {
+                    rcc.$enr.modify(|_, w| w.[<tim $n en>]().enabled());
+                    rcc.$enr.read(); // delay after an RCC peripheral clock enabling
+                }
+
+                #[inline]
+                unsafe fn disable_clock(rcc: &mut pac::RCC) {
+                    rcc.$enr.modify(|_, w| w.[<tim $n en>]().disabled());
+                }
+            }
+        }
+    };
+}
+
+impl_tim_for!(1, apb2enr, apb2rstr, apb2timx, bdtr);
+impl_tim_for!(2, apb1enr1, apb1rstr1, apb1timx);
+impl_tim_for!(16, apb2enr, apb2rstr, apb2timx, bdtr);
+impl_tim_for!(17, apb2enr, apb2rstr, apb2timx, bdtr);
+
+macro_rules! impl_eh_for {
+    ($tim:ident, $ch:ident) => {
+        impl embedded_hal::timer::CountDown for $tim {
+            type Time = Timing;
+
+            fn start<T>(&mut self, timing: T)
+            where
+                T: Into<Self::Time>,
+            {
+                self.stop_counter();
+                self.set_timing(timing.into());
+                self.clear_sr(irq::UI);
+                self.start_counter();
+            }
+
+            fn wait(&mut self) -> nb::Result<(), Void> {
+                if Self::sr() & irq::UI == 0 {
+                    Err(nb::Error::WouldBlock)
+                } else {
+                    self.clear_sr(irq::UI);
+                    Ok(())
+                }
+            }
+        }
+
+        impl embedded_hal::timer::Periodic for $tim {}
+
+        impl embedded_hal::Pwm for $tim {
+            type Channel = $ch;
+            type Time = Timing;
+            type Duty = u32;
+
+            fn disable(&mut self, channel: Self::Channel) {
+                let ch: Ch = channel.into();
+                modify_reg(Self::read_ccer, Self::write_ccer, |ccer| {
+                    ccer & !(ch.cce() | ch.ccne())
+                })
+            }
+
+            fn enable(&mut self, channel: Self::Channel) {
+                let ch: Ch = channel.into();
+                setup_pwm::<Self>(ch);
+                modify_reg(Self::read_ccer, Self::write_ccer, |ccer| ccer | ch.cce())
+            }
+
+            fn get_period(&self) -> Self::Time {
+                self.timing()
+            }
+
+            fn get_duty(&self, channel: Self::Channel) -> Self::Duty {
+                duty::<Self>(channel.into())
+            }
+
+            fn get_max_duty(&self) -> Self::Duty {
+                max_duty::<Self>()
+            }
+
+            fn set_duty(&mut self, channel: Self::Channel, duty: Self::Duty) {
+                set_duty::<Self>(channel.into(), duty)
+            }
+
+            fn set_period<P>(&mut self, period: P)
+            where
+                P: Into<Self::Time>,
+            {
+                self.set_timing(period.into());
+                self.start_counter();
+            }
+        }
+    };
+}
+
+impl_eh_for!(Tim1, Ch);
+impl_eh_for!(Tim2, Ch);
+impl_eh_for!(Tim16, SingleCh);
+impl_eh_for!(Tim17, SingleCh);
+
+/// PWM output channel bound to a pin.
+///
+/// Constructed with the `new_pwm_ch*` methods on a timer, for example
+/// [`Tim1::new_pwm_ch1`] or [`Tim1::new_pwm_ch1n`].
+///
+/// The duty cycle is shared between a channel and its complementary
+/// channel.
+/// A duty cycle of [`get_max_duty`](embedded_hal::PwmPin::get_max_duty)
+/// is 100%.
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct PwmCh<TIM, P> {
+    pin: P,
+    ch: Ch,
+    en: u32,
+    _tim: PhantomData<TIM>,
+}
+
+impl<TIM: Tim, P> PwmCh<TIM, P> {
+    fn new(pin: P, ch: Ch, en: u32) -> Self {
+        setup_pwm::<TIM>(ch);
+        Self {
+            pin,
+            ch,
+            en,
+            _tim: PhantomData,
+        }
+    }
+
+    /// Get the timer channel.
+    #[inline]
+    pub fn ch(&self) -> Ch {
+        self.ch
+    }
+
+    /// Disable the output and free the pin.
+    #[inline]
+    pub fn free(mut self) -> P {
+        embedded_hal::PwmPin::disable(&mut self);
+        self.pin
+    }
+}
+
+impl<TIM: Tim, P> embedded_hal::PwmPin for PwmCh<TIM, P> {
+    type Duty = u32;
+
+    fn disable(&mut self) {
+        modify_reg(TIM::read_ccer, TIM::write_ccer, |ccer| ccer & !self.en)
+    }
+
+    fn enable(&mut self) {
+        modify_reg(TIM::read_ccer, TIM::write_ccer, |ccer| ccer | self.en)
+    }
+
+    fn get_duty(&self) -> Self::Duty {
+        duty::<TIM>(self.ch)
+    }
+
+    fn get_max_duty(&self) -> Self::Duty {
+        max_duty::<TIM>()
+    }
+
+    fn set_duty(&mut self, duty: Self::Duty) {
+        set_duty::<TIM>(self.ch, duty)
+    }
+}
+
+macro_rules! impl_pwm_ch {
+    ($tim:ident, $n:expr, $ch:ident, $trt:ident, $method:ident) => {
+        paste! {
+            impl $tim {
+                #[doc = "Setup a new PWM output on channel " $n "."]
+                ///
+                /// The output is disabled until
+                /// [`PwmPin::enable`](embedded_hal::PwmPin::enable) is called.
+                #[inline]
+                pub fn [<new_pwm_ $ch:lower>]<P: $trt>(
+                    &mut self,
+                    mut pin: P,
+                    cs: &CriticalSection,
+                ) -> PwmCh<$tim, P> {
+                    pin.$method(cs);
+                    PwmCh::new(pin, Ch::$ch, Ch::$ch.cce())
+                }
+            }
+        }
+    };
+    ($tim:ident, $n:expr, $ch:ident, $trt:ident, $method:ident, n) => {
+        paste! {
+            impl $tim {
+                #[doc = "Setup a new complementary PWM output on channel " $n "."]
+                ///
+                /// The output is disabled until
+                /// [`PwmPin::enable`](embedded_hal::PwmPin::enable) is called.
+                #[inline]
+                pub fn [<new_pwm_ $ch:lower n>]<P: $trt>(
+                    &mut self,
+                    mut pin: P,
+                    cs: &CriticalSection,
+                ) -> PwmCh<$tim, P> {
+                    pin.$method(cs);
+                    PwmCh::new(pin, Ch::$ch, Ch::$ch.ccne())
+                }
+            }
+        }
+    };
+}
+
+impl_pwm_ch!(Tim1, 1, Ch1, Tim1Ch1, set_tim1_ch1_af);
+impl_pwm_ch!(Tim1, 2, Ch2, Tim1Ch2, set_tim1_ch2_af);
+impl_pwm_ch!(Tim1, 3, Ch3, Tim1Ch3, set_tim1_ch3_af);
+impl_pwm_ch!(Tim1, 4, Ch4, Tim1Ch4, set_tim1_ch4_af);
+impl_pwm_ch!(Tim1, 1, Ch1, Tim1Ch1n, set_tim1_ch1n_af, n);
+impl_pwm_ch!(Tim1, 2, Ch2, Tim1Ch2n, set_tim1_ch2n_af, n);
+impl_pwm_ch!(Tim1, 3, Ch3, Tim1Ch3n, set_tim1_ch3n_af, n);
+impl_pwm_ch!(Tim2, 1, Ch1, Tim2Ch1, set_tim2_ch1_af);
+impl_pwm_ch!(Tim2, 2, Ch2, Tim2Ch2, set_tim2_ch2_af);
+impl_pwm_ch!(Tim2, 3, Ch3, Tim2Ch3, set_tim2_ch3_af);
+impl_pwm_ch!(Tim2, 4, Ch4, Tim2Ch4, set_tim2_ch4_af);
+impl_pwm_ch!(Tim16, 1, Ch1, Tim16Ch1, set_tim16_ch1_af);
+impl_pwm_ch!(Tim16, 1, Ch1, Tim16Ch1n, set_tim16_ch1n_af, n);
+impl_pwm_ch!(Tim17, 1, Ch1, Tim17Ch1, set_tim17_ch1_af);
+impl_pwm_ch!(Tim17, 1, Ch1, Tim17Ch1n, set_tim17_ch1n_af, n);
+
+macro_rules! impl_dead_time {
+    ($tim:ident) => {
+        impl $tim {
+            /// Set the dead-time inserted between complementary outputs.
+            ///
+            /// # Example
+            ///
+            /// ```no_run
+            /// use core::time::Duration;
+            /// use stm32wlxx_hal::{
+            ///     pac,
+            ///     tim::{DeadTime, Tim, Tim1},
+            /// };
+            ///
+            /// let mut dp: pac::Peripherals = pac::Peripherals::take().unwrap();
+            /// let mut tim1: Tim1 = Tim1::new(dp.TIM1, &mut dp.RCC);
+            /// let dt: DeadTime =
+            ///     DeadTime::from_duration(tim1.hz().to_integer(), Duration::from_nanos(500))?;
+            /// tim1.set_dead_time(dt);
+            /// # Ok::<(), stm32wlxx_hal::tim::Error>(())
+            /// ```
+            #[inline]
+            pub fn set_dead_time(&mut self, dt: DeadTime) {
+                self.tim.bdtr.modify(|_, w| w.dtg().bits(dt.as_bits()))
+            }
+
+            /// Get the dead-time inserted between complementary outputs.
+            #[inline]
+            pub fn dead_time(&self) -> DeadTime {
+                DeadTime::from_raw(self.tim.bdtr.read().dtg().bits())
+            }
+        }
+    };
+}
+
+impl_dead_time!(Tim1);
+impl_dead_time!(Tim16);
+impl_dead_time!(Tim17);
+
+impl Tim1 {
+    /// Route comparator outputs to the break input.
+    ///
+    /// The break input is enabled and active high when either comparator is
+    /// routed, a break disables the outputs until the main output enable
+    /// is set again.
+    ///
+    /// # Example
+    ///
+    /// Disable the outputs when the COMP1 output is high.
+    ///
+    /// ```no_run
+    /// use stm32wlxx_hal::{
+    ///     pac,
+    ///     tim::{Tim, Tim1},
+    /// };
+    ///
+    /// let mut dp: pac::Peripherals = pac::Peripherals::take().unwrap();
+    /// let mut tim1: Tim1 = Tim1::new(dp.TIM1, &mut dp.RCC);
+    /// tim1.set_comp_break(true, false);
+    /// ```
+    #[inline]
+    pub fn set_comp_break(&mut self, comp1: bool, comp2: bool) {
+        self.tim
+            .af1
+            .modify(|_, w| w.bkcmp1e().bit(comp1).bkcmp2e().bit(comp2));
+        let en: bool = comp1 || comp2;
+        self.tim.bdtr.modify(|_, w| w.bke().bit(en).bkp().bit(en));
+    }
+
+    /// Set the main output enable, re-enabling the outputs after a break.
+    #[inline]
+    pub fn set_moe(&mut self) {
+        self.tim.bdtr.modify(|_, w| w.moe().set_bit())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DeadTime, Error, Timing};
+
+    #[test]
+    fn timing_exact() {
+        for hz in [1, 2, 10, 50, 100, 1_000, 48_000, 1_000_000, 24_000_000] {
+            let timing: Timing = Timing::from_hz(48_000_000, hz, 0xFFFF_FFFF).unwrap();
+            assert_eq!(timing.psc(), 0);
+            assert_eq!(timing.cycles() * u64::from(hz), 48_000_000);
+            assert_eq!(timing.hz(48_000_000), hz);
+        }
+        for hz in [1_000, 48_000, 1_000_000, 24_000_000] {
+            let timing: Timing = Timing::from_hz(48_000_000, hz, 0xFFFF).unwrap();
+            assert_eq!(timing.psc(), 0);
+            assert_eq!(timing.cycles() * u64::from(hz), 48_000_000);
+        }
+    }
+
+    #[test]
+    fn timing_smallest_prescaler() {
+        for hz in 1..10_000 {
+            let timing: Timing = Timing::from_hz(48_000_000, hz, 0xFFFF).unwrap();
+            assert!(timing.arr() <= 0xFFFF);
+            if timing.psc() > 0 {
+                // the next smallest prescaler would not fit
+                let max_cycles: u64 = u64::from(timing.psc()) * 0x1_0000;
+                assert!(
+                    max_cycles * u64::from(hz) < 48_000_000,
+                    "hz={hz} timing={timing:?}"
+                );
+            }
+            // error is at most half of one prescaled cycle
+            let err: i64 = (timing.cycles() * u64::from(hz)) as i64 - 48_000_000;
+            assert!(
+                err.unsigned_abs() <= (u64::from(timing.psc()) + 1) * u64::from(hz) / 2,
+                "hz={hz} timing={timing:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn timing_limits() {
+        assert_eq!(Timing::from_hz(48_000_000, 0, 0xFFFF), Err(Error::TooSlow));
+        assert_eq!(
+            Timing::from_hz(48_000_000, 24_000_001, 0xFFFF),
+            Err(Error::TooFast)
+        );
+        assert_eq!(
+            Timing::from_hz(48_000_000, 24_000_000, 0xFFFF),
+            Ok(Timing::from_raw(0, 1))
+        );
+        // slowest 16-bit timer with maximum prescaler and autoreload
+        assert_eq!(
+            Timing::from_hz(0xFFFF_FFFF, 1, 0xFFFF),
+            Ok(Timing::from_raw(0xFFFF, 0xFFFF))
+        );
+        assert_eq!(Timing::from_hz(48_000_000, 1, 0xFF), Err(Error::TooSlow));
+    }
+
+    #[test]
+    fn dead_time_round_trip() {
+        for dtg in 0..=u8::MAX {
+            let dt: DeadTime = DeadTime::from_raw(dtg);
+            assert_eq!(DeadTime::from_cycles(dt.cycles()), Ok(dt));
+        }
+    }
+
+    #[test]
+    fn dead_time_rounds_up() {
+        let mut prev: u16 = 0;
+        for cycles in 0..=1008 {
+            let dt: DeadTime = DeadTime::from_cycles(cycles).unwrap();
+            assert!(dt.cycles() >= cycles);
+            assert!(dt.cycles() >= prev);
+            prev = dt.cycles();
+        }
+        assert_eq!(DeadTime::from_cycles(1009), Err(Error::DeadTime));
+        assert_eq!(DeadTime::from_cycles(u16::MAX), Err(Error::DeadTime));
+    }
+}
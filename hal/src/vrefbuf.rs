@@ -0,0 +1,259 @@
+//! Voltage reference buffer
+//!
+//! The VREFBUF drives the V<sub>REF+</sub> pin with an internally generated
+//! 2.048 V or 2.5 V reference, which is used by the ADC and DAC as their
+//! full-scale voltage.
+//!
+//! The output voltage is trimmed in production, the factory trim is loaded by
+//! hardware for the selected [`Scale`] and applied by [`Vrefbuf::new`].
+//!
+//! **Note:** V<sub>DDA</sub> must be at least a few hundred millivolts above
+//! the selected scale for the buffer to become ready, see the datasheet for
+//! the exact limits.
+//!
+//! # Example
+//!
+//! Enable the buffer at 2.048 V and convert an ADC sample to millivolts.
+//!
+//! ```no_run
+//! use stm32wlxx_hal::{
+//!     adc::{self, Adc},
+//!     pac,
+//!     vrefbuf::{Scale, Vrefbuf},
+//! };
+//!
+//! let mut dp: pac::Peripherals = pac::Peripherals::take().unwrap();
+//!
+//! let vrefbuf: Vrefbuf = Vrefbuf::new(dp.VREFBUF, Scale::V2_048);
+//!
+//! // enable the HSI16 source clock
+//! dp.RCC.cr.modify(|_, w| w.hsion().set_bit());
+//! while dp.RCC.cr.read().hsirdy().is_not_ready() {}
+//!
+//! let mut adc = Adc::new(dp.ADC, adc::Clk::RccHsi, &mut dp.RCC);
+//! adc.set_max_sample_time();
+//! adc.enable();
+//! adc.enable_vbat();
+//!
+//! let vbat_mv: u16 = adc.vbat_mv(vrefbuf.millivolts());
+//! ```
+
+use crate::pac;
+
+use pac::vrefbuf::csr::VRS_A;
+
+/// Maximum trim code.
+pub const TRIM_MAX: u8 = 0x3F;
+
+/// Voltage reference scale.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Scale {
+    /// V<sub>REF+</sub> is 2.048 V.
+    V2_048,
+    /// V<sub>REF+</sub> is 2.5 V.
+    V2_5,
+}
+
+impl Scale {
+    /// Get the nominal reference voltage in millivolts.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::vrefbuf::Scale;
+    ///
+    /// assert_eq!(Scale::V2_048.millivolts(), 2048);
+    /// assert_eq!(Scale::V2_5.millivolts(), 2500);
+    /// ```
+    pub const fn millivolts(&self) -> u16 {
+        match self {
+            Scale::V2_048 => 2048,
+            Scale::V2_5 => 2500,
+        }
+    }
+
+    const fn vrs(&self) -> VRS_A {
+        match self {
+            Scale::V2_048 => VRS_A::V2048,
+            Scale::V2_5 => VRS_A::V25,
+        }
+    }
+}
+
+/// Voltage reference buffer driver.
+#[derive(Debug)]
+pub struct Vrefbuf {
+    vrefbuf: pac::VREFBUF,
+    scale: Scale,
+    factory_trim: u8,
+}
+
+impl Vrefbuf {
+    /// Create a new voltage reference buffer driver.
+    ///
+    /// This will select the scale, apply the factory trim, enable the
+    /// buffer, and wait for the output voltage to be ready.
+    ///
+    /// Any external voltage on the V<sub>REF+</sub> pin must be removed
+    /// before calling this.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use stm32wlxx_hal::{
+    ///     pac,
+    ///     vrefbuf::{Scale, Vrefbuf},
+    /// };
+    ///
+    /// let dp: pac::Peripherals = pac::Peripherals::take().unwrap();
+    /// let vrefbuf: Vrefbuf = Vrefbuf::new(dp.VREFBUF, Scale::V2_5);
+    /// assert_eq!(vrefbuf.millivolts(), 2500);
+    /// ```
+    pub fn new(vrefbuf: pac::VREFBUF, scale: Scale) -> Vrefbuf {
+        let mut vrefbuf: Vrefbuf = Vrefbuf {
+            vrefbuf,
+            scale,
+            factory_trim: 0,
+        };
+        vrefbuf.set_scale(scale);
+        vrefbuf
+    }
+
+    /// Free the VREFBUF peripheral from the driver.
+    ///
+    /// This will **not** disable the buffer.
+    #[inline]
+    pub fn free(self) -> pac::VREFBUF {
+        self.vrefbuf
+    }
+
+    /// Steal the VREFBUF peripheral from whatever is currently using it.
+    ///
+    /// The scale is read from the hardware, and the current trim is taken to
+    /// be the factory trim.
+    ///
+    /// # Safety
+    ///
+    /// 1. Ensure that the code stealing the VREFBUF has exclusive access to
+    ///    the peripheral. Singleton checks are bypassed with this method.
+    /// 2. You are responsible for setting up the VREFBUF correctly.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use stm32wlxx_hal::vrefbuf::Vrefbuf;
+    ///
+    /// // ... setup happens here
+    ///
+    /// let vrefbuf: Vrefbuf = unsafe { Vrefbuf::steal() };
+    /// ```
+    pub unsafe fn steal() -> Vrefbuf {
+        let vrefbuf: pac::VREFBUF = pac::Peripherals::steal().VREFBUF;
+        let scale: Scale = match vrefbuf.csr.read().vrs().variant() {
+            VRS_A::V2048 => Scale::V2_048,
+            VRS_A::V25 => Scale::V2_5,
+        };
+        let factory_trim: u8 = vrefbuf.ccr.read().trim().bits();
+        Vrefbuf {
+            vrefbuf,
+            scale,
+            factory_trim,
+        }
+    }
+
+    /// Change the voltage reference scale.
+    ///
+    /// The buffer is disabled while the scale is changed, then re-enabled
+    /// with the factory trim for the new scale.
+    /// This blocks until the output voltage is ready.
+    pub fn set_scale(&mut self, scale: Scale) {
+        self.vrefbuf
+            .csr
+            .write(|w| w.hiz().high_z().vrs().variant(scale.vrs()));
+        // hardware loads the factory trim for the selected scale
+        self.factory_trim = self.vrefbuf.ccr.read().trim().bits();
+        self.scale = scale;
+        self.reset_trim();
+        self.enable();
+    }
+
+    /// Get the voltage reference scale.
+    #[inline]
+    pub const fn scale(&self) -> Scale {
+        self.scale
+    }
+
+    /// Get the nominal reference voltage in millivolts.
+    ///
+    /// This is the value to use with the ADC millivolt conversions, such as
+    /// `Adc::pin_mv`.
+    #[inline]
+    pub const fn millivolts(&self) -> u16 {
+        self.scale.millivolts()
+    }
+
+    /// Enable the buffer, driving V<sub>REF+</sub> with the internal
+    /// reference.
+    ///
+    /// This blocks until the output voltage is ready.
+    pub fn enable(&mut self) {
+        self.vrefbuf
+            .csr
+            .write(|w| w.envr().enabled().vrs().variant(self.scale.vrs()));
+        while !self.is_ready() {}
+    }
+
+    /// Disable the buffer and set the V<sub>REF+</sub> pin to high impedance.
+    ///
+    /// This allows an external voltage reference to be used.
+    #[inline]
+    pub fn disable(&mut self) {
+        self.vrefbuf
+            .csr
+            .write(|w| w.hiz().high_z().vrs().variant(self.scale.vrs()));
+    }
+
+    /// Returns `true` if the buffer is enabled.
+    #[inline]
+    #[must_use]
+    pub fn is_enabled(&self) -> bool {
+        self.vrefbuf.csr.read().envr().is_enabled()
+    }
+
+    /// Returns `true` if the buffer output voltage is ready.
+    #[inline]
+    #[must_use]
+    pub fn is_ready(&self) -> bool {
+        self.vrefbuf.csr.read().vrr().is_ready()
+    }
+
+    /// Get the current trim code.
+    #[inline]
+    pub fn trim(&self) -> u8 {
+        self.vrefbuf.ccr.read().trim().bits()
+    }
+
+    /// Get the factory trim code for the current scale.
+    #[inline]
+    pub const fn factory_trim(&self) -> u8 {
+        self.factory_trim
+    }
+
+    /// Set the trim code.
+    ///
+    /// Values above [`TRIM_MAX`] are saturated.
+    /// Use [`reset_trim`](Self::reset_trim) to restore the factory trim.
+    #[inline]
+    pub fn set_trim(&mut self, trim: u8) {
+        self.vrefbuf
+            .ccr
+            .write(|w| w.trim().bits(trim.min(TRIM_MAX)));
+    }
+
+    /// Restore the factory trim code for the current scale.
+    #[inline]
+    pub fn reset_trim(&mut self) {
+        self.set_trim(self.factory_trim)
+    }
+}
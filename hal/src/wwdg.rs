@@ -0,0 +1,545 @@
+//! Window watchdog
+//!
+//! The window watchdog is clocked by PCLK1, it is suited to supervising
+//! control loops that must refresh within a tight time window.
+//!
+//! The watchdog counter counts down from the reload value, and the device is
+//! reset when the counter rolls over from `0x40` to `0x3F`, or when the
+//! watchdog is fed before the counter has dropped to the window value.
+//!
+//! An optional early wakeup interrupt (EWI) fires when the counter reaches
+//! `0x40`, one tick before the reset.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use core::time::Duration;
+//! use stm32wlxx_hal::{pac, wwdg::Wwdg};
+//!
+//! let mut dp: pac::Peripherals = pac::Peripherals::take().unwrap();
+//!
+//! // reset if not fed within 40 ms, or if fed earlier than 10 ms
+//! let mut wwdg: Wwdg = Wwdg::new(
+//!     dp.WWDG,
+//!     Duration::from_millis(40),
+//!     Duration::from_millis(10),
+//!     &mut dp.RCC,
+//! )?;
+//! wwdg.start();
+//!
+//! loop {
+//!     // ... do work
+//!     wwdg.feed();
+//! }
+//! # Ok::<(), stm32wlxx_hal::wwdg::Error>(())
+//! ```
+
+use crate::pac;
+use core::time::Duration;
+
+// the counter resets the device when T6 is cleared
+const COUNTER_MIN: u8 = 0x40;
+const COUNTER_MAX: u8 = 0x7F;
+
+// each counter tick is 4096 PCLK1 cycles before the prescaler
+const PCLK1_DIV: u128 = 4096;
+
+const NANOS_PER_SEC: u128 = 1_000_000_000;
+
+/// Window watchdog configuration errors.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Error {
+    /// The timeout is shorter than a single tick of the watchdog with the
+    /// smallest prescaler.
+    TooShort,
+    /// The timeout is longer than the maximum counter value with the largest
+    /// prescaler.
+    TooLong,
+    /// The window is longer than or equal to the timeout.
+    Window,
+}
+
+/// Window watchdog prescaler.
+#[repr(u8)]
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Prescaler {
+    /// Counter clock is PCLK1 / 4096 / 1.
+    Div1 = 0b000,
+    /// Counter clock is PCLK1 / 4096 / 2.
+    Div2 = 0b001,
+    /// Counter clock is PCLK1 / 4096 / 4.
+    Div4 = 0b010,
+    /// Counter clock is PCLK1 / 4096 / 8.
+    Div8 = 0b011,
+    /// Counter clock is PCLK1 / 4096 / 16.
+    Div16 = 0b100,
+    /// Counter clock is PCLK1 / 4096 / 32.
+    Div32 = 0b101,
+    /// Counter clock is PCLK1 / 4096 / 64.
+    Div64 = 0b110,
+    /// Counter clock is PCLK1 / 4096 / 128.
+    Div128 = 0b111,
+}
+
+impl Prescaler {
+    const ALL: [Prescaler; 8] = [
+        Prescaler::Div1,
+        Prescaler::Div2,
+        Prescaler::Div4,
+        Prescaler::Div8,
+        Prescaler::Div16,
+        Prescaler::Div32,
+        Prescaler::Div64,
+        Prescaler::Div128,
+    ];
+
+    /// Get the divisor of the prescaler.
+    ///
+    /// This does not include the fixed divide by 4096.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stm32wlxx_hal::wwdg::Prescaler;
+    ///
+    /// assert_eq!(Prescaler::Div1.div(), 1);
+    /// assert_eq!(Prescaler::Div128.div(), 128);
+    /// ```
+    pub const fn div(&self) -> u8 {
+        1 << (*self as u8)
+    }
+}
+
+/// Window watchdog configuration.
+///
+/// This is the register encoding of a timeout and window, use
+/// [`Config::new`] to compute it from [`Duration`]s.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Config {
+    pre: Prescaler,
+    counter: u8,
+    window: u8,
+}
+
+impl Config {
+    const fn ticks(pclk1_hz: u32, pre: Prescaler, duration: Duration) -> u128 {
+        let numer: u128 = duration.as_nanos() * (pclk1_hz as u128);
+        let denom: u128 = PCLK1_DIV * (pre.div() as u128) * NANOS_PER_SEC;
+        (numer + denom / 2) / denom
+    }
+
+    /// Compute a configuration from a timeout and a window.
+    ///
+    /// * `timeout` is the time after a feed at which the device is reset.
+    /// * `window` is the time after a feed before which feeding the
+    ///   watchdog will reset the device.
+    ///   Use [`Duration::ZERO`] to disable the window.
+    ///
+    /// The smallest prescaler that can represent the timeout is selected to
+    /// provide the best resolution, and the values are rounded to the
+    /// nearest tick.
+    ///
+    /// The PCLK1 frequency is typically obtained with
+    /// [`rcc::pclk1_hz`](crate::rcc::pclk1_hz).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use core::time::Duration;
+    /// use stm32wlxx_hal::wwdg::{Config, Error, Prescaler};
+    ///
+    /// const PCLK1_HZ: u32 = 4_096_000;
+    ///
+    /// // 4.096 MHz / 4096 = 1 kHz, 40 ms is 40 ticks
+    /// let config: Config = Config::new(PCLK1_HZ, Duration::from_millis(40), Duration::ZERO)?;
+    /// assert_eq!(config.prescaler(), Prescaler::Div1);
+    /// assert_eq!(config.counter(), 0x3F + 40);
+    /// assert_eq!(config.window(), 0x7F);
+    ///
+    /// let config: Config = Config::new(
+    ///     PCLK1_HZ,
+    ///     Duration::from_millis(40),
+    ///     Duration::from_millis(10),
+    /// )?;
+    /// assert_eq!(config.window(), 0x3F + 30);
+    ///
+    /// assert_eq!(
+    ///     Config::new(PCLK1_HZ, Duration::from_secs(10), Duration::ZERO),
+    ///     Err(Error::TooLong)
+    /// );
+    /// assert_eq!(
+    ///     Config::new(
+    ///         PCLK1_HZ,
+    ///         Duration::from_millis(40),
+    ///         Duration::from_millis(40)
+    ///     ),
+    ///     Err(Error::Window)
+    /// );
+    /// # Ok::<(), stm32wlxx_hal::wwdg::Error>(())
+    /// ```
+    pub fn new(pclk1_hz: u32, timeout: Duration, window: Duration) -> Result<Config, Error> {
+        const MAX_TICKS: u128 = (COUNTER_MAX - COUNTER_MIN + 1) as u128;
+
+        for pre in Prescaler::ALL {
+            let ticks: u128 = Self::ticks(pclk1_hz, pre, timeout);
+
+            if ticks == 0 {
+                return Err(Error::TooShort);
+            } else if ticks <= MAX_TICKS {
+                let counter: u8 = COUNTER_MIN - 1 + (ticks as u8);
+
+                let window: u8 = if window.is_zero() {
+                    COUNTER_MAX
+                } else {
+                    let window_ticks: u128 = Self::ticks(pclk1_hz, pre, window);
+                    if window_ticks >= ticks {
+                        return Err(Error::Window);
+                    }
+                    counter - (window_ticks as u8)
+                };
+
+                return Ok(Config {
+                    pre,
+                    counter,
+                    window,
+                });
+            }
+        }
+
+        Err(Error::TooLong)
+    }
+
+    /// Get the prescaler.
+    pub const fn prescaler(&self) -> Prescaler {
+        self.pre
+    }
+
+    /// Get the counter reload value.
+    ///
+    /// This is always in the range `0x40..=0x7F`.
+    pub const fn counter(&self) -> u8 {
+        self.counter
+    }
+
+    /// Get the window value.
+    ///
+    /// A window value of `0x7F` (or greater than or equal to the counter)
+    /// disables the window.
+    pub const fn window(&self) -> u8 {
+        self.window
+    }
+
+    /// Calculate the timeout for a given PCLK1 frequency.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use core::time::Duration;
+    /// use stm32wlxx_hal::wwdg::Config;
+    ///
+    /// let config: Config = Config::new(4_096_000, Duration::from_millis(40), Duration::ZERO)?;
+    /// assert_eq!(config.timeout(4_096_000), Duration::from_millis(40));
+    /// # Ok::<(), stm32wlxx_hal::wwdg::Error>(())
+    /// ```
+    pub fn timeout(&self, pclk1_hz: u32) -> Duration {
+        let ticks: u128 = u128::from(self.counter - (COUNTER_MIN - 1));
+        let nanos: u128 = ticks * PCLK1_DIV * u128::from(self.pre.div()) * NANOS_PER_SEC;
+        Duration::from_nanos((nanos / u128::from(pclk1_hz)) as u64)
+    }
+}
+
+/// Window watchdog driver.
+#[derive(Debug)]
+pub struct Wwdg {
+    wwdg: pac::WWDG,
+    counter: u8,
+}
+
+impl Wwdg {
+    /// Create a new window watchdog driver from a timeout and a window.
+    ///
+    /// This will enable the WWDG clock and configure the prescaler and window
+    /// using the current PCLK1 frequency, but it will not start the watchdog.
+    ///
+    /// See [`Config::new`] for a description of the arguments.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use core::time::Duration;
+    /// use stm32wlxx_hal::{pac, wwdg::Wwdg};
+    ///
+    /// let mut dp: pac::Peripherals = pac::Peripherals::take().unwrap();
+    ///
+    /// let wwdg: Wwdg = Wwdg::new(
+    ///     dp.WWDG,
+    ///     Duration::from_millis(40),
+    ///     Duration::ZERO,
+    ///     &mut dp.RCC,
+    /// )?;
+    /// # Ok::<(), stm32wlxx_hal::wwdg::Error>(())
+    /// ```
+    pub fn new(
+        wwdg: pac::WWDG,
+        timeout: Duration,
+        window: Duration,
+        rcc: &mut pac::RCC,
+    ) -> Result<Wwdg, Error> {
+        let config: Config = Config::new(crate::rcc::pclk1_hz(rcc), timeout, window)?;
+        Ok(Self::with_config(wwdg, config, rcc))
+    }
+
+    /// Create a new window watchdog driver from a precomputed configuration.
+    ///
+    /// This will enable the WWDG clock and apply the configuration, but it
+    /// will not start the watchdog.
+    pub fn with_config(wwdg: pac::WWDG, config: Config, rcc: &mut pac::RCC) -> Wwdg {
+        Self::enable_clock(rcc);
+        let mut wwdg: Wwdg = Wwdg {
+            wwdg,
+            counter: config.counter,
+        };
+        wwdg.set_config(config);
+        wwdg
+    }
+
+    /// Free the WWDG peripheral from the driver.
+    ///
+    /// This will **not** stop the watchdog.
+    #[inline]
+    pub fn free(self) -> pac::WWDG {
+        self.wwdg
+    }
+
+    /// Steal the WWDG peripheral from whatever is currently using it.
+    ///
+    /// # Safety
+    ///
+    /// 1. Ensure that the code stealing the WWDG has exclusive access to the
+    ///    peripheral. Singleton checks are bypassed with this method.
+    /// 2. The counter reload value used by [`feed`](Self::feed) is read from
+    ///    the hardware, ensure the watchdog has been started.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use stm32wlxx_hal::wwdg::Wwdg;
+    ///
+    /// // ... setup happens here
+    ///
+    /// let wwdg: Wwdg = unsafe { Wwdg::steal() };
+    /// ```
+    #[inline]
+    pub unsafe fn steal() -> Wwdg {
+        let wwdg: pac::WWDG = pac::Peripherals::steal().WWDG;
+        let counter: u8 = wwdg.cr.read().t().bits();
+        Wwdg { wwdg, counter }
+    }
+
+    /// Enable the WWDG clock.
+    ///
+    /// [`new`](Self::new) will enable the clock for you.
+    ///
+    /// There is no method to disable the clock, once enabled the WWDG clock
+    /// can only be disabled by a reset.
+    #[inline]
+    pub fn enable_clock(rcc: &mut pac::RCC) {
+        rcc.apb1enr1.modify(|_, w| w.wwdgen().enabled());
+        rcc.apb1enr1.read(); // delay after an RCC peripheral clock enabling
+    }
+
+    /// Unmask the WWDG IRQ in the NVIC.
+    ///
+    /// # Safety
+    ///
+    /// This can break mask-based critical sections.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # #[cfg(all(not(feature = "stm32wl5x_cm0p"), feature = "rt"))]
+    /// unsafe { stm32wlxx_hal::wwdg::Wwdg::unmask_irq() };
+    /// ```
+    #[cfg(all(not(feature = "stm32wl5x_cm0p"), feature = "rt"))]
+    #[inline]
+    pub unsafe fn unmask_irq() {
+        pac::NVIC::unmask(pac::Interrupt::WWDG)
+    }
+
+    /// Mask the WWDG IRQ in the NVIC.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # #[cfg(all(not(feature = "stm32wl5x_cm0p"), feature = "rt"))]
+    /// stm32wlxx_hal::wwdg::Wwdg::mask_irq()
+    /// ```
+    #[cfg(all(not(feature = "stm32wl5x_cm0p"), feature = "rt"))]
+    #[inline]
+    pub fn mask_irq() {
+        pac::NVIC::mask(pac::Interrupt::WWDG)
+    }
+
+    /// Apply a new configuration.
+    ///
+    /// If the watchdog is running the new counter value takes effect at the
+    /// next [`feed`](Self::feed).
+    pub fn set_config(&mut self, config: Config) {
+        self.counter = config.counter;
+        // safety: the PAC only enumerates WDGTB values up to div 8, all 3-bit
+        // prescaler values are valid
+        self.wwdg.cfr.modify(|_, w| unsafe {
+            w.wdgtb()
+                .bits(config.pre as u8)
+                .w()
+                .bits(config.window & COUNTER_MAX)
+        });
+    }
+
+    /// Start the watchdog.
+    ///
+    /// Once started the watchdog can only be stopped by a reset.
+    #[inline]
+    pub fn start(&mut self) {
+        self.wwdg
+            .cr
+            .write(|w| w.wdga().enabled().t().bits(self.counter))
+    }
+
+    /// Returns `true` if the watchdog has been started.
+    #[inline]
+    pub fn is_started(&self) -> bool {
+        self.wwdg.cr.read().wdga().is_enabled()
+    }
+
+    /// Feed (refresh) the watchdog.
+    ///
+    /// This reloads the counter.
+    ///
+    /// Feeding the watchdog before the window has elapsed will reset the
+    /// device.
+    #[inline]
+    pub fn feed(&mut self) {
+        self.start()
+    }
+
+    /// Get the current counter value.
+    #[inline]
+    pub fn counter(&self) -> u8 {
+        self.wwdg.cr.read().t().bits()
+    }
+
+    /// Enable the early wakeup interrupt.
+    ///
+    /// The interrupt fires when the counter reaches `0x40`.
+    ///
+    /// Once set this can only be cleared by a reset.
+    #[inline]
+    pub fn enable_ewi(&mut self) {
+        self.wwdg.cfr.modify(|_, w| w.ewi().enable())
+    }
+
+    /// Returns `true` if the early wakeup interrupt flag is set.
+    #[inline]
+    pub fn ewi_pending(&self) -> bool {
+        self.wwdg.sr.read().ewif().is_pending()
+    }
+
+    /// Clear the early wakeup interrupt flag.
+    #[inline]
+    pub fn clear_ewi(&mut self) {
+        self.wwdg.sr.write(|w| w.ewif().finished())
+    }
+}
+
+impl embedded_hal::watchdog::Watchdog for Wwdg {
+    #[inline]
+    fn feed(&mut self) {
+        Wwdg::feed(self)
+    }
+}
+
+impl embedded_hal::watchdog::WatchdogEnable for Wwdg {
+    type Time = Config;
+
+    #[inline]
+    fn start<T>(&mut self, period: T)
+    where
+        T: Into<Self::Time>,
+    {
+        self.set_config(period.into());
+        Wwdg::start(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Config, Error, Prescaler};
+    use core::time::Duration;
+
+    const PCLK1_HZ: u32 = 48_000_000;
+
+    #[test]
+    fn prescaler_selection() {
+        // 4096 / 48 MHz = 85.333 µs per tick at Div1, 64 ticks is 5.461 ms
+        let config: Config =
+            Config::new(PCLK1_HZ, Duration::from_millis(5), Duration::ZERO).unwrap();
+        assert_eq!(config.prescaler(), Prescaler::Div1);
+        assert_eq!(config.counter(), 0x3F + 59);
+
+        let config: Config =
+            Config::new(PCLK1_HZ, Duration::from_millis(6), Duration::ZERO).unwrap();
+        assert_eq!(config.prescaler(), Prescaler::Div2);
+        assert_eq!(config.counter(), 0x3F + 35);
+
+        // 64 ticks at Div128 is 699.05 ms
+        let config: Config =
+            Config::new(PCLK1_HZ, Duration::from_millis(699), Duration::ZERO).unwrap();
+        assert_eq!(config.prescaler(), Prescaler::Div128);
+        assert_eq!(config.counter(), 0x7F);
+    }
+
+    #[test]
+    fn limits() {
+        assert_eq!(
+            Config::new(PCLK1_HZ, Duration::from_micros(42), Duration::ZERO),
+            Err(Error::TooShort)
+        );
+        let config: Config =
+            Config::new(PCLK1_HZ, Duration::from_micros(43), Duration::ZERO).unwrap();
+        assert_eq!(config.counter(), 0x40);
+
+        assert_eq!(
+            Config::new(PCLK1_HZ, Duration::from_millis(705), Duration::ZERO),
+            Err(Error::TooLong)
+        );
+    }
+
+    #[test]
+    fn window() {
+        const TIMEOUT: Duration = Duration::from_millis(20);
+        let config: Config = Config::new(PCLK1_HZ, TIMEOUT, Duration::from_millis(5)).unwrap();
+        assert_eq!(config.prescaler(), Prescaler::Div4);
+        assert_eq!(config.counter(), 0x3F + 59);
+        assert_eq!(config.window(), 0x3F + 44);
+
+        assert_eq!(Config::new(PCLK1_HZ, TIMEOUT, TIMEOUT), Err(Error::Window));
+    }
+
+    #[test]
+    fn round_trip() {
+        for pclk1_hz in [1_000_000, 4_000_000, 16_000_000, PCLK1_HZ] {
+            for ms in [1, 7, 20, 100, 500] {
+                if let Ok(config) = Config::new(pclk1_hz, Duration::from_millis(ms), Duration::ZERO)
+                {
+                    let timeout: Duration = config.timeout(pclk1_hz);
+                    let computed: Config = Config::new(pclk1_hz, timeout, Duration::ZERO).unwrap();
+                    assert_eq!(computed.timeout(pclk1_hz), timeout);
+                }
+            }
+        }
+    }
+}
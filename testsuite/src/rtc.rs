@@ -9,6 +9,7 @@ use nucleo_wl55jc_bsp::hal::{
     pac::{self, DWT},
     rcc::{self, pulse_reset_backup_domain, setup_lsi, LsiPre},
     rtc::{self, Alarm, Rtc},
+    tamp::{Bkp, Tamp, NUM_BKP},
 };
 use panic_probe as _;
 
@@ -226,4 +227,27 @@ mod tests {
         defmt::assert!(rtc.is_alarm_a_en());
         defmt::assert_eq!(rtc.alarm_a(), alarm);
     }
+
+    #[test]
+    fn backup_registers(ta: &mut TestArgs) {
+        unsafe { pulse_reset_backup_domain(&mut ta.rcc, &mut ta.pwr) };
+
+        let dp: pac::Peripherals = unsafe { pac::Peripherals::steal() };
+        let mut tamp: Tamp = Tamp::new(dp.TAMP, &mut ta.pwr, &mut ta.rcc);
+
+        (0..NUM_BKP).for_each(|idx| {
+            let bkp: Bkp = unwrap!(Bkp::new(idx));
+            defmt::assert_eq!(tamp.bkp(bkp), 0);
+            tamp.set_bkp(bkp, 0xA5A5_0000 | u32::from(idx));
+        });
+        (0..NUM_BKP).for_each(|idx| {
+            let bkp: Bkp = unwrap!(Bkp::new(idx));
+            defmt::assert_eq!(tamp.bkp(bkp), 0xA5A5_0000 | u32::from(idx));
+        });
+
+        tamp.erase_bkp();
+        (0..NUM_BKP).for_each(|idx| {
+            defmt::assert_eq!(tamp.bkp(unwrap!(Bkp::new(idx))), 0);
+        });
+    }
 }
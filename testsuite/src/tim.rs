@@ -0,0 +1,144 @@
+#![no_std]
+#![no_main]
+
+use defmt::unwrap;
+use defmt_rtt as _; // global logger
+use nucleo_wl55jc_bsp::hal::{
+    cortex_m,
+    embedded_hal::{timer::CountDown, Pwm},
+    pac::{self, DWT},
+    rcc,
+    tim::{self, InputCapture, Tim, Tim1, Tim16, Tim17, Tim2, Timing},
+};
+use panic_probe as _;
+
+const FREQ: u32 = 48_000_000;
+const CYC_PER_US: u32 = FREQ / 1000 / 1000;
+
+// WARNING will wrap-around eventually, use this for relative timing only
+defmt::timestamp!("{=u32:us}", DWT::cycle_count() / CYC_PER_US);
+
+fn test_countdown<TIM>(tim: &mut TIM, hz: u32)
+where
+    TIM: Tim + CountDown<Time = Timing>,
+{
+    defmt::assert_eq!(tim.hz().to_integer(), FREQ);
+    let timing: Timing = unwrap!(tim.timing_for_hz(hz).ok());
+
+    tim.start(timing);
+    let start: u32 = DWT::cycle_count();
+    unwrap!(nb::block!(tim.wait()).ok());
+    let end: u32 = DWT::cycle_count();
+
+    // the timer and CPU share the same clock
+    let elapsed: u32 = end.wrapping_sub(start);
+    let expected_elapsed: u32 = unwrap!(u32::try_from(timing.cycles()).ok());
+    let elapsed_upper: u32 = expected_elapsed + expected_elapsed / 64;
+    let elapsed_lower: u32 = expected_elapsed - expected_elapsed / 64;
+
+    defmt::assert!(
+        elapsed_lower <= elapsed && elapsed <= elapsed_upper,
+        "Timer is incorrect: {} <= {} <= {}",
+        elapsed_lower,
+        elapsed,
+        elapsed_upper
+    );
+
+    // periodic, the next overflow arrives one period later
+    let start: u32 = DWT::cycle_count();
+    unwrap!(nb::block!(tim.wait()).ok());
+    let elapsed: u32 = DWT::cycle_count().wrapping_sub(start);
+    defmt::assert!(elapsed <= elapsed_upper);
+}
+
+#[defmt_test::tests]
+mod tests {
+    use super::*;
+
+    struct TestArgs {
+        rcc: pac::RCC,
+    }
+
+    #[init]
+    fn init() -> TestArgs {
+        cortex_m::interrupt::free(|cs| {
+            let mut dp: pac::Peripherals = unwrap!(pac::Peripherals::take());
+            let mut cp: pac::CorePeripherals = unwrap!(pac::CorePeripherals::take());
+
+            unsafe { rcc::set_sysclk_msi_max(&mut dp.FLASH, &mut dp.PWR, &mut dp.RCC, cs) };
+            defmt::assert_eq!(rcc::sysclk_hz(&dp.RCC), FREQ);
+
+            cp.DCB.enable_trace();
+            cp.DWT.enable_cycle_counter();
+            cp.DWT.set_cycle_count(0);
+
+            TestArgs { rcc: dp.RCC }
+        })
+    }
+
+    #[test]
+    fn countdown(ta: &mut TestArgs) {
+        const HZ: [u32; 3] = [10, 100, 1_000];
+
+        for hz in HZ {
+            let dp: pac::Peripherals = unsafe { pac::Peripherals::steal() };
+            defmt::info!("TIM1 {} Hz", hz);
+            test_countdown(&mut Tim1::new(dp.TIM1, &mut ta.rcc), hz);
+            defmt::info!("TIM2 {} Hz", hz);
+            test_countdown(&mut Tim2::new(dp.TIM2, &mut ta.rcc), hz);
+            defmt::info!("TIM16 {} Hz", hz);
+            test_countdown(&mut Tim16::new(dp.TIM16, &mut ta.rcc), hz);
+            defmt::info!("TIM17 {} Hz", hz);
+            test_countdown(&mut Tim17::new(dp.TIM17, &mut ta.rcc), hz);
+        }
+    }
+
+    #[test]
+    fn pwm_duty(ta: &mut TestArgs) {
+        let dp: pac::Peripherals = unsafe { pac::Peripherals::steal() };
+        let mut tim1: Tim1 = Tim1::new(dp.TIM1, &mut ta.rcc);
+        let timing: Timing = unwrap!(tim1.timing_for_hz(1_000).ok());
+        tim1.set_period(timing);
+
+        let max_duty: u32 = tim1.get_max_duty();
+        defmt::assert_eq!(max_duty, timing.arr() + 1);
+
+        tim1.enable(tim::Ch::Ch1);
+        tim1.set_duty(tim::Ch::Ch1, max_duty / 2);
+        defmt::assert_eq!(tim1.get_duty(tim::Ch::Ch1), max_duty / 2);
+
+        // duty is clamped to the maximum
+        tim1.set_duty(tim::Ch::Ch1, u32::MAX);
+        defmt::assert_eq!(tim1.get_duty(tim::Ch::Ch1), max_duty);
+        tim1.disable(tim::Ch::Ch1);
+    }
+
+    #[test]
+    fn capture_ticks(ta: &mut TestArgs) {
+        let dp: pac::Peripherals = unsafe { pac::Peripherals::steal() };
+        let tim1: Tim1 = Tim1::new(dp.TIM1, &mut ta.rcc);
+        let mut ic: InputCapture<Tim1> = InputCapture::new(tim1, 0);
+        defmt::assert_eq!(ic.tick_hz(), FREQ);
+
+        // the 16-bit counter overflows several times, the extended ticks
+        // must keep up with the CPU cycle counter
+        let start_ticks: u32 = ic.ticks();
+        let start: u32 = DWT::cycle_count();
+        let mut prev: u32 = start_ticks;
+        while DWT::cycle_count().wrapping_sub(start) < 1_000_000 {
+            let ticks: u32 = ic.ticks();
+            defmt::assert!(ticks.wrapping_sub(prev) < 0x1_0000);
+            prev = ticks;
+        }
+        let elapsed: u32 = DWT::cycle_count().wrapping_sub(start);
+        let elapsed_ticks: u32 = ic.ticks().wrapping_sub(start_ticks);
+
+        defmt::assert!(
+            elapsed - elapsed / 64 <= elapsed_ticks && elapsed_ticks <= elapsed + elapsed / 64,
+            "Ticks are incorrect: {} != {}",
+            elapsed_ticks,
+            elapsed
+        );
+        ic.free();
+    }
+}